@@ -1,19 +1,114 @@
-use std::path::Path;
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufRead, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
 
-use clap::{Parser, ValueEnum};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use hotln::Reporter;
 
-#[derive(Clone, ValueEnum)]
+#[derive(Clone, Copy, ValueEnum)]
 enum Backend {
     Github,
     Linear,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Resolution {
+    Done,
+    Canceled,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PrivacyLevel {
+    Full,
+    Minimal,
+    Anonymous,
+}
+
 #[derive(Parser)]
-#[command(about = "File a bug report")]
+#[command(about = "File and follow up on bug reports")]
 struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Proxy URL (or set HOTLINE_PROXY_URL)
+    #[arg(long, env = "HOTLINE_PROXY_URL", global = true)]
+    proxy_url: Option<String>,
+
+    /// Bearer token for proxy auth (or set HOTLINE_PROXY_TOKEN)
+    #[arg(
+        long,
+        env = "HOTLINE_PROXY_TOKEN",
+        global = true,
+        hide_env_values = true
+    )]
+    proxy_token: Option<String>,
+
+    /// Bearer token for the proxy's issue and viewer routes, used by status,
+    /// comment, close, search, and whoami (or set HOTLINE_ADMIN_TOKEN)
+    #[arg(
+        long,
+        env = "HOTLINE_ADMIN_TOKEN",
+        global = true,
+        hide_env_values = true
+    )]
+    admin_token: Option<String>,
+
+    /// Log what the client does to stderr (-vv for more detail)
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet", global = true)]
+    verbose: u8,
+
+    /// Print nothing; only the exit code reports the outcome
+    #[arg(short, long, global = true)]
+    quiet: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// File a bug report
+    Create(CreateArgs),
+    /// Show an issue's state, assignee, and last update
+    Status(StatusArgs),
+    /// Comment on an existing issue
+    Comment(CommentArgs),
+    /// Close an issue filed with a fingerprint, e.g. once a fix has shipped
+    Close(CloseArgs),
+    /// Search for issues, e.g. to check whether a bug was already reported
+    Search(SearchArgs),
+    /// Show the Linear user, organization, and teams behind the proxy's key
+    Whoami(WhoamiArgs),
+    /// Watch a directory for crash dumps and report JSON files and file them
+    WatchDumps(WatchDumpsArgs),
+    /// Tail a log file and file an issue for each new kind of matching line
+    WatchLog(WatchLogArgs),
+    /// Follow the systemd journal and file an issue for each new kind of error
+    WatchJournal(WatchJournalArgs),
+    /// Watch for macOS crash reports (.ips and .crash) from an app and file them
+    WatchCrashes(WatchCrashesArgs),
+    /// Watch for Windows minidumps, such as from Windows Error Reporting, and file them
+    WatchMinidumps(WatchMinidumpsArgs),
+    /// Generate a Cloudflare Worker project that runs the proxy
+    InitProxy(InitProxyArgs),
+    /// `hotln github TITLE`, from before `create`; the same as `create github`
+    #[command(hide = true)]
+    Github(ReportArgs),
+    /// `hotln linear TITLE`, from before `create`; the same as `create linear`
+    #[command(hide = true)]
+    Linear(ReportArgs),
+}
+
+#[derive(Args)]
+struct CreateArgs {
     /// Backend to file the issue to
     backend: Backend,
 
+    #[command(flatten)]
+    report: ReportArgs,
+}
+
+#[derive(Args)]
+struct ReportArgs {
     /// Short summary of the bug
     title: String,
 
@@ -29,21 +124,351 @@ struct Cli {
     #[arg(short, long)]
     attachment: Vec<String>,
 
-    /// Proxy URL (or set HOTLINE_PROXY_URL)
-    #[arg(long, env = "HOTLINE_PROXY_URL")]
-    proxy_url: String,
+    /// Add a row to the system info table, e.g. --info "GPU=RTX 4080" (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_info)]
+    info: Vec<(String, String)>,
 
-    /// Bearer token for proxy auth (or set HOTLINE_PROXY_TOKEN)
-    #[arg(long, env = "HOTLINE_PROXY_TOKEN")]
-    proxy_token: Option<String>,
+    /// Leave the system info table out of the report
+    #[arg(long, conflicts_with_all = ["info", "omit_info"])]
+    no_system_info: bool,
+
+    /// Drop a row from the system info table, e.g. --omit-info OS (repeatable)
+    #[arg(long, value_name = "KEY")]
+    omit_info: Vec<String>,
+
+    /// How much context to send; minimal and anonymous leave out system info
+    #[arg(
+        long,
+        value_enum,
+        env = "HOTLINE_PRIVACY_LEVEL",
+        default_value = "full"
+    )]
+    privacy_level: PrivacyLevel,
+
+    /// Print the request payload instead of sending it
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Open the created issue in the browser
+    #[arg(long, conflicts_with = "dry_run")]
+    open: bool,
+
+    /// Show the report and ask before sending (default when run in a terminal)
+    #[arg(long, overrides_with = "yes")]
+    confirm: bool,
+
+    /// Send without asking
+    #[arg(short, long, overrides_with = "confirm")]
+    yes: bool,
 }
 
-fn system_info_text() -> String {
-    format!(
-        "## System Info\n\n| Field | Value |\n|-------|-------|\n| OS | {} |\n| Arch | {} |",
-        std::env::consts::OS,
-        std::env::consts::ARCH,
-    )
+#[derive(Args)]
+struct CommentArgs {
+    /// Issue identifier (ENG-123 or #42) or URL
+    issue: String,
+
+    /// Comment text; read from stdin when omitted or "-"
+    text: Option<String>,
+
+    /// Inline a file as a code block in the comment (repeatable, must be UTF-8)
+    #[arg(short, long)]
+    file: Vec<String>,
+
+    /// Backend the issue is on [default: guessed from the issue]
+    #[arg(long)]
+    backend: Option<Backend>,
+}
+
+#[derive(Args)]
+struct CloseArgs {
+    /// Issue identifier (ENG-123 or #42) or URL
+    issue: String,
+
+    /// Whether the issue was fixed or won't be
+    #[arg(long, value_enum, default_value = "done")]
+    resolution: Resolution,
+
+    /// Backend the issue is on [default: guessed from the issue]
+    #[arg(long)]
+    backend: Option<Backend>,
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// Text to look for in titles and descriptions
+    #[arg(default_value = "")]
+    text: String,
+
+    /// open, closed, or (Linear) a workflow state name
+    #[arg(long)]
+    state: Option<String>,
+
+    /// Only show issues with this label (repeatable)
+    #[arg(long)]
+    label: Vec<String>,
+
+    /// Show at most this many issues (the proxy caps it at 50)
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    /// Print the results as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Backend to search
+    #[arg(long, env = "HOTLINE_BACKEND", default_value = "linear")]
+    backend: Backend,
+}
+
+#[derive(Args)]
+struct WhoamiArgs {
+    /// Print the result as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct WatchDumpsArgs {
+    /// Directory a crash handler writes dumps and report JSON files to
+    dir: PathBuf,
+
+    /// Backend to file reports to
+    #[arg(long, env = "HOTLINE_BACKEND", default_value = "linear")]
+    backend: Backend,
+
+    /// Keep reports here while the proxy can't be reached [default: DIR/spool]
+    #[arg(long)]
+    spool: Option<PathBuf>,
+
+    /// Seconds between scans of the directory
+    #[arg(long, default_value_t = 5)]
+    interval: u64,
+
+    /// File what is in the directory now, then exit
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(Args)]
+struct WatchLogArgs {
+    /// Log file to tail; it may be truncated or rotated while watched
+    file: PathBuf,
+
+    /// Regular expression for lines worth an issue
+    #[arg(long, default_value = "ERROR|panicked")]
+    pattern: String,
+
+    /// Read the file from the start instead of only new lines
+    #[arg(long)]
+    from_start: bool,
+
+    /// Seconds between checks for new lines
+    #[arg(long, default_value_t = 1)]
+    interval: u64,
+
+    #[command(flatten)]
+    watch: WatchArgs,
+}
+
+#[derive(Args)]
+struct WatchJournalArgs {
+    /// Only read entries from this systemd unit (repeatable) [default: all]
+    #[arg(long)]
+    unit: Vec<String>,
+
+    /// File entries at this priority or more severe: emerg, alert, crit,
+    /// err, warning, notice, info, debug, or 0-7
+    #[arg(long, default_value = "err", value_parser = parse_priority)]
+    priority: u8,
+
+    #[command(flatten)]
+    watch: WatchArgs,
+}
+
+#[derive(Args)]
+struct WatchCrashesArgs {
+    /// Only file crashes of the app with this bundle identifier
+    #[arg(long)]
+    bundle_id: String,
+
+    /// Directory macOS writes crash reports to
+    /// [default: ~/Library/Logs/DiagnosticReports]
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[command(flatten)]
+    dedup: DedupArgs,
+}
+
+#[derive(Args)]
+struct WatchMinidumpsArgs {
+    /// Only file dumps of this executable, such as myapp.exe [default: all]
+    #[arg(long)]
+    process: Option<String>,
+
+    /// Directory dumps are written to [default: %LOCALAPPDATA%\CrashDumps]
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[command(flatten)]
+    dedup: DedupArgs,
+}
+
+#[derive(Args)]
+#[command(group = clap::ArgGroup::new("backend").required(true).multiple(true))]
+struct InitProxyArgs {
+    /// Directory to create the project in
+    #[arg(default_value = "hotline-proxy")]
+    dir: PathBuf,
+
+    /// Worker name [default: the directory name]
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Linear team ID to file issues to
+    #[arg(long, group = "backend", requires = "linear_project")]
+    linear_team: Option<String>,
+
+    /// Linear project ID to file issues to
+    #[arg(long, requires = "linear_team")]
+    linear_project: Option<String>,
+
+    /// GitHub repository to file issues to, as owner/repo
+    #[arg(long, group = "backend", value_parser = parse_repo)]
+    github_repo: Option<String>,
+
+    /// Replace files that already exist
+    #[arg(long)]
+    force: bool,
+}
+
+/// Options shared by the commands that scan a directory the OS writes
+/// crash reports to.
+#[derive(Args)]
+struct ScanArgs {
+    /// Also file reports already in the directory when starting
+    #[arg(long)]
+    existing: bool,
+
+    /// Seconds between scans of the directory
+    #[arg(long, default_value_t = 10)]
+    interval: u64,
+
+    /// File what is in the directory now, then exit (implies --existing)
+    #[arg(long)]
+    once: bool,
+}
+
+/// Options shared by the commands that file issues from logs.
+#[derive(Args)]
+struct WatchArgs {
+    /// Include this many lines before a match
+    #[arg(long, default_value_t = 20)]
+    before: usize,
+
+    /// Include this many lines after a match, such as a panic's message
+    #[arg(long, default_value_t = 5)]
+    after: usize,
+
+    #[command(flatten)]
+    dedup: DedupArgs,
+}
+
+/// Options shared by the commands that file the same error only once.
+#[derive(Args)]
+struct DedupArgs {
+    /// Backend to file issues to
+    #[arg(long, env = "HOTLINE_BACKEND", default_value = "linear")]
+    backend: Backend,
+
+    /// Add this label to every issue (repeatable)
+    #[arg(long)]
+    label: Vec<String>,
+
+    /// File the same error again after this many seconds [default: never]
+    #[arg(long, value_name = "SECS")]
+    refile_after: Option<u64>,
+
+    /// Keep a comment counting repeats on each issue; needs --admin-token, or
+    /// ALLOW_OCCURRENCE_COMMENTS on the proxy
+    #[arg(long)]
+    comment_occurrences: bool,
+
+    /// Remember filed errors in this file across restarts
+    #[arg(long)]
+    registry: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct StatusArgs {
+    /// Issue identifier (ENG-123 or #42) or URL
+    issue: String,
+
+    /// Backend the issue is on [default: guessed from the issue]
+    #[arg(long)]
+    backend: Option<Backend>,
+}
+
+/// Exit codes, stable so scripts can branch on them. Usage errors exit
+/// with clap's 2.
+mod exit {
+    pub const FAILURE: u8 = 1;
+    pub const NETWORK: u8 = 3;
+    pub const AUTH: u8 = 4;
+    pub const RATE_LIMITED: u8 = 5;
+    pub const CONFIG: u8 = 6;
+}
+
+fn exit_code(error: &anyhow::Error) -> u8 {
+    match error.downcast_ref::<hotln::Error>() {
+        Some(hotln::Error::Http(_) | hotln::Error::Timeout(_)) => exit::NETWORK,
+        Some(hotln::Error::Proxy {
+            status: 401 | 403, ..
+        }) => exit::AUTH,
+        Some(hotln::Error::Proxy { status: 429, .. }) => exit::RATE_LIMITED,
+        Some(hotln::Error::Config(_)) => exit::CONFIG,
+        _ => exit::FAILURE,
+    }
+}
+
+/// Auto-collected system info, with `--info` rows added or overriding and
+/// `--omit-info` keys removed.
+fn system_info(extra: &[(String, String)], omit: &[String]) -> Vec<(String, String)> {
+    let mut rows = hotln::system_info();
+    for (key, value) in extra {
+        match rows.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some(row) => row.1 = value.clone(),
+            None => rows.push((key.clone(), value.clone())),
+        }
+    }
+    rows.retain(|(key, _)| !omit.iter().any(|o| o.eq_ignore_ascii_case(key)));
+    rows
+}
+
+fn system_info_text(rows: &[(String, String)]) -> String {
+    let escape = |s: &str| s.replace('|', "\\|").replace('\n', " ");
+    let mut text = "## System Info\n\n| Field | Value |\n|-------|-------|".to_string();
+    for (key, value) in rows {
+        text += &format!("\n| {} | {} |", escape(key), escape(value));
+    }
+    text
+}
+
+fn parse_info(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got {s:?}"))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("missing key in {s:?}"));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
 }
 
 fn read_file(path_str: &str) -> anyhow::Result<(String, Vec<u8>)> {
@@ -65,54 +490,1469 @@ fn read_file_text(path_str: &str) -> anyhow::Result<(String, String)> {
     Ok((filename, content))
 }
 
-fn main() -> anyhow::Result<()> {
+/// Print the report to stderr and ask whether to send it.
+fn confirm(report: &hotln::Report) -> anyhow::Result<bool> {
+    let mut stderr = std::io::stderr().lock();
+    writeln!(stderr, "Title: {}\n", report.title)?;
+    writeln!(stderr, "{}\n", report.description)?;
+    if !report.attachments.is_empty() {
+        let names: Vec<&str> = report
+            .attachments
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        writeln!(stderr, "Attachments: {}\n", names.join(", "))?;
+    }
+    write!(stderr, "Send this report? [y/N] ")?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn open_in_browser(url: &str) -> anyhow::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    let status = command
+        .arg(url)
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to open {}: {}", url, e))?;
+    if !status.success() {
+        anyhow::bail!("failed to open {}: opener exited with {}", url, status);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    if !cli.quiet {
+        let level = match cli.verbose {
+            0 => tracing::Level::WARN,
+            1 => tracing::Level::INFO,
+            _ => tracing::Level::DEBUG,
+        };
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if !cli.quiet {
+                eprintln!("Error: {e:#}");
+            }
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    match &cli.command {
+        Command::Create(args) => create(cli, args.backend, &args.report),
+        Command::Github(args) => create_deprecated(cli, Backend::Github, args),
+        Command::Linear(args) => create_deprecated(cli, Backend::Linear, args),
+        Command::Status(args) => status(cli, args),
+        Command::Comment(args) => comment(cli, args),
+        Command::Close(args) => close(cli, args),
+        Command::Search(args) => search(cli, args),
+        Command::Whoami(args) => whoami(cli, args),
+        Command::WatchDumps(args) => watch_dumps(cli, args),
+        Command::WatchLog(args) => watch_log(cli, args),
+        Command::WatchJournal(args) => watch_journal(cli, args),
+        Command::WatchCrashes(args) => watch_crashes(cli, args),
+        Command::WatchMinidumps(args) => watch_minidumps(cli, args),
+        Command::InitProxy(args) => init_proxy(cli, args),
+    }
+}
+
+fn proxy_url(cli: &Cli) -> Result<&str, hotln::Error> {
+    cli.proxy_url
+        .as_deref()
+        .ok_or_else(|| hotln::Error::Config("set --proxy-url or HOTLINE_PROXY_URL".to_string()))
+}
+
+/// GitHub issues are numbers; anything else is taken to be Linear.
+fn guess_backend(issue: &str) -> Backend {
+    let issue = issue.trim();
+    if issue.contains("github.com/")
+        || issue
+            .trim_start_matches('#')
+            .chars()
+            .all(|c| c.is_ascii_digit())
+    {
+        Backend::Github
+    } else {
+        Backend::Linear
+    }
+}
+
+/// A client for reading and commenting on existing issues, or filing reports.
+enum Client {
+    GitHub(hotln::GitHubClient),
+    Linear(hotln::LinearClient),
+}
+
+impl Client {
+    /// A client using the proxy's admin token, for the issue routes.
+    fn admin(cli: &Cli, backend: Option<Backend>, issue: &str) -> Result<Self, hotln::Error> {
+        Self::new(cli, backend, issue, cli.admin_token.as_deref())
+    }
+
+    /// A client using the app token, for filing reports.
+    fn filing(cli: &Cli, backend: Backend) -> Result<Self, hotln::Error> {
+        Self::new(cli, Some(backend), "", cli.proxy_token.as_deref())
+    }
+
+    fn new(
+        cli: &Cli,
+        backend: Option<Backend>,
+        issue: &str,
+        token: Option<&str>,
+    ) -> Result<Self, hotln::Error> {
+        let url = proxy_url(cli)?;
+        Ok(match backend.unwrap_or_else(|| guess_backend(issue)) {
+            Backend::Github => {
+                let mut client = hotln::GitHubClient::new(url);
+                if let Some(token) = token {
+                    client.with_token(token);
+                }
+                Client::GitHub(client)
+            }
+            Backend::Linear => {
+                let mut client = hotln::LinearClient::new(url);
+                if let Some(token) = token {
+                    client.with_token(token);
+                }
+                Client::Linear(client)
+            }
+        })
+    }
+
+    fn status(&self, issue: &str) -> Result<hotln::IssueStatus, hotln::Error> {
+        match self {
+            Client::GitHub(client) => client.status(issue),
+            Client::Linear(client) => client.status(issue),
+        }
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, hotln::Error> {
+        match self {
+            Client::GitHub(client) => client.comment(issue, body),
+            Client::Linear(client) => client.comment(issue, body),
+        }
+    }
+
+    fn close_issue(
+        &self,
+        issue: &str,
+        resolution: hotln::Resolution,
+    ) -> Result<hotln::IssueStatus, hotln::Error> {
+        match self {
+            Client::GitHub(client) => client.close_issue(issue, resolution),
+            Client::Linear(client) => client.close_issue(issue, resolution),
+        }
+    }
+
+    fn search(&self, query: &hotln::IssueQuery) -> Result<Vec<hotln::IssueStatus>, hotln::Error> {
+        match self {
+            Client::GitHub(client) => client.search(query),
+            Client::Linear(client) => client.search(query),
+        }
+    }
+
+    fn spool(&mut self, spool: hotln::Spool) {
+        match self {
+            Client::GitHub(client) => {
+                client.spool(spool);
+            }
+            Client::Linear(client) => {
+                client.spool(spool);
+            }
+        }
+    }
+
+    fn send_spooled(&self) -> Result<usize, hotln::Error> {
+        match self {
+            Client::GitHub(client) => client.send_spooled(),
+            Client::Linear(client) => client.send_spooled(),
+        }
+    }
+
+    fn submit(&self, report: &hotln::Report) -> Result<hotln::Submission, hotln::Error> {
+        match self {
+            Client::GitHub(client) => client.submit(report),
+            Client::Linear(client) => client.submit(report),
+        }
+    }
+}
+
+fn status(cli: &Cli, args: &StatusArgs) -> anyhow::Result<()> {
+    let status = Client::admin(cli, args.backend, &args.issue)?.status(&args.issue)?;
+    if !cli.quiet {
+        println!("{}  {}", status.identifier, status.title);
+        println!("State:     {}", status.state);
+        println!(
+            "Assignee:  {}",
+            status.assignee.as_deref().unwrap_or("unassigned")
+        );
+        println!("Updated:   {}", status.updated_at);
+        println!("{}", status.url);
+    }
+    Ok(())
+}
+
+fn comment(cli: &Cli, args: &CommentArgs) -> anyhow::Result<()> {
+    // Build the body the same way as a report's description.
+    let mut body = hotln::Report::new();
+    match args.text.as_deref() {
+        Some(text) if text != "-" => {
+            body.text(text);
+        }
+        _ if !std::io::stdin().is_terminal() => {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+            body.text(text.trim_end());
+        }
+        _ => {}
+    }
+    for path_str in &args.file {
+        let (filename, content) = read_file_text(path_str)?;
+        body.file(&filename, &content);
+    }
+    if body.description.trim().is_empty() {
+        anyhow::bail!("nothing to comment: pass text, --file, or pipe to stdin");
+    }
+
+    let client = Client::admin(cli, args.backend, &args.issue)?;
+    let url = client.comment(&args.issue, &body.description)?;
+    if !cli.quiet {
+        println!("{}", url);
+    }
+    Ok(())
+}
+
+fn close(cli: &Cli, args: &CloseArgs) -> anyhow::Result<()> {
+    let resolution = match args.resolution {
+        Resolution::Done => hotln::Resolution::Done,
+        Resolution::Canceled => hotln::Resolution::Canceled,
+    };
+    let status =
+        Client::admin(cli, args.backend, &args.issue)?.close_issue(&args.issue, resolution)?;
+    if !cli.quiet {
+        println!("{}  {}", status.identifier, status.state);
+    }
+    Ok(())
+}
+
+fn whoami(cli: &Cli, args: &WhoamiArgs) -> anyhow::Result<()> {
+    let mut client = hotln::LinearClient::new(proxy_url(cli)?);
+    if let Some(token) = &cli.admin_token {
+        client.with_token(token);
+    }
+    let viewer = client.viewer()?;
+    if cli.quiet {
+        return Ok(());
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&viewer)?);
+        return Ok(());
+    }
+    println!("{} <{}>", viewer.name, viewer.email);
+    println!(
+        "Organization:  {} (linear.app/{})",
+        viewer.organization, viewer.organization_key
+    );
+    println!("Teams:");
+    for team in &viewer.teams {
+        let marker = if team.configured {
+            "  (configured)"
+        } else {
+            ""
+        };
+        println!("  {:<8}{}{marker}", team.key, team.name);
+    }
+    if !viewer.projects.is_empty() {
+        println!("Projects:");
+    }
+    for project in &viewer.projects {
+        let kind = if project.feedback {
+            "feedback"
+        } else {
+            "issues"
+        };
+        let name = project.name.as_deref().unwrap_or("(not found)");
+        println!("  {kind:<10}{name}");
+    }
+    if !viewer.teams.iter().any(|team| team.configured) {
+        eprintln!("Warning: LINEAR_TEAM_ID is not one of these teams");
+    }
+    for project in viewer.projects.iter().filter(|p| p.name.is_none()) {
+        let var = if project.feedback {
+            "LINEAR_FEEDBACK_PROJECT_ID"
+        } else {
+            "LINEAR_PROJECT_ID"
+        };
+        eprintln!("Warning: {var} doesn't match a project the key can see");
+    }
+    Ok(())
+}
+
+fn search(cli: &Cli, args: &SearchArgs) -> anyhow::Result<()> {
+    let mut query = hotln::IssueQuery::new(&args.text);
+    query.limit(args.limit);
+    if let Some(state) = &args.state {
+        query.state(state);
+    }
+    for label in &args.label {
+        query.label(label);
+    }
+    let issues = Client::admin(cli, Some(args.backend), "")?.search(&query)?;
+    if cli.quiet {
+        return Ok(());
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+        return Ok(());
+    }
+    if issues.is_empty() {
+        eprintln!("No matching issues");
+        return Ok(());
+    }
+
+    let rows: Vec<[&str; 5]> = issues
+        .iter()
+        .map(|issue| {
+            [
+                issue.identifier.as_str(),
+                issue.state.as_str(),
+                issue.assignee.as_deref().unwrap_or("-"),
+                issue.updated_at.get(..10).unwrap_or(&issue.updated_at),
+                issue.title.as_str(),
+            ]
+        })
+        .collect();
+    let header = ["ID", "STATE", "ASSIGNEE", "UPDATED", "TITLE"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row[..4]
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}  {}", cells.join("  "), row[4]);
+    }
+    Ok(())
+}
+
+/// `hotln github|linear TITLE`, which scripts written before `create` still
+/// run.
+fn create_deprecated(cli: &Cli, backend: Backend, args: &ReportArgs) -> anyhow::Result<()> {
+    if !cli.quiet {
+        let name = match backend {
+            Backend::Github => "github",
+            Backend::Linear => "linear",
+        };
+        eprintln!("warning: `hotln {name}` is deprecated; use `hotln create {name}`");
+    }
+    create(cli, backend, args)
+}
 
-    if !cli.attachment.is_empty() && matches!(cli.backend, Backend::Github) {
+fn create(cli: &Cli, backend: Backend, args: &ReportArgs) -> anyhow::Result<()> {
+    if !args.attachment.is_empty() && matches!(backend, Backend::Github) {
         anyhow::bail!("--attachment is only supported with the linear backend");
     }
 
-    let system_info = system_info_text();
+    let backend = match backend {
+        Backend::Github => hotln::Backend::GitHub,
+        Backend::Linear => hotln::Backend::Linear,
+    };
+    let mut config = hotln::Config::new(backend, proxy_url(cli)?);
+    config.token = cli.proxy_token.clone();
+    config.dry_run = args.dry_run;
+    config.privacy_level = match args.privacy_level {
+        PrivacyLevel::Full => hotln::PrivacyLevel::Full,
+        PrivacyLevel::Minimal => hotln::PrivacyLevel::Minimal,
+        PrivacyLevel::Anonymous => hotln::PrivacyLevel::Anonymous,
+    };
+
+    let mut report = hotln::Report::new();
+    report.title(&args.title);
+    if let Some(desc) = &args.description {
+        report.text(desc);
+    }
+    for path_str in &args.file {
+        let (filename, content) = read_file_text(path_str)?;
+        report.file(&filename, &content);
+    }
+    for path_str in &args.attachment {
+        let (filename, data) = read_file(path_str)?;
+        report.attachment(&filename, &data);
+    }
+    let info = system_info(&args.info, &args.omit_info);
+    if !args.no_system_info && config.privacy_level.collects_context() && !info.is_empty() {
+        report.text(&system_info_text(&info));
+    }
+
+    let interactive = std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+    if !args.dry_run && (args.confirm || (interactive && !args.yes)) && !confirm(&report)? {
+        anyhow::bail!("aborted, report not sent");
+    }
+
+    let url = config.into_reporter().report(&report)?;
+    if !cli.quiet || args.dry_run {
+        println!("{}", url);
+    }
+    if !cli.quiet && !args.dry_run {
+        eprintln!("Reference: {}", report.reference);
+    }
+    if args.open {
+        open_in_browser(&url)?;
+    }
+    Ok(())
+}
+
+/// Files modified more recently than this may still be being written.
+const DUMP_SETTLE: Duration = Duration::from_secs(2);
+
+fn watch_dumps(cli: &Cli, args: &WatchDumpsArgs) -> anyhow::Result<()> {
+    if !args.dir.is_dir() {
+        anyhow::bail!("{} is not a directory", args.dir.display());
+    }
+    let mut client = Client::filing(cli, args.backend)?;
+    let spool = hotln::Spool::new(args.spool.clone().unwrap_or_else(|| args.dir.join("spool")));
+    client.spool(spool.clone());
+
+    loop {
+        match client.send_spooled() {
+            Ok(0) => {}
+            Ok(sent) => tracing::info!("sent {sent} spooled reports"),
+            Err(e) => tracing::info!("spooled reports not sent yet: {e}"),
+        }
+        // Once the spool is empty, every report in it has been filed.
+        if spool.is_empty() {
+            file_pending(&args.dir);
+        }
+        for group in dump_groups(&args.dir)? {
+            file_dump(cli, &client, &args.dir, &group);
+        }
+        if args.once {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(args.interval));
+    }
+}
+
+/// Settled files directly in `dir`, grouped by file stem so a crash
+/// handler's `<id>.json` and `<id>.dmp` become one report.
+fn dump_groups(dir: &Path) -> anyhow::Result<Vec<Vec<PathBuf>>> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("failed to read {}: {}", dir.display(), e))?;
+    let now = SystemTime::now();
+    let mut files: Vec<PathBuf> = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let settled = meta
+            .modified()
+            .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() >= DUMP_SETTLE);
+        // Temporary files are renamed into place once complete.
+        let partial = path
+            .extension()
+            .is_some_and(|ext| ext == "tmp" || ext == "part");
+        if meta.is_file() && !hidden && !partial && settled {
+            files.push(path);
+        }
+    }
+    files.sort();
+    let mut groups: Vec<Vec<PathBuf>> = Vec::new();
+    for path in files {
+        match groups.last_mut() {
+            Some(group) if group[0].file_stem() == path.file_stem() => group.push(path),
+            _ => groups.push(vec![path]),
+        }
+    }
+    Ok(groups)
+}
+
+/// File one group, then move its files to `filed/`, or to `failed/` if the
+/// report was rejected. Reports waiting in the retry queue or the spool go
+/// to `pending/` until they have been sent, and dropped reports stay where
+/// they are to be retried by the next scan.
+fn file_dump(cli: &Cli, client: &Client, dir: &Path, group: &[PathBuf]) {
+    let what = group[0].display().to_string();
+    let outcome = match dump_report(group) {
+        Ok(report) => match client.submit(&report) {
+            Err(e) if e.is_retryable() => {
+                if !cli.quiet {
+                    eprintln!("Spooled {what} ({})", report.reference);
+                }
+                Some("pending")
+            }
+            result => {
+                let outcome = match &result {
+                    Ok(hotln::Submission::Created(_) | hotln::Submission::Deduplicated(_)) => {
+                        Some("filed")
+                    }
+                    Ok(hotln::Submission::Queued(_) | hotln::Submission::Spooled(_)) => {
+                        Some("pending")
+                    }
+                    // Nothing kept the report, so the next scan retries it.
+                    Ok(_) => None,
+                    Err(_) => Some("failed"),
+                };
+                print_submission(cli, &what, result);
+                outcome
+            }
+        },
+        Err(e) => {
+            if !cli.quiet {
+                eprintln!("Failed to file {what}: {e:#}");
+            }
+            Some("failed")
+        }
+    };
+    if let Some(outcome) = outcome {
+        move_dumps(dir, group, outcome);
+    }
+}
+
+/// Move the dumps in `pending/` to `filed/`.
+fn file_pending(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir.join("pending")) else {
+        return;
+    };
+    let pending: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    if !pending.is_empty() {
+        move_dumps(dir, &pending, "filed");
+    }
+}
+
+/// Move `files` into the `outcome` subdirectory of `dir`.
+fn move_dumps(dir: &Path, files: &[PathBuf], outcome: &str) {
+    let target = dir.join(outcome);
+    for path in files {
+        let moved = std::fs::create_dir_all(&target).and_then(|()| {
+            std::fs::rename(path, target.join(path.file_name().unwrap_or_default()))
+        });
+        if let Err(e) = moved {
+            tracing::warn!("failed to move {}: {e}", path.display());
+        }
+    }
+}
+
+/// A report from a group of dump files. A `.json` file gives the title,
+/// description, severity, labels, fingerprint, and extra fields; other
+/// files are attached, or inlined if they are text.
+fn dump_report(group: &[PathBuf]) -> anyhow::Result<hotln::Report> {
+    let stem = group[0]
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut report = hotln::Report::new();
+    report
+        .title(&format!("Crash dump: {stem}"))
+        .severity(hotln::Severity::Crash);
+    for path in group {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path.display(), e))?;
+            let json: serde_json::Value = serde_json::from_str(&text)
+                .map_err(|e| anyhow::anyhow!("{} is not valid JSON: {}", path.display(), e))?;
+            apply_dump_json(&mut report, &json)?;
+        }
+    }
+    for path in group {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            continue;
+        }
+        let (filename, data) = read_file(&path.to_string_lossy())?;
+        match std::str::from_utf8(&data) {
+            Ok(text) => report.file(&filename, text),
+            Err(_) => report.attachment(&filename, &data),
+        };
+    }
+    Ok(report)
+}
+
+fn apply_dump_json(report: &mut hotln::Report, json: &serde_json::Value) -> anyhow::Result<()> {
+    let json = json
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("report JSON must be an object"))?;
+    let string = |key: &str| json.get(key).and_then(|v| v.as_str());
+    if let Some(title) = string("title") {
+        report.title(title);
+    }
+    if let Some(description) = string("description") {
+        report.text(description);
+    }
+    if let Some(severity) = string("severity") {
+        report.severity(match severity {
+            "crash" => hotln::Severity::Crash,
+            "bug" => hotln::Severity::Bug,
+            "degraded" => hotln::Severity::Degraded,
+            "feedback" => hotln::Severity::Feedback,
+            other => anyhow::bail!("unknown severity {other:?}"),
+        });
+    }
+    for label in json
+        .get("labels")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+    {
+        report.label(label);
+    }
+    if let Some(fingerprint) = string("fingerprint") {
+        report.fingerprint = Some(fingerprint.to_string());
+    }
+    if let Some(extra) = json.get("extra").and_then(|v| v.as_object()) {
+        for (key, value) in extra {
+            report.extra(key, value);
+        }
+    }
+    Ok(())
+}
+
+fn watch_log(cli: &Cli, args: &WatchLogArgs) -> anyhow::Result<()> {
+    let pattern = regex::Regex::new(&args.pattern)
+        .map_err(|e| hotln::Error::Config(format!("invalid --pattern: {e}")))?;
+    let reporter = watch_reporter(cli, &args.watch.dedup)?;
+    let name = args
+        .file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let origin = format!("Matched in `{}`", args.file.display());
+    let file = |found| file_log_match(cli, &reporter, &name, &origin, found);
 
-    let url = match cli.backend {
-        Backend::Github => {
-            let mut issue = hotln::github(&cli.proxy_url);
-            issue.title(&cli.title);
-            if let Some(token) = &cli.proxy_token {
-                issue.with_token(token);
+    let mut tail = LogTail::new(&args.file, args.from_start);
+    let mut context = LineContext::new(args.watch.before, args.watch.after);
+    loop {
+        let lines = tail.read_lines()?;
+        if lines.is_empty() {
+            // Nothing more is coming for now, so file what has waited for
+            // lines after it.
+            context.flush().into_iter().for_each(file);
+            std::thread::sleep(Duration::from_secs(args.interval));
+            continue;
+        }
+        for line in lines {
+            let matched = pattern.is_match(&line);
+            context.push(line, matched).into_iter().for_each(file);
+        }
+    }
+}
+
+fn watch_journal(cli: &Cli, args: &WatchJournalArgs) -> anyhow::Result<()> {
+    let reporter = watch_reporter(cli, &args.watch.dedup)?;
+    let mut command = std::process::Command::new("journalctl");
+    command.args(["--follow", "--output=json", "--lines=0"]);
+    for unit in &args.unit {
+        command.args(["--unit", unit]);
+    }
+    let mut child = command
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("failed to run journalctl (systemd only): {e}"))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    // Read on another thread so a quiet journal still lets waiting matches
+    // be filed.
+    let (lines, entries) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Context is kept per unit, so one service's lines don't pad another's.
+    let mut contexts: std::collections::HashMap<String, LineContext> = Default::default();
+    loop {
+        match entries.recv_timeout(Duration::from_secs(1)) {
+            Ok(json) => {
+                let Some(entry) = JournalEntry::parse(&json) else {
+                    continue;
+                };
+                let matched = entry.priority.is_some_and(|p| p <= args.priority);
+                let context = contexts
+                    .entry(entry.source.clone())
+                    .or_insert_with(|| LineContext::new(args.watch.before, args.watch.after));
+                let origin = format!("Logged by `{}`", entry.source);
+                for found in context.push(entry.message, matched) {
+                    file_log_match(cli, &reporter, &entry.source, &origin, found);
+                }
             }
-            if let Some(desc) = &cli.description {
-                issue.text(desc);
+            Err(timeout) => {
+                for (source, context) in &mut contexts {
+                    let origin = format!("Logged by `{source}`");
+                    for found in context.flush() {
+                        file_log_match(cli, &reporter, source, &origin, found);
+                    }
+                }
+                if timeout == std::sync::mpsc::RecvTimeoutError::Disconnected {
+                    let status = child.wait()?;
+                    anyhow::bail!("journalctl exited with {status}");
+                }
+            }
+        }
+    }
+}
+
+fn watch_crashes(cli: &Cli, args: &WatchCrashesArgs) -> anyhow::Result<()> {
+    let dir = match &args.dir {
+        Some(dir) => dir.clone(),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join("Library/Logs/DiagnosticReports"))
+            .ok_or_else(|| anyhow::anyhow!("HOME is not set; pass --dir"))?,
+    };
+    let reporter = watch_reporter(cli, &args.dedup)?;
+    scan_reports(&dir, &args.scan, &["ips", "crash"], |path| {
+        file_crash(cli, &reporter, &args.bundle_id, path)
+    })
+}
+
+fn watch_minidumps(cli: &Cli, args: &WatchMinidumpsArgs) -> anyhow::Result<()> {
+    let dir = match &args.dir {
+        Some(dir) => dir.clone(),
+        None => std::env::var_os("LOCALAPPDATA")
+            .map(|local| PathBuf::from(local).join("CrashDumps"))
+            .ok_or_else(|| anyhow::anyhow!("LOCALAPPDATA is not set; pass --dir"))?,
+    };
+    let reporter = watch_reporter(cli, &args.dedup)?;
+    scan_reports(&dir, &args.scan, &["dmp", "mdmp", "hdmp"], |path| {
+        file_minidump(cli, &reporter, args.process.as_deref(), path)
+    })
+}
+
+/// Call `file` with each new report in `dir` with one of `extensions`.
+/// The OS keeps these directories tidy itself, so reports are remembered
+/// rather than moved.
+fn scan_reports(
+    dir: &Path,
+    args: &ScanArgs,
+    extensions: &[&str],
+    mut file: impl FnMut(&Path),
+) -> anyhow::Result<()> {
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    if !args.existing && !args.once {
+        seen.extend(settled_files(dir, extensions)?);
+    }
+    loop {
+        for path in settled_files(dir, extensions)? {
+            if seen.insert(path.clone()) {
+                file(&path);
             }
-            for path_str in &cli.file {
-                let (filename, content) = read_file_text(path_str)?;
-                issue.file(&filename, &content);
+        }
+        if args.once {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(args.interval));
+    }
+}
+
+/// Settled files directly in `dir` with one of `extensions`.
+fn settled_files(dir: &Path, extensions: &[&str]) -> anyhow::Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("failed to read {}: {}", dir.display(), e))?;
+    let now = SystemTime::now();
+    let mut reports: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            entry.metadata().is_ok_and(|meta| {
+                meta.is_file()
+                    && meta.modified().is_ok_and(|modified| {
+                        now.duration_since(modified).unwrap_or_default() >= DUMP_SETTLE
+                    })
+            })
+        })
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| {
+                extensions
+                    .iter()
+                    .any(|wanted| ext.eq_ignore_ascii_case(wanted))
+            })
+        })
+        .collect();
+    reports.sort();
+    Ok(reports)
+}
+
+/// File the crash report at `path` if it is from `bundle_id`.
+fn file_crash(cli: &Cli, reporter: &impl Reporter, bundle_id: &str, path: &Path) {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::warn!("failed to read {}: {e}", path.display());
+            return;
+        }
+    };
+    let Some(crash) = hotln::AppleCrash::parse(&text) else {
+        tracing::info!("{} is not a crash report", path.display());
+        return;
+    };
+    if crash.bundle_id.as_deref() != Some(bundle_id) {
+        return;
+    }
+    submit_crash(cli, reporter, path, &crash.report());
+}
+
+/// File the minidump at `path` if it is from `process`, or from any
+/// process when that is `None`.
+fn file_minidump(cli: &Cli, reporter: &impl Reporter, process: Option<&str>, path: &Path) {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("failed to read {}: {e}", path.display());
+            return;
+        }
+    };
+    let Some(dump) = hotln::Minidump::parse(&data) else {
+        tracing::info!("{} is not a minidump", path.display());
+        return;
+    };
+    if let Some(process) = process
+        && !dump
+            .process
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case(process))
+    {
+        return;
+    }
+    let mut report = dump.report();
+    // Keep the dump's own name, which WER makes unique per crash.
+    if let (Some(name), Some(attachment)) = (path.file_name(), report.attachments.first_mut()) {
+        attachment.0 = name.to_string_lossy().into_owned();
+    }
+    submit_crash(cli, reporter, path, &report);
+}
+
+fn submit_crash(cli: &Cli, reporter: &impl Reporter, path: &Path, report: &hotln::Report) {
+    print_submission(cli, &path.display().to_string(), reporter.submit(report));
+}
+
+/// Print the new issue's URL, or say what happened to the report instead.
+fn print_submission(cli: &Cli, what: &str, result: Result<hotln::Submission, hotln::Error>) {
+    match result {
+        Ok(hotln::Submission::Created(url)) => {
+            if !cli.quiet {
+                println!("{url}");
             }
-            issue.text(&system_info);
-            issue.create()?
         }
-        Backend::Linear => {
-            let mut issue = hotln::linear(&cli.proxy_url);
-            issue.title(&cli.title);
-            if let Some(token) = &cli.proxy_token {
-                issue.with_token(token);
+        Ok(hotln::Submission::Deduplicated(url)) => tracing::info!("repeat of {url}"),
+        Ok(hotln::Submission::Queued(reference)) => {
+            if !cli.quiet {
+                eprintln!("Queued {what} ({reference})");
             }
-            if let Some(desc) = &cli.description {
-                issue.text(desc);
+        }
+        Ok(hotln::Submission::Spooled(reference)) => {
+            if !cli.quiet {
+                eprintln!("Spooled {what} ({reference})");
             }
-            for path_str in &cli.file {
-                let (filename, content) = read_file_text(path_str)?;
-                issue.file(&filename, &content);
+        }
+        Ok(hotln::Submission::Dropped) => {
+            if !cli.quiet {
+                eprintln!("Dropped {what}");
             }
-            for path_str in &cli.attachment {
-                let (filename, data) = read_file(path_str)?;
-                issue.attachment(&filename, &data);
+        }
+        Ok(submission) => tracing::info!("filed {what}: {submission:?}"),
+        Err(e) => {
+            if !cli.quiet {
+                eprintln!("Failed to file {what}: {e}");
             }
-            issue.text(&system_info);
-            issue.create()?
         }
+    }
+}
+
+/// The `hotln-proxy` release the generated project depends on: the first
+/// with protocol version 3 and the admin-token routes the CLI calls. Keep it
+/// in step with `hotln-proxy/package.json`.
+const PROXY_PACKAGE_VERSION: &str = "^0.3.0";
+
+fn init_proxy(cli: &Cli, args: &InitProxyArgs) -> anyhow::Result<()> {
+    let name = match &args.name {
+        Some(name) => name.clone(),
+        None => args
+            .dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "hotline-proxy".to_string()),
     };
+    let mut vars = Vec::new();
+    let mut secrets = Vec::new();
+    if let (Some(team), Some(project)) = (&args.linear_team, &args.linear_project) {
+        vars.push(("LINEAR_TEAM_ID", team.as_str()));
+        vars.push(("LINEAR_PROJECT_ID", project.as_str()));
+        secrets.push(("LINEAR_API_KEY", "a Linear API key"));
+    }
+    if let Some(repo) = &args.github_repo {
+        vars.push(("GITHUB_REPO", repo.as_str()));
+        secrets.push((
+            "GITHUB_TOKEN",
+            "a GitHub token that can create issues (or set up a GitHub App, see the hotln-proxy README)",
+        ));
+    }
+    secrets.push((
+        "HOTLINE_PROXY_TOKEN",
+        "a shared token clients must send, such as from `openssl rand -hex 32`",
+    ));
+    secrets.push((
+        "HOTLINE_ADMIN_TOKEN",
+        "a separate token for reading, commenting on, and closing issues; keep it out of apps",
+    ));
 
-    println!("{}", url);
+    let files = [
+        ("package.json", proxy_package_json(&name)),
+        ("wrangler.toml", proxy_wrangler_toml(&name, &vars)),
+        (
+            "src/index.js",
+            "// The hotline proxy: holds the tracker credentials so clients don't.\n\
+             export { default } from \"hotln-proxy\";\n"
+                .to_string(),
+        ),
+        (
+            ".gitignore",
+            "node_modules/\n.wrangler/\n.dev.vars\n".to_string(),
+        ),
+        ("README.md", proxy_readme(&name, &secrets)),
+    ];
+    if !args.force
+        && let Some((path, _)) = files.iter().find(|(path, _)| args.dir.join(path).exists())
+    {
+        anyhow::bail!(
+            "{} already exists; pass --force to replace it",
+            args.dir.join(path).display()
+        );
+    }
+    for (path, content) in &files {
+        let path = args.dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)
+            .map_err(|e| anyhow::anyhow!("writing {}: {e}", path.display()))?;
+    }
+
+    if !cli.quiet {
+        println!("Created {} in {}", name, args.dir.display());
+        println!();
+        println!("Next steps:");
+        println!("  cd {}", args.dir.display());
+        println!("  npm install");
+        for (secret, _) in &secrets {
+            println!("  npx wrangler secret put {secret}");
+        }
+        println!("  npm run deploy");
+        println!();
+        println!(
+            "Then point clients at the worker's URL with HOTLINE_PROXY_URL and HOTLINE_PROXY_TOKEN."
+        );
+        println!("Use HOTLINE_ADMIN_TOKEN for hotln status, comment, close, search, and whoami.");
+        if args.linear_team.is_some() {
+            println!("`hotln whoami` checks that the API key can see the team.");
+        }
+    }
     Ok(())
 }
+
+fn parse_repo(s: &str) -> Result<String, String> {
+    match s.split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+            Ok(s.to_string())
+        }
+        _ => Err("expected owner/repo".to_string()),
+    }
+}
+
+fn proxy_package_json(name: &str) -> String {
+    format!(
+        r#"{{
+  "name": {name:?},
+  "private": true,
+  "type": "module",
+  "scripts": {{
+    "dev": "wrangler dev",
+    "deploy": "wrangler deploy"
+  }},
+  "dependencies": {{
+    "hotln-proxy": "{PROXY_PACKAGE_VERSION}"
+  }},
+  "devDependencies": {{
+    "wrangler": "^4"
+  }}
+}}
+"#
+    )
+}
+
+fn proxy_wrangler_toml(name: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = format!(
+        "name = {name:?}\n\
+         main = \"src/index.js\"\n\
+         compatibility_date = \"2025-01-01\"\n\
+         # The GitHub App auth in hotln-proxy uses Node's crypto.\n\
+         compatibility_flags = [\"nodejs_compat\"]\n\
+         \n\
+         # Credentials are secrets, set with `npx wrangler secret put NAME`.\n\
+         [vars]\n"
+    );
+    for (key, value) in vars {
+        out.push_str(&format!("{key} = {value:?}\n"));
+    }
+    out
+}
+
+fn proxy_readme(name: &str, secrets: &[(&str, &str)]) -> String {
+    let mut out = format!(
+        "# {name}\n\n\
+         A [hotline](https://github.com/empathic/hotline) proxy on Cloudflare \
+         Workers. It holds the issue tracker credentials, so apps can file bug \
+         reports without them.\n\n\
+         ## Deploy\n\n\
+         ```sh\n\
+         npm install\n"
+    );
+    for (secret, _) in secrets {
+        out.push_str(&format!("npx wrangler secret put {secret}\n"));
+    }
+    out.push_str("npm run deploy\n```\n\n## Secrets\n\n");
+    for (secret, description) in secrets {
+        out.push_str(&format!("- `{secret}`: {description}\n"));
+    }
+    out.push_str(
+        "\nThe team, project, and repository are in `wrangler.toml`. See the \
+         [hotln-proxy README](https://www.npmjs.com/package/hotln-proxy) for \
+         the other settings, such as rate limits and feedback labels.\n",
+    );
+    out
+}
+
+/// The fields of a journal entry that go into a report.
+struct JournalEntry {
+    /// The unit, or the syslog identifier for entries outside one.
+    source: String,
+    priority: Option<u8>,
+    message: String,
+}
+
+impl JournalEntry {
+    /// Parse a line of `journalctl --output=json`. Messages that aren't
+    /// UTF-8 come as byte arrays.
+    fn parse(json: &str) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_str(json).ok()?;
+        let field = |key: &str| json.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let message = match json.get("MESSAGE")? {
+            serde_json::Value::String(message) => message.clone(),
+            serde_json::Value::Array(bytes) => {
+                let bytes: Vec<u8> = bytes
+                    .iter()
+                    .filter_map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                    .collect();
+                String::from_utf8_lossy(&bytes).into_owned()
+            }
+            _ => return None,
+        };
+        Some(Self {
+            source: field("_SYSTEMD_UNIT")
+                .or_else(|| field("SYSLOG_IDENTIFIER"))
+                .unwrap_or_else(|| "journal".to_string()),
+            priority: field("PRIORITY").and_then(|p| p.parse().ok()),
+            message,
+        })
+    }
+}
+
+fn parse_priority(s: &str) -> Result<u8, String> {
+    const NAMES: [&str; 8] = [
+        "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+    ];
+    match s.parse::<u8>() {
+        Ok(n) if n < 8 => Ok(n),
+        _ => NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(s))
+            .map(|n| n as u8)
+            .ok_or_else(|| format!("expected one of {} or 0-7", NAMES.join(", "))),
+    }
+}
+
+/// A [`hotln::Dedup`] for the configured backend, grouping reports by
+/// their fingerprint.
+fn watch_reporter(cli: &Cli, args: &DedupArgs) -> anyhow::Result<hotln::Dedup> {
+    let backend = match args.backend {
+        Backend::Github => hotln::Backend::GitHub,
+        Backend::Linear => hotln::Backend::Linear,
+    };
+    let mut config = hotln::Config::new(backend, proxy_url(cli)?);
+    // Occurrence comments need the admin token, unless the proxy allows
+    // them with the app token.
+    config.token = match &cli.admin_token {
+        Some(token) if args.comment_occurrences => Some(token.clone()),
+        _ => cli.proxy_token.clone(),
+    };
+    config.labels = args.label.clone();
+    let mut reporter = hotln::Dedup::new(config.into_reporter());
+    if let Some(secs) = args.refile_after {
+        reporter.window(Duration::from_secs(secs));
+    }
+    if args.comment_occurrences {
+        reporter.comment_occurrences();
+    }
+    if let Some(registry) = &args.registry {
+        reporter.registry(registry);
+    }
+    Ok(reporter)
+}
+
+/// A matching log line and the lines around it.
+struct LogMatch {
+    line: String,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+/// Keeps the last few lines, and holds matches back until the lines after
+/// them have arrived.
+struct LineContext {
+    before: usize,
+    after: usize,
+    recent: VecDeque<String>,
+    pending: Vec<LogMatch>,
+}
+
+impl LineContext {
+    fn new(before: usize, after: usize) -> Self {
+        Self {
+            before,
+            after,
+            recent: VecDeque::with_capacity(before + 1),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Add a line, returning the matches that are now complete.
+    fn push(&mut self, line: String, matched: bool) -> Vec<LogMatch> {
+        for found in &mut self.pending {
+            found.after.push(line.clone());
+        }
+        let (mut done, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|found| found.after.len() >= self.after);
+        self.pending = waiting;
+        if matched {
+            let found = LogMatch {
+                line: line.clone(),
+                before: self.recent.iter().cloned().collect(),
+                after: Vec::new(),
+            };
+            if self.after == 0 {
+                done.push(found);
+            } else {
+                self.pending.push(found);
+            }
+        }
+        self.recent.push_back(line);
+        if self.recent.len() > self.before {
+            self.recent.pop_front();
+        }
+        done
+    }
+
+    /// Matches still waiting for lines after them.
+    fn flush(&mut self) -> Vec<LogMatch> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// File a match as an issue titled after `source`, such as the log file's
+/// name, with `origin` saying where it was found.
+fn file_log_match(
+    cli: &Cli,
+    reporter: &impl Reporter,
+    source: &str,
+    origin: &str,
+    found: LogMatch,
+) {
+    const MAX_TITLE_CHARS: usize = 80;
+    let message = found.line.trim();
+    let mut title: String = message.chars().take(MAX_TITLE_CHARS).collect();
+    if title.len() < message.len() {
+        title.push_str("...");
+    }
+    let mut context = found.before;
+    context.push(found.line.clone());
+    context.extend(found.after);
+
+    let mut report = hotln::Report::new();
+    report
+        .title(&format!("{source}: {title}"))
+        .severity(hotln::Severity::Bug)
+        .text(&format!("{origin}:\n\n```\n{message}\n```"))
+        .file("context.log", &context.join("\n"));
+    report.fingerprint = Some(hotln::message_fingerprint(message));
+    print_submission(cli, &format!("{title:?}"), reporter.submit(&report));
+}
+
+/// Reads lines appended to a file, starting over when it is truncated or
+/// replaced by a shorter one, as by log rotation.
+struct LogTail {
+    path: PathBuf,
+    /// Where to read from next; `None` until the file is first opened.
+    offset: Option<u64>,
+    partial: String,
+}
+
+impl LogTail {
+    fn new(path: &Path, from_start: bool) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            offset: from_start.then_some(0),
+            partial: String::new(),
+        }
+    }
+
+    /// Complete lines written since the last call. A missing file has none
+    /// yet.
+    fn read_lines(&mut self) -> anyhow::Result<Vec<String>> {
+        let mut file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => anyhow::bail!("failed to open {}: {}", self.path.display(), e),
+        };
+        let len = file.metadata()?.len();
+        let offset = match self.offset {
+            Some(offset) if offset <= len => offset,
+            Some(_) => {
+                tracing::info!("{} was truncated or rotated", self.path.display());
+                self.partial.clear();
+                0
+            }
+            None => len,
+        };
+        file.seek(SeekFrom::Start(offset))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        self.offset = Some(offset + data.len() as u64);
+        self.partial.push_str(&String::from_utf8_lossy(&data));
+        let Some(end) = self.partial.rfind('\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(complete
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let code = |e: hotln::Error| exit_code(&e.into());
+        let proxy = |status| hotln::Error::Proxy {
+            status,
+            body: String::new(),
+        };
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let http = hotln::Error::Http(Box::new(ureq::Error::from(refused)));
+        assert_eq!(code(http), exit::NETWORK);
+        assert_eq!(
+            code(hotln::Error::Timeout(Duration::from_secs(5))),
+            exit::NETWORK
+        );
+        assert_eq!(code(proxy(401)), exit::AUTH);
+        assert_eq!(code(proxy(403)), exit::AUTH);
+        assert_eq!(code(proxy(429)), exit::RATE_LIMITED);
+        assert_eq!(code(hotln::Error::Config("no proxy".into())), exit::CONFIG);
+        assert_eq!(code(proxy(500)), exit::FAILURE);
+        assert_eq!(code(hotln::Error::Parse("bad json".into())), exit::FAILURE);
+        assert_eq!(
+            exit_code(&anyhow::anyhow!("unreadable file")),
+            exit::FAILURE
+        );
+        // Context added on the way up doesn't change the code.
+        let wrapped = anyhow::Error::from(proxy(429)).context("filing failed");
+        assert_eq!(exit_code(&wrapped), exit::RATE_LIMITED);
+    }
+
+    #[test]
+    fn test_parse_info() {
+        assert_eq!(
+            parse_info(" GPU = RTX 4080 "),
+            Ok(("GPU".into(), "RTX 4080".into()))
+        );
+        assert_eq!(parse_info("Build=a=b"), Ok(("Build".into(), "a=b".into())));
+        assert_eq!(parse_info("Empty="), Ok(("Empty".into(), String::new())));
+        assert_eq!(
+            parse_info("GPU"),
+            Err("expected KEY=VALUE, got \"GPU\"".into())
+        );
+        assert_eq!(
+            parse_info(" =nightly"),
+            Err("missing key in \" =nightly\"".into())
+        );
+    }
+
+    #[test]
+    fn test_system_info() {
+        let rows = system_info(&[("os".into(), "plan9".into())], &["arch".into()]);
+        let os: Vec<_> = rows
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("os"))
+            .collect();
+        assert_eq!(os, [&("OS".to_string(), "plan9".to_string())]);
+        assert!(!rows.iter().any(|(key, _)| key == "Arch"));
+    }
+
+    #[test]
+    fn test_deprecated_create() {
+        let cli =
+            Cli::try_parse_from(["hotln", "linear", "crash on startup", "-d", "details"]).unwrap();
+        match cli.command {
+            Command::Linear(args) => {
+                assert_eq!(args.title, "crash on startup");
+                assert_eq!(args.description.as_deref(), Some("details"));
+            }
+            _ => panic!("expected the linear command"),
+        }
+        let cli = Cli::try_parse_from(["hotln", "create", "github", "crash"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Create(CreateArgs {
+                backend: Backend::Github,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_line_context() {
+        let mut context = LineContext::new(2, 1);
+        assert!(context.push("a".into(), false).is_empty());
+        assert!(context.push("b".into(), false).is_empty());
+        assert!(context.push("c".into(), false).is_empty());
+        // The match waits for the line after it.
+        assert!(context.push("error".into(), true).is_empty());
+        let done = context.push("d".into(), false);
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].line, "error");
+        assert_eq!(done[0].before, ["b", "c"]);
+        assert_eq!(done[0].after, ["d"]);
+
+        // A match at the end is flushed with what came after it so far.
+        assert!(context.push("error again".into(), true).is_empty());
+        let flushed = context.flush();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].before, ["error", "d"]);
+        assert!(flushed[0].after.is_empty());
+
+        // Without lines after, matches are complete at once.
+        let mut context = LineContext::new(0, 0);
+        let done = context.push("error".into(), true);
+        assert_eq!(done.len(), 1);
+        assert!(done[0].before.is_empty());
+    }
+
+    #[test]
+    fn test_log_tail() {
+        let dir = std::env::temp_dir().join(format!("hotline-cli-tail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let _ = std::fs::remove_file(&path);
+
+        // A missing file has no lines yet, and reading starts at its end.
+        let mut tail = LogTail::new(&path, false);
+        assert!(tail.read_lines().unwrap().is_empty());
+        std::fs::write(&path, "old\n").unwrap();
+        assert!(tail.read_lines().unwrap().is_empty());
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"first\r\nsecond\npart").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), ["first", "second"]);
+        file.write_all(b"ial\n").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), ["partial"]);
+
+        // A rotated file is read from its start.
+        std::fs::write(&path, "new\n").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), ["new"]);
+
+        let mut from_start = LogTail::new(&path, true);
+        assert_eq!(from_start.read_lines().unwrap(), ["new"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_journal_entry() {
+        let entry = JournalEntry::parse(
+            r#"{"_SYSTEMD_UNIT":"app.service","SYSLOG_IDENTIFIER":"app","PRIORITY":"3","MESSAGE":"failed"}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.source, "app.service");
+        assert_eq!(entry.priority, Some(3));
+        assert_eq!(entry.message, "failed");
+
+        // Messages that aren't UTF-8 come as bytes.
+        let entry =
+            JournalEntry::parse(r#"{"SYSLOG_IDENTIFIER":"kernel","MESSAGE":[104,105,255]}"#)
+                .unwrap();
+        assert_eq!(entry.source, "kernel");
+        assert_eq!(entry.priority, None);
+        assert_eq!(entry.message, "hi\u{fffd}");
+
+        assert_eq!(
+            JournalEntry::parse(r#"{"MESSAGE":"x"}"#).unwrap().source,
+            "journal"
+        );
+        assert!(JournalEntry::parse(r#"{"PRIORITY":"3"}"#).is_none());
+        assert!(JournalEntry::parse("not json").is_none());
+    }
+
+    #[test]
+    fn test_proxy_package_version() {
+        let package: serde_json::Value =
+            serde_json::from_str(include_str!("../../hotln-proxy/package.json")).unwrap();
+        assert_eq!(
+            package["version"],
+            PROXY_PACKAGE_VERSION.trim_start_matches('^')
+        );
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        assert_eq!(parse_priority("3"), Ok(3));
+        assert_eq!(parse_priority("warning"), Ok(4));
+        assert_eq!(parse_priority("ERR"), Ok(3));
+        assert!(parse_priority("8").is_err());
+        assert!(parse_priority("loud").is_err());
+    }
+}
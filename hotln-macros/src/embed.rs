@@ -0,0 +1,157 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::path::PathBuf;
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::LitStr;
+
+const ENV_VARS: &[&str] = &[
+    "HOTLINE_PROXY_URL",
+    "HOTLINE_PROXY_TOKEN",
+    "HOTLINE_LABELS",
+    "HOTLINE_BACKEND",
+    "HOTLINE_APP_VERSION",
+];
+
+#[derive(Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    proxy_url: Option<String>,
+    token: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    backend: Option<String>,
+    app_version: Option<String>,
+}
+
+pub(crate) fn expand(path: Option<LitStr>) -> syn::Result<TokenStream2> {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+    let (file, track_file) = match &path {
+        Some(path) => {
+            let full = resolve(&path.value());
+            let text = std::fs::read_to_string(&full).map_err(|e| {
+                syn::Error::new_spanned(path, format!("reading {}: {e}", full.display()))
+            })?;
+            let config: FileConfig = toml::from_str(&text).map_err(|e| {
+                syn::Error::new_spanned(path, format!("parsing {}: {e}", full.display()))
+            })?;
+            let full = full.to_string_lossy().into_owned();
+            (
+                config,
+                Some(quote!(
+                    const _: &[u8] = ::core::include_bytes!(#full);
+                )),
+            )
+        }
+        None => (FileConfig::default(), None),
+    };
+
+    // The file wins; the environment fills in whatever it leaves out, so the
+    // token can stay out of version control.
+    let Some(proxy_url) = file.proxy_url.or_else(|| env("HOTLINE_PROXY_URL")) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "no proxy URL: set HOTLINE_PROXY_URL or `proxy_url` in the config file",
+        ));
+    };
+    let token = file.token.or_else(|| env("HOTLINE_PROXY_TOKEN"));
+    let labels = if file.labels.is_empty() {
+        env("HOTLINE_LABELS")
+            .map(|v| {
+                v.split(',')
+                    .map(|l| l.trim().to_string())
+                    .filter(|l| !l.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        file.labels
+    };
+    let backend = file.backend.or_else(|| env("HOTLINE_BACKEND"));
+    let app_version = match file.app_version.or_else(|| env("HOTLINE_APP_VERSION")) {
+        Some(version) => quote!(#version),
+        None => quote!(::core::env!("CARGO_PKG_VERSION")),
+    };
+
+    let client = match backend.as_deref() {
+        None | Some("linear") => quote!(::hotln::LinearClient),
+        Some("github") => quote!(::hotln::GitHubClient),
+        Some(other) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                format!("unknown backend {other:?}, expected \"linear\" or \"github\""),
+            ));
+        }
+    };
+    let token = token.map(|token| {
+        let key = random_key();
+        let bytes = xor(token.as_bytes(), &key);
+        quote! {
+            client.with_token(&::hotln::__private::deobfuscate(&[#(#bytes),*], &[#(#key),*]));
+        }
+    });
+
+    // Referencing the variables makes cargo rebuild when they change.
+    let env_vars = ENV_VARS;
+    Ok(quote! {{
+        #( let _ = ::core::option_env!(#env_vars); )*
+        #track_file
+        let mut client = #client::new(#proxy_url);
+        #token
+        client.labels(&[#(#labels),*]).app_version(#app_version);
+        client
+    }})
+}
+
+fn resolve(path: &str) -> PathBuf {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    PathBuf::from(dir).join(path)
+}
+
+fn random_key() -> Vec<u8> {
+    let state = RandomState::new();
+    (0u64..2)
+        .flat_map(|i| state.hash_one(i).to_le_bytes())
+        .collect()
+}
+
+fn xor(data: &[u8], key: &[u8]) -> Vec<u8> {
+    data.iter()
+        .zip(key.iter().cycle())
+        .map(|(b, k)| b ^ k)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xor_round_trip() {
+        let key = random_key();
+        assert_eq!(key.len(), 16);
+        let hidden = xor(b"secret-token", &key);
+        assert_ne!(hidden, b"secret-token");
+        assert_eq!(xor(&hidden, &key), b"secret-token");
+    }
+
+    #[test]
+    fn test_expand_from_file() {
+        let dir = std::env::temp_dir().join("hotln-macros-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("hotline.toml");
+        std::fs::write(
+            &file,
+            "proxy_url = \"https://proxy\"\nbackend = \"github\"\nlabels = [\"desktop\"]\napp_version = \"1.2.3\"\n",
+        )
+        .unwrap();
+
+        let path = LitStr::new(file.to_str().unwrap(), Span::call_site());
+        let out = expand(Some(path)).unwrap().to_string();
+        assert!(out.contains(":: hotln :: GitHubClient :: new (\"https://proxy\")"));
+        assert!(out.contains("labels (& [\"desktop\"])"));
+        assert!(out.contains("app_version (\"1.2.3\")"));
+    }
+}
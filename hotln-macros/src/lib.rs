@@ -0,0 +1,183 @@
+//! Procedural macros for [hotln](https://docs.rs/hotln). Use them through
+//! the `macros` feature of `hotln` rather than depending on this crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, ItemFn, LitStr};
+
+mod embed;
+mod system_info;
+
+/// Install a panic hook at the start of `main` that reports through a proxy.
+///
+/// ```ignore
+/// #[hotln::report_panics(proxy = "https://worker.example.com", token_env = "APP_REPORT_TOKEN")]
+/// fn main() {
+///     // ...
+/// }
+/// ```
+///
+/// `token_env` names an environment variable read at compile time, so the
+/// token is baked into the binary. `backend` is `"linear"` (the default) or
+/// `"github"`.
+#[proc_macro_attribute]
+pub fn report_panics(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut config = Config::default();
+    let parser = syn::meta::parser(|meta| config.parse(meta));
+    syn::parse_macro_input!(args with parser);
+    let item = syn::parse_macro_input!(item as ItemFn);
+    expand(config, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a client from configuration baked in at compile time.
+///
+/// ```ignore
+/// // From HOTLINE_PROXY_URL, HOTLINE_PROXY_TOKEN, HOTLINE_LABELS (comma
+/// // separated), HOTLINE_BACKEND, and HOTLINE_APP_VERSION:
+/// let client = hotln::embed_config!();
+///
+/// // From a TOML file next to Cargo.toml, with the same keys in lowercase
+/// // (`proxy_url`, `token`, `labels`, `backend`, `app_version`):
+/// let client = hotln::embed_config!("hotline.toml");
+/// ```
+///
+/// Values missing from the file fall back to the environment. The app
+/// version defaults to the calling crate's `CARGO_PKG_VERSION`. The token is
+/// XORed with a random key so it doesn't show up in `strings` output; this
+/// deters casual inspection, not a determined attacker.
+#[proc_macro]
+pub fn embed_config(input: TokenStream) -> TokenStream {
+    let path = syn::parse_macro_input!(input as Option<LitStr>);
+    embed::expand(path)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `hotln::SystemInfoSource`, turning each field into a row of
+/// the "System Info" table.
+///
+/// ```ignore
+/// #[derive(hotln::SystemInfo)]
+/// struct AppInfo {
+///     renderer: String,              // "Renderer"
+///     #[info(rename = "Save Dir")]
+///     save_dir: Option<String>,      // left out when None
+///     #[info(redact)]
+///     account_id: u64,               // a short hash instead of the value
+///     #[info(skip)]
+///     cache: Vec<u8>,
+///     #[info(flatten)]
+///     plugins: PluginInfo,           // rows of another SystemInfoSource
+/// }
+/// ```
+///
+/// Row names default to the field name in title case. Values are formatted
+/// with `Display`.
+#[proc_macro_derive(SystemInfo, attributes(info))]
+pub fn derive_system_info(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    system_info::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Config {
+    proxy: Option<LitStr>,
+    token_env: Option<LitStr>,
+    backend: Option<LitStr>,
+}
+
+impl Config {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        let slot = if meta.path.is_ident("proxy") {
+            &mut self.proxy
+        } else if meta.path.is_ident("token_env") {
+            &mut self.token_env
+        } else if meta.path.is_ident("backend") {
+            &mut self.backend
+        } else {
+            return Err(meta.error("expected `proxy`, `token_env`, or `backend`"));
+        };
+        *slot = Some(meta.value()?.parse()?);
+        Ok(())
+    }
+}
+
+fn expand(config: Config, mut item: ItemFn) -> syn::Result<TokenStream2> {
+    let Some(proxy) = config.proxy else {
+        return Err(syn::Error::new_spanned(
+            &item.sig,
+            "missing `proxy = \"https://...\"`",
+        ));
+    };
+    let client = match config.backend.as_ref().map(LitStr::value).as_deref() {
+        None | Some("linear") => quote!(::hotln::LinearClient),
+        Some("github") => quote!(::hotln::GitHubClient),
+        Some(_) => {
+            return Err(syn::Error::new_spanned(
+                config.backend,
+                "expected \"linear\" or \"github\"",
+            ));
+        }
+    };
+    let token = config.token_env.map(|var| {
+        quote! {
+            if let ::core::option::Option::Some(token) = ::core::option_env!(#var) {
+                client.with_token(token);
+            }
+        }
+    });
+
+    let body = item.block;
+    item.block = syn::parse_quote!({
+        {
+            let mut client = #client::new(#proxy);
+            #token
+            ::hotln::PanicHook::new(client).install();
+        }
+        #body
+    });
+    Ok(quote!(#item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(proxy: Option<&str>, backend: Option<&str>) -> Config {
+        let lit = |s: &str| LitStr::new(s, proc_macro2::Span::call_site());
+        Config {
+            proxy: proxy.map(lit),
+            token_env: Some(lit("APP_REPORT_TOKEN")),
+            backend: backend.map(lit),
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let item: ItemFn = syn::parse_quote!(
+            fn main() {
+                run();
+            }
+        );
+        let out = expand(config(Some("https://proxy"), None), item)
+            .unwrap()
+            .to_string();
+        assert!(out.contains(":: hotln :: LinearClient :: new (\"https://proxy\")"));
+        assert!(out.contains("option_env ! (\"APP_REPORT_TOKEN\")"));
+        assert!(out.contains("run ()"));
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let item: ItemFn = syn::parse_quote!(
+            fn main() {}
+        );
+        assert!(expand(config(None, None), item.clone()).is_err());
+        assert!(expand(config(Some("https://proxy"), Some("jira")), item).is_err());
+    }
+}
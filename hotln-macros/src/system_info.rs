@@ -0,0 +1,166 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, Type};
+
+/// How one field becomes rows.
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<LitStr>,
+    redact: bool,
+    skip: bool,
+    flatten: bool,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut out = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("info")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    out.rename = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("redact") {
+                    out.redact = true;
+                } else if meta.path.is_ident("skip") {
+                    out.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    out.flatten = true;
+                } else {
+                    return Err(meta.error("expected `rename`, `redact`, `skip`, or `flatten`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "SystemInfo can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "SystemInfo needs named fields, which name the rows",
+        ));
+    };
+
+    let mut pushes = Vec::new();
+    for field in &fields.named {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        if attrs.flatten {
+            pushes.push(quote! {
+                rows.extend(::hotln::SystemInfoSource::collect(&self.#ident));
+            });
+            continue;
+        }
+        let key = match attrs.rename {
+            Some(rename) => rename.value(),
+            None => row_name(&ident.to_string()),
+        };
+        let value = if attrs.redact {
+            quote!(::hotln::__private::hash_value(&value.to_string()))
+        } else {
+            quote!(value.to_string())
+        };
+        // Optional fields that are unset are left out, like rows the
+        // built-in collectors can't determine.
+        pushes.push(if is_option(&field.ty) {
+            quote! {
+                if let ::core::option::Option::Some(value) = &self.#ident {
+                    rows.push((#key.to_string(), #value));
+                }
+            }
+        } else {
+            quote! {
+                {
+                    let value = &self.#ident;
+                    rows.push((#key.to_string(), #value));
+                }
+            }
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::hotln::SystemInfoSource for #name #ty_generics #where_clause {
+            fn collect(&self) -> ::std::vec::Vec<(::std::string::String, ::std::string::String)> {
+                let mut rows = ::std::vec::Vec::new();
+                #(#pushes)*
+                rows
+            }
+        }
+    })
+}
+
+/// `gpu_driver` becomes "Gpu Driver".
+fn row_name(field: &str) -> String {
+    field
+        .trim_start_matches("r#")
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_option(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path)
+        if path.qself.is_none()
+            && path.path.segments.last().is_some_and(|segment| segment.ident == "Option"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Info {
+                gpu_driver: String,
+                #[info(rename = "Save Dir")]
+                save_dir: Option<String>,
+                #[info(redact)]
+                account_id: u64,
+                #[info(skip)]
+                cache: Vec<u8>,
+            }
+        };
+        let out = expand(input).unwrap().to_string();
+        assert!(out.contains("\"Gpu Driver\" . to_string ()"));
+        assert!(out.contains("\"Save Dir\""));
+        assert!(out.contains("Some (value) = & self . save_dir"));
+        assert!(out.contains("hash_value (& value . to_string ())"));
+        assert!(!out.contains("cache"));
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let tuple: DeriveInput = syn::parse_quote!(
+            struct Info(String);
+        );
+        assert!(expand(tuple).is_err());
+        let unknown: DeriveInput = syn::parse_quote! {
+            struct Info {
+                #[info(hide)]
+                a: String,
+            }
+        };
+        assert!(expand(unknown).is_err());
+    }
+}
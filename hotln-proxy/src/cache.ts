@@ -0,0 +1,83 @@
+// Remembers tracker metadata, such as label IDs, so resolving names doesn't
+// cost an API round trip on every report.
+
+// The subset of a Cloudflare KV namespace the cache uses. Any store with
+// the same shape works.
+export interface MetadataStore {
+	get(key: string): Promise<string | null>;
+	put(
+		key: string,
+		value: string,
+		options?: { expirationTtl?: number },
+	): Promise<void>;
+}
+
+export interface CacheEnv {
+	METADATA_CACHE_TTL_SECONDS?: string;
+	HOTLINE_CACHE?: MetadataStore;
+}
+
+const DEFAULT_TTL_SECONDS = 600;
+
+// KV rejects expirations shorter than a minute.
+const MIN_STORE_TTL_SECONDS = 60;
+
+// Bounds memory when clients send many distinct names.
+const MAX_ENTRIES = 1000;
+
+// Lives as long as the isolate or process, so it is shared by requests.
+const memory = new Map<string, { value: string; expires: number }>();
+
+function ttlSeconds(env: CacheEnv): number {
+	const ttl = Number(env.METADATA_CACHE_TTL_SECONDS);
+	return Number.isFinite(ttl) && env.METADATA_CACHE_TTL_SECONDS
+		? Math.max(ttl, 0)
+		: DEFAULT_TTL_SECONDS;
+}
+
+// Returns the cached value for `key`, or calls `load` and caches its result.
+// Checks memory, then the HOTLINE_CACHE store if bound. Empty strings are
+// cached too, so a name with no match isn't looked up again until it
+// expires. Errors from `load` are not cached.
+export async function cached(
+	env: CacheEnv,
+	key: string,
+	load: () => Promise<string>,
+): Promise<string> {
+	const ttl = ttlSeconds(env);
+	if (ttl === 0) return load();
+
+	const now = Date.now();
+	const hit = memory.get(key);
+	if (hit && hit.expires > now) return hit.value;
+
+	const store = env.HOTLINE_CACHE;
+	let value: string | null = null;
+	if (store) {
+		try {
+			value = await store.get(key);
+		} catch (err) {
+			console.error(`Failed to read ${key} from the metadata cache:`, err);
+		}
+	}
+	if (value === null) {
+		value = await load();
+		if (store) {
+			try {
+				await store.put(key, value, {
+					expirationTtl: Math.max(ttl, MIN_STORE_TTL_SECONDS),
+				});
+			} catch (err) {
+				console.error(`Failed to write ${key} to the metadata cache:`, err);
+			}
+		}
+	}
+	memory.delete(key);
+	if (memory.size >= MAX_ENTRIES) {
+		// Maps iterate in insertion order, so this is the oldest entry.
+		const oldest = memory.keys().next().value;
+		if (oldest !== undefined) memory.delete(oldest);
+	}
+	memory.set(key, { value, expires: now + ttl * 1000 });
+	return value;
+}
@@ -0,0 +1,92 @@
+export interface ReporterRequest {
+	name?: string;
+	email?: string;
+}
+
+export function withReporter(
+	description: string,
+	reporter?: ReporterRequest,
+): string {
+	const name = reporter?.name?.trim();
+	const email = reporter?.email?.trim();
+	if (!name && !email) return description;
+
+	const contact = name && email ? `${name} <${email}>` : (name || email);
+	const section = `## Reported by\n\n${contact}`;
+	return description ? `${description}\n\n${section}` : section;
+}
+
+// Clients send a fingerprint that is the same for every occurrence of a
+// crash, on any machine. It is recorded in the issue body, where later
+// reports of the same crash look for it. Anything but a short token is
+// ignored, since it ends up in search queries.
+export function fingerprintLine(
+	fingerprint: string | undefined,
+): string | null {
+	if (!fingerprint || !/^[\w.:-]{1,128}$/.test(fingerprint)) return null;
+	return `Fingerprint: \`${fingerprint}\``;
+}
+
+export function withFingerprint(
+	description: string,
+	fingerprint: string | undefined,
+): string {
+	const line = fingerprintLine(fingerprint);
+	if (!line) return description;
+	return description ? `${description}\n\n${line}` : line;
+}
+
+// Whether an issue body has a fingerprint line, which marks it as filed by a
+// client. Only those issues can be closed through the proxy.
+export function hasFingerprint(body: string | null | undefined): boolean {
+	return /^Fingerprint: `[\w.:-]{1,128}`$/m.test(body ?? "");
+}
+
+export type Resolution = "done" | "canceled";
+
+export function parseResolution(value: unknown): Resolution | null {
+	return value === "done" || value === "canceled" ? value : null;
+}
+
+// First line of the occurrence comments clients keep on duplicated issues.
+// Only comments starting with it can be updated through the proxy.
+export const OCCURRENCES_HEADING = "**Hotline occurrences**";
+
+export function isOccurrenceComment(body: string | undefined): boolean {
+	return body?.startsWith(OCCURRENCES_HEADING) ?? false;
+}
+
+export interface FeedbackEnv {
+	FEEDBACK_TITLE_PREFIX?: string;
+	FEEDBACK_LABELS?: string;
+}
+
+export function feedbackTitle(title: string, env: FeedbackEnv): string {
+	return `${env.FEEDBACK_TITLE_PREFIX ?? "Feedback: "}${title}`;
+}
+
+export function feedbackLabels(env: FeedbackEnv): string[] {
+	return (env.FEEDBACK_LABELS ?? "")
+		.split(",")
+		.map((label) => label.trim())
+		.filter(Boolean);
+}
+
+export interface SearchQuery {
+	text: string;
+	state?: string;
+	labels: string[];
+	limit: number;
+}
+
+const MAX_SEARCH_RESULTS = 50;
+
+export function searchQuery(params: URLSearchParams): SearchQuery {
+	const limit = Number(params.get("limit")) || 20;
+	return {
+		text: params.get("q")?.trim() ?? "",
+		state: params.get("state")?.trim() || undefined,
+		labels: params.getAll("label").filter(Boolean),
+		limit: Math.min(Math.max(limit, 1), MAX_SEARCH_RESULTS),
+	};
+}
@@ -1,6 +1,19 @@
 import { createAppAuth } from "@octokit/auth-app";
+import {
+	type FeedbackEnv,
+	type ReporterRequest,
+	type SearchQuery,
+	feedbackLabels,
+	feedbackTitle,
+	fingerprintLine,
+	hasFingerprint,
+	isOccurrenceComment,
+	parseResolution,
+	withFingerprint,
+	withReporter,
+} from "./format";
 
-export interface GitHubEnv {
+export interface GitHubEnv extends FeedbackEnv {
 	GITHUB_TOKEN?: string;
 	GITHUB_REPO?: string;
 	GITHUB_APP_ID?: string;
@@ -11,21 +24,23 @@ export interface GitHubEnv {
 interface GitHubRequest {
 	title: string;
 	description: string;
+	severity?: "crash" | "bug" | "degraded" | "feedback";
+	kind?: "bug" | "feedback";
+	labels?: string[];
+	reporter?: ReporterRequest;
+	fingerprint?: string;
 }
 
 const GITHUB_API_URL = "https://api.github.com";
 
-export async function handleGitHub(
-	request: Request,
-	env: GitHubEnv,
-): Promise<Response> {
+// Resolves the token to call GitHub with, or the error response to return
+// when the backend isn't configured or App auth fails.
+async function githubToken(env: GitHubEnv): Promise<string | Response> {
 	if (!env.GITHUB_REPO) {
 		return new Response("GitHub backend not configured: missing GITHUB_REPO", {
 			status: 500,
 		});
 	}
-
-	let token: string;
 	if (
 		env.GITHUB_APP_ID &&
 		env.GITHUB_APP_PRIVATE_KEY &&
@@ -37,15 +52,33 @@ export async function handleGitHub(
 				privateKey: env.GITHUB_APP_PRIVATE_KEY,
 				installationId: env.GITHUB_INSTALLATION_ID,
 			});
-			const { token: t } = await auth({ type: "installation" });
-			token = t;
+			const { token } = await auth({ type: "installation" });
+			return token;
 		} catch (err) {
 			return new Response(`GitHub App auth failed: ${err}`, { status: 502 });
 		}
-	} else if (env.GITHUB_TOKEN) {
-		token = env.GITHUB_TOKEN;
-	} else {
-		return new Response("GitHub backend not configured", { status: 500 });
+	}
+	if (env.GITHUB_TOKEN) {
+		return env.GITHUB_TOKEN;
+	}
+	return new Response("GitHub backend not configured", { status: 500 });
+}
+
+function githubHeaders(token: string): Record<string, string> {
+	return {
+		Authorization: `Bearer ${token}`,
+		Accept: "application/vnd.github+json",
+		"User-Agent": "hotline",
+	};
+}
+
+export async function handleGitHub(
+	request: Request,
+	env: GitHubEnv,
+): Promise<Response> {
+	const token = await githubToken(env);
+	if (token instanceof Response) {
+		return token;
 	}
 
 	let body: GitHubRequest;
@@ -59,19 +92,39 @@ export async function handleGitHub(
 		return new Response("Missing title", { status: 400 });
 	}
 
+	const existing = await openIssueWithFingerprint(
+		token,
+		env,
+		body.fingerprint,
+	);
+	if (existing) {
+		return Response.json({ url: existing, deduplicated: true });
+	}
+
+	const isFeedback = body.kind === "feedback";
+	const labels = isFeedback ? feedbackLabels(env) : [];
+	if (body.severity) {
+		labels.push(body.severity);
+	}
+	for (const label of body.labels ?? []) {
+		if (!labels.includes(label)) labels.push(label);
+	}
+
 	const resp = await fetch(
 		`${GITHUB_API_URL}/repos/${env.GITHUB_REPO}/issues`,
 		{
 			method: "POST",
 			headers: {
-				Authorization: `Bearer ${token}`,
-				Accept: "application/vnd.github+json",
-				"User-Agent": "hotline",
+				...githubHeaders(token),
 				"Content-Type": "application/json",
 			},
 			body: JSON.stringify({
-				title: body.title,
-				body: body.description,
+				title: isFeedback ? feedbackTitle(body.title, env) : body.title,
+				body: withFingerprint(
+					withReporter(body.description, body.reporter),
+					body.fingerprint,
+				),
+				...(labels.length ? { labels } : {}),
 			}),
 		},
 	);
@@ -83,8 +136,8 @@ export async function handleGitHub(
 		});
 	}
 
-	const data: any = await resp.json();
-	const url = data?.html_url;
+	const data = (await resp.json()) as { html_url?: string };
+	const url = data.html_url;
 	if (!url) {
 		return new Response(`Unexpected GitHub response: ${JSON.stringify(data)}`, {
 			status: 502,
@@ -93,3 +146,310 @@ export async function handleGitHub(
 
 	return Response.json({ url });
 }
+
+// The URL of an open issue filed for the same fingerprint, if any. Lookup
+// failures are logged and a new issue is filed instead.
+async function openIssueWithFingerprint(
+	token: string,
+	env: GitHubEnv,
+	fingerprint: string | undefined,
+): Promise<string | null> {
+	const line = fingerprintLine(fingerprint);
+	if (!line) return null;
+	const params = new URLSearchParams({
+		q: `repo:${env.GITHUB_REPO} is:issue is:open "${fingerprint}" in:body`,
+		per_page: "5",
+	});
+	try {
+		const resp = await fetch(`${GITHUB_API_URL}/search/issues?${params}`, {
+			headers: githubHeaders(token),
+		});
+		if (!resp.ok) {
+			const text = await resp.text();
+			throw new Error(`GitHub API returned ${resp.status}: ${text}`);
+		}
+		const data = (await resp.json()) as {
+			items: { html_url: string; body: string | null }[];
+		};
+		const issue = data.items.find((item) => item.body?.includes(line));
+		return issue?.html_url ?? null;
+	} catch (err) {
+		console.error("Failed to look up fingerprint:", err);
+		return null;
+	}
+}
+
+interface GitHubIssueData {
+	number: number;
+	title: string;
+	html_url: string;
+	state: string;
+	assignee: { login: string } | null;
+	updated_at: string;
+	body?: string | null;
+}
+
+// Reports an issue's state. Only issues in GITHUB_REPO can be read.
+export async function handleGitHubStatus(
+	number: string,
+	env: GitHubEnv,
+): Promise<Response> {
+	if (!/^\d+$/.test(number)) {
+		return new Response("Invalid issue number", { status: 400 });
+	}
+	const token = await githubToken(env);
+	if (token instanceof Response) {
+		return token;
+	}
+
+	const resp = await fetch(
+		`${GITHUB_API_URL}/repos/${env.GITHUB_REPO}/issues/${number}`,
+		{ headers: githubHeaders(token) },
+	);
+	if (resp.status === 404) {
+		return new Response("Issue not found", { status: 404 });
+	}
+	if (!resp.ok) {
+		const text = await resp.text();
+		return new Response(`GitHub API returned ${resp.status}: ${text}`, {
+			status: 502,
+		});
+	}
+
+	return Response.json(issueStatus((await resp.json()) as GitHubIssueData));
+}
+
+function issueStatus(issue: GitHubIssueData) {
+	return {
+		identifier: `#${issue.number}`,
+		title: issue.title,
+		url: issue.html_url,
+		state: issue.state,
+		assignee: issue.assignee?.login ?? null,
+		updatedAt: issue.updated_at,
+	};
+}
+
+// Finds issues in GITHUB_REPO with GitHub's issue search.
+export async function handleGitHubSearch(
+	query: SearchQuery,
+	env: GitHubEnv,
+): Promise<Response> {
+	const token = await githubToken(env);
+	if (token instanceof Response) {
+		return token;
+	}
+
+	const terms = [`repo:${env.GITHUB_REPO}`, "is:issue"];
+	if (query.state) terms.push(`state:${query.state}`);
+	for (const label of query.labels) {
+		terms.push(`label:${JSON.stringify(label)}`);
+	}
+	if (query.text) terms.push(query.text);
+	const params = new URLSearchParams({
+		q: terms.join(" "),
+		sort: "updated",
+		per_page: String(query.limit),
+	});
+
+	const resp = await fetch(`${GITHUB_API_URL}/search/issues?${params}`, {
+		headers: githubHeaders(token),
+	});
+	if (!resp.ok) {
+		const text = await resp.text();
+		return new Response(`GitHub API returned ${resp.status}: ${text}`, {
+			status: 502,
+		});
+	}
+
+	const data = (await resp.json()) as { items: GitHubIssueData[] };
+	return Response.json({ issues: data.items.map(issueStatus) });
+}
+
+// Adds a markdown comment to an issue and returns the comment's URL.
+export async function handleGitHubComment(
+	request: Request,
+	number: string,
+	env: GitHubEnv,
+): Promise<Response> {
+	if (!/^\d+$/.test(number)) {
+		return new Response("Invalid issue number", { status: 400 });
+	}
+	const token = await githubToken(env);
+	if (token instanceof Response) {
+		return token;
+	}
+
+	let body: { body?: string };
+	try {
+		body = (await request.json()) as { body?: string };
+	} catch {
+		return new Response("Invalid JSON", { status: 400 });
+	}
+	if (!body.body) {
+		return new Response("Missing body", { status: 400 });
+	}
+
+	const resp = await fetch(
+		`${GITHUB_API_URL}/repos/${env.GITHUB_REPO}/issues/${number}/comments`,
+		{
+			method: "POST",
+			headers: {
+				...githubHeaders(token),
+				"Content-Type": "application/json",
+			},
+			body: JSON.stringify({ body: body.body }),
+		},
+	);
+	if (resp.status === 404) {
+		return new Response("Issue not found", { status: 404 });
+	}
+	if (!resp.ok) {
+		const text = await resp.text();
+		return new Response(`GitHub API returned ${resp.status}: ${text}`, {
+			status: 502,
+		});
+	}
+
+	const data = (await resp.json()) as { html_url: string };
+	return Response.json({ url: data.html_url });
+}
+
+// Replaces the body of an occurrence comment on an issue. Other comments
+// can't be edited, so a proxy token can't rewrite what people wrote.
+export async function handleGitHubCommentUpdate(
+	request: Request,
+	number: string,
+	commentId: string,
+	env: GitHubEnv,
+): Promise<Response> {
+	if (!/^\d+$/.test(number) || !/^\d+$/.test(commentId)) {
+		return new Response("Invalid issue or comment number", { status: 400 });
+	}
+	const token = await githubToken(env);
+	if (token instanceof Response) {
+		return token;
+	}
+
+	let body: { body?: string };
+	try {
+		body = (await request.json()) as { body?: string };
+	} catch {
+		return new Response("Invalid JSON", { status: 400 });
+	}
+	if (!isOccurrenceComment(body.body)) {
+		return new Response("Only occurrence comments can be updated", {
+			status: 400,
+		});
+	}
+
+	const commentUrl = `${GITHUB_API_URL}/repos/${env.GITHUB_REPO}/issues/comments/${commentId}`;
+	const existing = await fetch(commentUrl, { headers: githubHeaders(token) });
+	if (existing.status === 404) {
+		return new Response("Comment not found", { status: 404 });
+	}
+	if (!existing.ok) {
+		const text = await existing.text();
+		return new Response(`GitHub API returned ${existing.status}: ${text}`, {
+			status: 502,
+		});
+	}
+	const comment = (await existing.json()) as {
+		body: string;
+		issue_url: string;
+	};
+	if (!comment.issue_url.endsWith(`/issues/${number}`)) {
+		return new Response("Comment not found", { status: 404 });
+	}
+	if (!isOccurrenceComment(comment.body)) {
+		return new Response("Only occurrence comments can be updated", {
+			status: 403,
+		});
+	}
+
+	const resp = await fetch(commentUrl, {
+		method: "PATCH",
+		headers: {
+			...githubHeaders(token),
+			"Content-Type": "application/json",
+		},
+		body: JSON.stringify({ body: body.body }),
+	});
+	if (!resp.ok) {
+		const text = await resp.text();
+		return new Response(`GitHub API returned ${resp.status}: ${text}`, {
+			status: 502,
+		});
+	}
+
+	const data = (await resp.json()) as { html_url: string };
+	return Response.json({ url: data.html_url });
+}
+
+// Closes an issue as completed or not planned. Only issues filed with a
+// fingerprint can be closed, so clients can't close issues people filed by
+// hand.
+export async function handleGitHubClose(
+	request: Request,
+	number: string,
+	env: GitHubEnv,
+): Promise<Response> {
+	if (!/^\d+$/.test(number)) {
+		return new Response("Invalid issue number", { status: 400 });
+	}
+	const token = await githubToken(env);
+	if (token instanceof Response) {
+		return token;
+	}
+
+	let body: { resolution?: unknown };
+	try {
+		body = (await request.json()) as { resolution?: unknown };
+	} catch {
+		return new Response("Invalid JSON", { status: 400 });
+	}
+	const resolution = parseResolution(body.resolution);
+	if (!resolution) {
+		return new Response("Resolution must be done or canceled", {
+			status: 400,
+		});
+	}
+
+	const issueUrl = `${GITHUB_API_URL}/repos/${env.GITHUB_REPO}/issues/${number}`;
+	const existing = await fetch(issueUrl, { headers: githubHeaders(token) });
+	if (existing.status === 404) {
+		return new Response("Issue not found", { status: 404 });
+	}
+	if (!existing.ok) {
+		const text = await existing.text();
+		return new Response(`GitHub API returned ${existing.status}: ${text}`, {
+			status: 502,
+		});
+	}
+	const issue = (await existing.json()) as GitHubIssueData;
+	if (!hasFingerprint(issue.body)) {
+		return new Response("Only issues filed with a fingerprint can be closed", {
+			status: 403,
+		});
+	}
+
+	const resp = await fetch(issueUrl, {
+		method: "PATCH",
+		headers: {
+			...githubHeaders(token),
+			"Content-Type": "application/json",
+		},
+		body: JSON.stringify({
+			state: "closed",
+			state_reason: resolution === "done" ? "completed" : "not_planned",
+		}),
+	});
+	if (!resp.ok) {
+		const text = await resp.text();
+		return new Response(`GitHub API returned ${resp.status}: ${text}`, {
+			status: 502,
+		});
+	}
+
+	return Response.json(issueStatus((await resp.json()) as GitHubIssueData));
+}
@@ -1,24 +1,77 @@
-import { handleGitHub } from "./github";
-import { handleLinear } from "./linear";
+import type { MetadataStore } from "./cache";
+import { isOccurrenceComment, searchQuery } from "./format";
+import {
+	handleGitHub,
+	handleGitHubClose,
+	handleGitHubComment,
+	handleGitHubCommentUpdate,
+	handleGitHubSearch,
+	handleGitHubStatus,
+} from "./github";
+import {
+	handleLinear,
+	handleLinearClose,
+	handleLinearComment,
+	handleLinearCommentUpdate,
+	handleLinearSearch,
+	handleLinearStatus,
+	handleLinearUpload,
+	handleLinearViewer,
+	LINEAR_EXPOSED_HEADERS,
+} from "./linear";
 
-export { handleGitHub, type GitHubEnv } from "./github";
-export { handleLinear, type LinearEnv } from "./linear";
+export type { MetadataStore } from "./cache";
+export {
+	handleGitHub,
+	handleGitHubClose,
+	handleGitHubComment,
+	handleGitHubCommentUpdate,
+	handleGitHubSearch,
+	handleGitHubStatus,
+	type GitHubEnv,
+} from "./github";
+export {
+	handleLinear,
+	handleLinearClose,
+	handleLinearComment,
+	handleLinearCommentUpdate,
+	handleLinearSearch,
+	handleLinearStatus,
+	handleLinearUpload,
+	handleLinearViewer,
+	type LinearEnv,
+} from "./linear";
 
 export interface Env {
 	LINEAR_API_KEY?: string;
+	LINEAR_OAUTH_TOKEN?: string;
 	LINEAR_TEAM_ID?: string;
 	LINEAR_PROJECT_ID?: string;
+	LINEAR_FEEDBACK_PROJECT_ID?: string;
+	LINEAR_CREATE_AS_USER?: string;
+	LINEAR_DISPLAY_ICON_URL?: string;
 	GITHUB_TOKEN?: string;
 	GITHUB_REPO?: string;
 	GITHUB_APP_ID?: string;
 	GITHUB_APP_PRIVATE_KEY?: string;
 	GITHUB_INSTALLATION_ID?: string;
+	FEEDBACK_TITLE_PREFIX?: string;
+	FEEDBACK_LABELS?: string;
 	HOTLINE_PROXY_TOKEN?: string;
+	HOTLINE_ADMIN_TOKEN?: string;
+	ALLOW_OCCURRENCE_COMMENTS?: string;
 	RATE_LIMIT_MAX?: string;
 	RATE_LIMIT_WINDOW_MS?: string;
 	CORS_ORIGIN?: string;
+	METADATA_CACHE_TTL_SECONDS?: string;
+	HOTLINE_CACHE?: MetadataStore;
 }
 
+// Version 2 added severity, kind, labels, customer, and reporter, and version
+// 3 added links. Clients probe with OPTIONS and fold those into the
+// description for older workers.
+const PROTOCOL_VERSION = "3";
+
 const hits = new Map<string, number[]>();
 
 function isRateLimited(ip: string, max: number, windowMs: number): boolean {
@@ -48,17 +101,79 @@ function clientIp(request: Request): string | null {
 	);
 }
 
+// Routes that read or change existing issues, or say who the tracker key
+// belongs to. Apps ship HOTLINE_PROXY_TOKEN inside their binaries, so these
+// need HOTLINE_ADMIN_TOKEN instead, and are off when it isn't set.
+function isAdminRoute(url: URL): boolean {
+	return (
+		url.pathname.split("/")[2] === "issues" || url.pathname === "/linear/viewer"
+	);
+}
+
+// Whether the request posts or updates an occurrence comment, which apps
+// send through Dedup. Only allowed with the app token when
+// ALLOW_OCCURRENCE_COMMENTS is "true".
+async function isAllowedOccurrenceComment(
+	request: Request,
+	url: URL,
+	env: Env,
+): Promise<boolean> {
+	if (env.ALLOW_OCCURRENCE_COMMENTS !== "true" || request.method !== "POST") {
+		return false;
+	}
+	const [, , , id, comments, ...rest] = url.pathname.split("/");
+	if (!id || comments !== "comments" || rest.length > 1) return false;
+	try {
+		const body = (await request.clone().json()) as { body?: string };
+		return isOccurrenceComment(body.body);
+	} catch {
+		return false;
+	}
+}
+
+// Returns the response to refuse the request with, or null to go ahead. The
+// admin token works everywhere; the app token only creates issues and
+// uploads files.
+async function authorize(
+	request: Request,
+	url: URL,
+	env: Env,
+): Promise<Response | null> {
+	const authHeader = request.headers.get("Authorization");
+	if (
+		env.HOTLINE_ADMIN_TOKEN &&
+		authHeader === `Bearer ${env.HOTLINE_ADMIN_TOKEN}`
+	) {
+		return null;
+	}
+	if (
+		env.HOTLINE_PROXY_TOKEN &&
+		authHeader !== `Bearer ${env.HOTLINE_PROXY_TOKEN}`
+	) {
+		return new Response("Unauthorized", { status: 401 });
+	}
+	if (
+		!isAdminRoute(url) ||
+		(await isAllowedOccurrenceComment(request, url, env))
+	) {
+		return null;
+	}
+	if (!env.HOTLINE_ADMIN_TOKEN) {
+		return new Response("Set HOTLINE_ADMIN_TOKEN to enable this route", {
+			status: 403,
+		});
+	}
+	return new Response("Unauthorized", { status: 401 });
+}
+
 async function handleRequest(request: Request, env: Env): Promise<Response> {
-	if (request.method !== "POST") {
+	if (request.method !== "POST" && request.method !== "GET") {
 		return new Response("Method not allowed", { status: 405 });
 	}
 
-	if (env.HOTLINE_PROXY_TOKEN) {
-		const authHeader = request.headers.get("Authorization");
-		if (!authHeader || authHeader !== `Bearer ${env.HOTLINE_PROXY_TOKEN}`) {
-			return new Response("Unauthorized", { status: 401 });
-		}
-	}
+	const url = new URL(request.url);
+	const refused = await authorize(request, url, env);
+	if (refused) return refused;
 
 	const ip = clientIp(request);
 	const max = Number(env.RATE_LIMIT_MAX) || 5;
@@ -67,11 +182,21 @@ async function handleRequest(request: Request, env: Env): Promise<Response> {
 		return new Response("Rate limit exceeded", { status: 429 });
 	}
 
-	const url = new URL(request.url);
+	if (url.pathname.split("/")[2] === "issues") {
+		return handleIssue(request, url, env);
+	}
+	if (request.method === "GET" && url.pathname === "/linear/viewer") {
+		return handleLinearViewer(env);
+	}
+	if (request.method !== "POST") {
+		return new Response("Method not allowed", { status: 405 });
+	}
 	switch (url.pathname) {
 		case "/": // for backwards compatibility with v0.1
 		case "/linear":
 			return handleLinear(request, env);
+		case "/linear/uploads":
+			return handleLinearUpload(request, env);
 		case "/github":
 			return handleGitHub(request, env);
 		default:
@@ -79,6 +204,71 @@ async function handleRequest(request: Request, env: Env): Promise<Response> {
 	}
 }
 
+// GET /{backend}/issues, GET /{backend}/issues/{id},
+// POST /{backend}/issues/{id}/comments,
+// POST /{backend}/issues/{id}/comments/{commentId}, and
+// POST /{backend}/issues/{id}/close
+async function handleIssue(
+	request: Request,
+	url: URL,
+	env: Env,
+): Promise<Response> {
+	const [, backend, , id, ...rest] = url.pathname.split("/");
+	if (!id && request.method === "GET") {
+		const query = searchQuery(url.searchParams);
+		switch (backend) {
+			case "linear":
+				return handleLinearSearch(query, env);
+			case "github":
+				return handleGitHubSearch(query, env);
+		}
+		return new Response("Not found", { status: 404 });
+	}
+	const issue = decodeURIComponent(id ?? "");
+	if (request.method === "POST" && rest[0] === "comments" && rest[1]) {
+		const comment = decodeURIComponent(rest[1]);
+		if (rest.length === 2) {
+			switch (backend) {
+				case "linear":
+					return handleLinearCommentUpdate(request, issue, comment, env);
+				case "github":
+					return handleGitHubCommentUpdate(request, issue, comment, env);
+			}
+		}
+		return new Response("Not found", { status: 404 });
+	}
+	const route = `${request.method} ${rest.join("/")}`;
+	if (
+		!id ||
+		(route !== "GET " && route !== "POST comments" && route !== "POST close")
+	) {
+		return new Response("Not found", { status: 404 });
+	}
+	if (route === "GET ") {
+		switch (backend) {
+			case "linear":
+				return handleLinearStatus(issue, env);
+			case "github":
+				return handleGitHubStatus(issue, env);
+		}
+	} else if (route === "POST close") {
+		switch (backend) {
+			case "linear":
+				return handleLinearClose(request, issue, env);
+			case "github":
+				return handleGitHubClose(request, issue, env);
+		}
+	} else {
+		switch (backend) {
+			case "linear":
+				return handleLinearComment(request, issue, env);
+			case "github":
+				return handleGitHubComment(request, issue, env);
+		}
+	}
+	return new Response("Not found", { status: 404 });
+}
+
 export default {
 	async fetch(request: Request, platformEnv?: Env): Promise<Response> {
 		const env = resolveEnv(platformEnv);
@@ -89,15 +279,22 @@ export default {
 				status: 204,
 				headers: {
 					"Access-Control-Allow-Origin": origin,
-					"Access-Control-Allow-Methods": "POST",
-					"Access-Control-Allow-Headers": "Content-Type, Authorization",
+					"Access-Control-Allow-Methods": "GET, POST",
+					"Access-Control-Allow-Headers":
+						"Content-Type, Authorization, Hotline-Protocol",
 					"Access-Control-Max-Age": "86400",
+					"Hotline-Protocol": PROTOCOL_VERSION,
 				},
 			});
 		}
 
 		const response = await handleRequest(request, env);
 		response.headers.set("Access-Control-Allow-Origin", origin);
+		response.headers.set(
+			"Access-Control-Expose-Headers",
+			`Hotline-Protocol, ${LINEAR_EXPOSED_HEADERS}`,
+		);
+		response.headers.set("Hotline-Protocol", PROTOCOL_VERSION);
 		return response;
 	},
 };
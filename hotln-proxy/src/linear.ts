@@ -1,7 +1,26 @@
-export interface LinearEnv {
+import { type CacheEnv, cached } from "./cache";
+import {
+	type FeedbackEnv,
+	type ReporterRequest,
+	type SearchQuery,
+	feedbackLabels,
+	feedbackTitle,
+	fingerprintLine,
+	hasFingerprint,
+	isOccurrenceComment,
+	parseResolution,
+	withFingerprint,
+	withReporter,
+} from "./format";
+
+export interface LinearEnv extends FeedbackEnv, CacheEnv {
 	LINEAR_API_KEY?: string;
+	LINEAR_OAUTH_TOKEN?: string;
 	LINEAR_TEAM_ID?: string;
 	LINEAR_PROJECT_ID?: string;
+	LINEAR_FEEDBACK_PROJECT_ID?: string;
+	LINEAR_CREATE_AS_USER?: string;
+	LINEAR_DISPLAY_ICON_URL?: string;
 }
 
 interface AttachmentRequest {
@@ -11,19 +30,211 @@ interface AttachmentRequest {
 	encoding?: "text" | "base64";
 }
 
+interface LinkRequest {
+	url: string;
+	title: string;
+	subtitle?: string;
+}
+
+interface CustomerRequest {
+	externalId?: string;
+	email?: string;
+}
+
+type Severity = "crash" | "bug" | "degraded" | "feedback";
+
 interface LinearRequest {
 	title: string;
 	description: string;
+	severity?: Severity;
+	kind?: "bug" | "feedback";
+	labels?: string[];
 	attachments?: AttachmentRequest[];
+	links?: LinkRequest[];
+	customer?: CustomerRequest;
+	reporter?: ReporterRequest;
+	fingerprint?: string;
 }
 
 const LINEAR_API_URL = "https://api.linear.app/graphql";
 
+// Linear priorities: 1 = Urgent, 2 = High, 3 = Medium, 4 = Low.
+const SEVERITY_PRIORITY: Record<Severity, number> = {
+	crash: 1,
+	bug: 2,
+	degraded: 3,
+	feedback: 4,
+};
+
+interface IssueCreateData {
+	issueCreate: { success: boolean; issue: { id: string; url: string } | null };
+}
+
+interface IssueFields {
+	identifier: string;
+	title: string;
+	url: string;
+	updatedAt: string;
+	state: { name: string };
+	assignee: { name: string } | null;
+}
+
+interface IssueData {
+	issue:
+		| (IssueFields & {
+				id: string;
+				description: string | null;
+				team: { id: string };
+		  })
+		| null;
+}
+
+interface WorkflowStatesData {
+	workflowStates: { nodes: { id: string; position: number }[] };
+}
+
+interface IssueUpdateData {
+	issueUpdate: { success: boolean; issue: IssueFields | null };
+}
+
+interface ViewerData {
+	viewer: { name: string; email: string };
+	organization: { name: string; urlKey: string };
+	teams: { nodes: { id: string; key: string; name: string }[] };
+}
+
+interface ProjectData {
+	project: { name: string } | null;
+}
+
+interface IssuesData {
+	issues: {
+		nodes: {
+			identifier: string;
+			title: string;
+			url: string;
+			updatedAt: string;
+			state: { name: string };
+			assignee: { name: string } | null;
+		}[];
+	};
+}
+
+interface CommentCreateData {
+	commentCreate: { success: boolean; comment: { url: string } | null };
+}
+
+interface CommentData {
+	comment: { id: string; body: string; issue: { id: string } | null } | null;
+}
+
+interface CommentUpdateData {
+	commentUpdate: { success: boolean; comment: { url: string } | null };
+}
+
+interface IssueLabelsData {
+	issueLabels: { nodes: { id: string }[] };
+}
+
+interface CustomersData {
+	customers: { nodes: { id: string }[] };
+}
+
+interface CustomerNeedCreateData {
+	customerNeedCreate: { success: boolean };
+}
+
+interface FileUploadData {
+	fileUpload: {
+		uploadFile: {
+			uploadUrl: string;
+			assetUrl: string;
+			headers: { key: string; value: string }[];
+		} | null;
+	};
+}
+
+interface AttachmentCreateData {
+	attachmentCreate: { success: boolean };
+}
+
+class LinearApiError extends Error {}
+
+// OAuth access tokens use the Bearer scheme; personal API keys are sent as-is.
+function authorization(env: LinearEnv): string | undefined {
+	if (env.LINEAR_OAUTH_TOKEN) return `Bearer ${env.LINEAR_OAUTH_TOKEN}`;
+	return env.LINEAR_API_KEY;
+}
+
+// Linear's request ID and rate-limit headers, passed back to the client so
+// failed submissions can be matched to Linear's logs.
+const TRACE_HEADERS = [
+	"x-request-id",
+	"x-ratelimit-requests-limit",
+	"x-ratelimit-requests-remaining",
+	"x-ratelimit-requests-reset",
+	"x-ratelimit-complexity-limit",
+	"x-ratelimit-complexity-remaining",
+	"x-ratelimit-complexity-reset",
+];
+
+export const LINEAR_EXPOSED_HEADERS = TRACE_HEADERS.join(", ");
+
+// Runs a GraphQL operation and returns its `data`, throwing LinearApiError
+// on HTTP failures, GraphQL errors, or a response without `data`. Linear's
+// trace headers are copied into `trace` when given.
+async function graphql<T>(
+	auth: string,
+	query: string,
+	variables: Record<string, unknown>,
+	trace?: Headers,
+): Promise<T> {
+	const resp = await fetch(LINEAR_API_URL, {
+		method: "POST",
+		headers: {
+			Authorization: auth,
+			"Content-Type": "application/json",
+		},
+		body: JSON.stringify({ query, variables }),
+	});
+	for (const name of TRACE_HEADERS) {
+		const value = resp.headers.get(name);
+		if (trace && value !== null) trace.set(name, value);
+	}
+
+	if (!resp.ok) {
+		const text = await resp.text();
+		throw new LinearApiError(`Linear API returned ${resp.status}: ${text}`);
+	}
+
+	const json = (await resp.json()) as { data?: T; errors?: unknown };
+	if (json.errors) {
+		throw new LinearApiError(
+			`Linear GraphQL errors: ${JSON.stringify(json.errors)}`,
+		);
+	}
+	if (!json.data) {
+		throw new LinearApiError(
+			`Unexpected Linear response: missing data in ${JSON.stringify(json)}`,
+		);
+	}
+	return json.data;
+}
+
+// Narrows a nullable field, naming its path in the error when it is missing.
+function required<T>(value: T | null | undefined, path: string): T {
+	if (value === null || value === undefined) {
+		throw new LinearApiError(`Unexpected Linear response: missing ${path}`);
+	}
+	return value;
+}
+
 export async function handleLinear(
 	request: Request,
 	env: LinearEnv,
 ): Promise<Response> {
-	if (!env.LINEAR_API_KEY || !env.LINEAR_TEAM_ID || !env.LINEAR_PROJECT_ID) {
+	const auth = authorization(env);
+	if (!auth || !env.LINEAR_TEAM_ID || !env.LINEAR_PROJECT_ID) {
 		return new Response("Linear backend not configured", { status: 500 });
 	}
 
@@ -38,6 +249,15 @@ export async function handleLinear(
 		return new Response("Missing title", { status: 400 });
 	}
 
+	const existing = await openIssueWithFingerprint(
+		auth,
+		env.LINEAR_TEAM_ID,
+		body.fingerprint,
+	);
+	if (existing) {
+		return Response.json({ url: existing, deduplicated: true });
+	}
+
 	const query = `mutation IssueCreate($input: IssueCreateInput!) {
 		issueCreate(input: $input) {
 			success
@@ -45,108 +265,696 @@ export async function handleLinear(
 		}
 	}`;
 
-	const resp = await fetch(LINEAR_API_URL, {
-		method: "POST",
-		headers: {
-			Authorization: env.LINEAR_API_KEY,
-			"Content-Type": "application/json",
-		},
-		body: JSON.stringify({
+	const isFeedback = body.kind === "feedback";
+	const input: Record<string, unknown> = {
+		teamId: env.LINEAR_TEAM_ID,
+		projectId: isFeedback
+			? (env.LINEAR_FEEDBACK_PROJECT_ID ?? env.LINEAR_PROJECT_ID)
+			: env.LINEAR_PROJECT_ID,
+		title: isFeedback ? feedbackTitle(body.title, env) : body.title,
+		description: withFingerprint(
+			withReporter(body.description, body.reporter),
+			body.fingerprint,
+		),
+	};
+	// Only honored by Linear for OAuth apps authorized with actor=application.
+	if (env.LINEAR_CREATE_AS_USER) {
+		input.createAsUser = env.LINEAR_CREATE_AS_USER;
+	}
+	if (env.LINEAR_DISPLAY_ICON_URL) {
+		input.displayIconUrl = env.LINEAR_DISPLAY_ICON_URL;
+	}
+	const labels = isFeedback ? feedbackLabels(env) : [];
+	if (body.severity && body.severity in SEVERITY_PRIORITY) {
+		input.priority = SEVERITY_PRIORITY[body.severity];
+		labels.push(body.severity);
+	}
+	for (const label of body.labels ?? []) {
+		if (!labels.includes(label)) labels.push(label);
+	}
+	if (labels.length) {
+		const labelIds = await resolveLabelIds(auth, env, labels);
+		if (labelIds.length) {
+			input.labelIds = labelIds;
+		}
+	}
+
+	let issueId: string;
+	let url: string;
+	const trace = new Headers();
+	try {
+		const data = await graphql<IssueCreateData>(
+			auth,
 			query,
-			variables: {
-				input: {
-					teamId: env.LINEAR_TEAM_ID,
-					projectId: env.LINEAR_PROJECT_ID,
-					title: body.title,
-					description: body.description,
+			{ input },
+			trace,
+		);
+		const issue = required(data.issueCreate?.issue, "issueCreate.issue");
+		issueId = required(issue.id, "issueCreate.issue.id");
+		url = required(issue.url, "issueCreate.issue.url");
+	} catch (err) {
+		if (err instanceof LinearApiError) {
+			return new Response(err.message, { status: 502, headers: trace });
+		}
+		throw err;
+	}
+
+	if (body.attachments?.length) {
+		for (const att of body.attachments) {
+			try {
+				await uploadAttachment(auth, issueId, att);
+			} catch (err) {
+				console.error(`Failed to attach ${att.filename}:`, err);
+			}
+		}
+	}
+
+	for (const link of body.links ?? []) {
+		try {
+			await createAttachment(auth, issueId, link);
+		} catch (err) {
+			console.error(`Failed to link ${link.url}:`, err);
+		}
+	}
+
+	if (body.customer) {
+		try {
+			await linkCustomer(auth, env, issueId, body.customer);
+		} catch (err) {
+			console.error("Failed to link customer:", err);
+		}
+	}
+
+	return Response.json({ url }, { headers: trace });
+}
+
+// The URL of an open issue in the team filed for the same fingerprint, if
+// any. Lookup failures are logged and a new issue is filed instead.
+async function openIssueWithFingerprint(
+	auth: string,
+	teamId: string,
+	fingerprint: string | undefined,
+): Promise<string | null> {
+	const line = fingerprintLine(fingerprint);
+	if (!line) return null;
+	try {
+		const data = await graphql<{ issues: { nodes: { url: string }[] } }>(
+			auth,
+			`query Issues($filter: IssueFilter!) {
+				issues(filter: $filter, first: 1) {
+					nodes { url }
+				}
+			}`,
+			{
+				filter: {
+					team: { id: { eq: teamId } },
+					description: { contains: line },
+					state: { type: { nin: ["completed", "canceled"] } },
 				},
 			},
-		}),
+		);
+		return data.issues.nodes[0]?.url ?? null;
+	} catch (err) {
+		console.error("Failed to look up fingerprint:", err);
+		return null;
+	}
+}
+
+// Looks up an issue by identifier (ENG-123) or ID. Issues outside
+// LINEAR_TEAM_ID come back as null, so the proxy can't read other teams.
+async function teamIssue(
+	auth: string,
+	id: string,
+	teamId: string,
+): Promise<IssueData["issue"]> {
+	const data = await graphql<IssueData>(
+		auth,
+		`query Issue($id: String!) {
+			issue(id: $id) {
+				id identifier title url updatedAt description
+				team { id }
+				state { name }
+				assignee { name }
+			}
+		}`,
+		{ id },
+	);
+	return data.issue?.team.id === teamId ? data.issue : null;
+}
+
+// Reports who the configured key belongs to, which teams it can see, and
+// whether the configured projects exist, so a misconfigured key, team, or
+// project shows up before anything is filed.
+export async function handleLinearViewer(env: LinearEnv): Promise<Response> {
+	const auth = authorization(env);
+	if (!auth) {
+		return new Response("Linear backend not configured", { status: 500 });
+	}
+
+	let data: ViewerData;
+	try {
+		data = await graphql<ViewerData>(
+			auth,
+			`query Viewer {
+				viewer { name email }
+				organization { name urlKey }
+				teams { nodes { id key name } }
+			}`,
+			{},
+		);
+	} catch (err) {
+		if (err instanceof LinearApiError) {
+			return new Response(err.message, { status: 502 });
+		}
+		throw err;
+	}
+
+	const projects: { name: string | null; feedback: boolean }[] = [];
+	if (env.LINEAR_PROJECT_ID) {
+		projects.push({
+			name: await projectName(auth, env.LINEAR_PROJECT_ID),
+			feedback: false,
+		});
+	}
+	if (env.LINEAR_FEEDBACK_PROJECT_ID) {
+		projects.push({
+			name: await projectName(auth, env.LINEAR_FEEDBACK_PROJECT_ID),
+			feedback: true,
+		});
+	}
+
+	return Response.json({
+		name: data.viewer.name,
+		email: data.viewer.email,
+		organization: data.organization.name,
+		organizationKey: data.organization.urlKey,
+		teams: data.teams.nodes.map((team) => ({
+			key: team.key,
+			name: team.name,
+			configured: team.id === env.LINEAR_TEAM_ID,
+		})),
 	});
+}
 
-	if (!resp.ok) {
-		const text = await resp.text();
-		return new Response(`Linear API returned ${resp.status}: ${text}`, {
-			status: 502,
+// The name of a project, or null if it doesn't exist or the key can't see
+// it. Linear answers an unknown ID with a GraphQL error rather than null.
+async function projectName(auth: string, id: string): Promise<string | null> {
+	try {
+		const data = await graphql<ProjectData>(
+			auth,
+			`query Project($id: String!) {
+				project(id: $id) { name }
+			}`,
+			{ id },
+		);
+		return data.project?.name ?? null;
+	} catch (err) {
+		if (err instanceof LinearApiError) return null;
+		throw err;
+	}
+}
+
+// Reports an issue's state.
+export async function handleLinearStatus(
+	id: string,
+	env: LinearEnv,
+): Promise<Response> {
+	const auth = authorization(env);
+	if (!auth || !env.LINEAR_TEAM_ID) {
+		return new Response("Linear backend not configured", { status: 500 });
+	}
+
+	let issue: IssueData["issue"];
+	try {
+		issue = await teamIssue(auth, id, env.LINEAR_TEAM_ID);
+	} catch (err) {
+		if (err instanceof LinearApiError) {
+			return new Response(err.message, { status: 502 });
+		}
+		throw err;
+	}
+	if (!issue) {
+		return new Response("Issue not found", { status: 404 });
+	}
+
+	return Response.json(issueStatus(issue));
+}
+
+function issueStatus(issue: IssueFields) {
+	return {
+		identifier: issue.identifier,
+		title: issue.title,
+		url: issue.url,
+		state: issue.state.name,
+		assignee: issue.assignee?.name ?? null,
+		updatedAt: issue.updatedAt,
+	};
+}
+
+// Finds issues in LINEAR_TEAM_ID. "open" and "closed" match on the kind of
+// workflow state; any other state is matched by name.
+export async function handleLinearSearch(
+	query: SearchQuery,
+	env: LinearEnv,
+): Promise<Response> {
+	const auth = authorization(env);
+	if (!auth || !env.LINEAR_TEAM_ID) {
+		return new Response("Linear backend not configured", { status: 500 });
+	}
+
+	const filters: Record<string, unknown>[] = [
+		{ team: { id: { eq: env.LINEAR_TEAM_ID } } },
+	];
+	if (query.text) {
+		filters.push({
+			or: [
+				{ title: { containsIgnoreCase: query.text } },
+				{ description: { containsIgnoreCase: query.text } },
+			],
 		});
 	}
+	const closed = ["completed", "canceled"];
+	if (query.state === "open") {
+		filters.push({ state: { type: { nin: closed } } });
+	} else if (query.state === "closed") {
+		filters.push({ state: { type: { in: closed } } });
+	} else if (query.state) {
+		filters.push({ state: { name: { eqIgnoreCase: query.state } } });
+	}
+	for (const label of query.labels) {
+		filters.push({ labels: { some: { name: { eqIgnoreCase: label } } } });
+	}
+
+	let data: IssuesData;
+	try {
+		data = await graphql<IssuesData>(
+			auth,
+			`query Issues($filter: IssueFilter!, $first: Int!) {
+				issues(filter: $filter, first: $first, orderBy: updatedAt) {
+					nodes {
+						identifier title url updatedAt
+						state { name }
+						assignee { name }
+					}
+				}
+			}`,
+			{ filter: { and: filters }, first: query.limit },
+		);
+	} catch (err) {
+		if (err instanceof LinearApiError) {
+			return new Response(err.message, { status: 502 });
+		}
+		throw err;
+	}
 
-	const data: any = await resp.json();
+	return Response.json({
+		issues: data.issues.nodes.map((issue) => ({
+			identifier: issue.identifier,
+			title: issue.title,
+			url: issue.url,
+			state: issue.state.name,
+			assignee: issue.assignee?.name ?? null,
+			updatedAt: issue.updatedAt,
+		})),
+	});
+}
 
-	if (data.errors) {
-		const errMsg = JSON.stringify(data.errors);
-		return new Response(`Linear GraphQL errors: ${errMsg}`, { status: 502 });
+// Adds a markdown comment to an issue and returns the comment's URL.
+export async function handleLinearComment(
+	request: Request,
+	id: string,
+	env: LinearEnv,
+): Promise<Response> {
+	const auth = authorization(env);
+	if (!auth || !env.LINEAR_TEAM_ID) {
+		return new Response("Linear backend not configured", { status: 500 });
+	}
+
+	let body: { body?: string };
+	try {
+		body = (await request.json()) as { body?: string };
+	} catch {
+		return new Response("Invalid JSON", { status: 400 });
+	}
+	if (!body.body) {
+		return new Response("Missing body", { status: 400 });
+	}
+
+	try {
+		const issue = await teamIssue(auth, id, env.LINEAR_TEAM_ID);
+		if (!issue) {
+			return new Response("Issue not found", { status: 404 });
+		}
+		const data = await graphql<CommentCreateData>(
+			auth,
+			`mutation CommentCreate($input: CommentCreateInput!) {
+				commentCreate(input: $input) {
+					success
+					comment { url }
+				}
+			}`,
+			{ input: { issueId: issue.id, body: body.body } },
+		);
+		const comment = required(
+			data.commentCreate?.comment,
+			"commentCreate.comment",
+		);
+		return Response.json({ url: comment.url });
+	} catch (err) {
+		if (err instanceof LinearApiError) {
+			return new Response(err.message, { status: 502 });
+		}
+		throw err;
+	}
+}
+
+// Replaces the body of an occurrence comment on an issue. Other comments
+// can't be edited, so a proxy token can't rewrite what people wrote.
+export async function handleLinearCommentUpdate(
+	request: Request,
+	id: string,
+	commentId: string,
+	env: LinearEnv,
+): Promise<Response> {
+	const auth = authorization(env);
+	if (!auth || !env.LINEAR_TEAM_ID) {
+		return new Response("Linear backend not configured", { status: 500 });
 	}
 
-	const issue = data?.data?.issueCreate?.issue;
-	const url = issue?.url;
-	const issueId = issue?.id;
-	if (!url || !issueId) {
-		return new Response(`Unexpected Linear response: ${JSON.stringify(data)}`, {
-			status: 502,
+	let body: { body?: string };
+	try {
+		body = (await request.json()) as { body?: string };
+	} catch {
+		return new Response("Invalid JSON", { status: 400 });
+	}
+	if (!isOccurrenceComment(body.body)) {
+		return new Response("Only occurrence comments can be updated", {
+			status: 400,
 		});
 	}
 
-	if (body.attachments?.length) {
-		for (const att of body.attachments) {
-			try {
-				await uploadAttachment(env.LINEAR_API_KEY, issueId, att);
-			} catch (err) {
-				console.error(`Failed to attach ${att.filename}:`, err);
-			}
+	try {
+		const issue = await teamIssue(auth, id, env.LINEAR_TEAM_ID);
+		if (!issue) {
+			return new Response("Issue not found", { status: 404 });
+		}
+		// Comment URLs carry a short hash; full IDs work too.
+		const byId = /^[0-9a-f]{8}-[0-9a-f]{4}-/.test(commentId);
+		const data = await graphql<CommentData>(
+			auth,
+			byId
+				? `query Comment($id: String!) {
+					comment(id: $id) { id body issue { id } }
+				}`
+				: `query Comment($hash: String!) {
+					comment(hash: $hash) { id body issue { id } }
+				}`,
+			byId ? { id: commentId } : { hash: commentId },
+		);
+		const comment = data.comment;
+		if (comment?.issue?.id !== issue.id) {
+			return new Response("Comment not found", { status: 404 });
+		}
+		if (!isOccurrenceComment(comment.body)) {
+			return new Response("Only occurrence comments can be updated", {
+				status: 403,
+			});
+		}
+		const updated = await graphql<CommentUpdateData>(
+			auth,
+			`mutation CommentUpdate($id: String!, $input: CommentUpdateInput!) {
+				commentUpdate(id: $id, input: $input) {
+					success
+					comment { url }
+				}
+			}`,
+			{ id: comment.id, input: { body: body.body } },
+		);
+		const result = required(
+			updated.commentUpdate?.comment,
+			"commentUpdate.comment",
+		);
+		return Response.json({ url: result.url });
+	} catch (err) {
+		if (err instanceof LinearApiError) {
+			return new Response(err.message, { status: 502 });
+		}
+		throw err;
+	}
+}
+
+// Moves an issue to the team's first completed or canceled workflow state.
+// Only issues filed with a fingerprint can be closed, so clients can't close
+// issues people filed by hand.
+export async function handleLinearClose(
+	request: Request,
+	id: string,
+	env: LinearEnv,
+): Promise<Response> {
+	const auth = authorization(env);
+	const teamId = env.LINEAR_TEAM_ID;
+	if (!auth || !teamId) {
+		return new Response("Linear backend not configured", { status: 500 });
+	}
+
+	let body: { resolution?: unknown };
+	try {
+		body = (await request.json()) as { resolution?: unknown };
+	} catch {
+		return new Response("Invalid JSON", { status: 400 });
+	}
+	const resolution = parseResolution(body.resolution);
+	if (!resolution) {
+		return new Response("Resolution must be done or canceled", {
+			status: 400,
+		});
+	}
+	const type = resolution === "done" ? "completed" : "canceled";
+
+	try {
+		const issue = await teamIssue(auth, id, teamId);
+		if (!issue) {
+			return new Response("Issue not found", { status: 404 });
+		}
+		if (!hasFingerprint(issue.description)) {
+			return new Response("Only issues filed with a fingerprint can be closed", {
+				status: 403,
+			});
+		}
+		const stateId = await cached(
+			env,
+			`linear:${teamId}:state:${type}`,
+			async () => {
+				const data = await graphql<WorkflowStatesData>(
+					auth,
+					`query WorkflowStates($teamId: ID!, $type: String!) {
+						workflowStates(
+							filter: { team: { id: { eq: $teamId } }, type: { eq: $type } }
+						) {
+							nodes { id position }
+						}
+					}`,
+					{ teamId, type },
+				);
+				const [first] = data.workflowStates.nodes.sort(
+					(a, b) => a.position - b.position,
+				);
+				return first?.id ?? "";
+			},
+		);
+		if (!stateId) {
+			return new Response(`Team has no ${type} workflow state`, {
+				status: 500,
+			});
+		}
+		const updated = await graphql<IssueUpdateData>(
+			auth,
+			`mutation IssueUpdate($id: String!, $input: IssueUpdateInput!) {
+				issueUpdate(id: $id, input: $input) {
+					success
+					issue {
+						identifier title url updatedAt
+						state { name }
+						assignee { name }
+					}
+				}
+			}`,
+			{ id: issue.id, input: { stateId } },
+		);
+		return Response.json(
+			issueStatus(required(updated.issueUpdate?.issue, "issueUpdate.issue")),
+		);
+	} catch (err) {
+		if (err instanceof LinearApiError) {
+			return new Response(err.message, { status: 502 });
+		}
+		throw err;
+	}
+}
+
+// Looks up labels by name (case-insensitive), through the metadata cache.
+// Names without a matching label are skipped rather than failing the
+// request.
+async function resolveLabelIds(
+	auth: string,
+	env: LinearEnv,
+	names: string[],
+): Promise<string[]> {
+	const ids: string[] = [];
+	for (const name of names) {
+		try {
+			const id = await cached(
+				env,
+				`linear:${env.LINEAR_TEAM_ID}:label:${name.toLowerCase()}`,
+				async () => {
+					const data = await graphql<IssueLabelsData>(
+						auth,
+						`query LabelByName($name: String!) {
+							issueLabels(filter: { name: { eqIgnoreCase: $name } }, first: 1) {
+								nodes { id }
+							}
+						}`,
+						{ name },
+					);
+					return data.issueLabels.nodes[0]?.id ?? "";
+				},
+			);
+			if (id) ids.push(id);
+		} catch (err) {
+			console.error(`Failed to look up label ${name}:`, err);
 		}
 	}
+	return ids;
+}
+
+async function linkCustomer(
+	auth: string,
+	env: LinearEnv,
+	issueId: string,
+	customer: CustomerRequest,
+): Promise<void> {
+	const input: Record<string, string> = { issueId };
+	if (customer.externalId) {
+		input.customerExternalId = customer.externalId;
+	} else if (customer.email) {
+		// Linear customers are keyed by domain, not by individual email.
+		const domain = customer.email.split("@").pop();
+		const id = await cached(
+			env,
+			`linear:${env.LINEAR_TEAM_ID}:customer:${domain}`,
+			async () => {
+				const data = await graphql<CustomersData>(
+					auth,
+					`query CustomerByDomain($domain: String!) {
+						customers(filter: { domains: { some: { eq: $domain } } }, first: 1) {
+							nodes { id }
+						}
+					}`,
+					{ domain },
+				);
+				return data.customers.nodes[0]?.id ?? "";
+			},
+		);
+		if (!id) {
+			throw new Error(`no Linear customer with domain ${domain}`);
+		}
+		input.customerId = id;
+	} else {
+		return;
+	}
+
+	await graphql<CustomerNeedCreateData>(
+		auth,
+		`mutation CustomerNeedCreate($input: CustomerNeedCreateInput!) {
+			customerNeedCreate(input: $input) {
+				success
+			}
+		}`,
+		{ input },
+	);
+}
+
+// Uploads a file without creating an issue and returns its asset URL, so
+// clients can reference one upload from several issues or comments.
+export async function handleLinearUpload(
+	request: Request,
+	env: LinearEnv,
+): Promise<Response> {
+	const auth = authorization(env);
+	if (!auth) {
+		return new Response("Linear backend not configured", { status: 500 });
+	}
+
+	let body: AttachmentRequest;
+	try {
+		body = (await request.json()) as AttachmentRequest;
+	} catch {
+		return new Response("Invalid JSON", { status: 400 });
+	}
+	if (!body.filename || !body.contentType || typeof body.data !== "string") {
+		return new Response("Missing filename, contentType, or data", {
+			status: 400,
+		});
+	}
 
-	return Response.json({ url });
+	try {
+		return Response.json({ url: await uploadAsset(auth, body) });
+	} catch (err) {
+		return new Response(
+			err instanceof Error ? err.message : "Upload failed",
+			{ status: 502 },
+		);
+	}
 }
 
 async function uploadAttachment(
-	apiKey: string,
+	auth: string,
 	issueId: string,
 	att: AttachmentRequest,
 ): Promise<void> {
+	const url = await uploadAsset(auth, att);
+	await createAttachment(auth, issueId, { url, title: att.filename });
+}
+
+// Uploads a file to Linear's storage and returns its asset URL.
+async function uploadAsset(
+	auth: string,
+	att: AttachmentRequest,
+): Promise<string> {
 	const bytes =
 		att.encoding === "text"
 			? new TextEncoder().encode(att.data)
 			: Uint8Array.from(atob(att.data), (c) => c.charCodeAt(0));
 
 	// Step 1: Get presigned upload URL
-	const uploadResp = await fetch(LINEAR_API_URL, {
-		method: "POST",
-		headers: {
-			Authorization: apiKey,
-			"Content-Type": "application/json",
-		},
-		body: JSON.stringify({
-			query: `mutation FileUpload($contentType: String!, $filename: String!, $size: Int!) {
-				fileUpload(contentType: $contentType, filename: $filename, size: $size) {
-					uploadFile {
-						uploadUrl
-						assetUrl
-						headers { key value }
-					}
+	const uploadData = await graphql<FileUploadData>(
+		auth,
+		`mutation FileUpload($contentType: String!, $filename: String!, $size: Int!) {
+			fileUpload(contentType: $contentType, filename: $filename, size: $size) {
+				uploadFile {
+					uploadUrl
+					assetUrl
+					headers { key value }
 				}
-			}`,
-			variables: {
-				contentType: att.contentType,
-				filename: att.filename,
-				size: bytes.length,
-			},
-		}),
-	});
-
-	const uploadData: any = await uploadResp.json();
-	const uploadFile = uploadData?.data?.fileUpload?.uploadFile;
-	if (!uploadFile) {
-		throw new Error(`fileUpload failed: ${JSON.stringify(uploadData)}`);
-	}
+			}
+		}`,
+		{
+			contentType: att.contentType,
+			filename: att.filename,
+			size: bytes.length,
+		},
+	);
+	const uploadFile = required(
+		uploadData.fileUpload.uploadFile,
+		"fileUpload.uploadFile",
+	);
 
 	// Step 2: PUT file bytes to presigned URL
 	const putHeaders: Record<string, string> = {
 		"Content-Type": att.contentType,
 		"Content-Length": String(bytes.length),
 	};
-	for (const h of uploadFile.headers ?? []) {
+	for (const h of uploadFile.headers) {
 		putHeaders[h.key] = h.value;
 	}
 	const putResp = await fetch(uploadFile.uploadUrl, {
@@ -157,32 +965,31 @@ async function uploadAttachment(
 	if (!putResp.ok) {
 		throw new Error(`PUT upload failed: ${putResp.status}`);
 	}
+	return uploadFile.assetUrl;
+}
 
-	// Step 3: Link attachment to issue
-	const attachResp = await fetch(LINEAR_API_URL, {
-		method: "POST",
-		headers: {
-			Authorization: apiKey,
-			"Content-Type": "application/json",
-		},
-		body: JSON.stringify({
-			query: `mutation AttachmentCreate($issueId: String!, $url: String!, $title: String!) {
-				attachmentCreate(input: { issueId: $issueId, url: $url, title: $title }) {
-					success
-				}
-			}`,
-			variables: {
-				issueId,
-				url: uploadFile.assetUrl,
-				title: att.filename,
-			},
-		}),
-	});
-
-	const attachData: any = await attachResp.json();
-	if (attachData.errors) {
-		throw new Error(
-			`attachmentCreate failed: ${JSON.stringify(attachData.errors)}`,
-		);
+// Attaches a URL to an issue. Linear shows it with the issue's attachments,
+// with the subtitle under the title.
+async function createAttachment(
+	auth: string,
+	issueId: string,
+	link: LinkRequest,
+): Promise<void> {
+	const input: Record<string, string> = {
+		issueId,
+		url: link.url,
+		title: link.title,
+	};
+	if (link.subtitle) {
+		input.subtitle = link.subtitle;
 	}
+	await graphql<AttachmentCreateData>(
+		auth,
+		`mutation AttachmentCreate($input: AttachmentCreateInput!) {
+			attachmentCreate(input: $input) {
+				success
+			}
+		}`,
+		{ input },
+	);
 }
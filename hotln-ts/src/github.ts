@@ -1,11 +1,21 @@
 import { HotlineError } from "./errors.js";
-import { inlineFile } from "./helpers.js";
+import {
+	type Severity,
+	type Submission,
+	type SubmitOptions,
+	feedbackTitle,
+	inlineFile,
+} from "./helpers.js";
 
 export class GitHubIssue {
 	private proxyUrl: string;
 	private token?: string;
 	private issueTitle = "Untitled";
 	private description = "";
+	private issueSeverity?: Severity;
+	private labels: string[] = [];
+	private isFeedback = false;
+	private reporterInfo?: { name?: string; email?: string };
 
 	constructor(proxyUrl: string) {
 		this.proxyUrl = proxyUrl;
@@ -37,7 +47,38 @@ export class GitHubIssue {
 		return this;
 	}
 
-	async create(): Promise<string> {
+	label(name: string): this {
+		this.labels.push(name);
+		return this;
+	}
+
+	severity(severity: Severity): this {
+		this.issueSeverity = severity;
+		return this;
+	}
+
+	feedback(text: string): this {
+		this.isFeedback = true;
+		if (this.issueTitle === "Untitled") {
+			this.issueTitle = feedbackTitle(text);
+		}
+		this.issueSeverity ??= "feedback";
+		return this.text(text);
+	}
+
+	reporter(name: string, email: string): this {
+		this.reporterInfo = {
+			...(name ? { name } : {}),
+			...(email ? { email } : {}),
+		};
+		return this;
+	}
+
+	async create(options?: SubmitOptions): Promise<string> {
+		return (await this.submit(options)).url;
+	}
+
+	async submit(options?: SubmitOptions): Promise<Submission> {
 		const headers: Record<string, string> = {
 			"Content-Type": "application/json",
 		};
@@ -48,9 +89,14 @@ export class GitHubIssue {
 		const resp = await fetch(`${this.proxyUrl}/github`, {
 			method: "POST",
 			headers,
+			...(options?.signal && { signal: options.signal }),
 			body: JSON.stringify({
 				title: this.issueTitle,
 				description: this.description,
+				...(this.issueSeverity && { severity: this.issueSeverity }),
+				...(this.labels.length ? { labels: this.labels } : {}),
+				...(this.isFeedback ? { kind: "feedback" } : {}),
+				...(this.reporterInfo && { reporter: this.reporterInfo }),
 			}),
 		});
 
@@ -63,10 +109,13 @@ export class GitHubIssue {
 			);
 		}
 
-		const json = (await resp.json()) as { url?: string };
+		const json = (await resp.json()) as {
+			url?: string;
+			deduplicated?: boolean;
+		};
 		if (typeof json.url !== "string") {
 			throw new HotlineError("Proxy response missing url");
 		}
-		return json.url;
+		return { url: json.url, deduplicated: json.deduplicated === true };
 	}
 }
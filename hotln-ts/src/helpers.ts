@@ -1,3 +1,23 @@
+export type Severity = "crash" | "bug" | "degraded" | "feedback";
+
+/** The proxy's answer: the issue URL, and whether it was an existing issue. */
+export interface Submission {
+	url: string;
+	deduplicated: boolean;
+}
+
+export interface SubmitOptions {
+	/** Aborts the upload, rejecting with the signal's reason. */
+	signal?: AbortSignal;
+}
+
+export function feedbackTitle(text: string): string {
+	const maxChars = 80;
+	const line = (text.split("\n")[0] ?? "").trim();
+	if (line.length <= maxChars) return line;
+	return `${line.slice(0, maxChars - 3).trimEnd()}...`;
+}
+
 export function inlineFile(filename: string, content: string): string {
 	const ext = filename.split(".").pop() ?? filename;
 	return `**${filename}**\n\`\`\`${ext}\n${content}\n\`\`\``;
@@ -4,6 +4,7 @@ import { LinearIssue } from "./linear.js";
 export { GitHubIssue } from "./github.js";
 export { LinearIssue } from "./linear.js";
 export { HotlineError } from "./errors.js";
+export type { Severity, Submission, SubmitOptions } from "./helpers.js";
 
 export function github(proxyUrl: string): GitHubIssue {
 	return new GitHubIssue(proxyUrl);
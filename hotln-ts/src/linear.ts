@@ -1,5 +1,12 @@
 import { HotlineError } from "./errors.js";
-import { inlineFile, mimeForExt } from "./helpers.js";
+import {
+	type Severity,
+	type Submission,
+	type SubmitOptions,
+	feedbackTitle,
+	inlineFile,
+	mimeForExt,
+} from "./helpers.js";
 
 interface Attachment {
 	filename: string;
@@ -11,7 +18,12 @@ export class LinearIssue {
 	private token?: string;
 	private issueTitle = "Untitled";
 	private description = "";
+	private issueSeverity?: Severity;
+	private labels: string[] = [];
+	private isFeedback = false;
+	private reporterInfo?: { name?: string; email?: string };
 	private attachments: Attachment[] = [];
+	private customer?: { externalId: string } | { email: string };
 
 	constructor(proxyUrl: string) {
 		this.proxyUrl = proxyUrl;
@@ -48,7 +60,48 @@ export class LinearIssue {
 		return this;
 	}
 
-	async create(): Promise<string> {
+	customerId(externalId: string): this {
+		this.customer = { externalId };
+		return this;
+	}
+
+	customerEmail(email: string): this {
+		this.customer = { email };
+		return this;
+	}
+
+	label(name: string): this {
+		this.labels.push(name);
+		return this;
+	}
+
+	severity(severity: Severity): this {
+		this.issueSeverity = severity;
+		return this;
+	}
+
+	feedback(text: string): this {
+		this.isFeedback = true;
+		if (this.issueTitle === "Untitled") {
+			this.issueTitle = feedbackTitle(text);
+		}
+		this.issueSeverity ??= "feedback";
+		return this.text(text);
+	}
+
+	reporter(name: string, email: string): this {
+		this.reporterInfo = {
+			...(name ? { name } : {}),
+			...(email ? { email } : {}),
+		};
+		return this;
+	}
+
+	async create(options?: SubmitOptions): Promise<string> {
+		return (await this.submit(options)).url;
+	}
+
+	async submit(options?: SubmitOptions): Promise<Submission> {
 		const headers: Record<string, string> = {
 			"Content-Type": "application/json",
 		};
@@ -84,10 +137,16 @@ export class LinearIssue {
 		const resp = await fetch(`${this.proxyUrl}/linear`, {
 			method: "POST",
 			headers,
+			...(options?.signal && { signal: options.signal }),
 			body: JSON.stringify({
 				title: this.issueTitle,
 				description: this.description,
 				attachments: encodedAttachments,
+				...(this.customer && { customer: this.customer }),
+				...(this.issueSeverity && { severity: this.issueSeverity }),
+				...(this.labels.length ? { labels: this.labels } : {}),
+				...(this.isFeedback ? { kind: "feedback" } : {}),
+				...(this.reporterInfo && { reporter: this.reporterInfo }),
 			}),
 		});
 
@@ -100,10 +159,13 @@ export class LinearIssue {
 			);
 		}
 
-		const json = (await resp.json()) as { url?: string };
+		const json = (await resp.json()) as {
+			url?: string;
+			deduplicated?: boolean;
+		};
 		if (typeof json.url !== "string") {
 			throw new HotlineError("Proxy response missing url");
 		}
-		return json.url;
+		return { url: json.url, deduplicated: json.deduplicated === true };
 	}
 }
@@ -102,4 +102,63 @@ describe("github", () => {
 		const body = JSON.parse(mock.mock.calls[0][1]!.body as string);
 		expect(body.title).toBe("Untitled");
 	});
+
+	it("sends the reporter when set", async () => {
+		const mock = mockFetch("https://github.com/owner/repo/issues/5");
+
+		await github(PROXY)
+			.title("reporter test")
+			.reporter("Jane", "")
+			.create();
+
+		const body = JSON.parse(mock.mock.calls[0][1]!.body as string);
+		expect(body.reporter).toEqual({ name: "Jane" });
+	});
+
+	it("files feedback with a derived title", async () => {
+		const mock = mockFetch("https://github.com/owner/repo/issues/6");
+
+		await github(PROXY).feedback("love the new editor").create();
+
+		const body = JSON.parse(mock.mock.calls[0][1]!.body as string);
+		expect(body.title).toBe("love the new editor");
+		expect(body.kind).toBe("feedback");
+		expect(body.severity).toBe("feedback");
+	});
+
+	it("reports when the proxy matched an existing issue", async () => {
+		vi.mocked(fetch).mockResolvedValueOnce(
+			Response.json({
+				url: "https://github.com/owner/repo/issues/7",
+				deduplicated: true,
+				existing: true,
+			}),
+		);
+
+		const submission = await github(PROXY).title("dup").submit();
+
+		expect(submission).toEqual({
+			url: "https://github.com/owner/repo/issues/7",
+			deduplicated: true,
+		});
+	});
+
+	it("abandons the upload when the signal aborts", async () => {
+		vi.mocked(fetch).mockImplementationOnce(
+			(_url, init) =>
+				new Promise((_resolve, reject) => {
+					init?.signal?.addEventListener("abort", () =>
+						reject(init?.signal?.reason),
+					);
+				}),
+		);
+		const controller = new AbortController();
+
+		const pending = github(PROXY).title("shutdown").submit({
+			signal: controller.signal,
+		});
+		controller.abort();
+
+		await expect(pending).rejects.toMatchObject({ name: "AbortError" });
+	});
 });
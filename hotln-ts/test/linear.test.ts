@@ -130,4 +130,38 @@ describe("linear", () => {
 			'Bad config\n\n**config.toml**\n```toml\nkey = "value"\n```',
 		);
 	});
+
+	it("sends the customer when set", async () => {
+		const mock = mockFetch("https://linear.app/test-org/issue/TEST-54");
+
+		await linear(PROXY)
+			.title("customer test")
+			.customerId("acme-42")
+			.create();
+
+		const body = JSON.parse(mock.mock.calls[0][1]!.body as string);
+		expect(body.customer).toEqual({ externalId: "acme-42" });
+	});
+
+	it("sends the severity when set", async () => {
+		const mock = mockFetch("https://linear.app/test-org/issue/TEST-55");
+
+		await linear(PROXY).title("crash").severity("crash").create();
+
+		const body = JSON.parse(mock.mock.calls[0][1]!.body as string);
+		expect(body.severity).toBe("crash");
+	});
+
+	it("sends labels when set", async () => {
+		const mock = mockFetch("https://linear.app/test-org/issue/TEST-56");
+
+		await linear(PROXY)
+			.title("export")
+			.label("desktop")
+			.label("export")
+			.create();
+
+		const body = JSON.parse(mock.mock.calls[0][1]!.body as string);
+		expect(body.labels).toEqual(["desktop", "export"]);
+	});
 });
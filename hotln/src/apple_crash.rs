@@ -0,0 +1,369 @@
+//! Reading the crash reports macOS writes for crashes that never reach the
+//! panic hook, such as segfaults in native code or aborts.
+//!
+//! macOS 12 and later write `.ips` files, a JSON header line followed by a
+//! JSON body; earlier versions write plain-text `.crash` files. Both are in
+//! `~/Library/Logs/DiagnosticReports`. Parsing works on any platform, so
+//! reports can be filed from another machine.
+//!
+//! ```no_run
+//! use hotln::{AppleCrash, Reporter};
+//!
+//! let text = std::fs::read_to_string("MyApp-2024-05-01-120000.ips")?;
+//! if let Some(crash) = AppleCrash::parse(&text)
+//!     && crash.bundle_id.as_deref() == Some("com.example.myapp")
+//! {
+//!     hotln::LinearClient::new("https://worker.example.com").report(&crash.report())?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use serde_json::Value;
+
+use crate::{Report, Severity, info_table};
+
+/// Frames of the crashed thread shown in the description. The full report
+/// is attached.
+const MAX_FRAMES: usize = 30;
+/// Frames hashed into the fingerprint.
+const FINGERPRINT_FRAMES: usize = 5;
+
+/// The parts of a macOS crash report that say what crashed and where.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct AppleCrash {
+    pub process: String,
+    pub bundle_id: Option<String>,
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    /// Such as `EXC_BAD_ACCESS (SIGSEGV)`.
+    pub exception: Option<String>,
+    /// Such as `KERN_INVALID_ADDRESS at 0x0000000000000000`.
+    pub exception_detail: Option<String>,
+    pub termination: Option<String>,
+    pub incident_id: Option<String>,
+    pub crashed_thread: Option<u64>,
+    /// The crashed thread's frames, innermost first, as `image  symbol`.
+    pub frames: Vec<String>,
+    /// The report as it was read, attached to the issue.
+    pub text: String,
+    /// `ips` or `crash`.
+    pub format: &'static str,
+}
+
+impl AppleCrash {
+    /// Parse an `.ips` or `.crash` file's contents, or `None` if `text`
+    /// isn't a crash report.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut crash = if text.trim_start().starts_with('{') {
+            parse_ips(text)?
+        } else {
+            parse_crash(text)?
+        };
+        crash.text = text.to_string();
+        Some(crash)
+    }
+
+    /// A crash report titled after the process and exception, with the
+    /// details in a table, the crashed thread's frames, and the original
+    /// file attached. Reports of the same crash site share a fingerprint.
+    pub fn report(&self) -> Report {
+        let mut title = format!("{} crashed", self.process);
+        if let Some(exception) = &self.exception {
+            title.push_str(&format!(": {exception}"));
+        }
+        let thread = self.crashed_thread.map(|t| t.to_string());
+        let rows: Vec<(&str, &str)> = [
+            ("Process", Some(self.process.as_str())),
+            ("Bundle ID", self.bundle_id.as_deref()),
+            ("Version", self.app_version.as_deref()),
+            ("OS", self.os_version.as_deref()),
+            ("Exception", self.exception.as_deref()),
+            ("Details", self.exception_detail.as_deref()),
+            ("Termination", self.termination.as_deref()),
+            ("Crashed Thread", thread.as_deref()),
+            ("Incident", self.incident_id.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect();
+
+        let mut report = Report::new();
+        report
+            .title(&title)
+            .severity(Severity::Crash)
+            .text(&info_table("Crash", &rows));
+        if !self.frames.is_empty() {
+            let frames: Vec<String> = self
+                .frames
+                .iter()
+                .take(MAX_FRAMES)
+                .enumerate()
+                .map(|(i, frame)| format!("{i:<3} {frame}"))
+                .collect();
+            report.text(&format!(
+                "## Crashed thread\n\n```\n{}\n```",
+                frames.join("\n")
+            ));
+            let top = self.frames[..self.frames.len().min(FINGERPRINT_FRAMES)].join("\n");
+            report.fingerprint = Some(crate::message_fingerprint(&top));
+        }
+        report.attachment(
+            &format!("{}.{}", self.process, self.format),
+            self.text.as_bytes(),
+        );
+        report
+    }
+}
+
+/// The `.ips` format: a one-line JSON header, then the JSON body.
+fn parse_ips(text: &str) -> Option<AppleCrash> {
+    let (header, body) = text.split_once('\n').unwrap_or((text, ""));
+    let header: Value = serde_json::from_str(header).ok()?;
+    let body: Value = if body.trim().is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(body).ok()?
+    };
+    let str_at = |value: &Value, path: &str| {
+        value
+            .pointer(path)
+            .and_then(Value::as_str)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    let exception =
+        str_at(&body, "/exception/type").map(|kind| match str_at(&body, "/exception/signal") {
+            Some(signal) => format!("{kind} ({signal})"),
+            None => kind,
+        });
+    let termination = body.get("termination").map(|t| {
+        let part = |key| {
+            t.get(key)
+                .map(|v| v.to_string().trim_matches('"').to_string())
+        };
+        [part("namespace"), part("code"), part("indicator")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+    let os_version = str_at(&body, "/osVersion/train")
+        .map(|train| match str_at(&body, "/osVersion/build") {
+            Some(build) => format!("{train} ({build})"),
+            None => train,
+        })
+        .or_else(|| str_at(&header, "/os_version"));
+    let crashed_thread = body.get("faultingThread").and_then(Value::as_u64);
+
+    let images = body.get("usedImages").and_then(Value::as_array);
+    let image_name = |index: Option<u64>| {
+        index
+            .and_then(|i| images?.get(usize::try_from(i).ok()?))
+            .and_then(|image| image.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or("???")
+            .to_string()
+    };
+    let threads = body.get("threads").and_then(Value::as_array);
+    let thread = threads.and_then(|threads| {
+        threads
+            .iter()
+            .find(|t| t.get("triggered").and_then(Value::as_bool) == Some(true))
+            .or_else(|| threads.get(usize::try_from(crashed_thread?).ok()?))
+    });
+    let frames = thread
+        .and_then(|t| t.get("frames"))
+        .and_then(Value::as_array)
+        .map(|frames| {
+            frames
+                .iter()
+                .map(|frame| {
+                    let image = image_name(frame.get("imageIndex").and_then(Value::as_u64));
+                    let location = match frame.get("symbol").and_then(Value::as_str) {
+                        Some(symbol) => match frame.get("symbolLocation").and_then(Value::as_u64) {
+                            Some(offset) => format!("{symbol} + {offset}"),
+                            None => symbol.to_string(),
+                        },
+                        None => {
+                            let offset = frame.get("imageOffset").and_then(Value::as_u64);
+                            format!("0x{:x}", offset.unwrap_or(0))
+                        }
+                    };
+                    format!("{image:<30} {location}")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let app_version = str_at(&body, "/bundleInfo/CFBundleShortVersionString")
+        .or_else(|| str_at(&header, "/app_version"))
+        .map(
+            |version| match str_at(&body, "/bundleInfo/CFBundleVersion") {
+                Some(build) if build != version => format!("{version} ({build})"),
+                _ => version,
+            },
+        );
+    Some(AppleCrash {
+        process: str_at(&body, "/procName")
+            .or_else(|| str_at(&header, "/app_name"))
+            .or_else(|| str_at(&header, "/name"))?,
+        bundle_id: str_at(&body, "/bundleInfo/CFBundleIdentifier")
+            .or_else(|| str_at(&header, "/bundleID")),
+        app_version,
+        os_version,
+        exception,
+        exception_detail: str_at(&body, "/exception/subtype")
+            .or_else(|| str_at(&body, "/exception/codes")),
+        termination,
+        incident_id: str_at(&header, "/incident_id").or_else(|| str_at(&body, "/incident")),
+        crashed_thread,
+        frames,
+        text: String::new(),
+        format: "ips",
+    })
+}
+
+/// The plain-text `.crash` format: `Key: value` lines, then one block of
+/// frames per thread.
+fn parse_crash(text: &str) -> Option<AppleCrash> {
+    let field = |key: &str| {
+        text.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            (name.trim() == key)
+                .then(|| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        })
+    };
+    let process = field("Process").map(|process| match process.split_once(" [") {
+        Some((name, _)) => name.to_string(),
+        None => process,
+    })?;
+    let crashed_thread =
+        field("Crashed Thread").and_then(|t| t.split_whitespace().next()?.parse::<u64>().ok());
+
+    // "Thread 0 Crashed::  Dispatch queue: ..." starts the crashed thread's
+    // frames, which run until the next blank line.
+    let mut frames = Vec::new();
+    let mut lines = text.lines();
+    if lines.any(|line| line.starts_with("Thread ") && line.contains(" Crashed")) {
+        for line in lines.take_while(|line| !line.trim().is_empty()) {
+            let mut parts = line.split_whitespace();
+            let (Some(_), Some(image), Some(_address)) = (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let symbol: Vec<&str> = parts.collect();
+            frames.push(format!("{image:<30} {}", symbol.join(" ")));
+        }
+    }
+
+    Some(AppleCrash {
+        process,
+        bundle_id: field("Identifier"),
+        app_version: field("Version"),
+        os_version: field("OS Version"),
+        exception: field("Exception Type"),
+        exception_detail: field("Exception Codes"),
+        termination: field("Termination Reason"),
+        incident_id: field("Incident Identifier"),
+        crashed_thread,
+        frames,
+        text: String::new(),
+        format: "crash",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRASH: &str = "\
+Incident Identifier: 5D1E2A3B-0000-4000-8000-000000000001
+Process:               MyApp [4321]
+Path:                  /Applications/MyApp.app/Contents/MacOS/MyApp
+Identifier:            com.example.myapp
+Version:               1.2.3 (45)
+OS Version:            macOS 11.7 (20G817)
+
+Crashed Thread:        0  Dispatch queue: com.apple.main-thread
+
+Exception Type:        EXC_BAD_ACCESS (SIGSEGV)
+Exception Codes:       KERN_INVALID_ADDRESS at 0x0000000000000000
+Termination Reason:    Namespace SIGNAL, Code 0xb
+
+Thread 0 Crashed:: Dispatch queue: com.apple.main-thread
+0   MyApp                         \t0x0000000100003f24 myapp::render::draw + 36
+1   MyApp                         \t0x0000000100003a10 myapp::main + 120
+2   libdyld.dylib                 \t0x00007fff2035ef3d start + 1
+
+Thread 1:
+0   libsystem_kernel.dylib        \t0x00007fff2030c2f6 __workq_kernreturn + 10
+";
+
+    #[test]
+    fn test_parse_crash() {
+        let crash = AppleCrash::parse(CRASH).unwrap();
+        assert_eq!(crash.process, "MyApp");
+        assert_eq!(crash.bundle_id.as_deref(), Some("com.example.myapp"));
+        assert_eq!(crash.exception.as_deref(), Some("EXC_BAD_ACCESS (SIGSEGV)"));
+        assert_eq!(crash.crashed_thread, Some(0));
+        assert_eq!(crash.frames.len(), 3);
+        assert!(crash.frames[0].ends_with("myapp::render::draw + 36"));
+
+        let report = crash.report();
+        assert_eq!(report.title, "MyApp crashed: EXC_BAD_ACCESS (SIGSEGV)");
+        assert!(
+            report
+                .description
+                .contains("| Bundle ID | com.example.myapp |")
+        );
+        assert!(report.description.contains("## Crashed thread"));
+        assert!(!report.description.contains("__workq_kernreturn"));
+        assert_eq!(report.attachments[0].0, "MyApp.crash");
+    }
+
+    #[test]
+    fn test_parse_ips() {
+        let header = serde_json::json!({
+            "app_name": "MyApp",
+            "app_version": "1.2.3",
+            "bundleID": "com.example.myapp",
+            "incident_id": "5D1E2A3B-0000-4000-8000-000000000002",
+        });
+        let body = serde_json::json!({
+            "procName": "MyApp",
+            "bundleInfo": { "CFBundleShortVersionString": "1.2.3", "CFBundleVersion": "45" },
+            "osVersion": { "train": "macOS 14.1", "build": "23B74" },
+            "exception": { "type": "EXC_CRASH", "signal": "SIGABRT" },
+            "termination": { "namespace": "SIGNAL", "code": 6, "indicator": "Abort trap: 6" },
+            "faultingThread": 1,
+            "usedImages": [{ "name": "MyApp" }, { "name": "libsystem_kernel.dylib" }],
+            "threads": [
+                { "frames": [{ "imageIndex": 1, "imageOffset": 4096 }] },
+                { "triggered": true, "frames": [
+                    { "imageIndex": 1, "imageOffset": 33912, "symbol": "__pthread_kill", "symbolLocation": 8 },
+                    { "imageIndex": 0, "imageOffset": 16164 },
+                ] },
+            ],
+        });
+        let text = format!("{header}\n{body:#}");
+        let crash = AppleCrash::parse(&text).unwrap();
+        assert_eq!(crash.exception.as_deref(), Some("EXC_CRASH (SIGABRT)"));
+        assert_eq!(crash.app_version.as_deref(), Some("1.2.3 (45)"));
+        assert_eq!(crash.os_version.as_deref(), Some("macOS 14.1 (23B74)"));
+        assert_eq!(crash.termination.as_deref(), Some("SIGNAL 6 Abort trap: 6"));
+        assert_eq!(crash.crashed_thread, Some(1));
+        assert!(crash.frames[0].starts_with("libsystem_kernel.dylib"));
+        assert!(crash.frames[0].ends_with("__pthread_kill + 8"));
+        assert!(crash.frames[1].ends_with("0x3f24"));
+        assert!(crash.report().fingerprint.is_some());
+    }
+
+    #[test]
+    fn test_not_a_crash_report() {
+        assert!(AppleCrash::parse("hello\nworld").is_none());
+        assert!(AppleCrash::parse("{\"bug_type\": \"288\"}\n{}").is_none());
+    }
+}
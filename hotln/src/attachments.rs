@@ -0,0 +1,180 @@
+//! Keeping large attachments, such as core dumps, from making a report too
+//! big to submit or for the tracker to accept.
+//!
+//! Large files are gzipped first, then files over the per-file limit, and
+//! any that would take the report over the total, are left out. Each file
+//! left out is listed in the description with the reason, so triagers know
+//! to ask for it.
+
+use std::io::Write;
+
+use crate::sysinfo::format_bytes;
+use crate::{Report, append_block};
+
+const MIB: u64 = 1024 * 1024;
+
+/// Size limits for a report's attachments, applied by the client before
+/// sending. The defaults are a 25 MiB limit per file, 50 MiB in total, and
+/// compressing files over 1 MiB.
+///
+/// ```
+/// let mut policy = hotln::AttachmentPolicy::default();
+/// policy.max_file_size(10 << 20).compress_above(512 << 10);
+/// ```
+///
+/// In a [`Config`](crate::Config) file, with sizes in bytes:
+///
+/// ```toml
+/// [attachments]
+/// max_file_size = 10485760
+/// max_total_size = 20971520
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttachmentPolicy {
+    max_file_size: Option<u64>,
+    max_total_size: Option<u64>,
+    compress_above: Option<u64>,
+}
+
+impl Default for AttachmentPolicy {
+    fn default() -> Self {
+        Self {
+            max_file_size: Some(25 * MIB),
+            max_total_size: Some(50 * MIB),
+            compress_above: Some(MIB),
+        }
+    }
+}
+
+impl AttachmentPolicy {
+    /// No limits and no compression: attachments are sent as they are.
+    pub fn unlimited() -> Self {
+        Self {
+            max_file_size: None,
+            max_total_size: None,
+            compress_above: None,
+        }
+    }
+
+    /// Leave out files larger than `bytes` after compression.
+    pub fn max_file_size(&mut self, bytes: u64) -> &mut Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Leave out files once the attachments would add up to more than
+    /// `bytes`, keeping earlier ones.
+    pub fn max_total_size(&mut self, bytes: u64) -> &mut Self {
+        self.max_total_size = Some(bytes);
+        self
+    }
+
+    /// Gzip files larger than `bytes`, adding `.gz` to their names. Files
+    /// that are already compressed are left as they are.
+    pub fn compress_above(&mut self, bytes: u64) -> &mut Self {
+        self.compress_above = Some(bytes);
+        self
+    }
+
+    pub(crate) fn apply(&self, report: &mut Report) {
+        let mut total = 0;
+        let mut skipped = Vec::new();
+        for (mut filename, mut data) in std::mem::take(&mut report.attachments) {
+            if let Some(limit) = self.compress_above
+                && data.len() as u64 > limit
+                && !is_compressed(&data)
+            {
+                match gzip(&data) {
+                    Ok(compressed) if compressed.len() < data.len() => {
+                        filename.push_str(".gz");
+                        data = compressed;
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("failed to compress {filename}: {e}"),
+                }
+            }
+            let size = data.len() as u64;
+            let reason = if let Some(limit) = self.max_file_size
+                && size > limit
+            {
+                format!("over the {} limit per file", format_bytes(limit))
+            } else if let Some(limit) = self.max_total_size
+                && total + size > limit
+            {
+                format!("would take the attachments over {}", format_bytes(limit))
+            } else {
+                total += size;
+                report.attachments.push((filename, data));
+                continue;
+            };
+            skipped.push(format!("- `{filename}` ({}): {reason}", format_bytes(size)));
+        }
+        if !skipped.is_empty() {
+            append_block(
+                &mut report.description,
+                &format!("## Attachments left out\n\n{}", skipped.join("\n")),
+            );
+        }
+    }
+}
+
+/// Whether `data` starts like a gzip, zip, zstd, xz, bzip2, or 7z file.
+fn is_compressed(data: &[u8]) -> bool {
+    const MAGIC: &[&[u8]] = &[
+        b"\x1f\x8b",
+        b"PK\x03\x04",
+        b"\x28\xb5\x2f\xfd",
+        b"\xfd7zXZ\x00",
+        b"BZh",
+        b"7z\xbc\xaf\x27\x1c",
+    ];
+    MAGIC.iter().any(|magic| data.starts_with(magic))
+}
+
+fn gzip(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut policy = AttachmentPolicy::unlimited();
+        policy
+            .compress_above(1000)
+            .max_file_size(2000)
+            .max_total_size(2000);
+        // Already compressed, so left as is.
+        let mut archive = b"PK\x03\x04".to_vec();
+        archive.resize(2500, 0);
+        let mut report = Report::new();
+        report
+            .attachment("core", &vec![0; 100_000])
+            .attachment("small.txt", &[b'a'; 500])
+            .attachment("logs.zip", &archive)
+            .attachment("last.txt", &[b'b'; 900])
+            .attachment("over.txt", &[b'c'; 900]);
+        policy.apply(&mut report);
+
+        let names: Vec<&str> = report.attachments.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["core.gz", "small.txt", "last.txt"]);
+        let core = &report.attachments[0].1;
+        let mut decoded = Vec::new();
+        std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(&core[..]), &mut decoded)
+            .unwrap();
+        assert_eq!(decoded.len(), 100_000);
+        assert!(report.description.contains(
+            "## Attachments left out\n\n- `logs.zip` (2.4 KiB): over the 2.0 KiB limit per file"
+        ));
+        assert!(
+            report
+                .description
+                .contains("- `over.txt` (900 B): would take the attachments over 2.0 KiB")
+        );
+    }
+}
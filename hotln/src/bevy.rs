@@ -0,0 +1,199 @@
+//! Bevy plugin that reports panics and lets game code file bug reports.
+//!
+//! ```ignore
+//! use bevy::prelude::*;
+//! use hotln::bevy::{BevyHotlinePlugin, FileBugReport};
+//!
+//! let mut client = hotln::LinearClient::new("https://worker.example.com");
+//! client.with_token("secret");
+//!
+//! App::new()
+//!     .add_plugins((DefaultPlugins, BevyHotlinePlugin::new(client)))
+//!     .add_systems(Update, report_on_f12)
+//!     .run();
+//!
+//! fn report_on_f12(keys: Res<ButtonInput<KeyCode>>, mut reports: MessageWriter<FileBugReport>) {
+//!     if keys.just_pressed(KeyCode::F12) {
+//!         reports.write(FileBugReport::new("Player stuck", "Fell through the floor."));
+//!     }
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use bevy::app::{App, Plugin, Startup, Update};
+use bevy::ecs::message::{Message, MessageReader};
+use bevy::ecs::resource::Resource;
+use bevy::ecs::system::Res;
+use bevy::render::renderer::RenderAdapterInfo;
+
+use crate::{PanicHook, Report, Reporter, Severity, Submission, info_table};
+
+/// Send this message to file a bug report.
+#[derive(Message, Debug, Clone)]
+pub struct FileBugReport {
+    pub title: String,
+    pub description: String,
+}
+
+impl FileBugReport {
+    pub fn new(title: &str, description: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Installs a [`PanicHook`] and files a report for each [`FileBugReport`].
+///
+/// Both panic and bug reports get a "System Info" table with the engine,
+/// the rows from [`system_info`](crate::system_info), and the GPU adapter
+/// picked by the renderer.
+pub struct BevyHotlinePlugin {
+    reporter: Arc<dyn Reporter + Send + Sync>,
+}
+
+impl BevyHotlinePlugin {
+    pub fn new(reporter: impl Reporter + Send + Sync + 'static) -> Self {
+        Self {
+            reporter: Arc::new(reporter),
+        }
+    }
+}
+
+impl Plugin for BevyHotlinePlugin {
+    fn build(&self, app: &mut App) {
+        let reporter = HotlineReporter {
+            inner: self.reporter.clone(),
+            system_info: Arc::new(Mutex::new(base_system_info())),
+        };
+        PanicHook::new(reporter.clone()).install();
+
+        app.add_message::<FileBugReport>()
+            .insert_resource(reporter)
+            .add_systems(Startup, capture_adapter_info)
+            .add_systems(Update, file_bug_reports);
+    }
+}
+
+/// The plugin's reporter, which appends the captured system info.
+#[derive(Resource, Clone)]
+struct HotlineReporter {
+    inner: Arc<dyn Reporter + Send + Sync>,
+    system_info: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl HotlineReporter {
+    fn with_system_info(&self, report: &Report) -> Report {
+        if !self.inner.privacy_level().collects_context() {
+            return report.clone();
+        }
+        let info = self.system_info.lock().unwrap();
+        let rows: Vec<(&str, &str)> = info.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        let mut report = report.clone();
+        report.text(&info_table("System Info", &rows));
+        report
+    }
+}
+
+impl Reporter for HotlineReporter {
+    fn report(&self, report: &Report) -> Result<String, crate::Error> {
+        self.inner.report(&self.with_system_info(report))
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, crate::Error> {
+        self.inner.submit(&self.with_system_info(report))
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, crate::Error> {
+        self.inner.comment(issue, body)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, crate::Error> {
+        self.inner.update_comment(comment, body)
+    }
+
+    fn privacy_level(&self) -> crate::PrivacyLevel {
+        self.inner.privacy_level()
+    }
+
+    fn spools(&self, report: &Report) -> bool {
+        self.inner.spools(report)
+    }
+}
+
+fn base_system_info() -> Vec<(String, String)> {
+    let mut rows = vec![("Engine".to_string(), "Bevy".to_string())];
+    rows.extend(crate::system_info());
+    rows
+}
+
+// The adapter is only known once the render plugin has initialized.
+fn capture_adapter_info(reporter: Res<HotlineReporter>, adapter: Option<Res<RenderAdapterInfo>>) {
+    let Some(adapter) = adapter else {
+        return;
+    };
+    let mut info = reporter.system_info.lock().unwrap();
+    info.push(("GPU".to_string(), adapter.name.clone()));
+    info.push(("Backend".to_string(), format!("{:?}", adapter.backend)));
+    info.push((
+        "Device Type".to_string(),
+        format!("{:?}", adapter.device_type),
+    ));
+    if !adapter.driver.is_empty() {
+        let driver = format!("{} {}", adapter.driver, adapter.driver_info);
+        info.push(("Driver".to_string(), driver.trim().to_string()));
+    }
+}
+
+// Reporters block on network I/O, so file off the main schedule.
+fn file_bug_reports(reporter: Res<HotlineReporter>, mut messages: MessageReader<FileBugReport>) {
+    for message in messages.read() {
+        let mut report = Report::new();
+        report
+            .title(&message.title)
+            .text(&message.description)
+            .severity(Severity::Bug);
+        reporter.fire_and_forget(report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockReporter;
+    use bevy::ecs::message::Messages;
+
+    #[test]
+    fn test_file_bug_report() {
+        let mock = Arc::new(MockReporter::new());
+        let mut app = App::new();
+        // Build the plugin's parts by hand so no global panic hook is installed.
+        app.add_message::<FileBugReport>()
+            .insert_resource(HotlineReporter {
+                inner: mock.clone(),
+                system_info: Arc::new(Mutex::new(base_system_info())),
+            })
+            .add_systems(Update, file_bug_reports);
+
+        app.world_mut()
+            .resource_mut::<Messages<FileBugReport>>()
+            .write(FileBugReport::new(
+                "Player stuck",
+                "Fell through the floor.",
+            ));
+        app.update();
+
+        for _ in 0..100 {
+            if !mock.reports().is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let reports = mock.reports();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].title, "Player stuck");
+        assert!(reports[0].description.contains("| Engine | Bevy |"));
+    }
+}
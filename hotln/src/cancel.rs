@@ -0,0 +1,81 @@
+//! Abandoning submissions that are no longer wanted, such as an async one
+//! whose future was dropped during shutdown.
+//!
+//! A submission's token is set for the thread it runs on, so the transport
+//! can check it without every reporter passing it along. The transport
+//! checks before each request it would make, and hands the token to the
+//! retry queue with the report; a request already being sent isn't
+//! interrupted.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::Error;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Cancel>> = const { RefCell::new(None) };
+}
+
+/// Set once a submission is cancelled, and shared by everything sending it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// [`Error::Cancelled`] once the submission has been cancelled.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if self.0.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Run `f` with this as the calling thread's token.
+    pub(crate) fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        let result = f();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+        result
+    }
+}
+
+/// The token of the submission running on this thread, if any.
+pub(crate) fn current() -> Option<Cancel> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Run `f` with `cancel` as the calling thread's token, as when a submission
+/// continues on another thread.
+pub(crate) fn scope<T>(cancel: Option<&Cancel>, f: impl FnOnce() -> T) -> T {
+    match cancel {
+        Some(cancel) => cancel.scope(f),
+        None => f(),
+    }
+}
+
+/// [`Error::Cancelled`] if `cancel` has been cancelled.
+pub(crate) fn check(cancel: Option<&Cancel>) -> Result<(), Error> {
+    cancel.map_or(Ok(()), Cancel::check)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope() {
+        assert!(current().is_none());
+        let cancel = Cancel::default();
+        cancel.scope(|| {
+            assert!(check(current().as_ref()).is_ok());
+            cancel.cancel();
+            assert!(matches!(check(current().as_ref()), Err(Error::Cancelled)));
+        });
+        // Only while the submission runs.
+        assert!(current().is_none());
+    }
+}
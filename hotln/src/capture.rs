@@ -0,0 +1,225 @@
+//! Keeping the last output written to stderr, for apps without a logging
+//! framework whose last error messages would otherwise be lost in a crash.
+//!
+//! File descriptor 2 is redirected into a pipe. A background thread copies
+//! everything back to the original stderr, so the terminal sees the same
+//! output, and keeps the tail in a ring buffer. Dropping the capture, or the
+//! thread failing, puts the original stderr back.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// The tail of stderr since [`StderrCapture::start`].
+pub(crate) struct StderrCapture {
+    tail: Arc<Mutex<VecDeque<u8>>>,
+    /// Our own copy of the pipe's read end, for checking what's unread;
+    /// the thread's copy closes when it exits.
+    #[cfg(unix)]
+    pipe: std::os::fd::OwnedFd,
+    /// The stderr to restore on drop.
+    #[cfg(unix)]
+    original: std::os::fd::RawFd,
+}
+
+impl StderrCapture {
+    /// Start teeing stderr, keeping the last `max_bytes`.
+    #[cfg(unix)]
+    pub(crate) fn start(max_bytes: usize) -> std::io::Result<Self> {
+        use std::fs::File;
+        use std::io::{Error, ErrorKind, Read, Write};
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        // Every descriptor here is close-on-exec, so child processes don't
+        // inherit the pipe or the saved stderr. `dup2` clears the flag on
+        // the new descriptor 2, so children still get the redirected stderr.
+        let [read_end, write_end] = cloexec_pipe()?;
+        let dup = |fd| {
+            if fd < 0 {
+                -1
+            } else {
+                unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) }
+            }
+        };
+        // One copy to restore on drop, and one for the thread to write to.
+        let saved = dup(libc::STDERR_FILENO);
+        let copy = dup(saved);
+        let pipe = dup(read_end);
+        if pipe < 0 || unsafe { libc::dup2(write_end, libc::STDERR_FILENO) } < 0 {
+            let e = Error::last_os_error();
+            unsafe {
+                for fd in [read_end, write_end, saved, copy, pipe] {
+                    if fd >= 0 {
+                        libc::close(fd);
+                    }
+                }
+            }
+            return Err(e);
+        }
+        let pipe = unsafe { OwnedFd::from_raw_fd(pipe) };
+        unsafe { libc::close(write_end) };
+        let mut reader = unsafe { File::from_raw_fd(read_end) };
+        let mut original = unsafe { File::from_raw_fd(copy) };
+
+        let tail = Arc::new(Mutex::new(VecDeque::with_capacity(max_bytes)));
+        let buffer = tail.clone();
+        std::thread::Builder::new()
+            .name("hotline-stderr".into())
+            .spawn(move || {
+                let mut chunk = [0u8; 4096];
+                loop {
+                    match reader.read(&mut chunk) {
+                        // Every write end is closed, so stderr was restored.
+                        Ok(0) => return,
+                        Ok(n) => {
+                            let _ = original.write_all(&chunk[..n]);
+                            let Ok(mut tail) = buffer.lock() else { break };
+                            push_tail(&mut tail, &chunk[..n], max_bytes);
+                        }
+                        Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                        Err(_) => break,
+                    }
+                }
+                // Nothing drains the pipe anymore, so writes to stderr would
+                // block or fail: put the original back.
+                unsafe { libc::dup2(original.as_raw_fd(), libc::STDERR_FILENO) };
+            })
+            .inspect_err(|_| unsafe {
+                libc::dup2(saved, libc::STDERR_FILENO);
+                libc::close(saved);
+            })?;
+        Ok(Self {
+            tail,
+            pipe,
+            original: saved,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn start(_max_bytes: usize) -> std::io::Result<Self> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+
+    /// What's been captured, once output already written has been read.
+    pub(crate) fn tail(&self) -> String {
+        self.settle();
+        let tail: Vec<u8> = self
+            .tail
+            .lock()
+            .map(|tail| tail.iter().copied().collect())
+            .unwrap_or_default();
+        String::from_utf8_lossy(&tail).into_owned()
+    }
+
+    /// Give the copying thread a moment to drain the pipe, so a message
+    /// written just before a panic is both captured and shown.
+    #[cfg(unix)]
+    fn settle(&self) {
+        use std::os::fd::AsRawFd;
+
+        for _ in 0..50 {
+            let mut pending: libc::c_int = 0;
+            let ok =
+                unsafe { libc::ioctl(self.pipe.as_raw_fd(), libc::FIONREAD, &mut pending) } == 0;
+            if !ok || pending == 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn settle(&self) {}
+}
+
+#[cfg(unix)]
+impl Drop for StderrCapture {
+    fn drop(&mut self) {
+        // The pipe's last write end closes, so the thread stops too.
+        unsafe {
+            libc::dup2(self.original, libc::STDERR_FILENO);
+            libc::close(self.original);
+        }
+    }
+}
+
+/// A pipe whose ends are both close-on-exec.
+#[cfg(all(unix, not(target_vendor = "apple")))]
+fn cloexec_pipe() -> std::io::Result<[std::os::fd::RawFd; 2]> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(fds)
+}
+
+/// A pipe whose ends are both close-on-exec. Apple platforms have no
+/// `pipe2`, so the flag is set just after the pipe is made.
+#[cfg(target_vendor = "apple")]
+fn cloexec_pipe() -> std::io::Result<[std::os::fd::RawFd; 2]> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    for fd in fds {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+            let e = std::io::Error::last_os_error();
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+            return Err(e);
+        }
+    }
+    Ok(fds)
+}
+
+/// Append `bytes`, keeping only the last `max_bytes`.
+#[cfg(unix)]
+fn push_tail(tail: &mut VecDeque<u8>, bytes: &[u8], max_bytes: usize) {
+    tail.extend(bytes);
+    let excess = tail.len().saturating_sub(max_bytes);
+    tail.drain(..excess);
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_tail() {
+        let mut tail = VecDeque::new();
+        push_tail(&mut tail, b"warning: low disk\n", 15);
+        push_tail(&mut tail, b"error: config missing\n", 15);
+        assert_eq!(
+            tail.iter().copied().collect::<Vec<_>>(),
+            b"config missing\n"
+        );
+    }
+
+    #[test]
+    fn test_capture() {
+        use std::io::Write;
+
+        let stderr_id = || {
+            let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+            assert_eq!(unsafe { libc::fstat(libc::STDERR_FILENO, &mut stat) }, 0);
+            (stat.st_dev, stat.st_ino)
+        };
+        let before = stderr_id();
+        let capture = StderrCapture::start(64 * 1024).unwrap();
+        assert_ne!(stderr_id(), before);
+        // Children inherit the redirected stderr, but not our descriptors.
+        let cloexec = |fd| unsafe { libc::fcntl(fd, libc::F_GETFD) } & libc::FD_CLOEXEC != 0;
+        assert!(!cloexec(libc::STDERR_FILENO));
+        assert!(cloexec(capture.original));
+        assert!(cloexec(std::os::fd::AsRawFd::as_raw_fd(&capture.pipe)));
+        // Written to the descriptor; `eprintln!` would go to the test harness.
+        // Other tests may write to stderr meanwhile, hence `contains`.
+        std::io::stderr()
+            .write_all(b"warning: low disk\nerror: config missing\n")
+            .unwrap();
+        assert!(capture.tail().contains("error: config missing\n"));
+        drop(capture);
+        assert_eq!(stderr_id(), before);
+    }
+}
@@ -0,0 +1,168 @@
+//! Tagging reports filed from CI, so failures in test runs aren't mistaken
+//! for crashes users hit.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{Report, info_table};
+
+type Var = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// Where [`detect`] reads environment variables from: the process
+/// environment by default.
+#[derive(Clone)]
+pub(crate) struct Env(Arc<Var>);
+
+impl Env {
+    /// Read variables with `var` instead of from the process environment.
+    #[cfg(test)]
+    pub(crate) fn new(var: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(var))
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Self(Arc::new(|name| std::env::var(name).ok()))
+    }
+}
+
+impl fmt::Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Env")
+    }
+}
+
+/// The CI job a report was filed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Job {
+    provider: &'static str,
+    url: Option<String>,
+}
+
+impl Job {
+    /// Add the `ci` label and a "CI" table with the provider and job URL.
+    pub(crate) fn tag(&self, report: &mut Report) {
+        let mut rows = vec![("Provider", self.provider)];
+        if let Some(url) = &self.url {
+            rows.push(("Job", url));
+        }
+        report.label("ci").text(&info_table("CI", &rows));
+    }
+}
+
+/// The CI job described by `env`, if any.
+pub(crate) fn detect(env: &Env) -> Option<Job> {
+    detect_from(|name| (env.0)(name).filter(|v| !v.is_empty()))
+}
+
+fn detect_from(var: impl Fn(&str) -> Option<String>) -> Option<Job> {
+    let set = |name: &str| var(name).is_some_and(|v| v != "false" && v != "0");
+    if set("GITHUB_ACTIONS") {
+        let url = match (
+            var("GITHUB_SERVER_URL"),
+            var("GITHUB_REPOSITORY"),
+            var("GITHUB_RUN_ID"),
+        ) {
+            (Some(server), Some(repo), Some(run)) => {
+                Some(format!("{server}/{repo}/actions/runs/{run}"))
+            }
+            _ => None,
+        };
+        return Some(Job {
+            provider: "GitHub Actions",
+            url,
+        });
+    }
+    if set("GITLAB_CI") {
+        return Some(Job {
+            provider: "GitLab CI",
+            url: var("CI_JOB_URL"),
+        });
+    }
+    if set("BUILDKITE") {
+        let url = var("BUILDKITE_BUILD_URL").map(|build| match var("BUILDKITE_JOB_ID") {
+            Some(job) => format!("{build}#{job}"),
+            None => build,
+        });
+        return Some(Job {
+            provider: "Buildkite",
+            url,
+        });
+    }
+    // Most other CI services set `CI`.
+    set("CI").then_some(Job {
+        provider: "CI",
+        url: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let pairs: Vec<(String, String)> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| {
+            pairs
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let job = detect_from(vars(&[
+            ("CI", "true"),
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "acme/app"),
+            ("GITHUB_RUN_ID", "42"),
+        ]))
+        .unwrap();
+        assert_eq!(job.provider, "GitHub Actions");
+        assert_eq!(
+            job.url.as_deref(),
+            Some("https://github.com/acme/app/actions/runs/42")
+        );
+
+        let job = detect_from(vars(&[
+            ("BUILDKITE", "true"),
+            (
+                "BUILDKITE_BUILD_URL",
+                "https://buildkite.com/acme/app/builds/7",
+            ),
+            ("BUILDKITE_JOB_ID", "abc"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            job.url.as_deref(),
+            Some("https://buildkite.com/acme/app/builds/7#abc")
+        );
+
+        assert_eq!(detect_from(vars(&[("CI", "false")])), None);
+        assert_eq!(detect_from(vars(&[])), None);
+    }
+
+    #[test]
+    fn test_tag() {
+        let job = detect_from(vars(&[
+            ("GITLAB_CI", "true"),
+            ("CI_JOB_URL", "https://gitlab.com/acme/app/-/jobs/9"),
+        ]))
+        .unwrap();
+        let mut report = Report::new();
+        report.title("flaky test");
+        job.tag(&mut report);
+        assert_eq!(report.labels, ["ci"]);
+        assert!(
+            report
+                .description
+                .contains("| Job | https://gitlab.com/acme/app/-/jobs/9 |")
+        );
+    }
+}
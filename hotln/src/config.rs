@@ -0,0 +1,297 @@
+use std::path::Path;
+
+use crate::{
+    AttachmentPolicy, Error, GitHubClient, Layout, LinearClient, PrivacyLevel, Redaction, Reporter,
+    Secrets,
+};
+
+/// Which tracker reports are filed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+pub enum Backend {
+    #[default]
+    #[serde(rename = "linear")]
+    Linear,
+    #[serde(rename = "github")]
+    GitHub,
+}
+
+/// Reporter settings loaded from a file or the environment.
+///
+/// ```no_run
+/// let reporter = hotln::Config::from_file("hotline.toml")?.into_reporter();
+/// # Ok::<(), hotln::Error>(())
+/// ```
+///
+/// Files use the field names below, in TOML or JSON:
+///
+/// ```toml
+/// backend = "github"
+/// proxy_url = "https://worker.example.com"
+/// token = "secret"
+/// labels = ["desktop"]
+/// secrets = "refuse"
+/// privacy_level = "minimal"
+/// layout = "description, backtrace, logs, system_info"
+///
+/// [attachments]
+/// max_file_size = 10485760
+///
+/// [redact]
+/// HOME = "drop"
+/// machine_name = "hash"
+/// ```
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct Config {
+    #[serde(default)]
+    pub backend: Backend,
+    pub proxy_url: String,
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Labels applied to every report.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub app_version: Option<String>,
+    /// See [`LinearClient::with_pinned_cert`].
+    #[serde(default)]
+    pub pinned_certs: Vec<String>,
+    /// See [`LinearClient::socks_proxy`].
+    #[serde(default)]
+    pub socks_proxy: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Per-key rules applied to every report. See [`Redaction`].
+    #[serde(default)]
+    pub redact: Redaction,
+    /// `allow`, `redact` (the default), or `refuse`. See [`Secrets`].
+    #[serde(default)]
+    pub secrets: Secrets,
+    /// `full` (the default), `minimal`, or `anonymous`. See [`PrivacyLevel`].
+    #[serde(default)]
+    pub privacy_level: PrivacyLevel,
+    /// Size limits for attachments. See [`AttachmentPolicy`].
+    #[serde(default)]
+    pub attachments: AttachmentPolicy,
+    /// The sections of the issue body, in order, such as
+    /// `"description, backtrace, system_info"`. See [`Layout`].
+    #[serde(default)]
+    pub layout: Option<Layout>,
+}
+
+impl Config {
+    pub fn new(backend: Backend, proxy_url: &str) -> Self {
+        Self {
+            backend,
+            proxy_url: proxy_url.to_string(),
+            token: None,
+            labels: Vec::new(),
+            app_version: None,
+            pinned_certs: Vec::new(),
+            socks_proxy: None,
+            dry_run: false,
+            redact: Redaction::default(),
+            secrets: Secrets::default(),
+            privacy_level: PrivacyLevel::default(),
+            attachments: AttachmentPolicy::default(),
+            layout: None,
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, Error> {
+        toml::from_str(text).map_err(|e| Error::Config(e.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, Error> {
+        serde_json::from_str(text).map_err(|e| Error::Config(e.to_string()))
+    }
+
+    /// Load a `.json` file as JSON and anything else as TOML.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("reading {}: {e}", path.display())))?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+    }
+
+    /// Read `HOTLINE_PROXY_URL` (required), `HOTLINE_PROXY_TOKEN`,
+    /// `HOTLINE_BACKEND`, `HOTLINE_LABELS` (comma separated),
+    /// `HOTLINE_APP_VERSION`, `HOTLINE_PINNED_CERTS` (comma separated),
+    /// `HOTLINE_SOCKS_PROXY`, `HOTLINE_PRIVACY_LEVEL`, and `HOTLINE_LAYOUT`.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_vars(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let proxy_url = var("HOTLINE_PROXY_URL")
+            .ok_or_else(|| Error::Config("HOTLINE_PROXY_URL is not set".to_string()))?;
+        let backend = match var("HOTLINE_BACKEND").as_deref() {
+            None | Some("linear") => Backend::Linear,
+            Some("github") => Backend::GitHub,
+            Some(other) => {
+                return Err(Error::Config(format!(
+                    "unknown HOTLINE_BACKEND {other:?}, expected linear or github"
+                )));
+            }
+        };
+        let mut config = Self::new(backend, &proxy_url);
+        config.token = var("HOTLINE_PROXY_TOKEN");
+        config.app_version = var("HOTLINE_APP_VERSION");
+        config.labels = split_list(var("HOTLINE_LABELS"));
+        config.pinned_certs = split_list(var("HOTLINE_PINNED_CERTS"));
+        config.socks_proxy = var("HOTLINE_SOCKS_PROXY");
+        config.privacy_level = match var("HOTLINE_PRIVACY_LEVEL").as_deref() {
+            None | Some("full") => PrivacyLevel::Full,
+            Some("minimal") => PrivacyLevel::Minimal,
+            Some("anonymous") => PrivacyLevel::Anonymous,
+            Some(other) => {
+                return Err(Error::Config(format!(
+                    "unknown HOTLINE_PRIVACY_LEVEL {other:?}, expected full, minimal, or anonymous"
+                )));
+            }
+        };
+        config.layout = var("HOTLINE_LAYOUT").map(|spec| spec.parse()).transpose()?;
+        Ok(config)
+    }
+
+    /// Build the client for the configured backend.
+    pub fn into_reporter(self) -> Box<dyn Reporter + Send + Sync> {
+        let labels: Vec<&str> = self.labels.iter().map(String::as_str).collect();
+        macro_rules! configure {
+            ($client:expr) => {{
+                let mut client = $client;
+                if let Some(token) = &self.token {
+                    client.with_token(token);
+                }
+                if let Some(version) = &self.app_version {
+                    client.app_version(version);
+                }
+                for pin in &self.pinned_certs {
+                    client.with_pinned_cert(pin);
+                }
+                if let Some(proxy) = &self.socks_proxy {
+                    client.socks_proxy(proxy);
+                }
+                if let Some(layout) = &self.layout {
+                    client.layout(layout.clone());
+                }
+                client
+                    .labels(&labels)
+                    .dry_run(self.dry_run)
+                    .redact(self.redact.clone())
+                    .secrets(self.secrets)
+                    .with_privacy_level(self.privacy_level)
+                    .attachment_policy(self.attachments);
+                Box::new(client)
+            }};
+        }
+        match self.backend {
+            Backend::Linear => configure!(LinearClient::new(&self.proxy_url)),
+            Backend::GitHub => configure!(GitHubClient::new(&self.proxy_url)),
+        }
+    }
+}
+
+fn split_list(value: Option<String>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let config = Config::from_toml(
+            "backend = \"github\"\nproxy_url = \"https://proxy\"\nlabels = [\"desktop\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.backend, Backend::GitHub);
+        assert_eq!(config.proxy_url, "https://proxy");
+        assert_eq!(config.labels, ["desktop"]);
+        assert_eq!(config.token, None);
+        assert!(config.redact.is_empty());
+
+        let config =
+            Config::from_toml("proxy_url = \"x\"\n[redact]\nHOME = \"drop\"\n\"*\" = \"pass\"\n")
+                .unwrap();
+        let mut redact = Redaction::new();
+        redact
+            .rule("HOME", crate::RedactRule::Drop)
+            .rule("*", crate::RedactRule::Pass);
+        assert_eq!(config.redact, redact);
+
+        let config =
+            Config::from_toml("proxy_url = \"x\"\nlayout = \"backtrace, description\"").unwrap();
+        assert_eq!(
+            config.layout,
+            Some(Layout::new(&[
+                crate::Section::Backtrace,
+                crate::Section::Description
+            ]))
+        );
+
+        assert!(matches!(
+            Config::from_toml("backend = \"jira\"\nproxy_url = \"x\""),
+            Err(Error::Config(_))
+        ));
+        assert!(Config::from_toml("proxy_url = \"x\"\nlayout = \"stack\"").is_err());
+    }
+
+    #[test]
+    fn test_from_json() {
+        let config = Config::from_json(r#"{"proxy_url": "https://proxy", "token": "t"}"#).unwrap();
+        assert_eq!(config.backend, Backend::Linear);
+        assert_eq!(config.token.as_deref(), Some("t"));
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let vars = |name: &str| match name {
+            "HOTLINE_PROXY_URL" => Some("https://proxy".to_string()),
+            "HOTLINE_BACKEND" => Some("github".to_string()),
+            "HOTLINE_LABELS" => Some("a, b,".to_string()),
+            _ => None,
+        };
+        let config = Config::from_vars(vars).unwrap();
+        assert_eq!(config.backend, Backend::GitHub);
+        assert_eq!(config.labels, ["a", "b"]);
+
+        assert!(Config::from_vars(|_| None).is_err());
+    }
+
+    #[test]
+    fn test_into_reporter() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/github")
+            .match_header("Authorization", "Bearer t")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({ "labels": ["desktop"] }).to_string(),
+            ))
+            .with_status(200)
+            .with_body(serde_json::json!({ "url": "https://github.com/o/r/issues/1" }).to_string())
+            .create();
+
+        let mut config = Config::new(Backend::GitHub, &server.url());
+        config.token = Some("t".to_string());
+        config.labels = vec!["desktop".to_string()];
+        let url = config
+            .into_reporter()
+            .report(&crate::Report::new())
+            .unwrap();
+
+        assert_eq!(url, "https://github.com/o/r/issues/1");
+        mock.assert();
+    }
+}
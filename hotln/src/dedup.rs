@@ -0,0 +1,697 @@
+//! Filing each distinct problem at most once per time window.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crate::{Error, PrivacyLevel, Report, Reporter, Severity, Submission};
+
+/// Most fingerprints kept in a registry file; the oldest issues go first.
+const MAX_REGISTRY_ENTRIES: usize = 1000;
+
+/// First line of occurrence comments. The proxies only let clients update
+/// comments that start with it.
+pub(crate) const OCCURRENCES_HEADING: &str = "**Hotline occurrences**";
+
+/// A [`Reporter`] that files reports with the same fingerprint at most once
+/// per window, and hands back the earlier issue for repeats.
+///
+/// Reports are grouped by [`Report::fingerprint`], or by title when they
+/// have none. By default each group is filed once per process; with a
+/// [`window`](Self::window), the next occurrence after the window has passed
+/// files a new issue. Repeats within the window return
+/// [`Submission::Deduplicated`], or the first report's
+/// [`Submission::Queued`] or [`Submission::Spooled`] until it has been
+/// filed, and with
+/// [`comment_occurrences`](Self::comment_occurrences) are counted in a
+/// comment on the earlier issue, or with [`digest`](Self::digest) in one
+/// summary comment per period. A [`registry`](Self::registry) keeps the
+/// fingerprints and issues across restarts.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use hotln::{Dedup, LinearClient, Severity};
+///
+/// let mut reporter = Dedup::new(LinearClient::new("https://your-proxy.example.com"));
+/// reporter
+///     .window(Duration::from_secs(24 * 60 * 60))
+///     .severity_window(Severity::Crash, Duration::ZERO)
+///     .comment_occurrences();
+/// ```
+pub struct Dedup<R = crate::Destination> {
+    pub(crate) inner: R,
+    window: Option<Duration>,
+    severities: Vec<(Severity, Duration)>,
+    comment: bool,
+    digest: Option<Duration>,
+    app_version: Option<String>,
+    seen: Arc<Mutex<HashMap<String, Seen>>>,
+    registry: Option<PathBuf>,
+}
+
+/// The last issue filed for a fingerprint.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Seen {
+    filed: SystemTime,
+    /// Empty until the report is filed, and for queued reports.
+    url: String,
+    /// While `url` is empty, what repeats return: the first report's
+    /// `Queued` or `Spooled` submission, or `Queued` while it is being sent.
+    #[serde(skip)]
+    waiting: Option<Submission>,
+    /// Repeats since the issue was filed.
+    repeats: u32,
+    #[serde(default)]
+    last_seen: Option<SystemTime>,
+    /// App versions the problem was seen in, oldest first.
+    #[serde(default)]
+    versions: Vec<String>,
+    /// The occurrence comment on the issue, once there is one.
+    #[serde(default)]
+    comment: Option<String>,
+    /// The latest repeat's reference code.
+    #[serde(default)]
+    reference: String,
+    /// Repeats not yet in a digest, and when the first of them happened.
+    #[serde(default)]
+    pending: u32,
+    #[serde(default)]
+    pending_since: Option<SystemTime>,
+}
+
+impl Seen {
+    /// The occurrence comment's body.
+    fn summary(&self) -> String {
+        let count = (self.repeats + 1).to_string();
+        self.body(&count)
+    }
+
+    /// A digest comment's body for the pending repeats, which are then
+    /// cleared.
+    fn take_digest(&mut self) -> String {
+        let since = crate::rfc3339(self.pending_since.unwrap_or(self.filed));
+        let count = format!(
+            "{} since {since}, {} in total",
+            self.pending,
+            self.repeats + 1
+        );
+        self.pending = 0;
+        self.pending_since = None;
+        self.body(&count)
+    }
+
+    fn body(&self, count: &str) -> String {
+        let mut body = format!(
+            "{OCCURRENCES_HEADING}\n\n- Occurrences: {count}\n- Last seen: {}",
+            crate::rfc3339(self.last_seen.unwrap_or(self.filed)),
+        );
+        if !self.versions.is_empty() {
+            body.push_str(&format!("\n- Versions: {}", self.versions.join(", ")));
+        }
+        if !self.reference.is_empty() {
+            body.push_str(&format!("\n- Latest reference: `{}`", self.reference));
+        }
+        body
+    }
+}
+
+impl<R: Reporter> Dedup<R> {
+    pub fn new(reporter: R) -> Self {
+        Self {
+            inner: reporter,
+            window: None,
+            severities: Vec::new(),
+            comment: false,
+            digest: None,
+            app_version: None,
+            seen: Arc::default(),
+            registry: None,
+        }
+    }
+
+    /// Remember fingerprints and their issues in a JSON file at `path`, so
+    /// repeats after a restart still find the earlier issue, without
+    /// searching the tracker. Fingerprints already in the file are loaded
+    /// now; a missing or unreadable file starts empty.
+    pub fn registry(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        let path = path.into();
+        match load(&path) {
+            Ok(entries) => *self.seen.lock().unwrap_or_else(|e| e.into_inner()) = entries,
+            Err(e) => tracing::warn!("cannot read dedup registry {}: {e}", path.display()),
+        }
+        self.registry = Some(path);
+        self
+    }
+
+    /// File each fingerprint again once `window` has passed since its last
+    /// issue, instead of once per process.
+    pub fn window(&mut self, window: Duration) -> &mut Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Use `window` for reports of `severity`. [`Duration::ZERO`] files
+    /// every one of them, so urgent crashes are never held back.
+    pub fn severity_window(&mut self, severity: Severity, window: Duration) -> &mut Self {
+        self.severities.retain(|(s, _)| *s != severity);
+        self.severities.push((severity, window));
+        self
+    }
+
+    /// Keep a comment on the earlier issue counting repeats within the
+    /// window, with when the problem was last seen and in which versions.
+    /// The comment is posted on the first repeat and updated after that.
+    /// See [`Reporter::comment`].
+    pub fn comment_occurrences(&mut self) -> &mut Self {
+        self.comment = true;
+        self
+    }
+
+    /// Collect repeats and post one summary comment per issue at most once
+    /// per `period`, such as a day, instead of updating a comment for each
+    /// one. Digests go out with the first repeat after the period has
+    /// passed; call [`flush_digests`](Self::flush_digests) to post the rest,
+    /// such as before exiting.
+    pub fn digest(&mut self, period: Duration) -> &mut Self {
+        self.digest = Some(period);
+        self
+    }
+
+    /// Post digests for every issue with repeats not yet posted, without
+    /// waiting for the period to pass.
+    pub fn flush_digests(&self) {
+        let digests: Vec<(String, String)> = {
+            let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+            let digests = seen
+                .values_mut()
+                .filter(|last| last.pending > 0 && !last.url.is_empty())
+                .map(|last| (last.url.clone(), last.take_digest()))
+                .collect();
+            self.save(&seen);
+            digests
+        };
+        for (issue, body) in digests {
+            if let Err(e) = self.inner.comment(&issue, &body) {
+                tracing::warn!("failed to comment on {issue}: {e}");
+            }
+        }
+    }
+
+    /// A callback for the inner client's `on_result`, which records the
+    /// issue once a [`Submission::Queued`] report is filed, so repeats are
+    /// counted on it from then on. Call it after [`registry`](Self::registry).
+    ///
+    /// ```no_run
+    /// use hotln::{Dedup, LinearClient};
+    ///
+    /// let mut reporter = Dedup::new(LinearClient::new("https://your-proxy.example.com"));
+    /// let on_delivery = reporter.on_delivery();
+    /// reporter.get_mut().retry_queue(16).on_result(on_delivery);
+    /// ```
+    pub fn on_delivery(&self) -> impl Fn(&str, Result<Submission, Error>) + Send + Sync + 'static {
+        let seen = self.seen.clone();
+        let registry = self.registry.clone();
+        move |reference, result| {
+            let mut seen = seen.lock().unwrap_or_else(|e| e.into_inner());
+            let key = seen.iter().find_map(|(key, last)| match &last.waiting {
+                Some(Submission::Queued(r) | Submission::Spooled(r)) if r == reference => {
+                    Some(key.clone())
+                }
+                _ => None,
+            });
+            let Some(key) = key else { return };
+            match result {
+                Ok(Submission::Created(url) | Submission::Deduplicated(url)) => {
+                    if let Some(last) = seen.get_mut(&key) {
+                        last.url = url;
+                        last.waiting = None;
+                    }
+                }
+                Ok(submission @ (Submission::Queued(_) | Submission::Spooled(_))) => {
+                    if let Some(last) = seen.get_mut(&key) {
+                        last.waiting = Some(submission);
+                    }
+                }
+                // Let the next occurrence try again.
+                Ok(Submission::Dropped) | Err(_) => {
+                    seen.remove(&key);
+                }
+            }
+            if let Some(path) = &registry
+                && let Err(e) = save(path, &seen)
+            {
+                tracing::warn!("cannot write dedup registry {}: {e}", path.display());
+            }
+        }
+    }
+
+    /// The wrapped reporter, such as to set its
+    /// [`on_result`](crate::LinearClient::on_result) callback.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// The app version to list in occurrence comments.
+    pub fn app_version(&mut self, version: &str) -> &mut Self {
+        self.app_version = Some(version.to_string());
+        self
+    }
+
+    fn window_for(&self, severity: Option<Severity>) -> Option<Duration> {
+        self.severities
+            .iter()
+            .find(|(s, _)| Some(*s) == severity)
+            .map(|(_, window)| *window)
+            .or(self.window)
+    }
+
+    /// Update the issue's occurrence comment, or post one if it has none or
+    /// the update fails, as when the comment was deleted.
+    fn count_occurrence(&self, key: &str, issue: &str, comment: Option<&str>, body: &str) {
+        if let Some(comment) = comment {
+            match self.inner.update_comment(comment, body) {
+                Ok(_) => return,
+                Err(e) => tracing::warn!("failed to update {comment}: {e}"),
+            }
+        }
+        match self.inner.comment(issue, body) {
+            Ok(comment) => {
+                let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(last) = seen.get_mut(key) {
+                    last.comment = Some(comment);
+                }
+                self.save(&seen);
+            }
+            Err(e) => tracing::warn!("failed to comment on {issue}: {e}"),
+        }
+    }
+
+    fn post_digest(&self, issue: &str, body: &str) {
+        if let Err(e) = self.inner.comment(issue, body) {
+            tracing::warn!("failed to comment on {issue}: {e}");
+        }
+    }
+
+    fn save(&self, seen: &HashMap<String, Seen>) {
+        if let Some(path) = &self.registry
+            && let Err(e) = save(path, seen)
+        {
+            tracing::warn!("cannot write dedup registry {}: {e}", path.display());
+        }
+    }
+}
+
+impl<R: Reporter> Reporter for Dedup<R> {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        self.submit(report).map(Submission::into_url)
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        let key = report.fingerprint.as_ref().unwrap_or(&report.title);
+        let window = self.window_for(report.severity);
+        let now = SystemTime::now();
+        let lookup = {
+            let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+            match seen.get_mut(key) {
+                Some(last)
+                    if window.is_none_or(|window| {
+                        now.duration_since(last.filed).unwrap_or_default() < window
+                    }) =>
+                {
+                    last.repeats += 1;
+                    last.last_seen = Some(now);
+                    last.reference = report.reference.clone();
+                    if let Some(version) = &self.app_version
+                        && !last.versions.contains(version)
+                    {
+                        last.versions.push(version.clone());
+                    }
+                    let note = match self.digest {
+                        // Pending repeats wait for the issue's URL.
+                        Some(period) if !last.url.is_empty() => {
+                            last.pending += 1;
+                            let since = *last.pending_since.get_or_insert(now);
+                            let due = now.duration_since(since).unwrap_or_default() >= period;
+                            due.then(|| Note::Digest(last.take_digest()))
+                        }
+                        Some(_) => {
+                            last.pending += 1;
+                            last.pending_since.get_or_insert(now);
+                            None
+                        }
+                        None if self.comment && !last.url.is_empty() => {
+                            Some(Note::Summary(last.comment.clone(), last.summary()))
+                        }
+                        None => None,
+                    };
+                    let submission = match &last.waiting {
+                        Some(waiting) if last.url.is_empty() => waiting.clone(),
+                        _ => Submission::Deduplicated(last.url.clone()),
+                    };
+                    let lookup = Lookup::Repeat(last.url.clone(), note, submission);
+                    self.save(&seen);
+                    lookup
+                }
+                last => {
+                    // Repeats held for a digest still go to the expired
+                    // issue, rather than being lost with its entry.
+                    let digest = last
+                        .filter(|last| last.pending > 0 && !last.url.is_empty())
+                        .map(|last| (last.url.clone(), last.take_digest()));
+                    // Claimed before filing, so concurrent repeats aren't
+                    // filed twice.
+                    let claim = Seen {
+                        filed: now,
+                        url: String::new(),
+                        waiting: Some(Submission::Queued(report.reference.clone())),
+                        repeats: 0,
+                        last_seen: None,
+                        versions: self.app_version.iter().cloned().collect(),
+                        comment: None,
+                        reference: String::new(),
+                        pending: 0,
+                        pending_since: None,
+                    };
+                    seen.insert(key.clone(), claim);
+                    Lookup::New(digest)
+                }
+            }
+        };
+        match lookup {
+            Lookup::Repeat(url, note, submission) => {
+                match note {
+                    Some(Note::Summary(comment, body)) => {
+                        self.count_occurrence(key, &url, comment.as_deref(), &body);
+                    }
+                    Some(Note::Digest(body)) => self.post_digest(&url, &body),
+                    None => {}
+                }
+                return Ok(submission);
+            }
+            Lookup::New(Some((url, body))) => self.post_digest(&url, &body),
+            Lookup::New(None) => {}
+        }
+        let result = self.inner.submit(report);
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        match &result {
+            Ok(Submission::Created(url) | Submission::Deduplicated(url)) => {
+                if let Some(last) = seen.get_mut(key) {
+                    last.url = url.clone();
+                    last.waiting = None;
+                }
+                self.save(&seen);
+            }
+            // Repeats wait on this report until it has been filed.
+            Ok(submission @ (Submission::Queued(_) | Submission::Spooled(_))) => {
+                if let Some(last) = seen.get_mut(key) {
+                    last.waiting = Some(submission.clone());
+                }
+            }
+            // Let the next occurrence try again.
+            Ok(Submission::Dropped) | Err(_) => {
+                seen.remove(key);
+            }
+        }
+        result
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        self.inner.comment(issue, body)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        self.inner.update_comment(comment, body)
+    }
+
+    fn privacy_level(&self) -> PrivacyLevel {
+        self.inner.privacy_level()
+    }
+
+    fn spools(&self, report: &Report) -> bool {
+        self.inner.spools(report)
+    }
+}
+
+/// Whether a report's fingerprint was seen within its window.
+enum Lookup {
+    /// A repeat, with the earlier issue's URL, what to post about it, and
+    /// what to return.
+    Repeat(String, Option<Note>, Submission),
+    /// A new issue is due, after posting the digest still pending for the
+    /// expired one, as `(issue, body)`.
+    New(Option<(String, String)>),
+}
+
+/// What to post about a repeat.
+enum Note {
+    /// Update the occurrence comment, if there is one, to this body.
+    Summary(Option<String>, String),
+    /// Post this digest as a new comment.
+    Digest(String),
+}
+
+fn load(path: &Path) -> Result<HashMap<String, Seen>, String> {
+    match std::fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Write the filed entries, skipping claims still being filed and queued
+/// reports, which have no URL to comment on.
+fn save(path: &Path, seen: &HashMap<String, Seen>) -> Result<(), String> {
+    let mut entries: Vec<(&String, &Seen)> = seen
+        .iter()
+        .filter(|(_, last)| !last.url.is_empty())
+        .collect();
+    entries.sort_by_key(|(_, last)| std::cmp::Reverse(last.filed));
+    entries.truncate(MAX_REGISTRY_ENTRIES);
+    let entries: HashMap<_, _> = entries.into_iter().collect();
+    let data = serde_json::to_vec_pretty(&entries).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    // Rename into place so a crash mid-write keeps the old registry.
+    let tmp = path.with_extension("tmp");
+    let _ = std::fs::remove_file(&tmp);
+    crate::spool::write_private(&tmp, &data).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_once_per_process() {
+        let mock = Arc::new(MockReporter::new());
+        let dedup = Dedup::new(mock.clone());
+        let mut report = Report::new();
+        report.title("boom");
+
+        let first = dedup.submit(&report).unwrap();
+        assert_eq!(
+            first,
+            Submission::Created("https://hotln.test/issue/1".into())
+        );
+        assert_eq!(
+            dedup.submit(&report).unwrap(),
+            Submission::Deduplicated("https://hotln.test/issue/1".into())
+        );
+        dedup.submit(Report::new().title("other")).unwrap();
+        assert_eq!(mock.reports().len(), 2);
+        assert!(mock.comments().is_empty());
+    }
+
+    #[test]
+    fn test_window() {
+        let mock = Arc::new(MockReporter::new());
+        let mut dedup = Dedup::new(mock.clone());
+        dedup
+            .window(Duration::from_secs(3600))
+            .severity_window(Severity::Crash, Duration::ZERO)
+            .comment_occurrences();
+
+        let mut bug = Report::new();
+        bug.title("slow").severity(Severity::Bug);
+        bug.fingerprint = Some("abc".into());
+        dedup.submit(&bug).unwrap();
+        dedup.app_version("1.1.0");
+        dedup.submit(&bug).unwrap();
+        dedup.app_version("1.2.0");
+        dedup.submit(&bug).unwrap();
+        assert_eq!(mock.reports().len(), 1);
+        let comments = mock.comments();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].0, "https://hotln.test/issue/1");
+        let body = &comments[0].1;
+        assert!(body.starts_with(OCCURRENCES_HEADING));
+        assert!(body.contains("- Occurrences: 3\n"));
+        assert!(body.contains("- Versions: 1.1.0, 1.2.0\n"));
+
+        let mut crash = Report::new();
+        crash.title("panic").severity(Severity::Crash);
+        dedup.submit(&crash).unwrap();
+        dedup.submit(&crash).unwrap();
+        assert_eq!(mock.reports().len(), 3);
+    }
+
+    /// Queues every report, as a client with a retry queue does while the
+    /// proxy is down.
+    struct Queueing(MockReporter);
+
+    impl Reporter for Queueing {
+        fn report(&self, report: &Report) -> Result<String, Error> {
+            self.submit(report).map(Submission::into_url)
+        }
+
+        fn submit(&self, report: &Report) -> Result<Submission, Error> {
+            self.0.report(report)?;
+            Ok(Submission::Queued(report.reference.clone()))
+        }
+
+        fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+            self.0.comment(issue, body)
+        }
+    }
+
+    #[test]
+    fn test_queued() {
+        let mut dedup = Dedup::new(Queueing(MockReporter::new()));
+        dedup.comment_occurrences();
+        let on_delivery = dedup.on_delivery();
+        let mut first = Report::new();
+        first.title("boom");
+        let queued = Submission::Queued(first.reference.clone());
+
+        assert_eq!(dedup.submit(&first).unwrap(), queued);
+        // Repeats wait on the first report, with nothing to comment on yet.
+        assert_eq!(dedup.submit(Report::new().title("boom")).unwrap(), queued);
+        assert_eq!(dedup.inner.0.reports().len(), 1);
+        assert!(dedup.inner.0.comments().is_empty());
+
+        // Another report's delivery changes nothing.
+        on_delivery(
+            "R-OTHER",
+            Ok(Submission::Created("https://x.test/9".into())),
+        );
+        assert_eq!(dedup.submit(Report::new().title("boom")).unwrap(), queued);
+
+        on_delivery(
+            &first.reference,
+            Ok(Submission::Created("https://hotln.test/issue/1".into())),
+        );
+        assert_eq!(
+            dedup.submit(Report::new().title("boom")).unwrap(),
+            Submission::Deduplicated("https://hotln.test/issue/1".into())
+        );
+        let comments = dedup.inner.0.comments();
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].1.contains("- Occurrences: 4\n"));
+
+        // A report the queue gave up on is filed by the next occurrence.
+        let mut other = Report::new();
+        other.title("other");
+        dedup.submit(&other).unwrap();
+        on_delivery(
+            &other.reference,
+            Err(Error::Timeout(Duration::from_secs(5))),
+        );
+        dedup.submit(Report::new().title("other")).unwrap();
+        assert_eq!(dedup.inner.0.reports().len(), 3);
+    }
+
+    #[test]
+    fn test_retries_failures() {
+        let mut mock = MockReporter::new();
+        mock.with_error(503, "unavailable");
+        let mock = Arc::new(mock);
+        let dedup = Dedup::new(mock.clone());
+
+        assert!(dedup.submit(Report::new().title("boom")).is_err());
+        assert!(dedup.submit(Report::new().title("boom")).is_err());
+        assert_eq!(mock.reports().len(), 2);
+    }
+
+    #[test]
+    fn test_registry() {
+        let dir = crate::spool::temp_dir("dedup");
+        let path = dir.join("issues.json");
+        let mut report = Report::new();
+        report.title("boom");
+        report.fingerprint = Some("abc".into());
+
+        let mock = Arc::new(MockReporter::new());
+        let mut dedup = Dedup::new(mock.clone());
+        dedup.registry(&path);
+        dedup.submit(&report).unwrap();
+
+        // A later run finds the issue filed by the first.
+        let mock = Arc::new(MockReporter::new());
+        let mut dedup = Dedup::new(mock.clone());
+        dedup.registry(&path).comment_occurrences();
+        assert_eq!(
+            dedup.submit(&report).unwrap(),
+            Submission::Deduplicated("https://hotln.test/issue/1".into())
+        );
+        assert!(mock.reports().is_empty());
+        assert_eq!(mock.comments()[0].0, "https://hotln.test/issue/1");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_digest() {
+        let mock = Arc::new(MockReporter::new());
+        let mut dedup = Dedup::new(mock.clone());
+        dedup.digest(Duration::from_secs(24 * 60 * 60));
+        let mut report = Report::new();
+        report.title("boom");
+
+        for _ in 0..4 {
+            dedup.submit(&report).unwrap();
+        }
+        assert!(mock.comments().is_empty());
+
+        dedup.flush_digests();
+        let comments = mock.comments();
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].1.contains("- Occurrences: 3 since "));
+        assert!(comments[0].1.contains(", 4 in total\n"));
+        dedup.flush_digests();
+        assert_eq!(mock.comments().len(), 1);
+    }
+
+    #[test]
+    fn test_digest_on_expiry() {
+        let mock = Arc::new(MockReporter::new());
+        let mut dedup = Dedup::new(mock.clone());
+        dedup
+            .window(Duration::from_secs(60 * 60))
+            .digest(Duration::from_secs(24 * 60 * 60));
+        let mut report = Report::new();
+        report.title("boom");
+        for _ in 0..3 {
+            dedup.submit(&report).unwrap();
+        }
+        assert!(mock.comments().is_empty());
+
+        // The window passes with two repeats still waiting for a digest.
+        if let Some(last) = dedup.seen.lock().unwrap().get_mut("boom") {
+            last.filed -= Duration::from_secs(2 * 60 * 60);
+        }
+        assert_eq!(
+            dedup.submit(&report).unwrap(),
+            Submission::Created("https://hotln.test/issue/2".into())
+        );
+        let comments = mock.comments();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].0, "https://hotln.test/issue/1");
+        assert!(comments[0].1.contains("- Occurrences: 2 since "));
+        assert!(comments[0].1.contains(", 3 in total\n"));
+    }
+}
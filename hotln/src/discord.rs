@@ -0,0 +1,227 @@
+//! Posting a summary of each report to a Discord channel.
+
+use std::time::Duration;
+
+use crate::prepare::{Pipeline, prepare};
+use crate::router::Destination;
+use crate::transport::Transport;
+use crate::{
+    Error, PrivacyLevel, Redaction, Report, Reporter, Secrets, Severity, Submission, post_webhook,
+};
+
+/// Discord rejects embeds with longer titles.
+const MAX_TITLE: usize = 256;
+
+/// Posts an embed summarizing each report to a Discord webhook.
+///
+/// Like [`SlackSink`](crate::SlackSink), it either only notifies the channel,
+/// returning the report's [`reference`](Report::reference), or is chained
+/// [`after`](Self::after) another reporter and links the embed to the issue:
+///
+/// ```no_run
+/// let linear = hotln::LinearClient::new("https://your-proxy.example.com");
+/// let mut discord = hotln::DiscordSink::new("https://discord.com/api/webhooks/123/abc");
+/// discord.after(linear);
+/// hotln::PanicHook::new(discord).install();
+/// ```
+pub struct DiscordSink {
+    webhook: String,
+    inner: Option<Destination>,
+    pipeline: Pipeline,
+    transport: Transport,
+}
+
+impl DiscordSink {
+    pub fn new(webhook_url: &str) -> Self {
+        Self {
+            webhook: webhook_url.to_string(),
+            inner: None,
+            pipeline: Pipeline::default(),
+            transport: Transport::default(),
+        }
+    }
+
+    /// File reports with `reporter` first, then post an embed linking to the
+    /// issue. Its result is returned even if posting to Discord fails, and
+    /// reports it deduplicates into an existing issue aren't posted again.
+    pub fn after(&mut self, reporter: impl Reporter + Send + Sync + 'static) -> &mut Self {
+        self.inner = Some(Box::new(reporter));
+        self
+    }
+
+    /// Give up on posting after `deadline`.
+    pub fn with_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.transport.deadline(deadline);
+        self
+    }
+
+    /// Route requests through a SOCKS proxy, such as `socks5://127.0.0.1:9050`.
+    pub fn socks_proxy(&mut self, url: &str) -> &mut Self {
+        self.transport.socks_proxy(url);
+        self
+    }
+
+    /// Apply these redaction rules to the embed before it is posted.
+    pub fn redact(&mut self, redaction: Redaction) -> &mut Self {
+        self.pipeline.redaction = redaction;
+        self
+    }
+
+    /// What to do with titles that seem to contain credentials. By default
+    /// they are replaced with `[REDACTED]`.
+    pub fn secrets(&mut self, policy: Secrets) -> &mut Self {
+        self.pipeline.secrets = policy;
+        self
+    }
+
+    /// Strip context from reports down to `level` before posting them.
+    pub fn with_privacy_level(&mut self, level: PrivacyLevel) -> &mut Self {
+        self.pipeline.privacy_level = level;
+        self
+    }
+
+    fn notify(&self, report: &Report, url: &str) -> Result<(), Error> {
+        let report = prepare(report, &self.pipeline)?;
+        post_webhook(self.transport.agent()?, &self.webhook, &embed(&report, url))
+    }
+}
+
+impl Reporter for DiscordSink {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        Ok(self.submit(report)?.into_url())
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        let Some(inner) = &self.inner else {
+            self.notify(report, "")?;
+            return Ok(Submission::Created(report.reference.clone()));
+        };
+        let submission = inner.submit(report)?;
+        if !matches!(submission, Submission::Deduplicated(_))
+            && let Err(e) = self.notify(report, submission.url())
+        {
+            tracing::warn!("Failed to post report to Discord: {e}");
+        }
+        Ok(submission)
+    }
+
+    /// Comment through the chained reporter.
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        match &self.inner {
+            Some(inner) => inner.comment(issue, body),
+            None => Err(Error::Config("no reporter to comment with".into())),
+        }
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        match &self.inner {
+            Some(inner) => inner.update_comment(comment, body),
+            None => Err(Error::Config("no reporter to comment with".into())),
+        }
+    }
+
+    /// The stricter of the sink's level and the chained reporter's.
+    fn privacy_level(&self) -> PrivacyLevel {
+        match &self.inner {
+            Some(inner) => inner.privacy_level().max(self.pipeline.privacy_level),
+            None => self.pipeline.privacy_level,
+        }
+    }
+}
+
+/// A webhook message with one embed: the title, linked to the issue when
+/// there is one, colored by severity, with severity, labels, and reference
+/// fields.
+fn embed(report: &Report, url: &str) -> serde_json::Value {
+    let color = match report.severity {
+        Some(Severity::Crash) => 0xE0_1E_5A,
+        Some(Severity::Bug) => 0xE6_7E_22,
+        Some(Severity::Degraded) => 0xF1_C4_0F,
+        Some(Severity::Feedback) => 0x34_98_DB,
+        None => 0x95_A5_A6,
+    };
+    let mut title = if report.title.is_empty() {
+        "Untitled report".to_string()
+    } else {
+        report.title.clone()
+    };
+    if title.chars().count() > MAX_TITLE {
+        title = title.chars().take(MAX_TITLE - 3).collect::<String>() + "...";
+    }
+
+    let mut fields = Vec::new();
+    let mut field = |name: &str, value: String| {
+        fields.push(serde_json::json!({ "name": name, "value": value, "inline": true }));
+    };
+    if let Some(severity) = report.severity {
+        field("Severity", severity.as_str().to_string());
+    }
+    if !report.labels.is_empty() {
+        field("Labels", report.labels.join(", "));
+    }
+    if !report.reference.is_empty() {
+        field("Reference", format!("`{}`", report.reference));
+    }
+    let mut embed = serde_json::json!({
+        "title": title,
+        "color": color,
+        "fields": fields,
+    });
+    if !url.is_empty() {
+        embed["url"] = url.into();
+    }
+    serde_json::json!({
+        "embeds": [embed],
+        // Report text must never ping @everyone or a role.
+        "allowed_mentions": { "parse": [] },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_embed() {
+        let mut report = Report::new();
+        report
+            .title("@everyone crash")
+            .severity(Severity::Crash)
+            .label("gpu");
+        report.reference = "HL-7K3Q".into();
+        let message = embed(&report, "https://linear.app/acme/issue/ENG-1");
+
+        assert_eq!(message["allowed_mentions"]["parse"], serde_json::json!([]));
+        let embed = &message["embeds"][0];
+        assert_eq!(embed["title"], "@everyone crash");
+        assert_eq!(embed["url"], "https://linear.app/acme/issue/ENG-1");
+        assert_eq!(embed["color"], 0xE01E5A);
+        assert_eq!(embed["fields"][2]["value"], "`HL-7K3Q`");
+
+        report.title(&"x".repeat(300));
+        let message = super::embed(&report, "");
+        let title = message["embeds"][0]["title"].as_str().unwrap();
+        assert_eq!(title.chars().count(), MAX_TITLE);
+        assert!(message["embeds"][0].get("url").is_none());
+    }
+
+    #[test]
+    fn test_chained() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/api/webhooks/1/abc")
+            .match_body(mockito::Matcher::Regex(
+                r#""url":"https://hotln.test/issue/1""#.into(),
+            ))
+            .with_status(204)
+            .create();
+
+        let mut discord = DiscordSink::new(&format!("{}/api/webhooks/1/abc", server.url()));
+        discord.after(MockReporter::new());
+        let url = discord.report(Report::new().title("sync fails")).unwrap();
+
+        assert_eq!(url, "https://hotln.test/issue/1");
+        mock.assert();
+    }
+}
@@ -0,0 +1,216 @@
+//! A "Report a problem" window for egui apps.
+//!
+//! ```ignore
+//! let mut client = hotln::LinearClient::new("https://worker.example.com");
+//! client.with_token("secret");
+//! let mut dialog = hotln::egui::ReportDialog::new(client);
+//!
+//! // In your update loop:
+//! if ui.button("Report a problem").clicked() {
+//!     dialog.open();
+//! }
+//! dialog.show(ctx);
+//! ```
+
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use crate::{Report, Reporter, Severity, SystemInfoSource, info_table};
+
+enum Status {
+    Editing,
+    Sending(Receiver<Result<String, String>>),
+    Sent(String),
+    Failed(String),
+}
+
+/// A bug report window with a title, description, consent checkbox, and a
+/// preview of the system info that will be attached.
+///
+/// Nothing is sent until the user ticks the consent checkbox and clicks
+/// Send. Reports are filed on a background thread so the UI stays
+/// responsive.
+pub struct ReportDialog {
+    reporter: Arc<dyn Reporter + Send + Sync>,
+    open: bool,
+    title: String,
+    description: String,
+    consent: bool,
+    system_info: Vec<(String, String)>,
+    status: Status,
+}
+
+impl ReportDialog {
+    pub fn new(reporter: impl Reporter + Send + Sync + 'static) -> Self {
+        Self {
+            reporter: Arc::new(reporter),
+            open: false,
+            title: String::new(),
+            description: String::new(),
+            consent: false,
+            system_info: crate::system_info(),
+            status: Status::Editing,
+        }
+    }
+
+    /// Replace the system info rows shown to the user and sent with the
+    /// report, such as with `&(hotln::Os, hotln::Hardware, app_info)`.
+    pub fn system_info(&mut self, source: &impl SystemInfoSource) -> &mut Self {
+        self.system_info = source.collect();
+        self
+    }
+
+    /// Show the window, clearing any previous report.
+    pub fn open(&mut self) {
+        self.open = true;
+        self.title.clear();
+        self.description.clear();
+        self.consent = false;
+        self.status = Status::Editing;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Draw the window if it is open. Call this every frame.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.poll();
+        if matches!(self.status, Status::Sending(_)) {
+            ctx.request_repaint();
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Report a problem")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .show(ctx, |ui| self.ui(ui));
+        self.open = open && self.open;
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        match &self.status {
+            Status::Sent(url) => {
+                ui.label("Thanks! Your report was filed.");
+                ui.hyperlink(url);
+                if ui.button("Close").clicked() {
+                    self.open = false;
+                }
+                return;
+            }
+            Status::Sending(_) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Sending...");
+                });
+                return;
+            }
+            Status::Failed(error) => {
+                ui.colored_label(ui.visuals().error_fg_color, format!("Failed: {error}"));
+            }
+            Status::Editing => {}
+        }
+
+        ui.label("Title");
+        ui.text_edit_singleline(&mut self.title);
+        ui.label("What happened?");
+        ui.text_edit_multiline(&mut self.description);
+
+        egui::CollapsingHeader::new("System info")
+            .default_open(false)
+            .show(ui, |ui| {
+                egui::Grid::new("hotln_system_info").show(ui, |ui| {
+                    for (key, value) in &self.system_info {
+                        ui.label(key);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+            });
+
+        ui.checkbox(
+            &mut self.consent,
+            "Send this report and the system info above",
+        );
+
+        ui.horizontal(|ui| {
+            let ready = self.consent && !self.title.trim().is_empty();
+            if ui.add_enabled(ready, egui::Button::new("Send")).clicked() {
+                self.send();
+            }
+            if ui.button("Cancel").clicked() {
+                self.open = false;
+            }
+        });
+    }
+
+    fn send(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        let reporter = self.reporter.clone();
+        let report = self.build_report();
+        std::thread::spawn(move || {
+            let _ = tx.send(reporter.report(&report).map_err(|e| e.to_string()));
+        });
+        self.status = Status::Sending(rx);
+    }
+
+    fn poll(&mut self) {
+        let Status::Sending(rx) = &self.status else {
+            return;
+        };
+        self.status = match rx.try_recv() {
+            Ok(Ok(url)) => Status::Sent(url),
+            Ok(Err(e)) => Status::Failed(e),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                Status::Failed("reporter thread panicked".to_string())
+            }
+        };
+    }
+
+    fn build_report(&self) -> Report {
+        let rows: Vec<(&str, &str)> = self
+            .system_info
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let mut report = Report::new();
+        report
+            .title(self.title.trim())
+            .text(&self.description)
+            .severity(Severity::Bug);
+        if self.reporter.privacy_level().collects_context() {
+            report.text(&info_table("System Info", &rows));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_send() {
+        let mock = Arc::new(MockReporter::new());
+        let mut dialog = ReportDialog::new(mock.clone());
+        dialog.open();
+        dialog.system_info(&[("OS", "plan9")]);
+        dialog.title = "Export fails".to_string();
+        dialog.description = "Nothing happens.".to_string();
+        dialog.send();
+
+        let Status::Sending(rx) = &dialog.status else {
+            panic!("not sending");
+        };
+        let result = rx.recv().unwrap();
+        assert_eq!(result.as_deref(), Ok("https://hotln.test/issue/1"));
+
+        let reports = mock.reports();
+        assert_eq!(reports[0].title, "Export fails");
+        assert!(reports[0].description.starts_with("Nothing happens."));
+        assert!(reports[0].description.contains("| OS | plan9 |"));
+    }
+}
@@ -0,0 +1,620 @@
+//! Sending reports by email, for networks where mail is the only way out.
+//!
+//! This is a minimal SMTP client: it requires STARTTLS, authenticates with
+//! `AUTH PLAIN`, and sends one message per report.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::prelude::*;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::prepare::{Pipeline, Prepared, prepare};
+use crate::{
+    AttachmentPolicy, Error, Layout, PrivacyLevel, Redaction, Report, Reporter, Secrets,
+    Submission, SystemInfoSource, append_block, append_reference, extra_summary, info_table,
+    links_block, merge_labels, state_json,
+};
+
+/// The submission port, which starts in plain text and upgrades to TLS.
+const DEFAULT_PORT: u16 = 587;
+
+/// Sends reports as email through an SMTP server.
+///
+/// ```no_run
+/// # use hotln::Reporter;
+/// let mut email = hotln::EmailReporter::new(
+///     "smtp.example.com",
+///     "crashes@example.com",
+///     "triage@example.com",
+/// );
+/// email.credentials("crashes@example.com", "app password");
+/// email.report(hotln::Report::new().title("crash"))?;
+/// # Ok::<(), hotln::Error>(())
+/// ```
+///
+/// The connection is always upgraded with STARTTLS, and a server that doesn't
+/// offer it is refused rather than sent the report in plain text.
+/// [`report`](Reporter::report) returns the message's `Message-ID`.
+#[derive(Debug, Clone)]
+pub struct EmailReporter {
+    host: String,
+    port: u16,
+    from: String,
+    to: String,
+    credentials: Option<(String, String)>,
+    deadline: Option<Duration>,
+    dry_run: bool,
+    labels: Vec<String>,
+    app_version: Option<String>,
+    pipeline: Pipeline,
+    layout: Option<Layout>,
+}
+
+impl EmailReporter {
+    /// Send from `from` to `to` through `server`, a host name with an
+    /// optional port. The port defaults to 587.
+    pub fn new(server: &str, from: &str, to: &str) -> Self {
+        let (host, port) = match server.rsplit_once(':') {
+            Some((host, port)) if !host.ends_with(':') => {
+                (host, port.parse().unwrap_or(DEFAULT_PORT))
+            }
+            _ => (server, DEFAULT_PORT),
+        };
+        Self {
+            host: host.trim_matches(['[', ']']).to_string(),
+            port,
+            from: from.to_string(),
+            to: to.to_string(),
+            credentials: None,
+            deadline: None,
+            dry_run: false,
+            labels: Vec::new(),
+            app_version: None,
+            pipeline: Pipeline::default(),
+            layout: None,
+        }
+    }
+
+    /// Log in with `AUTH PLAIN` once the connection is encrypted.
+    pub fn credentials(&mut self, username: &str, password: &str) -> &mut Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Build messages without sending them.
+    ///
+    /// When enabled, [`report`](Reporter::report) makes no network call and
+    /// returns the message that would have been sent instead of its ID.
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Give up when the server takes longer than `deadline` to connect or to
+    /// answer any command. The call then returns [`Error::Timeout`].
+    pub fn with_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Apply these redaction rules to every report before it is sent.
+    pub fn redact(&mut self, redaction: Redaction) -> &mut Self {
+        self.pipeline.redaction = redaction;
+        self
+    }
+
+    /// What to do with reports that seem to contain credentials. By default
+    /// they are replaced with `[REDACTED]`.
+    pub fn secrets(&mut self, policy: Secrets) -> &mut Self {
+        self.pipeline.secrets = policy;
+        self
+    }
+
+    /// Size limits and compression for attachments. By default files over
+    /// 1 MiB are gzipped and those still over 25 MiB are left out.
+    pub fn attachment_policy(&mut self, policy: AttachmentPolicy) -> &mut Self {
+        self.pipeline.attachments = policy;
+        self
+    }
+
+    /// Which sections of the issue body appear, and in what order. By
+    /// default the body keeps the order the report was built in.
+    pub fn layout(&mut self, layout: Layout) -> &mut Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Add the rows of `source`, such as [`Os`](crate::Os), to a "System
+    /// Info" table on every report, unless the privacy level leaves out
+    /// optional context. Call it once for each source.
+    pub fn system_info(
+        &mut self,
+        source: impl SystemInfoSource + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.pipeline.system_info.push(source);
+        self
+    }
+
+    /// Strip context from reports down to `level` before sending them.
+    pub fn with_privacy_level(&mut self, level: PrivacyLevel) -> &mut Self {
+        self.pipeline.privacy_level = level;
+        self
+    }
+
+    /// Labels applied to every report, in addition to the report's own.
+    /// They're listed in the body and the `X-Hotline-Labels` header.
+    pub fn labels(&mut self, labels: &[&str]) -> &mut Self {
+        self.labels = labels.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    pub fn app_version(&mut self, version: &str) -> &mut Self {
+        self.app_version = Some(version.to_string());
+        self
+    }
+
+    fn body(&self, report: &Prepared) -> String {
+        let mut body = report.description.clone();
+        if let Some(version) = &self.app_version {
+            append_block(&mut body, &info_table("App", &[("Version", version)]));
+        }
+        let labels = merge_labels(&self.labels, &report.labels);
+        if !labels.is_empty() {
+            append_block(&mut body, &format!("Labels: {}", labels.join(", ")));
+        }
+        if !report.links.is_empty() {
+            append_block(&mut body, &links_block(&report.links));
+        }
+        if !report.extra.is_empty() {
+            append_block(&mut body, &extra_summary(&report.extra));
+        }
+        if let Some(json) = state_json(report) {
+            append_block(&mut body, &format!("## App state\n\n{json}"));
+        }
+        if let Some(threads) = &report.threads {
+            append_block(&mut body, &format!("## Threads\n\n{threads}"));
+        }
+        if let Some(layout) = &self.layout {
+            body = layout.apply(&body);
+        }
+        append_reference(&mut body, &report.reference);
+        body
+    }
+
+    /// The full message, headers and MIME body, with CRLF line endings.
+    fn message(&self, report: &Prepared, message_id: &str) -> String {
+        let mut subject = report.title.clone();
+        if let Some(severity) = report.severity {
+            subject = format!("[{}] {subject}", severity.as_str());
+        }
+        let mut headers = vec![
+            ("From", self.from.clone()),
+            ("To", self.to.clone()),
+            ("Subject", encode_header(&subject)),
+            ("Date", rfc2822_date(SystemTime::now())),
+            ("Message-ID", message_id.to_string()),
+            ("MIME-Version", "1.0".to_string()),
+        ];
+        if let Some((_, email)) = &report.reporter
+            && !email.is_empty()
+        {
+            headers.push(("Reply-To", sanitize(email)));
+        }
+        let labels = merge_labels(&self.labels, &report.labels);
+        if !labels.is_empty() {
+            headers.push(("X-Hotline-Labels", sanitize(&labels.join(", "))));
+        }
+        if let Some(severity) = report.severity {
+            headers.push(("X-Hotline-Severity", severity.as_str().to_string()));
+        }
+        if !report.reference.is_empty() {
+            headers.push(("X-Hotline-Reference", sanitize(&report.reference)));
+        }
+
+        let text = [
+            ("Content-Type", "text/plain; charset=utf-8".to_string()),
+            ("Content-Transfer-Encoding", "quoted-printable".to_string()),
+        ];
+        let body = quoted_printable(&self.body(report));
+        let mut out = String::new();
+        if report.attachments.is_empty() {
+            write_headers(&mut out, headers.iter().chain(&text));
+            out.push_str(&body);
+            return out;
+        }
+
+        let boundary = format!("hotln-{}", random_hex(12));
+        headers.push((
+            "Content-Type",
+            format!("multipart/mixed; boundary=\"{boundary}\""),
+        ));
+        write_headers(&mut out, headers.iter());
+        out.push_str(&format!("--{boundary}\r\n"));
+        write_headers(&mut out, text.iter());
+        out.push_str(&body);
+        for (filename, data) in &report.attachments {
+            let filename = sanitize(filename).replace('"', "");
+            out.push_str(&format!("\r\n--{boundary}\r\n"));
+            write_headers(
+                &mut out,
+                [
+                    (
+                        "Content-Type",
+                        format!("{}; name=\"{filename}\"", crate::mime_for_ext(&filename)),
+                    ),
+                    ("Content-Transfer-Encoding", "base64".to_string()),
+                    (
+                        "Content-Disposition",
+                        format!("attachment; filename=\"{filename}\""),
+                    ),
+                ]
+                .iter(),
+            );
+            let encoded = BASE64_STANDARD.encode(data);
+            for line in encoded.as_bytes().chunks(76) {
+                out.push_str(std::str::from_utf8(line).unwrap_or_default());
+                out.push_str("\r\n");
+            }
+        }
+        out.push_str(&format!("\r\n--{boundary}--\r\n"));
+        out
+    }
+
+    fn send(&self, message: &str) -> Result<(), Error> {
+        let timeout = |e: std::io::Error| match (e.kind(), self.deadline) {
+            (std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock, Some(deadline)) => {
+                Error::Timeout(deadline)
+            }
+            _ => Error::Smtp(format!("{}:{}: {e}", self.host, self.port)),
+        };
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(timeout)?
+            .next()
+            .ok_or_else(|| Error::Smtp(format!("{} has no address", self.host)))?;
+        let stream = match self.deadline {
+            Some(deadline) => TcpStream::connect_timeout(&addr, deadline),
+            None => TcpStream::connect(addr),
+        }
+        .map_err(timeout)?;
+        stream.set_read_timeout(self.deadline).map_err(timeout)?;
+        stream.set_write_timeout(self.deadline).map_err(timeout)?;
+        // Servers want a name or address literal for the client.
+        let helo = match stream.local_addr().map_err(timeout)?.ip() {
+            std::net::IpAddr::V4(ip) => format!("[{ip}]"),
+            std::net::IpAddr::V6(ip) => format!("[IPv6:{ip}]"),
+        };
+
+        let mut plain = Session::new(stream);
+        plain.expect(220).map_err(|e| e.into_error(timeout))?;
+        let features = plain
+            .command(&format!("EHLO {helo}"), 250)
+            .map_err(|e| e.into_error(timeout))?;
+        if !features.iter().any(|l| l.eq_ignore_ascii_case("STARTTLS")) {
+            return Err(Error::Smtp(format!(
+                "{} does not offer STARTTLS; not sending the report in plain text",
+                self.host
+            )));
+        }
+        plain
+            .command("STARTTLS", 220)
+            .map_err(|e| e.into_error(timeout))?;
+
+        let tls = rustls::StreamOwned::new(self.tls()?, plain.into_inner());
+        let mut session = Session::new(tls);
+        session
+            .command(&format!("EHLO {helo}"), 250)
+            .map_err(|e| e.into_error(timeout))?;
+        if let Some((username, password)) = &self.credentials {
+            let token = BASE64_STANDARD.encode(format!("\0{username}\0{password}"));
+            session
+                .command(&format!("AUTH PLAIN {token}"), 235)
+                .map_err(|e| e.into_error(timeout))?;
+        }
+        session
+            .command(&format!("MAIL FROM:<{}>", self.from), 250)
+            .map_err(|e| e.into_error(timeout))?;
+        session
+            .command(&format!("RCPT TO:<{}>", self.to), 250)
+            .map_err(|e| e.into_error(timeout))?;
+        session
+            .command("DATA", 354)
+            .map_err(|e| e.into_error(timeout))?;
+        session
+            .command(&format!("{}\r\n.", dot_stuff(message)), 250)
+            .map_err(|e| e.into_error(timeout))?;
+        // The message is accepted; a failed goodbye changes nothing.
+        let _ = session.command("QUIT", 221);
+        Ok(())
+    }
+
+    fn tls(&self) -> Result<rustls::ClientConnection, Error> {
+        let roots = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::Config(e.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let name = rustls::pki_types::ServerName::try_from(self.host.clone())
+            .map_err(|e| Error::Config(format!("invalid SMTP host {:?}: {e}", self.host)))?;
+        rustls::ClientConnection::new(Arc::new(config), name)
+            .map_err(|e| Error::Smtp(format!("{}: {e}", self.host)))
+    }
+}
+
+impl Reporter for EmailReporter {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        match self.submit(report)? {
+            Submission::Created(id)
+            | Submission::Deduplicated(id)
+            | Submission::Queued(id)
+            | Submission::Spooled(id) => Ok(id),
+            Submission::Dropped => Ok(String::new()),
+        }
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        for address in [&self.from, &self.to] {
+            if address.is_empty() || address.contains(['<', '>', '\r', '\n']) {
+                return Err(Error::Config(format!("invalid email address {address:?}")));
+            }
+        }
+        let report = prepare(report, &self.pipeline)?;
+        let domain = self.from.rsplit('@').next().unwrap_or("hotln");
+        let message_id = format!("<{}@{domain}>", random_hex(16));
+        let message = self.message(&report, &message_id);
+        if self.dry_run {
+            return Ok(Submission::Created(message));
+        }
+        self.send(&message)?;
+        Ok(Submission::Created(message_id))
+    }
+
+    fn privacy_level(&self) -> PrivacyLevel {
+        self.pipeline.privacy_level
+    }
+}
+
+/// One side of an SMTP conversation.
+struct Session<S: Read + Write> {
+    stream: BufReader<S>,
+}
+
+enum SessionError {
+    Io(std::io::Error),
+    Reply(String),
+}
+
+impl SessionError {
+    fn into_error(self, io: impl Fn(std::io::Error) -> Error) -> Error {
+        match self {
+            SessionError::Io(e) => io(e),
+            SessionError::Reply(reply) => Error::Smtp(reply),
+        }
+    }
+}
+
+impl<S: Read + Write> Session<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    /// Send `line` and read the reply, which must have status `code`.
+    fn command(&mut self, line: &str, code: u16) -> Result<Vec<String>, SessionError> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{line}\r\n").as_bytes())
+            .and_then(|()| stream.flush())
+            .map_err(SessionError::Io)?;
+        self.expect(code)
+    }
+
+    /// Read a possibly multi-line reply, returning the text of each line.
+    fn expect(&mut self, code: u16) -> Result<Vec<String>, SessionError> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).map_err(SessionError::Io)? == 0 {
+                return Err(SessionError::Reply("server closed the connection".into()));
+            }
+            let line = line.trim_end();
+            let status = line.get(..3).and_then(|s| s.parse::<u16>().ok());
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line.get(4..).unwrap_or_default().to_string());
+            if !last {
+                continue;
+            }
+            return match status {
+                Some(status) if status == code || (code == 250 && status == 251) => Ok(lines),
+                _ => Err(SessionError::Reply(format!(
+                    "server replied {line:?}, expected {code}"
+                ))),
+            };
+        }
+    }
+}
+
+fn write_headers<'a>(out: &mut String, headers: impl Iterator<Item = &'a (&'a str, String)>) {
+    for (name, value) in headers {
+        out.push_str(&format!("{name}: {}\r\n", sanitize(value)));
+    }
+    out.push_str("\r\n");
+}
+
+/// Keeps a header value on one line, so report text can't add headers.
+fn sanitize(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// An RFC 2047 encoded word for non-ASCII header values.
+fn encode_header(value: &str) -> String {
+    let value = sanitize(value);
+    if value.is_ascii() {
+        value
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64_STANDARD.encode(value))
+    }
+}
+
+/// Quoted-printable, which keeps mostly-ASCII text readable and lines short.
+fn quoted_printable(text: &str) -> String {
+    let mut out = String::new();
+    for line in text.replace("\r\n", "\n").split('\n') {
+        let mut width = 0;
+        let bytes = line.as_bytes();
+        for (i, &b) in bytes.iter().enumerate() {
+            let last = i + 1 == bytes.len();
+            let encoded = match b {
+                b' ' | b'\t' if !last => (b as char).to_string(),
+                b'!'..=b'~' if b != b'=' => (b as char).to_string(),
+                _ => format!("={b:02X}"),
+            };
+            if width + encoded.len() > 75 {
+                out.push_str("=\r\n");
+                width = 0;
+            }
+            width += encoded.len();
+            out.push_str(&encoded);
+        }
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Doubles dots that start a line, which would otherwise end the message.
+fn dot_stuff(message: &str) -> String {
+    let message = message.strip_suffix("\r\n").unwrap_or(message);
+    let mut out = String::with_capacity(message.len());
+    for (i, line) in message.split("\r\n").enumerate() {
+        if i > 0 {
+            out.push_str("\r\n");
+        }
+        if line.starts_with('.') {
+            out.push('.');
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+fn rfc2822_date(time: SystemTime) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let days = secs / 86_400;
+    let (year, month, day) = crate::civil_from_days(days as i64);
+    format!(
+        "{}, {day:02} {} {year} {:02}:{:02}:{:02} +0000",
+        DAYS[(days % 7) as usize],
+        MONTHS[month as usize - 1],
+        secs % 86_400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    )
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        for (b, n) in bytes.iter_mut().zip(nanos.to_le_bytes().iter().cycle()) {
+            *b = *n;
+        }
+    }
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message() {
+        let mut email = EmailReporter::new("smtp.example.com", "app@example.com", "me@example.com");
+        email.dry_run(true).labels(&["desktop"]);
+        let mut report = Report::new();
+        report
+            .title("crash\r\nBcc: everyone@example.com")
+            .severity(crate::Severity::Crash)
+            .text(".hidden line\nnaïve")
+            .attachment("log.txt", b"hello");
+        let message = email.report(&report).unwrap();
+
+        assert!(message.contains("Subject: [crash] crash  Bcc: everyone@example.com\r\n"));
+        assert!(!message.contains("\r\nBcc:"));
+        assert!(message.contains("X-Hotline-Labels: desktop\r\n"));
+        assert!(message.contains("\r\n.hidden line\r\nna=C3=AFve\r\n"));
+        assert!(message.contains("filename=\"log.txt\"\r\n\r\naGVsbG8=\r\n"));
+        assert!(dot_stuff(&message).contains("\r\n..hidden line\r\n"));
+    }
+
+    #[test]
+    fn test_quoted_printable() {
+        assert_eq!(quoted_printable("a = b \nc"), "a =3D b=20\r\nc\r\n");
+        let long = quoted_printable(&"x".repeat(100));
+        assert!(long.lines().all(|l| l.len() <= 76));
+    }
+
+    #[test]
+    fn test_rfc2822_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(rfc2822_date(time), "Tue, 14 Nov 2023 22:13:20 +0000");
+    }
+
+    #[test]
+    fn test_requires_starttls() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"220 mail ready\r\n").unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut received = Vec::new();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                received.push(line.trim_end().to_string());
+                if line.starts_with("EHLO") {
+                    stream
+                        .write_all(b"250-mail.example.com\r\n250 AUTH PLAIN\r\n")
+                        .unwrap();
+                }
+                line.clear();
+            }
+            received
+        });
+
+        let email = EmailReporter::new(
+            &format!("127.0.0.1:{port}"),
+            "app@example.com",
+            "me@example.com",
+        );
+        let result = email.report(Report::new().title("crash"));
+        assert!(matches!(result, Err(Error::Smtp(e)) if e.contains("STARTTLS")));
+        drop(email);
+
+        let received = server.join().unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(received[0].starts_with("EHLO "));
+    }
+}
@@ -0,0 +1,233 @@
+//! Sending every report to several destinations at once.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::cancel;
+use crate::router::Destination;
+use crate::{Error, PrivacyLevel, Report, Reporter, Submission};
+
+/// Deliver every report to all of `sinks` concurrently.
+///
+/// ```no_run
+/// let linear = hotln::LinearClient::new("https://your-proxy.example.com");
+/// let slack = hotln::SlackSink::new("https://hooks.slack.com/services/T000/B000/XXXX");
+/// let sentry = hotln::SentryClient::new("https://abc123@o1.ingest.sentry.io/42");
+/// hotln::PanicHook::new(hotln::fanout(vec![
+///     Box::new(linear),
+///     Box::new(slack),
+///     Box::new(sentry),
+/// ]))
+/// .install();
+/// ```
+pub fn fanout(sinks: Vec<Destination>) -> Fanout {
+    Fanout {
+        sinks,
+        filed: Mutex::default(),
+    }
+}
+
+/// A [`Reporter`] that sends each report to every sink, unlike a
+/// [`Router`](crate::Router), which picks one.
+///
+/// Sinks run on their own threads, so a slow one doesn't hold up the rest,
+/// and one failing doesn't stop the others. [`deliver`](Self::deliver)
+/// returns every sink's result; as a [`Reporter`], a fan-out succeeds with
+/// the first sink's submission that succeeded, and fails only if all did.
+/// Comments go to the sink that filed the issue.
+pub struct Fanout {
+    sinks: Vec<Destination>,
+    /// Which sink filed each issue or comment URL.
+    filed: Mutex<HashMap<String, usize>>,
+}
+
+impl Fanout {
+    /// Also deliver reports to `sink`.
+    pub fn sink(&mut self, sink: impl Reporter + Send + Sync + 'static) -> &mut Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Send `report` to every sink and wait for all of them, returning their
+    /// results in the order the sinks were added.
+    pub fn deliver(&self, report: &Report) -> Vec<Result<Submission, Error>> {
+        // Sinks on other threads still see the submission's cancellation.
+        let cancel = cancel::current();
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .sinks
+                .iter()
+                .map(|sink| {
+                    let cancel = cancel.clone();
+                    scope.spawn(move || cancel::scope(cancel.as_ref(), || sink.submit(report)))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        });
+        if let Ok(mut filed) = self.filed.lock() {
+            for (i, result) in results.iter().enumerate() {
+                if let Ok(submission) = result
+                    && !submission.url().is_empty()
+                {
+                    filed.insert(submission.url().to_string(), i);
+                }
+            }
+        }
+        results
+    }
+
+    /// The sink that filed `url`, if this fan-out did.
+    fn sink_for(&self, url: &str) -> Option<usize> {
+        self.filed
+            .lock()
+            .ok()
+            .and_then(|filed| filed.get(url).copied())
+    }
+}
+
+impl Reporter for Fanout {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        Ok(self.submit(report)?.into_url())
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        let mut first = None;
+        let mut error = None;
+        for (i, result) in self.deliver(report).into_iter().enumerate() {
+            match result {
+                Ok(submission) => {
+                    first.get_or_insert(submission);
+                }
+                Err(e) => {
+                    tracing::warn!("Fan-out sink {i} failed: {e}");
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        match (first, error) {
+            (Some(submission), _) => Ok(submission),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(Error::Config("fan-out has no sinks".into())),
+        }
+    }
+
+    /// Comment through the sink that filed `issue`, or for issues this
+    /// fan-out didn't file, the first sink that takes the comment.
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        let (i, comment) = match self.sink_for(issue) {
+            Some(i) => (i, self.sinks[i].comment(issue, body)?),
+            None => {
+                let mut error = None;
+                let found = self.sinks.iter().enumerate().find_map(|(i, sink)| {
+                    match sink.comment(issue, body) {
+                        Ok(comment) => Some((i, comment)),
+                        Err(e) => {
+                            error.get_or_insert(e);
+                            None
+                        }
+                    }
+                });
+                match (found, error) {
+                    (Some(found), _) => found,
+                    (None, Some(e)) => return Err(e),
+                    (None, None) => return Err(Error::Config("fan-out has no sinks".into())),
+                }
+            }
+        };
+        if let Ok(mut filed) = self.filed.lock() {
+            filed.insert(comment.clone(), i);
+        }
+        Ok(comment)
+    }
+
+    /// Update a comment through the sink that posted it.
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        let i = self
+            .sink_for(comment)
+            .ok_or_else(|| Error::Config(format!("fan-out didn't post {comment}")))?;
+        self.sinks[i].update_comment(comment, body)
+    }
+
+    fn spools(&self, report: &Report) -> bool {
+        self.sinks.iter().any(|sink| sink.spools(report))
+    }
+
+    /// The strictest level of any sink, since collectors run once for all.
+    fn privacy_level(&self) -> PrivacyLevel {
+        self.sinks
+            .iter()
+            .map(|sink| sink.privacy_level())
+            .fold(PrivacyLevel::default(), PrivacyLevel::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_delivers_to_all() {
+        let linear = Arc::new(MockReporter::new());
+        let mut failing = MockReporter::new();
+        failing.with_error(503, "unavailable");
+        let slack = Arc::new(MockReporter::new());
+        let fanout = fanout(vec![
+            Box::new(failing),
+            Box::new(linear.clone()),
+            Box::new(slack.clone()),
+        ]);
+
+        let results = fanout.deliver(Report::new().title("crash"));
+        assert!(matches!(results[0], Err(Error::Proxy { status: 503, .. })));
+        assert!(results[1].is_ok() && results[2].is_ok());
+
+        let url = fanout.report(Report::new().title("crash")).unwrap();
+        assert_eq!(url, "https://hotln.test/issue/2");
+        assert_eq!(linear.reports().len(), 2);
+        assert_eq!(slack.reports().len(), 2);
+    }
+
+    #[test]
+    fn test_fails_when_all_fail() {
+        let mut failing = MockReporter::new();
+        failing.with_error(500, "down");
+        let fanout = fanout(vec![Box::new(failing)]);
+
+        assert!(fanout.report(&Report::new()).is_err());
+    }
+
+    #[test]
+    fn test_dedup_comments() {
+        let mut slack = MockReporter::new();
+        slack.with_url("https://hooks.slack.test/message");
+        let slack = Arc::new(slack);
+        let linear = Arc::new(MockReporter::new());
+        let mut dedup = crate::Dedup::new(fanout(vec![
+            Box::new(linear.clone()),
+            Box::new(slack.clone()),
+        ]));
+        dedup.comment_occurrences();
+        let mut report = Report::new();
+        report.title("crash");
+
+        for _ in 0..3 {
+            dedup.submit(&report).unwrap();
+        }
+        assert_eq!(linear.reports().len(), 1);
+        let comments = linear.comments();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].0, "https://hotln.test/issue/1");
+        assert!(comments[0].1.contains("- Occurrences: 3\n"));
+        assert!(slack.comments().is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! Grouping keys for crash reports, taken from where they happened rather
+//! than what they say.
+
+use ring::digest::{SHA256, digest};
+
+/// How many in-app frames to hash. Deeper frames are mostly the caller's
+/// caller, which shouldn't split one bug into several groups.
+const FRAMES: usize = 5;
+
+/// Frames from these crates are the runtime, not the application.
+const RUNTIME_CRATES: &[&str] = &["std::", "core::", "alloc::", "__rustc::", "__rust_"];
+
+/// A stable key for the code path in `backtrace`, as printed by
+/// [`std::backtrace::Backtrace`]'s `Display`.
+///
+/// Hashes the names of the top few in-app functions below the panic
+/// machinery, skipping `std`, `core`, and `alloc` frames. File names, line
+/// numbers, and the panic message are left out, so rewording a panic or
+/// editing nearby code keeps the fingerprint. Returns `None` when there are
+/// no in-app frames, such as when the binary has no symbols.
+pub fn fingerprint(backtrace: &str) -> Option<String> {
+    let frames: Vec<&str> = app_frames(backtrace).take(FRAMES).collect();
+    if frames.is_empty() {
+        return None;
+    }
+    Some(hash_hex(&frames.join("\n")))
+}
+
+/// The module of the innermost application frame in `backtrace`, such as
+/// `myapp::config` for `myapp::config::load`.
+pub(crate) fn module(backtrace: &str) -> Option<String> {
+    let symbol = app_frames(backtrace).next()?;
+    // `<myapp::App as core::default::Default>::default` is in `myapp`.
+    let path = match symbol.strip_prefix('<') {
+        Some(rest) => rest.split([' ', '>']).next()?,
+        None => symbol,
+    };
+    let mut segments: Vec<&str> = path
+        .split("::")
+        .filter(|segment| !segment.starts_with("{{"))
+        .collect();
+    segments.pop();
+    (!segments.is_empty()).then(|| segments.join("::"))
+}
+
+/// Function names below the panic machinery, innermost first, without
+/// runtime frames.
+fn app_frames(backtrace: &str) -> impl Iterator<Item = &str> {
+    let symbols: Vec<&str> = backtrace
+        .lines()
+        .filter_map(|line| {
+            let (index, symbol) = line.trim().split_once(": ")?;
+            index.parse::<usize>().ok()?;
+            Some(strip_hash(symbol.trim()))
+        })
+        .collect();
+    // Frames above the last panic entry point belong to the hook that
+    // captured the backtrace.
+    let start = symbols
+        .iter()
+        .rposition(|symbol| is_panic_entry(symbol))
+        .map_or(0, |i| i + 1);
+    symbols
+        .into_iter()
+        .skip(start)
+        .filter(|symbol| !is_runtime(symbol))
+}
+
+/// `message` with its run-specific parts replaced by placeholders, so that
+/// `index out of bounds: the len is 3 but the index is 7` and `... the
+/// index is 12` read the same.
+///
+/// Replaces UUIDs with `<uuid>`, `0x` addresses with `<addr>`, paths with
+/// `<path>`, long hex IDs with `<id>`, and any other run of digits with
+/// `<n>`.
+pub fn normalize_message(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut rest = message;
+    while !rest.is_empty() {
+        let start = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..end];
+        // Keep the quotes and punctuation around a word.
+        let core = word.trim_matches(|c: char| "\"'`()[]{}<>,;:.!?".contains(c));
+        let core_start = word.find(core).unwrap_or(0);
+        out.push_str(&word[..core_start]);
+        out.push_str(&normalize_word(core));
+        out.push_str(&word[core_start + core.len()..]);
+        rest = &rest[end..];
+    }
+    out
+}
+
+fn normalize_word(word: &str) -> String {
+    let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+    let uuid = word.len() == 36
+        && word.split('-').map(str::len).eq([8, 4, 4, 4, 12])
+        && word.split('-').all(is_hex);
+    if uuid {
+        return "<uuid>".to_string();
+    }
+    if word.strip_prefix("0x").is_some_and(is_hex) {
+        return "<addr>".to_string();
+    }
+    if word.len() > 1 && word.contains(['/', '\\']) {
+        return "<path>".to_string();
+    }
+    if word.len() >= 8
+        && is_hex(word)
+        && word.contains(|c: char| c.is_ascii_digit())
+        && word.contains(|c: char| c.is_ascii_alphabetic())
+    {
+        return "<id>".to_string();
+    }
+    let mut out = String::with_capacity(word.len());
+    let mut in_number = false;
+    for c in word.chars() {
+        if c.is_ascii_digit() {
+            if !in_number {
+                out.push_str("<n>");
+            }
+            in_number = true;
+        } else {
+            out.push(c);
+            in_number = false;
+        }
+    }
+    out
+}
+
+/// A grouping key for an error with no usable backtrace, such as a panic
+/// from a stripped build or a log line, from its
+/// [normalized](normalize_message) message.
+pub fn message_fingerprint(message: &str) -> String {
+    hash_hex(&format!("message:{}", normalize_message(message)))
+}
+
+pub(crate) fn hash_hex(text: &str) -> String {
+    let hash = digest(&SHA256, text.as_bytes());
+    hash.as_ref()[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn is_panic_entry(symbol: &str) -> bool {
+    symbol.ends_with("rust_begin_unwind")
+        || symbol.starts_with("core::panicking::")
+        || symbol.starts_with("std::panicking::")
+}
+
+fn is_runtime(symbol: &str) -> bool {
+    // Trait impls print as `<core::... as ...>::method`.
+    let symbol = symbol.trim_start_matches('<');
+    symbol == "main" || RUNTIME_CRATES.iter().any(|krate| symbol.starts_with(krate))
+}
+
+/// Drops the `::h0123456789abcdef` suffix some formats add to symbols.
+fn strip_hash(symbol: &str) -> &str {
+    match symbol.rsplit_once("::h") {
+        Some((name, hash)) if hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            name
+        }
+        _ => symbol,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKTRACE: &str = "   0: std::backtrace::Backtrace::force_capture
+             at /rustc/library/std/src/backtrace.rs:312:13
+   1: hotln::panic::panic_report
+             at ./src/panic.rs:80:15
+   2: std::panicking::rust_panic_with_hook
+   3: __rustc::rust_begin_unwind
+   4: core::panicking::panic_fmt
+   5: core::option::expect_failed
+   6: myapp::config::load::h0123456789abcdef
+             at ./src/config.rs:42:9
+   7: <myapp::App as core::default::Default>::default
+             at ./src/app.rs:10:5
+   8: myapp::main
+   9: core::ops::function::FnOnce::call_once
+  10: main";
+
+    #[test]
+    fn test_fingerprint() {
+        let key = fingerprint(BACKTRACE).unwrap();
+        assert_eq!(key.len(), 16);
+
+        // Other line numbers, the same functions.
+        let moved = BACKTRACE.replace(":42:9", ":57:13");
+        assert_eq!(fingerprint(&moved).as_deref(), Some(key.as_str()));
+
+        // The hook's own frames don't count.
+        let other_hook = BACKTRACE.replace("hotln::panic::panic_report", "my_hook");
+        assert_eq!(fingerprint(&other_hook).as_deref(), Some(key.as_str()));
+
+        let elsewhere = BACKTRACE.replace("myapp::config::load", "myapp::config::save");
+        assert_ne!(fingerprint(&elsewhere).unwrap(), key);
+
+        assert_eq!(fingerprint("disabled backtrace"), None);
+        assert_eq!(
+            fingerprint("   0: core::panicking::panic\n   1: main"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_module() {
+        assert_eq!(module(BACKTRACE).as_deref(), Some("myapp::config"));
+        let closure = BACKTRACE.replace(
+            "myapp::config::load::h0123456789abcdef",
+            "myapp::render::draw::{{closure}}",
+        );
+        assert_eq!(module(&closure).as_deref(), Some("myapp::render"));
+        let method = BACKTRACE.replace("   6: myapp::config::load::h0123456789abcdef\n", "");
+        assert_eq!(module(&method).as_deref(), Some("myapp"));
+    }
+
+    #[test]
+    fn test_normalize_message() {
+        assert_eq!(
+            normalize_message("index out of bounds: the len is 3 but the index is 7"),
+            normalize_message("index out of bounds: the len is 3 but the index is 12"),
+        );
+        assert_eq!(
+            normalize_message(
+                "failed to open \"/home/sam/.config/app/settings.toml\": item42 at 0x7ffd5e8c \
+                 (request 550e8400-e29b-41d4-a716-446655440000, commit 9bba647d2a1f)"
+            ),
+            "failed to open \"<path>\": item<n> at <addr> (request <uuid>, commit <id>)"
+        );
+        assert_ne!(
+            message_fingerprint("called `Option::unwrap()` on a `None` value"),
+            message_fingerprint("called `Result::unwrap()` on an `Err` value"),
+        );
+    }
+}
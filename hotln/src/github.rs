@@ -1,87 +1,466 @@
-use crate::{Error, inline_file};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
-pub struct Issue {
+use crate::issues::{SearchResponse, comment_id, get_json, issue_id, post_json_as};
+use crate::prepare::{Pipeline, Prepared, prepare};
+use crate::transport::Transport;
+use crate::{
+    AttachmentPolicy, DescriptionBuilder, Error, IssueQuery, IssueStatus, Layout, PrivacyLevel,
+    Redaction, Report, Reporter, Resolution, Secrets, Severity, Spool, Submission,
+    SystemInfoSource, append_block, append_reference, extra_summary, info_table, inline_file,
+    links_block, merge_labels, post_json, reporter_json, state_json,
+};
+
+/// Files reports as GitHub issues through a proxy.
+#[derive(Debug, Clone)]
+pub struct Client {
     url: String,
     token: Option<String>,
-    title: String,
-    description: String,
+    dry_run: bool,
+    labels: Vec<String>,
+    app_version: Option<String>,
+    protocol: Arc<OnceLock<u8>>,
+    pipeline: Pipeline,
+    layout: Option<Layout>,
+    transport: Transport,
 }
 
-impl Issue {
-    pub(crate) fn new(proxy_url: &str) -> Self {
+impl Client {
+    pub fn new(proxy_url: &str) -> Self {
         Self {
             url: proxy_url.to_string(),
             token: None,
-            title: "Untitled".to_string(),
-            description: String::new(),
+            dry_run: false,
+            labels: Vec::new(),
+            app_version: None,
+            protocol: Arc::new(OnceLock::new()),
+            pipeline: Pipeline::default(),
+            layout: None,
+            transport: Transport::default(),
         }
     }
 
+    /// Send `token` to the proxy as a bearer token. Filing reports needs its
+    /// app token; [`status`](Self::status), [`search`](Self::search),
+    /// [`comment`](Self::comment), and [`close_issue`](Self::close_issue) need
+    /// its admin token, which should stay out of shipped apps.
     pub fn with_token(&mut self, token: &str) -> &mut Self {
         self.token = Some(token.to_string());
         self
     }
 
+    /// Build payloads without sending them.
+    ///
+    /// When enabled, [`report`](Reporter::report) makes no network call and
+    /// returns the pretty-printed JSON that would have been posted to the
+    /// proxy instead of an issue URL.
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Only trust the proxy if its certificate matches `pin`.
+    ///
+    /// `pin` is either `sha256/<base64>`, the SHA-256 of the certificate's
+    /// public key as printed by curl, or the certificate's SHA-256
+    /// fingerprint in hex (colons optional). Call this more than once to
+    /// allow backup pins. A malformed pin makes every report fail with
+    /// [`Error::Config`] rather than silently disabling pinning.
+    pub fn with_pinned_cert(&mut self, pin: &str) -> &mut Self {
+        self.transport.pin(pin);
+        self
+    }
+
+    /// Authenticate to the proxy with a TLS client certificate (mTLS).
+    ///
+    /// Both arguments are PEM; `cert_pem` may hold the full chain. Invalid
+    /// PEM makes every report fail with [`Error::Config`].
+    pub fn with_client_cert(&mut self, cert_pem: &[u8], key_pem: &[u8]) -> &mut Self {
+        self.transport.client_cert(cert_pem, key_pem);
+        self
+    }
+
+    /// Give up on a submission after `deadline`, covering DNS, connecting,
+    /// the protocol probe, and reading the response. The call then returns
+    /// [`Error::Timeout`], so a crashing app never hangs on reporting.
+    pub fn with_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.transport.deadline(deadline);
+        self
+    }
+
+    /// Save reports to `spool` when the proxy can't be reached, to be resent
+    /// by [`send_spooled`](Self::send_spooled).
+    pub fn spool(&mut self, spool: Spool) -> &mut Self {
+        self.transport.spool(spool);
+        self
+    }
+
+    /// Retry reports that hit a network error or a 429/5xx on a background
+    /// thread, holding up to `capacity` of them in memory. Submitting then
+    /// returns [`Submission::Queued`] instead of the error. Reports that
+    /// still fail after a few attempts go to the [`spool`](Self::spool).
+    pub fn retry_queue(&mut self, capacity: usize) -> &mut Self {
+        self.transport.retry_queue(capacity);
+        self
+    }
+
+    /// Call `callback` with the reference and final outcome of each report
+    /// the [retry queue](Self::retry_queue) finishes with, such as to show
+    /// "Report filed as #123" once a [`Submission::Queued`] report goes
+    /// through. A report the queue gave up on and spooled is
+    /// [`Submission::Spooled`]. Runs on the queue's thread.
+    pub fn on_result(
+        &mut self,
+        callback: impl Fn(&str, Result<Submission, Error>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.transport.on_result(callback);
+        self
+    }
+
+    /// Resend spooled reports, oldest first, returning how many were
+    /// delivered. Stops at the first one that still can't be sent.
+    pub fn send_spooled(&self) -> Result<usize, Error> {
+        self.transport
+            .send_spooled(&self.url, "github", self.token.as_deref(), &self.protocol)
+    }
+
+    /// Route requests through a SOCKS proxy, such as Tor at
+    /// `socks5://127.0.0.1:9050`. Host names are resolved by the proxy.
+    pub fn socks_proxy(&mut self, url: &str) -> &mut Self {
+        self.transport.socks_proxy(url);
+        self
+    }
+
+    /// Apply these redaction rules to every report before it is sent.
+    pub fn redact(&mut self, redaction: Redaction) -> &mut Self {
+        self.pipeline.redaction = redaction;
+        self
+    }
+
+    /// What to do with reports that seem to contain credentials. By default
+    /// they are replaced with `[REDACTED]`.
+    pub fn secrets(&mut self, policy: Secrets) -> &mut Self {
+        self.pipeline.secrets = policy;
+        self
+    }
+
+    /// Size limits and compression for attachments. By default files over
+    /// 1 MiB are gzipped and those still over 25 MiB are left out.
+    pub fn attachment_policy(&mut self, policy: AttachmentPolicy) -> &mut Self {
+        self.pipeline.attachments = policy;
+        self
+    }
+
+    /// Which sections of the issue body appear, and in what order. By
+    /// default the body keeps the order the report was built in.
+    pub fn layout(&mut self, layout: Layout) -> &mut Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Add the rows of `source`, such as [`Os`](crate::Os), to a "System
+    /// Info" table on every report, unless the privacy level leaves out
+    /// optional context. Call it once for each source.
+    pub fn system_info(
+        &mut self,
+        source: impl SystemInfoSource + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.pipeline.system_info.push(source);
+        self
+    }
+
+    /// Strip context from reports down to `level` before sending them.
+    pub fn with_privacy_level(&mut self, level: PrivacyLevel) -> &mut Self {
+        self.pipeline.privacy_level = level;
+        self
+    }
+
+    /// Labels applied to every report, in addition to the report's own.
+    pub fn labels(&mut self, labels: &[&str]) -> &mut Self {
+        self.labels = labels.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    /// Look up an issue's state and assignee by identifier (`42`) or URL.
+    pub fn status(&self, issue: &str) -> Result<IssueStatus, Error> {
+        let id = issue_id(issue, "/issues/");
+        let url = format!("{}/github/issues/{id}", self.url);
+        get_json(self.transport.agent()?, &url, &[], self.token.as_deref())
+    }
+
+    /// Find issues matching `query`, most recently updated first.
+    pub fn search(&self, query: &IssueQuery) -> Result<Vec<IssueStatus>, Error> {
+        let url = format!("{}/github/issues", self.url);
+        let params = query.params();
+        let response: SearchResponse = get_json(
+            self.transport.agent()?,
+            &url,
+            &params,
+            self.token.as_deref(),
+        )?;
+        Ok(response.issues)
+    }
+
+    /// Add a markdown comment to an issue, returning the comment's URL.
+    pub fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        let id = issue_id(issue, "/issues/");
+        let url = format!("{}/github/issues/{id}/comments", self.url);
+        let payload = serde_json::json!({ "body": body });
+        post_json(
+            self.transport.agent()?,
+            &url,
+            self.token.as_deref(),
+            &payload,
+        )
+        .map(Submission::into_url)
+    }
+
+    /// Replace the body of an occurrence comment, given the URL
+    /// [`comment`](Self::comment) returned. The proxy refuses to edit other
+    /// comments.
+    pub fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        let id = issue_id(comment, "/issues/");
+        let comment_id = comment_id(comment)
+            .ok_or_else(|| Error::Config(format!("not a comment URL: {comment}")))?;
+        let url = format!("{}/github/issues/{id}/comments/{comment_id}", self.url);
+        let payload = serde_json::json!({ "body": body });
+        post_json(
+            self.transport.agent()?,
+            &url,
+            self.token.as_deref(),
+            &payload,
+        )
+        .map(Submission::into_url)
+    }
+
+    /// Close an issue by number (`42` or `#42`) or URL, as completed or not
+    /// planned, and return its new status.
+    ///
+    /// For automation that notices a fix, such as a fingerprint that stopped
+    /// showing up after a release. The proxy only closes issues filed with a
+    /// [fingerprint](crate::Report::fingerprint), so issues people filed by
+    /// hand are left alone.
+    pub fn close_issue(&self, issue: &str, resolution: Resolution) -> Result<IssueStatus, Error> {
+        let id = issue_id(issue, "/issues/");
+        let url = format!("{}/github/issues/{id}/close", self.url);
+        let payload = serde_json::json!({ "resolution": resolution });
+        post_json_as(
+            self.transport.agent()?,
+            &url,
+            self.token.as_deref(),
+            &payload,
+        )
+    }
+
+    /// Add an "App" table with this version to every report.
+    pub fn app_version(&mut self, version: &str) -> &mut Self {
+        self.app_version = Some(version.to_string());
+        self
+    }
+
+    fn payload(&self, report: &Prepared) -> serde_json::Value {
+        // GitHub has no attachments, so text attachments and the full extra
+        // JSON go in the body. Binary attachments are dropped.
+        let mut description = report.description.clone();
+        if let Some(version) = &self.app_version {
+            append_block(
+                &mut description,
+                &info_table("App", &[("Version", version)]),
+            );
+        }
+        for (filename, data) in &report.attachments {
+            match std::str::from_utf8(data) {
+                Ok(text) => append_block(&mut description, &inline_file(filename, text)),
+                Err(_) => tracing::warn!("GitHub cannot take binary attachment {filename}"),
+            }
+        }
+        if !report.links.is_empty() {
+            append_block(&mut description, &links_block(&report.links));
+        }
+        if !report.extra.is_empty() {
+            append_block(&mut description, &extra_summary(&report.extra));
+            if let Ok(json) = serde_json::to_string_pretty(&report.extra) {
+                append_block(&mut description, &inline_file("extra.json", &json));
+            }
+        }
+        if let Some(json) = state_json(report) {
+            let details = DescriptionBuilder::new()
+                .details("App state", DescriptionBuilder::new().code("json", &json))
+                .build();
+            append_block(&mut description, &details);
+        }
+        if let Some(threads) = &report.threads {
+            let details = DescriptionBuilder::new()
+                .details("Threads", DescriptionBuilder::new().code("", threads))
+                .build();
+            append_block(&mut description, &details);
+        }
+        if let Some(layout) = &self.layout {
+            description = layout.apply(&description);
+        }
+        append_reference(&mut description, &report.reference);
+
+        let mut payload = serde_json::json!({
+            "title": report.title,
+            "description": description,
+        });
+        let labels = merge_labels(&self.labels, &report.labels);
+        if !labels.is_empty() {
+            payload["labels"] = labels.into();
+        }
+        if let Some((name, email)) = &report.reporter {
+            payload["reporter"] = reporter_json(name, email);
+        }
+        if let Some(severity) = report.severity {
+            payload["severity"] = severity.as_str().into();
+        }
+        if report.feedback {
+            payload["kind"] = "feedback".into();
+        }
+        if let Some(fingerprint) = &report.fingerprint {
+            payload["fingerprint"] = fingerprint.as_str().into();
+        }
+        payload
+    }
+}
+
+impl Reporter for Client {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        self.submit(report).map(Submission::into_url)
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        let report = prepare(report, &self.pipeline)?;
+        let payload = self.payload(&report);
+        if self.dry_run {
+            return serde_json::to_string_pretty(&payload)
+                .map(Submission::Created)
+                .map_err(|e| Error::Parse(e.to_string()));
+        }
+        self.transport.submit(
+            &self.url,
+            "github",
+            self.token.clone(),
+            self.protocol.clone(),
+            payload,
+            &report.reference,
+        )
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        Client::comment(self, issue, body)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        Client::update_comment(self, comment, body)
+    }
+
+    fn privacy_level(&self) -> PrivacyLevel {
+        self.pipeline.privacy_level
+    }
+
+    fn spools(&self, _report: &Report) -> bool {
+        self.transport.has_spool()
+    }
+}
+
+/// A single GitHub issue, sent with [`create`](Self::create).
+pub struct Issue {
+    client: Client,
+    report: Report,
+}
+
+impl Issue {
+    pub(crate) fn new(proxy_url: &str) -> Self {
+        Self {
+            client: Client::new(proxy_url),
+            report: Report::new(),
+        }
+    }
+
+    pub fn with_token(&mut self, token: &str) -> &mut Self {
+        self.client.with_token(token);
+        self
+    }
+
     pub fn title(&mut self, title: &str) -> &mut Self {
-        self.title = title.to_string();
+        self.report.title(title);
         self
     }
 
     pub fn text(&mut self, text: &str) -> &mut Self {
-        if !self.description.is_empty() {
-            self.description.push_str("\n\n");
-        }
-        self.description.push_str(text);
+        self.report.text(text);
         self
     }
 
     pub fn file(&mut self, filename: &str, content: &str) -> &mut Self {
-        if !self.description.is_empty() {
-            self.description.push_str("\n\n");
-        }
-        self.description.push_str(&inline_file(filename, content));
+        self.report.file(filename, content);
         self
     }
 
-    /// Create the issue. Returns the issue URL.
-    pub fn create(&self) -> Result<String, Error> {
-        let payload = serde_json::json!({
-            "title": self.title,
-            "description": self.description,
-        });
+    /// See [`Report::label`].
+    pub fn label(&mut self, name: &str) -> &mut Self {
+        self.report.label(name);
+        self
+    }
 
-        let mut req =
-            ureq::post(&format!("{}/github", self.url)).set("Content-Type", "application/json");
-        if let Some(token) = &self.token {
-            req = req.set("Authorization", &format!("Bearer {}", token));
-        }
+    /// See [`Report::severity`].
+    pub fn severity(&mut self, severity: Severity) -> &mut Self {
+        self.report.severity(severity);
+        self
+    }
 
-        let resp_str = match req.send_string(&payload.to_string()) {
-            Ok(resp) => resp
-                .into_string()
-                .map_err(|e| Error::Parse(e.to_string()))?,
-            Err(ureq::Error::Status(code, resp)) => {
-                let body = resp.into_string().unwrap_or_default();
-                return Err(Error::Proxy { status: code, body });
-            }
-            Err(e) => return Err(e.into()),
-        };
+    /// See [`Report::feedback`].
+    pub fn feedback(&mut self, text: &str) -> &mut Self {
+        self.report.feedback(text);
+        self
+    }
 
-        let resp: serde_json::Value =
-            serde_json::from_str(&resp_str).map_err(|e| Error::Parse(e.to_string()))?;
+    /// See [`Report::reporter`].
+    pub fn reporter(&mut self, name: &str, email: &str) -> &mut Self {
+        self.report.reporter(name, email);
+        self
+    }
+
+    /// See [`Report::extra`].
+    pub fn extra(&mut self, key: &str, value: &impl serde::Serialize) -> &mut Self {
+        self.report.extra(key, value);
+        self
+    }
+
+    /// See [`Report::with_state`].
+    pub fn with_state(&mut self, state: &impl serde::Serialize) -> &mut Self {
+        self.report.with_state(state);
+        self
+    }
 
-        let url = resp["url"]
-            .as_str()
-            .ok_or_else(|| Error::Parse("proxy response missing url".into()))?
-            .to_string();
+    /// See [`Client::dry_run`].
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.client.dry_run(dry_run);
+        self
+    }
+
+    /// Create the issue. Returns the issue URL.
+    pub fn create(&self) -> Result<String, Error> {
+        self.client.report(&self.report)
+    }
+
+    /// Like [`create`](Self::create), but reports whether the proxy matched
+    /// an existing issue instead of creating a new one.
+    pub fn submit(&self) -> Result<Submission, Error> {
+        self.client.submit(&self.report)
+    }
 
-        Ok(url)
+    /// The report's support reference code. See [`Report::reference`].
+    pub fn reference(&self) -> &str {
+        &self.report.reference
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ci;
 
     #[test]
     fn test_create_issue() {
@@ -163,6 +542,182 @@ mod tests {
         mock.assert();
     }
 
+    #[test]
+    fn test_reporter() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/github")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({
+                    "reporter": { "name": "Jane", "email": "jane@example.com" }
+                })
+                .to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "url": "https://github.com/owner/repo/issues/4"
+                })
+                .to_string(),
+            )
+            .create();
+
+        let url = Issue::new(&server.url())
+            .title("reporter test")
+            .reporter("Jane", "jane@example.com")
+            .create()
+            .unwrap();
+
+        assert_eq!(url, "https://github.com/owner/repo/issues/4");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_feedback() {
+        let mut server = mockito::Server::new();
+        let mut issue = Issue::new(&server.url());
+        issue.client.pipeline.ci_env = ci::Env::new(|_| None);
+        issue.feedback("love the new editor");
+        let mock = server
+            .mock("POST", "/github")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({
+                    "title": "love the new editor",
+                    "description": format!("love the new editor\n\nReference: `{}`", issue.reference()),
+                    "severity": "feedback",
+                    "kind": "feedback",
+                })
+                .to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "url": "https://github.com/owner/repo/issues/5"
+                })
+                .to_string(),
+            )
+            .create();
+
+        let url = issue.create().unwrap();
+
+        assert_eq!(url, "https://github.com/owner/repo/issues/5");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/github").expect(0).create();
+
+        let mut issue = Issue::new(&server.url());
+        issue.client.pipeline.ci_env = ci::Env::new(|_| None);
+        issue.title("dry run").text("details").dry_run(true);
+        let payload = issue.create().unwrap();
+
+        let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "title": "dry run",
+                "description": format!("details\n\nReference: `{}`", issue.reference()),
+            })
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_with_state() {
+        let mut issue = Issue::new("http://unused");
+        issue.client.pipeline.ci_env = ci::Env::new(|_| None);
+        issue
+            .with_state(&serde_json::json!({ "dark_mode": true }))
+            .dry_run(true);
+        let payload: serde_json::Value = serde_json::from_str(&issue.create().unwrap()).unwrap();
+
+        assert!(payload["description"].as_str().unwrap().starts_with(
+            "<details>\n<summary>App state</summary>\n\n```json\n{\n  \"dark_mode\": true\n}\n```\n\n</details>"
+        ));
+    }
+
+    #[test]
+    fn test_response_missing_url() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/github")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"issue": 1}"#)
+            .create();
+
+        let result = Issue::new(&server.url()).title("test").create();
+
+        match result.unwrap_err() {
+            Error::Parse(msg) => assert!(msg.contains("missing field `url`"), "{msg}"),
+            other => panic!("expected Parse error, got: {}", other),
+        }
+        mock.assert();
+    }
+
+    #[test]
+    fn test_downgrades_for_v1_proxy() {
+        let mut server = mockito::Server::new();
+        // v1 workers answer the preflight without a protocol header.
+        let probe = server.mock("OPTIONS", "/github").with_status(204).create();
+        let mut client = Client::new(&server.url());
+        client.pipeline.ci_env = ci::Env::new(|_| None);
+        let mut report = Report::new();
+        report
+            .title("slow export")
+            .text("details")
+            .severity(Severity::Degraded);
+        let mock = server
+            .mock("POST", "/github")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "title": "slow export",
+                "description": format!(
+                    "details\n\nReference: `{}`\n\n## Triage\n\n| Field | Value |\n|-------|-------|\n| Severity | degraded |",
+                    report.reference
+                ),
+            })))
+            .with_status(200)
+            .with_body(serde_json::json!({ "url": "https://github.com/o/r/issues/8" }).to_string())
+            .create();
+
+        client.report(&report).unwrap();
+        client.report(&report).unwrap();
+
+        probe.expect(1).assert();
+        mock.expect(2).assert();
+    }
+
+    #[test]
+    fn test_submit_deduplicated() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/github")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "url": "https://github.com/test-owner/test-repo/issues/7",
+                    "deduplicated": true,
+                    "existing": true
+                })
+                .to_string(),
+            )
+            .create();
+
+        let submission = Issue::new(&server.url()).title("test").submit().unwrap();
+
+        assert_eq!(
+            submission,
+            Submission::Deduplicated("https://github.com/test-owner/test-repo/issues/7".into())
+        );
+        mock.assert();
+    }
+
     #[test]
     fn test_proxy_error() {
         let mut server = mockito::Server::new();
@@ -186,4 +741,95 @@ mod tests {
         }
         mock.assert();
     }
+
+    #[test]
+    fn test_comment() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/github/issues/42/comments")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({ "body": "new repro" }),
+            ))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({ "url": "https://github.com/o/r/issues/42#issuecomment-1" })
+                    .to_string(),
+            )
+            .create();
+
+        let url = Client::new(&server.url())
+            .comment("https://github.com/o/r/issues/42", "new repro")
+            .unwrap();
+
+        assert_eq!(url, "https://github.com/o/r/issues/42#issuecomment-1");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_update_comment() {
+        let mut server = mockito::Server::new();
+        let body = "**Hotline occurrences**\n\n- Occurrences: 2";
+        let mock = server
+            .mock("POST", "/github/issues/42/comments/1")
+            .match_body(mockito::Matcher::Json(serde_json::json!({ "body": body })))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({ "url": "https://github.com/o/r/issues/42#issuecomment-1" })
+                    .to_string(),
+            )
+            .create();
+
+        let client = Client::new(&server.url());
+        let url = client
+            .update_comment("https://github.com/o/r/issues/42#issuecomment-1", body)
+            .unwrap();
+
+        assert_eq!(url, "https://github.com/o/r/issues/42#issuecomment-1");
+        assert!(matches!(
+            client.update_comment("https://github.com/o/r/issues/42", body),
+            Err(Error::Config(_))
+        ));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_close_issue() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/github/issues/42/close")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({ "resolution": "canceled" }),
+            ))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "identifier": "#42",
+                    "title": "crash on start",
+                    "url": "https://github.com/o/r/issues/42",
+                    "state": "closed",
+                    "assignee": null,
+                    "updatedAt": "2026-01-02T03:04:05Z",
+                })
+                .to_string(),
+            )
+            .create();
+        let refused = server
+            .mock("POST", "/github/issues/7/close")
+            .with_status(403)
+            .with_body("Only issues filed with a fingerprint can be closed")
+            .create();
+
+        let client = Client::new(&server.url());
+        let status = client
+            .close_issue("https://github.com/o/r/issues/42", Resolution::Canceled)
+            .unwrap();
+
+        assert_eq!(status.state, "closed");
+        assert!(matches!(
+            client.close_issue("#7", Resolution::Done),
+            Err(Error::Proxy { status: 403, .. })
+        ));
+        mock.assert();
+        refused.assert();
+    }
 }
@@ -0,0 +1,193 @@
+//! Reading back issues that were already filed.
+
+use serde::de::DeserializeOwned;
+
+use crate::{Error, protocol};
+
+/// An issue's current state, from `status` on either client.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct IssueStatus {
+    /// `ENG-123` on Linear, `#42` on GitHub.
+    pub identifier: String,
+    pub title: String,
+    pub url: String,
+    /// The workflow state on Linear (such as "In Progress"), or `open` or
+    /// `closed` on GitHub.
+    pub state: String,
+    pub assignee: Option<String>,
+    /// When the issue last changed, as an RFC 3339 timestamp.
+    pub updated_at: String,
+}
+
+/// How an issue was settled, for `close_issue` on either client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Resolution {
+    /// Fixed: Linear's first completed state, or closed as completed on
+    /// GitHub.
+    Done,
+    /// Won't be fixed: Linear's first canceled state, or closed as not
+    /// planned on GitHub.
+    Canceled,
+}
+
+/// What to look for with `search` on either client.
+///
+/// ```no_run
+/// let mut query = hotln::IssueQuery::new("segfault");
+/// query.state("open").label("crash").limit(20);
+/// let issues = hotln::LinearClient::new("https://worker.example.com").search(&query)?;
+/// # Ok::<(), hotln::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IssueQuery {
+    text: String,
+    state: Option<String>,
+    labels: Vec<String>,
+    limit: Option<usize>,
+}
+
+impl IssueQuery {
+    /// Match issues whose title or description contains `text`.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// `open`, `closed`, or on Linear, the name of a workflow state.
+    pub fn state(&mut self, state: &str) -> &mut Self {
+        self.state = Some(state.to_string());
+        self
+    }
+
+    /// Only match issues with this label. Call again to require several.
+    pub fn label(&mut self, name: &str) -> &mut Self {
+        self.labels.push(name.to_string());
+        self
+    }
+
+    /// Return at most `limit` issues. The proxy caps this at 50.
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub(crate) fn params(&self) -> Vec<(&str, String)> {
+        let mut params = vec![("q", self.text.clone())];
+        if let Some(state) = &self.state {
+            params.push(("state", state.clone()));
+        }
+        for label in &self.labels {
+            params.push(("label", label.clone()));
+        }
+        if let Some(limit) = self.limit {
+            params.push(("limit", limit.to_string()));
+        }
+        params
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct SearchResponse {
+    pub(crate) issues: Vec<IssueStatus>,
+}
+
+/// The issue identifier in `issue`, which is either the identifier itself or
+/// an issue URL whose path has it right after `marker`.
+pub(crate) fn issue_id<'a>(issue: &'a str, marker: &str) -> &'a str {
+    let issue = issue.trim();
+    match issue.split_once(marker) {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or(rest),
+        None => issue.trim_start_matches('#'),
+    }
+}
+
+/// The comment ID in the fragment of a comment URL: `#comment-<id>` on
+/// Linear, `#issuecomment-<id>` on GitHub.
+pub(crate) fn comment_id(url: &str) -> Option<&str> {
+    let (_, fragment) = url.split_once('#')?;
+    fragment
+        .strip_prefix("issuecomment-")
+        .or_else(|| fragment.strip_prefix("comment-"))
+        .filter(|id| !id.is_empty())
+}
+
+pub(crate) fn get_json<T: DeserializeOwned>(
+    agent: &ureq::Agent,
+    url: &str,
+    params: &[(&str, String)],
+    token: Option<&str>,
+) -> Result<T, Error> {
+    let mut req = agent.get(url);
+    for (name, value) in params {
+        req = req.query(name, value);
+    }
+    if let Some(token) = token {
+        req = req.set("Authorization", &format!("Bearer {}", token));
+    }
+    read_json(req.call())
+}
+
+/// POST `payload` and parse the JSON response, for routes that answer with
+/// more than an issue URL.
+pub(crate) fn post_json_as<T: DeserializeOwned>(
+    agent: &ureq::Agent,
+    url: &str,
+    token: Option<&str>,
+    payload: &serde_json::Value,
+) -> Result<T, Error> {
+    let mut req = agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .set(protocol::HEADER, &protocol::VERSION.to_string());
+    if let Some(token) = token {
+        req = req.set("Authorization", &format!("Bearer {}", token));
+    }
+    read_json(req.send_string(&payload.to_string()))
+}
+
+fn read_json<T: DeserializeOwned>(result: Result<ureq::Response, ureq::Error>) -> Result<T, Error> {
+    match result {
+        Ok(resp) => {
+            let body = resp
+                .into_string()
+                .map_err(|e| Error::Parse(e.to_string()))?;
+            serde_json::from_str(&body).map_err(|e| Error::Parse(e.to_string()))
+        }
+        Err(ureq::Error::Status(status, resp)) => {
+            let body = resp.into_string().unwrap_or_default();
+            Err(Error::Proxy { status, body })
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_id() {
+        assert_eq!(issue_id("ENG-123", "/issue/"), "ENG-123");
+        assert_eq!(
+            issue_id(
+                "https://linear.app/acme/issue/ENG-123/crash-on-start",
+                "/issue/"
+            ),
+            "ENG-123"
+        );
+        assert_eq!(issue_id("#42", "/issues/"), "42");
+        assert_eq!(
+            issue_id(
+                "https://github.com/o/r/issues/42#issuecomment-1",
+                "/issues/"
+            ),
+            "42"
+        );
+    }
+}
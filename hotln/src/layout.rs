@@ -0,0 +1,270 @@
+//! Choosing which sections of an issue body appear, and in what order, to
+//! match a team's issue template.
+
+use std::str::FromStr;
+
+use crate::Error;
+
+/// A part of an issue body that a [`Layout`] can move or leave out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Section {
+    /// The report's own text, summary tables, and links.
+    Description,
+    /// System info, app version, and runtime tables.
+    SystemInfo,
+    /// Files added with [`Report::file`](crate::Report::file), such as logs
+    /// and captured stderr.
+    Logs,
+    /// Backtraces, source snippets, crashed threads, and thread dumps.
+    Backtrace,
+    /// The extra context table and app state.
+    Extra,
+}
+
+impl Section {
+    fn name(self) -> &'static str {
+        match self {
+            Section::Description => "description",
+            Section::SystemInfo => "system_info",
+            Section::Logs => "logs",
+            Section::Backtrace => "backtrace",
+            Section::Extra => "extra",
+        }
+    }
+
+    /// The section a block with this heading, collapsed summary, or file
+    /// name belongs to.
+    fn of_heading(heading: &str) -> Self {
+        match heading {
+            "System Info" | "System" | "App" | "Tokio Runtime" | "Terminal" => Section::SystemInfo,
+            "Extra" | "App state" => Section::Extra,
+            "Threads" | "Source" | "Crashed thread" => Section::Backtrace,
+            _ => Section::Description,
+        }
+    }
+
+    fn of_file(filename: &str) -> Self {
+        let stem = filename.split('.').next().unwrap_or(filename);
+        if ["backtrace", "stack", "panic", "message"].contains(&stem) || stem.contains("backtrace")
+        {
+            Section::Backtrace
+        } else {
+            Section::Logs
+        }
+    }
+}
+
+impl FromStr for Section {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Error> {
+        let sections = [
+            Section::Description,
+            Section::SystemInfo,
+            Section::Logs,
+            Section::Backtrace,
+            Section::Extra,
+        ];
+        sections
+            .into_iter()
+            .find(|section| section.name() == name.trim())
+            .ok_or_else(|| {
+                Error::Config(format!(
+                    "unknown report section {name:?}, expected description, system_info, \
+                     logs, backtrace, or extra"
+                ))
+            })
+    }
+}
+
+/// The sections of an issue body, in order. Sections left out of the
+/// layout are dropped; the support reference always comes last.
+///
+/// Without a layout, clients keep the order the report was built in.
+///
+/// ```
+/// let layout: hotln::Layout = "description, backtrace, logs, system_info".parse()?;
+/// let mut client = hotln::LinearClient::new("https://your-proxy.example.com");
+/// client.layout(layout);
+/// # Ok::<(), hotln::Error>(())
+/// ```
+///
+/// In a [`Config`](crate::Config) file:
+///
+/// ```toml
+/// layout = "description, backtrace, logs, system_info, extra"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct Layout {
+    sections: Vec<Section>,
+}
+
+impl Layout {
+    /// A layout with `sections` in this order. Repeats are ignored.
+    pub fn new(sections: &[Section]) -> Self {
+        let mut out = Vec::new();
+        for section in sections {
+            if !out.contains(section) {
+                out.push(*section);
+            }
+        }
+        Self { sections: out }
+    }
+
+    /// Reorder the blocks of `body` by section, keeping the order of blocks
+    /// within a section.
+    pub(crate) fn apply(&self, body: &str) -> String {
+        let blocks = classify(body);
+        let mut out = Vec::new();
+        for section in &self.sections {
+            out.extend(
+                blocks
+                    .iter()
+                    .filter(|(s, _)| s == section)
+                    .map(|(_, block)| block.as_str()),
+            );
+        }
+        out.join("\n\n")
+    }
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    /// Parse a comma-separated list of section names, such as
+    /// `"description, backtrace, system_info"`.
+    fn from_str(spec: &str) -> Result<Self, Error> {
+        let sections = spec
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Section>, Error>>()?;
+        if sections.is_empty() {
+            return Err(Error::Config("the layout has no sections".to_string()));
+        }
+        Ok(Self::new(&sections))
+    }
+}
+
+impl TryFrom<String> for Layout {
+    type Error = Error;
+
+    fn try_from(spec: String) -> Result<Self, Error> {
+        spec.parse()
+    }
+}
+
+/// Splits `body` into top-level blocks, keeping code blocks and collapsed
+/// sections whole, and works out the section of each. Plain blocks after a
+/// heading belong to the heading's section.
+fn classify(body: &str) -> Vec<(Section, String)> {
+    let mut raw: Vec<String> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut depth = 0usize;
+    for line in body.split('\n') {
+        let ticks = &line[..line.len() - line.trim_start_matches('`').len()];
+        match fence {
+            Some(open) if line.trim_end() == open => fence = None,
+            Some(_) => {}
+            None if ticks.len() >= 3 => fence = Some(ticks),
+            None if line == "<details>" || line.starts_with("+++ ") => depth += 1,
+            None if line == "</details>" || line == "+++" => depth = depth.saturating_sub(1),
+            None if line.is_empty() && depth == 0 => {
+                if !current.is_empty() {
+                    raw.push(current.join("\n"));
+                    current.clear();
+                }
+                continue;
+            }
+            None => {}
+        }
+        current.push(line);
+    }
+    if !current.is_empty() {
+        raw.push(current.join("\n"));
+    }
+
+    let mut owner = None;
+    raw.into_iter()
+        .map(|block| {
+            let section = match section_of(&block) {
+                Some(section) => {
+                    owner = block.starts_with("## ").then_some(section);
+                    section
+                }
+                None => owner.unwrap_or(Section::Description),
+            };
+            (section, block)
+        })
+        .collect()
+}
+
+/// The section a block's first line places it in, or `None` for plain text.
+fn section_of(block: &str) -> Option<Section> {
+    let mut lines = block.lines();
+    let first = lines.next().unwrap_or("");
+    let summary = if first == "<details>" {
+        lines
+            .next()
+            .and_then(|line| line.strip_prefix("<summary>"))
+            .and_then(|line| line.strip_suffix("</summary>"))
+    } else {
+        first.strip_prefix("+++ ")
+    };
+    if let Some(summary) = summary {
+        // Collapsed files are summarized as "name (N lines)".
+        return Some(match summary.rsplit_once(" (") {
+            Some((name, count)) if count.ends_with(" lines)") => Section::of_file(name),
+            _ => Section::of_heading(summary),
+        });
+    }
+    if let Some(heading) = first.strip_prefix("## ") {
+        return Some(Section::of_heading(heading));
+    }
+    if let Some(name) = first.strip_prefix("**").and_then(|s| s.strip_suffix("**"))
+        && lines.next().is_some_and(|line| line.starts_with("```"))
+    {
+        return Some(Section::of_file(name));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let layout: Layout = "backtrace, description,backtrace".parse().unwrap();
+        assert_eq!(
+            layout,
+            Layout::new(&[Section::Backtrace, Section::Description])
+        );
+        assert!("description, stack".parse::<Layout>().is_err());
+        assert!(" , ".parse::<Layout>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let body = "It crashed.\n\n\
+                    ## Panic\n\n| Field | Value |\n|-------|-------|\n| Thread | main |\n\n\
+                    **message.txt**\n```text\nboom\n```\n\n\
+                    **app.log**\n```log\nstarting\n\nready\n```\n\n\
+                    <details>\n<summary>backtrace.txt (40 lines)</summary>\n\n**backtrace.txt**\n```text\n0: main\n```\n\n</details>\n\n\
+                    ## System Info\n\n| Field | Value |\n|-------|-------|\n| OS | linux |\n\n\
+                    ## Threads\n\nthread 2\n\nthread 3";
+        let layout: Layout = "backtrace, description, system_info".parse().unwrap();
+        assert_eq!(
+            layout.apply(body),
+            "**message.txt**\n```text\nboom\n```\n\n\
+             <details>\n<summary>backtrace.txt (40 lines)</summary>\n\n**backtrace.txt**\n```text\n0: main\n```\n\n</details>\n\n\
+             ## Threads\n\nthread 2\n\nthread 3\n\n\
+             It crashed.\n\n\
+             ## Panic\n\n| Field | Value |\n|-------|-------|\n| Thread | main |\n\n\
+             ## System Info\n\n| Field | Value |\n|-------|-------|\n| OS | linux |"
+        );
+    }
+}
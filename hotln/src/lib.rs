@@ -24,11 +24,118 @@
 
 pub use ureq;
 
+mod apple_crash;
+mod attachments;
+#[cfg(feature = "bevy")]
+pub mod bevy;
+mod cancel;
+mod capture;
+mod ci;
+mod config;
+mod dedup;
+mod discord;
+#[cfg(feature = "egui")]
+pub mod egui;
+#[cfg(feature = "smtp")]
+mod email;
+mod fanout;
+mod fingerprint;
 mod github;
+mod issues;
+mod layout;
 mod linear;
+mod markdown;
+mod minidump;
+mod nonblocking;
+mod panic;
+mod pinning;
+mod prepare;
+mod privacy;
+mod protocol;
+mod queue;
+mod rate_limit;
+mod redact;
+mod report;
+mod router;
+#[cfg(feature = "tokio")]
+mod runtime;
+mod scrub;
+mod secrets;
+mod sentry;
+mod slack;
+mod spool;
+#[cfg(feature = "symbolicate")]
+mod symbolicate;
+mod sysinfo;
+#[cfg(feature = "tauri")]
+pub mod tauri;
+mod telemetry;
+pub mod testing;
+#[cfg(feature = "thread-dump")]
+mod threads;
+mod throttle;
+#[cfg(feature = "tower")]
+pub mod tower;
+mod transport;
 
+pub use apple_crash::AppleCrash;
+pub use attachments::AttachmentPolicy;
+pub use config::{Backend, Config};
+pub use dedup::Dedup;
+pub use discord::DiscordSink;
+#[cfg(feature = "smtp")]
+pub use email::EmailReporter;
+pub use fanout::{Fanout, fanout};
+pub use fingerprint::{fingerprint, message_fingerprint, normalize_message};
+pub use github::Client as GitHubClient;
 pub use github::Issue as GitHubIssue;
+#[cfg(feature = "macros")]
+pub use hotln_macros::{SystemInfo, embed_config, report_panics};
+pub use issues::{IssueQuery, IssueStatus, Resolution};
+pub use layout::{Layout, Section};
+pub use linear::Client as LinearClient;
 pub use linear::Issue as LinearIssue;
+pub use linear::{
+    Check, Project as LinearProject, Team as LinearTeam, ValidationReport, Viewer as LinearViewer,
+};
+pub use markdown::{DescriptionBuilder, code_language};
+pub use minidump::{Minidump, Module as MinidumpModule};
+pub use nonblocking::{SubmitFuture, submit_async};
+pub use panic::{PanicHook, panic_report};
+pub use privacy::PrivacyLevel;
+pub use rate_limit::RateLimitInfo;
+pub use redact::{Redaction, Rule as RedactRule};
+pub use report::{Customer, Link, Report};
+pub use router::{Destination, Matcher, Router};
+pub use secrets::Secrets;
+pub use sentry::Client as SentryClient;
+pub use slack::SlackSink;
+pub use spool::Spool;
+#[cfg(feature = "gpu")]
+pub use sysinfo::gpu_info;
+pub use sysinfo::{
+    Build, Env, Hardware, Os, SystemInfoSource, disk_space, system_info, terminal_info,
+};
+pub use throttle::Throttle;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    /// Reverse the XOR applied by `embed_config!`.
+    pub fn deobfuscate(data: &[u8], key: &[u8]) -> String {
+        let bytes: Vec<u8> = data
+            .iter()
+            .zip(key.iter().cycle())
+            .map(|(b, k)| b ^ k)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Hash a `#[info(redact)]` value for `#[derive(SystemInfo)]`.
+    pub fn hash_value(value: &str) -> String {
+        crate::redact::hash(value)
+    }
+}
 
 /// Create a GitHub issue builder that posts through a proxy.
 pub fn github(proxy_url: &str) -> GitHubIssue {
@@ -40,6 +147,221 @@ pub fn linear(proxy_url: &str) -> LinearIssue {
     LinearIssue::new(proxy_url)
 }
 
+/// Something that can file a [`Report`].
+///
+/// [`LinearClient`] and [`GitHubClient`] send reports through a proxy;
+/// [`testing::MockReporter`] records them for tests. Write your reporting
+/// code against this trait so it can be exercised without a server.
+pub trait Reporter {
+    /// File the report and return the URL of the created issue.
+    fn report(&self, report: &Report) -> Result<String, Error>;
+
+    /// File the report and say whether a new issue was created.
+    ///
+    /// The default treats every report as new. Proxy clients override this
+    /// to pass on the proxy's duplicate detection.
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        self.report(report).map(Submission::Created)
+    }
+
+    /// Add a markdown comment to an issue this reporter filed, given its
+    /// URL, and return the comment's URL.
+    ///
+    /// The default fails with [`Error::Config`]; the proxy clients override
+    /// it.
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        let _ = (issue, body);
+        Err(Error::Config(
+            "this reporter can't comment on issues".into(),
+        ))
+    }
+
+    /// Replace the body of a comment this reporter added, given the URL
+    /// [`comment`](Self::comment) returned, and return the comment's URL.
+    ///
+    /// The proxies only allow this for occurrence comments posted by
+    /// [`Dedup`].
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        let _ = (comment, body);
+        Err(Error::Config("this reporter can't update comments".into()))
+    }
+
+    /// How much context collectors should add to reports for this reporter.
+    fn privacy_level(&self) -> PrivacyLevel {
+        PrivacyLevel::Full
+    }
+
+    /// Whether a failed submission of `report` that
+    /// [might succeed later](Error::is_retryable) is saved to a spool.
+    ///
+    /// The default is false; the proxy clients say whether they have a
+    /// spool, and wrappers ask the reporter the report would go to.
+    fn spools(&self, report: &Report) -> bool {
+        let _ = report;
+        false
+    }
+
+    /// File the report, logging a failure with `tracing` instead of
+    /// returning it, for crash paths that can't act on a reporting error.
+    ///
+    /// A failed report is [`Submission::Spooled`] if the reporter kept it
+    /// to resend, and [`Submission::Dropped`] otherwise.
+    fn report_or_log(&self, report: &Report) -> Submission {
+        match self.submit(report) {
+            Ok(submission) => submission,
+            Err(e) if e.is_retryable() && self.spools(report) => {
+                tracing::warn!(
+                    "spooled report {} after failing to file it: {e}",
+                    report.reference
+                );
+                Submission::Spooled(report.reference.clone())
+            }
+            Err(e) => {
+                tracing::error!("failed to file report {}: {e}", report.reference);
+                Submission::Dropped
+            }
+        }
+    }
+
+    /// File the report on a detached thread and return at once, for code
+    /// that can't block at all, such as drop handlers. Failures are handled
+    /// as by [`report_or_log`](Self::report_or_log).
+    ///
+    /// The report is lost if the process exits before it is sent, so a
+    /// panic hook that exits afterwards should use `report_or_log`.
+    /// Clients are cheap to clone; share other reporters in an `Arc`.
+    fn fire_and_forget(&self, report: Report)
+    where
+        Self: Clone + Send + Sized + 'static,
+    {
+        let reporter = self.clone();
+        let spawned = std::thread::Builder::new()
+            .name("hotline-report".into())
+            .spawn(move || reporter.report_or_log(&report));
+        if let Err(e) = spawned {
+            tracing::error!("cannot start a thread to file a report: {e}");
+        }
+    }
+}
+
+impl<R: Reporter + ?Sized> Reporter for std::sync::Arc<R> {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        (**self).report(report)
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        (**self).submit(report)
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        (**self).comment(issue, body)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        (**self).update_comment(comment, body)
+    }
+
+    fn privacy_level(&self) -> PrivacyLevel {
+        (**self).privacy_level()
+    }
+
+    fn spools(&self, report: &Report) -> bool {
+        (**self).spools(report)
+    }
+}
+
+impl<R: Reporter + ?Sized> Reporter for Box<R> {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        (**self).report(report)
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        (**self).submit(report)
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        (**self).comment(issue, body)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        (**self).update_comment(comment, body)
+    }
+
+    fn privacy_level(&self) -> PrivacyLevel {
+        (**self).privacy_level()
+    }
+
+    fn spools(&self, report: &Report) -> bool {
+        (**self).spools(report)
+    }
+}
+
+/// The outcome of filing a report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Submission {
+    /// A new issue was created at this URL.
+    Created(String),
+    /// The proxy matched an existing issue and returned its URL instead.
+    Deduplicated(String),
+    /// The proxy couldn't be reached, and the report is waiting in the
+    /// client's retry queue. There is no URL yet, only the report's
+    /// [`reference`](Report::reference) code.
+    Queued(String),
+    /// The report was saved to the client's spool, to be sent later, with
+    /// its reference code. Returned when a report is held back by the
+    /// [rate limit reserve](LinearClient::rate_limit_reserve), or by
+    /// [`Reporter::report_or_log`] after a failure.
+    Spooled(String),
+    /// Filing failed and the report wasn't kept. Only returned by
+    /// [`Reporter::report_or_log`].
+    Dropped,
+}
+
+impl Submission {
+    /// The issue URL, or `""` if there is no issue yet.
+    pub fn url(&self) -> &str {
+        match self {
+            Submission::Created(url) | Submission::Deduplicated(url) => url,
+            Submission::Queued(_) | Submission::Spooled(_) | Submission::Dropped => "",
+        }
+    }
+
+    pub fn into_url(self) -> String {
+        match self {
+            Submission::Created(url) | Submission::Deduplicated(url) => url,
+            Submission::Queued(_) | Submission::Spooled(_) | Submission::Dropped => String::new(),
+        }
+    }
+}
+
+/// How serious a report is.
+///
+/// The proxy maps each severity to a Linear priority and applies a label of
+/// the same name (`crash`, `bug`, `degraded`, `feedback`) when one exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The application crashed or lost data. Urgent priority.
+    Crash,
+    /// Something is broken but the application keeps running. High priority.
+    Bug,
+    /// Slow or partially working. Medium priority.
+    Degraded,
+    /// Not a defect: suggestions and comments from users. Low priority.
+    Feedback,
+}
+
+impl Severity {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Severity::Crash => "crash",
+            Severity::Bug => "bug",
+            Severity::Degraded => "degraded",
+            Severity::Feedback => "feedback",
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum Error {
@@ -49,6 +371,60 @@ pub enum Error {
     Parse(String),
     #[error("Proxy returned error {status}: {body}")]
     Proxy { status: u16, body: String },
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    #[error("Submission did not finish within {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("Spool error: {0}")]
+    Spool(String),
+    /// The report looked like it held a credential, such as "an AWS access
+    /// key", and the client was set to refuse it.
+    #[error("Report not sent: it contains what looks like {0}")]
+    Secret(String),
+    /// The mail server couldn't be reached or refused the message.
+    #[error("SMTP error: {0}")]
+    Smtp(String),
+    /// A [`Throttle`] had already filed its limit of reports for the period.
+    /// The next report may be filed after this long.
+    #[error("Report not sent: report limit reached, try again in {0:?}")]
+    Throttled(std::time::Duration),
+    /// The submission was cancelled, as by dropping its [`SubmitFuture`],
+    /// before the report was sent.
+    #[error("Submission was cancelled")]
+    Cancelled,
+}
+
+impl Error {
+    /// Whether sending the same report again later might work: the proxy
+    /// couldn't be reached, took too long, or answered 408, 429 or 5xx. This
+    /// is what the spool and retry queue go by.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Timeout(_) => true,
+            Error::Http(_) | Error::Proxy { .. } => self
+                .status()
+                .is_none_or(|status| matches!(status, 408 | 429 | 500..)),
+            _ => false,
+        }
+    }
+
+    /// Whether the proxy, or the server behind it, rejected the credentials
+    /// (401 or 403).
+    pub fn is_auth(&self) -> bool {
+        matches!(self.status(), Some(401 | 403))
+    }
+
+    /// The HTTP status of the response that failed, if there was one.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Proxy { status, .. } => Some(*status),
+            Error::Http(e) => match e.as_ref() {
+                ureq::Error::Status(status, _) => Some(*status),
+                ureq::Error::Transport(_) => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 impl From<ureq::Error> for Error {
@@ -57,9 +433,227 @@ impl From<ureq::Error> for Error {
     }
 }
 
+pub(crate) fn post_json(
+    agent: &ureq::Agent,
+    url: &str,
+    token: Option<&str>,
+    payload: &serde_json::Value,
+) -> Result<Submission, Error> {
+    let mut req = agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .set(protocol::HEADER, &protocol::VERSION.to_string());
+    if let Some(token) = token {
+        req = req.set("Authorization", &format!("Bearer {}", token));
+    }
+
+    let span = tracing::info_span!(
+        "hotline.submit",
+        url,
+        status = tracing::field::Empty,
+        request_id = tracing::field::Empty,
+        ratelimit_requests_remaining = tracing::field::Empty,
+        ratelimit_requests_reset = tracing::field::Empty,
+        ratelimit_complexity_remaining = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+
+    let resp_str = match req.send_string(&payload.to_string()) {
+        Ok(resp) => {
+            record_response(&span, &resp);
+            resp.into_string()
+                .map_err(|e| Error::Parse(e.to_string()))?
+        }
+        Err(ureq::Error::Status(code, resp)) => {
+            record_response(&span, &resp);
+            let body = resp.into_string().unwrap_or_default();
+            return Err(Error::Proxy { status: code, body });
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let resp: ProxyResponse =
+        serde_json::from_str(&resp_str).map_err(|e| Error::Parse(e.to_string()))?;
+
+    if resp.deduplicated {
+        Ok(Submission::Deduplicated(resp.url))
+    } else {
+        Ok(Submission::Created(resp.url))
+    }
+}
+
+/// Post `payload` to a chat webhook, which answers with no useful body.
+pub(crate) fn post_webhook(
+    agent: &ureq::Agent,
+    url: &str,
+    payload: &serde_json::Value,
+) -> Result<(), Error> {
+    let response = agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&payload.to_string());
+    match response {
+        Ok(resp) => {
+            drain(resp);
+            Ok(())
+        }
+        Err(ureq::Error::Status(status, resp)) => Err(Error::Proxy {
+            status,
+            body: resp.into_string().unwrap_or_default(),
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Read the rest of a response nobody needs, so ureq returns the
+/// connection to the agent's pool instead of closing it.
+pub(crate) fn drain(resp: ureq::Response) {
+    let _ = std::io::copy(&mut resp.into_reader(), &mut std::io::sink());
+}
+
+/// Record the tracker's request ID and rate limits, which the proxy passes
+/// through, so a failed submission can be matched to the tracker's logs.
+fn record_response(span: &tracing::Span, resp: &ureq::Response) {
+    span.record("status", resp.status());
+    for (field, header) in [
+        ("request_id", "x-request-id"),
+        (
+            "ratelimit_requests_remaining",
+            "x-ratelimit-requests-remaining",
+        ),
+        ("ratelimit_requests_reset", "x-ratelimit-requests-reset"),
+        (
+            "ratelimit_complexity_remaining",
+            "x-ratelimit-complexity-remaining",
+        ),
+    ] {
+        if let Some(value) = resp.header(header) {
+            span.record(field, value);
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ProxyResponse {
+    url: String,
+    #[serde(default)]
+    deduplicated: bool,
+}
+
+/// A "Links" section for backends that can't attach links to the issue.
+pub(crate) fn links_block(links: &[Link]) -> String {
+    let mut out = String::from("## Links\n");
+    for Link {
+        url,
+        title,
+        subtitle,
+    } in links
+    {
+        out.push_str(&format!("\n- [{title}]({url})"));
+        if let Some(subtitle) = subtitle {
+            out.push_str(&format!(": {subtitle}"));
+        }
+    }
+    out
+}
+
 pub(crate) fn inline_file(filename: &str, content: &str) -> String {
-    let ext = filename.rsplit('.').next().unwrap_or("");
-    format!("**{filename}**\n```{ext}\n{content}\n```")
+    let lang = markdown::code_language(filename);
+    format!("**{filename}**\n{}", markdown::code_block(lang, content))
+}
+
+pub(crate) fn info_table(heading: &str, rows: &[(&str, &str)]) -> String {
+    let mut out = format!("## {heading}\n\n| Field | Value |\n|-------|-------|");
+    for (key, value) in rows {
+        out.push_str(&format!("\n| {key} | {} |", markdown::escape_cell(value)));
+    }
+    out
+}
+
+// Client-wide labels first, then the report's own, without duplicates.
+pub(crate) fn merge_labels(defaults: &[String], labels: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::new();
+    for label in defaults.iter().chain(labels) {
+        if !merged.contains(label) {
+            merged.push(label.clone());
+        }
+    }
+    merged
+}
+
+pub(crate) fn append_block(description: &mut String, block: &str) {
+    if !description.is_empty() {
+        description.push_str("\n\n");
+    }
+    description.push_str(block);
+}
+
+/// Ends the body with the report's support reference, unless it was cleared.
+pub(crate) fn append_reference(description: &mut String, reference: &str) {
+    if !reference.is_empty() {
+        append_block(description, &format!("Reference: `{reference}`"));
+    }
+}
+
+pub(crate) fn state_json(report: &Report) -> Option<String> {
+    serde_json::to_string_pretty(report.state.as_ref()?).ok()
+}
+
+pub(crate) fn extra_summary(extra: &serde_json::Map<String, serde_json::Value>) -> String {
+    const MAX_PREVIEW: usize = 60;
+    let mut out = String::from("## Extra\n\n| Key | Value |\n|-----|-------|");
+    for (key, value) in extra {
+        let mut preview = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        if preview.chars().count() > MAX_PREVIEW {
+            preview = preview.chars().take(MAX_PREVIEW - 3).collect::<String>() + "...";
+        }
+        let preview = preview.replace('|', "\\|").replace('\n', " ");
+        out.push_str(&format!("\n| {key} | {preview} |"));
+    }
+    out
+}
+
+pub(crate) fn reporter_json(name: &str, email: &str) -> serde_json::Value {
+    let mut reporter = serde_json::Map::new();
+    if !name.is_empty() {
+        reporter.insert("name".into(), name.into());
+    }
+    if !email.is_empty() {
+        reporter.insert("email".into(), email.into());
+    }
+    serde_json::Value::Object(reporter)
+}
+
+/// `time` as an RFC 3339 UTC timestamp, such as `2024-05-01T12:00:00Z`.
+pub(crate) fn rfc3339(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!(
+        "{year}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs % 86_400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    )
+}
+
+/// Year, month, and day of a count of days since 1970-01-01.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
 
 pub(crate) fn mime_for_ext(filename: &str) -> &'static str {
@@ -68,6 +662,7 @@ pub(crate) fn mime_for_ext(filename: &str) -> &'static str {
         "png" => "image/png",
         "jpg" | "jpeg" => "image/jpeg",
         "gif" => "image/gif",
+        "webp" => "image/webp",
         "json" => "application/json",
         "pdf" => "application/pdf",
         "txt" | "log" => "text/plain",
@@ -88,7 +683,42 @@ mod tests {
     #[test]
     fn test_inline_file_no_extension() {
         let result = inline_file("Makefile", "all: build");
-        assert_eq!(result, "**Makefile**\n```Makefile\nall: build\n```");
+        assert_eq!(result, "**Makefile**\n```makefile\nall: build\n```");
+    }
+
+    #[test]
+    fn test_info_table() {
+        assert_eq!(
+            info_table("System Info", &[("OS", "linux"), ("Shell", "a|b")]),
+            "## System Info\n\n| Field | Value |\n|-------|-------|\n| OS | linux |\n| Shell | a\\|b |"
+        );
+    }
+
+    #[test]
+    fn test_extra_summary() {
+        let mut extra = serde_json::Map::new();
+        extra.insert("build".into(), "nightly".into());
+        extra.insert("flags".into(), serde_json::json!({ "gpu": true }));
+        extra.insert("long".into(), "x".repeat(100).into());
+        assert_eq!(
+            extra_summary(&extra),
+            format!(
+                "## Extra\n\n| Key | Value |\n|-----|-------|\n| build | nightly |\n| flags | {{\"gpu\":true}} |\n| long | {}... |",
+                "x".repeat(57)
+            )
+        );
+    }
+
+    #[test]
+    fn test_reporter_json_skips_empty() {
+        assert_eq!(
+            reporter_json("Jane", ""),
+            serde_json::json!({ "name": "Jane" })
+        );
+        assert_eq!(
+            reporter_json("", "jane@example.com"),
+            serde_json::json!({ "email": "jane@example.com" })
+        );
     }
 
     #[test]
@@ -104,4 +734,73 @@ mod tests {
         assert_eq!(mime_for_ext("archive.tar.gz"), "application/octet-stream");
         assert_eq!(mime_for_ext("noext"), "application/octet-stream");
     }
+
+    #[test]
+    fn test_fire_and_forget() {
+        let mock = std::sync::Arc::new(testing::MockReporter::new());
+        let mut report = Report::new();
+        report.title("dropped guard");
+        mock.fire_and_forget(report);
+
+        let started = std::time::Instant::now();
+        while mock.reports().is_empty() && started.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(mock.reports()[0].title, "dropped guard");
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let proxy = |status| Error::Proxy {
+            status,
+            body: String::new(),
+        };
+        assert!(proxy(503).is_retryable());
+        assert!(proxy(429).is_retryable());
+        assert!(!proxy(400).is_retryable());
+        assert!(proxy(401).is_auth());
+        assert!(proxy(403).is_auth());
+        assert!(!proxy(500).is_auth());
+        assert_eq!(proxy(404).status(), Some(404));
+
+        let timeout = Error::Timeout(std::time::Duration::from_secs(5));
+        assert!(timeout.is_retryable());
+        assert_eq!(timeout.status(), None);
+        assert!(!Error::Config("bad pin".into()).is_retryable());
+        assert!(!Error::Secret("an AWS access key".into()).is_retryable());
+    }
+
+    #[test]
+    fn test_retryable_http_errors() {
+        let status = |code| {
+            let response = ureq::Response::new(code, "", "").unwrap();
+            Error::Http(Box::new(ureq::Error::Status(code, response)))
+        };
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let transport = Error::Http(Box::new(ureq::Error::from(refused)));
+        assert!(transport.is_retryable());
+        assert_eq!(transport.status(), None);
+
+        assert!(status(408).is_retryable());
+        assert!(status(429).is_retryable());
+        assert!(status(500).is_retryable());
+        assert!(status(503).is_retryable());
+        assert!(!status(400).is_retryable());
+        assert!(!status(401).is_retryable());
+        assert!(!status(404).is_retryable());
+        assert!(!status(422).is_retryable());
+    }
+
+    #[test]
+    fn test_retryable_proxy_errors() {
+        let proxy = |status| Error::Proxy {
+            status,
+            body: String::new(),
+        };
+        assert!(proxy(408).is_retryable());
+        assert!(proxy(429).is_retryable());
+        assert!(proxy(502).is_retryable());
+        assert!(!proxy(403).is_retryable());
+        assert!(!proxy(413).is_retryable());
+    }
 }
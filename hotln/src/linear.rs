@@ -1,119 +1,778 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
 use base64::prelude::*;
 
-use crate::{Error, inline_file, mime_for_ext};
+use crate::issues::{SearchResponse, comment_id, get_json, issue_id, post_json_as};
+use crate::markdown::{code_block, linear_details};
+use crate::prepare::{Pipeline, Prepared, prepare};
+use crate::rate_limit::RateLimitInfo;
+use crate::transport::Transport;
+use crate::{
+    AttachmentPolicy, Customer, Error, IssueQuery, IssueStatus, Layout, PrivacyLevel, Redaction,
+    Report, Reporter, Resolution, Secrets, Severity, Spool, Submission, SystemInfoSource,
+    append_block, append_reference, extra_summary, info_table, merge_labels, mime_for_ext,
+    post_json, reporter_json, state_json,
+};
 
-pub struct Issue {
+/// Files reports as Linear issues through a proxy.
+#[derive(Debug, Clone)]
+pub struct Client {
     url: String,
     token: Option<String>,
-    title: String,
-    description: String,
-    attachments: Vec<(String, Vec<u8>)>,
+    dry_run: bool,
+    labels: Vec<String>,
+    app_version: Option<String>,
+    protocol: Arc<OnceLock<u8>>,
+    pipeline: Pipeline,
+    layout: Option<Layout>,
+    transport: Transport,
 }
 
-impl Issue {
-    pub(crate) fn new(proxy_url: &str) -> Self {
+impl Client {
+    pub fn new(proxy_url: &str) -> Self {
         Self {
             url: proxy_url.to_string(),
             token: None,
-            title: "Untitled".to_string(),
-            description: String::new(),
-            attachments: Vec::new(),
+            dry_run: false,
+            labels: Vec::new(),
+            app_version: None,
+            protocol: Arc::new(OnceLock::new()),
+            pipeline: Pipeline::default(),
+            layout: None,
+            transport: Transport::default(),
         }
     }
 
+    /// Send `token` to the proxy as a bearer token. Filing reports needs its
+    /// app token; [`status`](Self::status), [`search`](Self::search),
+    /// [`viewer`](Self::viewer), [`comment`](Self::comment), and
+    /// [`close_issue`](Self::close_issue) need its admin token, which should
+    /// stay out of shipped apps.
     pub fn with_token(&mut self, token: &str) -> &mut Self {
         self.token = Some(token.to_string());
         self
     }
 
-    pub fn title(&mut self, title: &str) -> &mut Self {
-        self.title = title.to_string();
+    /// Build payloads without sending them.
+    ///
+    /// When enabled, [`report`](Reporter::report) makes no network call and
+    /// returns the pretty-printed JSON that would have been posted to the
+    /// proxy instead of an issue URL.
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
         self
     }
 
-    pub fn text(&mut self, text: &str) -> &mut Self {
-        if !self.description.is_empty() {
-            self.description.push_str("\n\n");
-        }
-        self.description.push_str(text);
+    /// Only trust the proxy if its certificate matches `pin`.
+    ///
+    /// `pin` is either `sha256/<base64>`, the SHA-256 of the certificate's
+    /// public key as printed by curl, or the certificate's SHA-256
+    /// fingerprint in hex (colons optional). Call this more than once to
+    /// allow backup pins. A malformed pin makes every report fail with
+    /// [`Error::Config`] rather than silently disabling pinning.
+    pub fn with_pinned_cert(&mut self, pin: &str) -> &mut Self {
+        self.transport.pin(pin);
         self
     }
 
-    pub fn file(&mut self, filename: &str, content: &str) -> &mut Self {
-        if !self.description.is_empty() {
-            self.description.push_str("\n\n");
-        }
-        self.description.push_str(&inline_file(filename, content));
+    /// Authenticate to the proxy with a TLS client certificate (mTLS).
+    ///
+    /// Both arguments are PEM; `cert_pem` may hold the full chain. Invalid
+    /// PEM makes every report fail with [`Error::Config`].
+    pub fn with_client_cert(&mut self, cert_pem: &[u8], key_pem: &[u8]) -> &mut Self {
+        self.transport.client_cert(cert_pem, key_pem);
         self
     }
 
-    pub fn attachment(&mut self, filename: &str, data: &[u8]) -> &mut Self {
-        self.attachments.push((filename.to_string(), data.to_vec()));
+    /// Give up on a submission after `deadline`, covering DNS, connecting,
+    /// the protocol probe, and reading the response. The call then returns
+    /// [`Error::Timeout`], so a crashing app never hangs on reporting.
+    pub fn with_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.transport.deadline(deadline);
         self
     }
 
-    /// Create the issue. Returns the issue URL.
-    pub fn create(&self) -> Result<String, Error> {
-        let encoded_attachments: Vec<serde_json::Value> = self
+    /// Save reports to `spool` when the proxy can't be reached, to be resent
+    /// by [`send_spooled`](Self::send_spooled).
+    pub fn spool(&mut self, spool: Spool) -> &mut Self {
+        self.transport.spool(spool);
+        self
+    }
+
+    /// Retry reports that hit a network error or a 429/5xx on a background
+    /// thread, holding up to `capacity` of them in memory. Submitting then
+    /// returns [`Submission::Queued`] instead of the error. Reports that
+    /// still fail after a few attempts go to the [`spool`](Self::spool).
+    pub fn retry_queue(&mut self, capacity: usize) -> &mut Self {
+        self.transport.retry_queue(capacity);
+        self
+    }
+
+    /// Call `callback` with the reference and final outcome of each report
+    /// the [retry queue](Self::retry_queue) finishes with, such as to show
+    /// "Report filed as ENG-123" once a [`Submission::Queued`] report goes
+    /// through. A report the queue gave up on and spooled is
+    /// [`Submission::Spooled`]. Runs on the queue's thread.
+    pub fn on_result(
+        &mut self,
+        callback: impl Fn(&str, Result<Submission, Error>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.transport.on_result(callback);
+        self
+    }
+
+    /// Once Linear has `requests` or fewer requests left before its rate
+    /// limit resets, save reports to the [`spool`](Self::spool) instead of
+    /// sending them, keeping the rest for crashes. A deferred report returns
+    /// [`Submission::Spooled`], and [`send_spooled`](Self::send_spooled) holds
+    /// it back until the limit resets.
+    ///
+    /// Needs a spool; without one, reports are sent regardless.
+    pub fn rate_limit_reserve(&mut self, requests: u64) -> &mut Self {
+        self.transport.reserve(requests);
+        self
+    }
+
+    /// Resend spooled reports, oldest first, returning how many were
+    /// delivered. Stops at the first one that still can't be sent.
+    pub fn send_spooled(&self) -> Result<usize, Error> {
+        self.transport
+            .send_spooled(&self.url, "linear", self.token.as_deref(), &self.protocol)
+    }
+
+    /// Route requests through a SOCKS proxy, such as Tor at
+    /// `socks5://127.0.0.1:9050`. Host names are resolved by the proxy.
+    pub fn socks_proxy(&mut self, url: &str) -> &mut Self {
+        self.transport.socks_proxy(url);
+        self
+    }
+
+    /// Apply these redaction rules to every report before it is sent.
+    pub fn redact(&mut self, redaction: Redaction) -> &mut Self {
+        self.pipeline.redaction = redaction;
+        self
+    }
+
+    /// What to do with reports that seem to contain credentials. By default
+    /// they are replaced with `[REDACTED]`.
+    pub fn secrets(&mut self, policy: Secrets) -> &mut Self {
+        self.pipeline.secrets = policy;
+        self
+    }
+
+    /// Size limits and compression for attachments. By default files over
+    /// 1 MiB are gzipped and those still over 25 MiB are left out.
+    pub fn attachment_policy(&mut self, policy: AttachmentPolicy) -> &mut Self {
+        self.pipeline.attachments = policy;
+        self
+    }
+
+    /// Which sections of the issue body appear, and in what order. By
+    /// default the body keeps the order the report was built in.
+    pub fn layout(&mut self, layout: Layout) -> &mut Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Add the rows of `source`, such as [`Os`](crate::Os), to a "System
+    /// Info" table on every report, unless the privacy level leaves out
+    /// optional context. Call it once for each source.
+    pub fn system_info(
+        &mut self,
+        source: impl SystemInfoSource + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.pipeline.system_info.push(source);
+        self
+    }
+
+    /// Strip context from reports down to `level` before sending them.
+    pub fn with_privacy_level(&mut self, level: PrivacyLevel) -> &mut Self {
+        self.pipeline.privacy_level = level;
+        self
+    }
+
+    /// Labels applied to every report, in addition to the report's own.
+    pub fn labels(&mut self, labels: &[&str]) -> &mut Self {
+        self.labels = labels.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    /// Look up an issue's state and assignee by identifier (`ENG-123`) or URL.
+    pub fn status(&self, issue: &str) -> Result<IssueStatus, Error> {
+        let id = issue_id(issue, "/issue/");
+        let url = format!("{}/linear/issues/{id}", self.url);
+        get_json(self.transport.agent()?, &url, &[], self.token.as_deref())
+    }
+
+    /// Find issues matching `query`, most recently updated first.
+    pub fn search(&self, query: &IssueQuery) -> Result<Vec<IssueStatus>, Error> {
+        let url = format!("{}/linear/issues", self.url);
+        let params = query.params();
+        let response: SearchResponse = get_json(
+            self.transport.agent()?,
+            &url,
+            &params,
+            self.token.as_deref(),
+        )?;
+        Ok(response.issues)
+    }
+
+    /// Who the proxy's Linear key belongs to, and the teams it can access.
+    pub fn viewer(&self) -> Result<Viewer, Error> {
+        let url = format!("{}/linear/viewer", self.url);
+        get_json(self.transport.agent()?, &url, &[], self.token.as_deref())
+    }
+
+    /// Linear's rate limits as of the latest response that reported them,
+    /// or `None` before any did. Clones of a client share this.
+    ///
+    /// High-volume reporters can check it to slow down before Linear
+    /// starts answering 429.
+    pub fn rate_limit(&self) -> Option<RateLimitInfo> {
+        self.transport.rate_limit()
+    }
+
+    /// Check the proxy token, the proxy's Linear key, its access to the
+    /// configured team, and that the configured projects exist, in one call.
+    ///
+    /// Meant for deploy checks and startup of your own services, so a broken
+    /// setup shows up before the first crash needs reporting. Uses the
+    /// proxy's viewer route, which needs its admin token. Never fails:
+    /// problems are in the returned report.
+    ///
+    /// ```no_run
+    /// let client = hotln::LinearClient::new("https://your-proxy.example.com");
+    /// let validation = client.validate();
+    /// if !validation.is_ok() {
+    ///     eprintln!("bug reporting is misconfigured:\n{validation}");
+    /// }
+    /// ```
+    pub fn validate(&self) -> ValidationReport {
+        ValidationReport::new(self.viewer())
+    }
+
+    /// Add a markdown comment to an issue, returning the comment's URL.
+    pub fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        let id = issue_id(issue, "/issue/");
+        let url = format!("{}/linear/issues/{id}/comments", self.url);
+        let payload = serde_json::json!({ "body": body });
+        post_json(
+            self.transport.agent()?,
+            &url,
+            self.token.as_deref(),
+            &payload,
+        )
+        .map(Submission::into_url)
+    }
+
+    /// Replace the body of an occurrence comment, given the URL
+    /// [`comment`](Self::comment) returned. The proxy refuses to edit other
+    /// comments.
+    pub fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        let id = issue_id(comment, "/issue/");
+        let comment_id = comment_id(comment)
+            .ok_or_else(|| Error::Config(format!("not a comment URL: {comment}")))?;
+        let url = format!("{}/linear/issues/{id}/comments/{comment_id}", self.url);
+        let payload = serde_json::json!({ "body": body });
+        post_json(
+            self.transport.agent()?,
+            &url,
+            self.token.as_deref(),
+            &payload,
+        )
+        .map(Submission::into_url)
+    }
+
+    /// Close an issue by identifier (`ENG-123`) or URL, moving it to the
+    /// team's first completed or canceled workflow state, and return its new
+    /// status.
+    ///
+    /// For automation that notices a fix, such as a fingerprint that stopped
+    /// showing up after a release. The proxy only closes issues filed with a
+    /// [fingerprint](crate::Report::fingerprint), so issues people filed by
+    /// hand are left alone.
+    pub fn close_issue(&self, issue: &str, resolution: Resolution) -> Result<IssueStatus, Error> {
+        let id = issue_id(issue, "/issue/");
+        let url = format!("{}/linear/issues/{id}/close", self.url);
+        let payload = serde_json::json!({ "resolution": resolution });
+        post_json_as(
+            self.transport.agent()?,
+            &url,
+            self.token.as_deref(),
+            &payload,
+        )
+    }
+
+    /// Upload a file to Linear without creating an issue, returning its asset
+    /// URL.
+    ///
+    /// Reference the URL in markdown from any number of issues or comments,
+    /// such as a large diagnostics bundle shared by several reports. The file
+    /// is sent as-is, without redaction or the secret scan.
+    pub fn upload_file(
+        &self,
+        filename: &str,
+        content_type: &str,
+        data: &[u8],
+    ) -> Result<String, Error> {
+        let url = format!("{}/linear/uploads", self.url);
+        post_json(
+            self.transport.agent()?,
+            &url,
+            self.token.as_deref(),
+            &file_json(filename, content_type, data),
+        )
+        .map(Submission::into_url)
+    }
+
+    /// Upload an image and return a markdown snippet that shows it inline,
+    /// such as `![screenshot.png](https://uploads.linear.app/...)`.
+    ///
+    /// Put the snippet in a report's description or a comment so the image
+    /// renders in place rather than as an attachment link. `filename` must
+    /// end in `.png`, `.jpg`, `.jpeg`, `.gif`, or `.webp`.
+    pub fn embed_image(&self, filename: &str, data: &[u8]) -> Result<String, Error> {
+        let content_type = mime_for_ext(filename);
+        if !content_type.starts_with("image/") {
+            return Err(Error::Config(format!("{filename} is not an image")));
+        }
+        let url = self.upload_file(filename, content_type, data)?;
+        let alt = filename.replace(['[', ']'], "");
+        Ok(format!("![{alt}]({url})"))
+    }
+
+    /// Add an "App" table with this version to every report.
+    pub fn app_version(&mut self, version: &str) -> &mut Self {
+        self.app_version = Some(version.to_string());
+        self
+    }
+
+    fn payload(&self, report: &Prepared) -> serde_json::Value {
+        let mut description = linear_details(&report.description);
+        if let Some(version) = &self.app_version {
+            append_block(
+                &mut description,
+                &info_table("App", &[("Version", version)]),
+            );
+        }
+        let mut extra_json = None;
+        if !report.extra.is_empty() {
+            append_block(&mut description, &extra_summary(&report.extra));
+            extra_json = serde_json::to_string_pretty(&report.extra).ok();
+        }
+        if let Some(json) = state_json(report) {
+            // Linear's markdown for a collapsed section.
+            append_block(
+                &mut description,
+                &format!("+++ App state\n\n{}\n\n+++", code_block("json", &json)),
+            );
+        }
+        if let Some(threads) = &report.threads {
+            append_block(
+                &mut description,
+                &format!("+++ Threads\n\n{}\n\n+++", code_block("", threads)),
+            );
+        }
+        if let Some(layout) = &self.layout {
+            description = layout.apply(&description);
+        }
+        append_reference(&mut description, &report.reference);
+
+        let encoded_attachments: Vec<serde_json::Value> = report
             .attachments
             .iter()
-            .map(|(filename, data)| {
-                let content_type = mime_for_ext(filename);
-                match std::str::from_utf8(data) {
-                    Ok(text) => serde_json::json!({
-                        "filename": filename,
-                        "contentType": content_type,
-                        "data": text,
-                        "encoding": "text",
-                    }),
-                    Err(_) => serde_json::json!({
-                        "filename": filename,
-                        "contentType": content_type,
-                        "data": BASE64_STANDARD.encode(data),
-                        "encoding": "base64",
-                    }),
-                }
-            })
+            .map(|(filename, data)| (filename.as_str(), data.as_slice()))
+            .chain(
+                extra_json
+                    .as_deref()
+                    .map(|json| ("extra.json", json.as_bytes())),
+            )
+            .map(|(filename, data)| file_json(filename, mime_for_ext(filename), data))
             .collect();
 
-        let payload = serde_json::json!({
-            "title": self.title,
-            "description": self.description,
+        let mut payload = serde_json::json!({
+            "title": report.title,
+            "description": description,
             "attachments": encoded_attachments,
         });
+        if !report.links.is_empty() {
+            payload["links"] = report
+                .links
+                .iter()
+                .map(|link| {
+                    let mut json = serde_json::json!({ "url": link.url, "title": link.title });
+                    if let Some(subtitle) = &link.subtitle {
+                        json["subtitle"] = subtitle.clone().into();
+                    }
+                    json
+                })
+                .collect();
+        }
+        match &report.customer {
+            Some(Customer::ExternalId(id)) => {
+                payload["customer"] = serde_json::json!({ "externalId": id });
+            }
+            Some(Customer::Email(email)) => {
+                payload["customer"] = serde_json::json!({ "email": email });
+            }
+            None => {}
+        }
+        let labels = merge_labels(&self.labels, &report.labels);
+        if !labels.is_empty() {
+            payload["labels"] = labels.into();
+        }
+        if let Some((name, email)) = &report.reporter {
+            payload["reporter"] = reporter_json(name, email);
+        }
+        if let Some(severity) = report.severity {
+            payload["severity"] = severity.as_str().into();
+        }
+        if report.feedback {
+            payload["kind"] = "feedback".into();
+        }
+        if let Some(fingerprint) = &report.fingerprint {
+            payload["fingerprint"] = fingerprint.as_str().into();
+        }
+        payload
+    }
+}
 
-        let mut req =
-            ureq::post(&format!("{}/linear", self.url)).set("Content-Type", "application/json");
-        if let Some(token) = &self.token {
-            req = req.set("Authorization", &format!("Bearer {}", token));
+/// A file as the proxy takes it: text as-is, anything else in base64.
+fn file_json(filename: &str, content_type: &str, data: &[u8]) -> serde_json::Value {
+    match std::str::from_utf8(data) {
+        Ok(text) => serde_json::json!({
+            "filename": filename,
+            "contentType": content_type,
+            "data": text,
+            "encoding": "text",
+        }),
+        Err(_) => serde_json::json!({
+            "filename": filename,
+            "contentType": content_type,
+            "data": BASE64_STANDARD.encode(data),
+            "encoding": "base64",
+        }),
+    }
+}
+
+impl Reporter for Client {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        self.submit(report).map(Submission::into_url)
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        let report = prepare(report, &self.pipeline)?;
+        let payload = self.payload(&report);
+        if self.dry_run {
+            return serde_json::to_string_pretty(&payload)
+                .map(Submission::Created)
+                .map_err(|e| Error::Parse(e.to_string()));
         }
+        self.transport.submit(
+            &self.url,
+            "linear",
+            self.token.clone(),
+            self.protocol.clone(),
+            payload,
+            &report.reference,
+        )
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        Client::comment(self, issue, body)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        Client::update_comment(self, comment, body)
+    }
+
+    fn privacy_level(&self) -> PrivacyLevel {
+        self.pipeline.privacy_level
+    }
+
+    fn spools(&self, _report: &Report) -> bool {
+        self.transport.has_spool()
+    }
+}
+
+/// The Linear user behind the proxy's API key, from [`Client::viewer`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct Viewer {
+    pub name: String,
+    pub email: String,
+    pub organization: String,
+    /// The workspace's URL key, as in `linear.app/<key>`.
+    pub organization_key: String,
+    pub teams: Vec<Team>,
+    /// The projects the proxy files into. Empty from older proxies.
+    #[serde(default)]
+    pub projects: Vec<Project>,
+}
+
+/// A team the proxy's Linear key can access.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
+pub struct Team {
+    /// The issue prefix, such as `ENG`.
+    pub key: String,
+    pub name: String,
+    /// Whether this is the team the proxy files issues in.
+    pub configured: bool,
+}
+
+/// A project the proxy is configured to file into.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
+pub struct Project {
+    /// The project's name, or `None` if it doesn't exist or the key can't
+    /// see it.
+    pub name: Option<String>,
+    /// Whether this is the project for feedback rather than bug reports.
+    pub feedback: bool,
+}
+
+/// The outcome of [`Client::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ValidationReport {
+    /// The proxy is reachable and accepts the client's token.
+    pub proxy: Check,
+    /// Linear accepts the proxy's API key.
+    pub api_key: Check,
+    /// The key can access the team the proxy files issues in.
+    pub team: Check,
+    /// The projects the proxy files issues and feedback in exist.
+    pub project: Check,
+}
 
-        let resp_str = match req.send_string(&payload.to_string()) {
-            Ok(resp) => resp
-                .into_string()
-                .map_err(|e| Error::Parse(e.to_string()))?,
-            Err(ureq::Error::Status(code, resp)) => {
-                let body = resp.into_string().unwrap_or_default();
-                return Err(Error::Proxy { status: code, body });
+/// The result of one [`ValidationReport`] check, with a short explanation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Passed(String),
+    Failed(String),
+    /// Not checked, because an earlier check failed or the proxy is too old
+    /// to say.
+    Skipped(String),
+}
+
+impl Check {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Check::Failed(_))
+    }
+}
+
+impl ValidationReport {
+    fn new(viewer: Result<Viewer, Error>) -> Self {
+        let skipped = || Check::Skipped("not checked".to_string());
+        let failed = |proxy, api_key| Self {
+            proxy,
+            api_key,
+            team: skipped(),
+            project: skipped(),
+        };
+        let viewer = match viewer {
+            Ok(viewer) => viewer,
+            Err(Error::Proxy { status: 401, .. }) => {
+                return failed(
+                    Check::Failed("the proxy rejected the token".to_string()),
+                    skipped(),
+                );
             }
-            Err(e) => return Err(e.into()),
+            // The proxy answers 500 when the Linear backend isn't configured,
+            // and 502 when Linear refuses its key.
+            Err(Error::Proxy {
+                status: 500 | 502,
+                body,
+            }) => {
+                return failed(Check::Passed("reachable".to_string()), Check::Failed(body));
+            }
+            Err(e) => return failed(Check::Failed(e.to_string()), skipped()),
         };
 
-        let resp: serde_json::Value =
-            serde_json::from_str(&resp_str).map_err(|e| Error::Parse(e.to_string()))?;
+        let api_key = Check::Passed(format!(
+            "{} <{}> in {}",
+            viewer.name, viewer.email, viewer.organization
+        ));
+        let team = match viewer.teams.iter().find(|team| team.configured) {
+            Some(team) => Check::Passed(format!("{} ({})", team.name, team.key)),
+            None => Check::Failed(format!(
+                "LINEAR_TEAM_ID is not one of the {} teams the key can access",
+                viewer.teams.len()
+            )),
+        };
+        let project = if viewer.projects.is_empty() {
+            Check::Skipped("the proxy doesn't report its projects".to_string())
+        } else if let Some(missing) = viewer.projects.iter().find(|p| p.name.is_none()) {
+            let var = if missing.feedback {
+                "LINEAR_FEEDBACK_PROJECT_ID"
+            } else {
+                "LINEAR_PROJECT_ID"
+            };
+            Check::Failed(format!("{var} doesn't match a project the key can see"))
+        } else {
+            let names: Vec<&str> = viewer
+                .projects
+                .iter()
+                .filter_map(|p| p.name.as_deref())
+                .collect();
+            Check::Passed(names.join(", "))
+        };
+        Self {
+            proxy: Check::Passed("reachable".to_string()),
+            api_key,
+            team,
+            project,
+        }
+    }
+
+    /// Whether no check failed. Skipped checks don't count.
+    pub fn is_ok(&self) -> bool {
+        ![&self.proxy, &self.api_key, &self.team, &self.project]
+            .iter()
+            .any(|check| check.is_failed())
+    }
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let checks = [
+            ("Proxy", &self.proxy),
+            ("API key", &self.api_key),
+            ("Team", &self.team),
+            ("Project", &self.project),
+        ];
+        for (i, (name, check)) in checks.into_iter().enumerate() {
+            let (mark, detail) = match check {
+                Check::Passed(detail) => ("ok", detail),
+                Check::Failed(detail) => ("FAILED", detail),
+                Check::Skipped(detail) => ("skipped", detail),
+            };
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{name}: {mark}, {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A single Linear issue, sent with [`create`](Self::create).
+pub struct Issue {
+    client: Client,
+    report: Report,
+}
+
+impl Issue {
+    pub(crate) fn new(proxy_url: &str) -> Self {
+        Self {
+            client: Client::new(proxy_url),
+            report: Report::new(),
+        }
+    }
+
+    pub fn with_token(&mut self, token: &str) -> &mut Self {
+        self.client.with_token(token);
+        self
+    }
+
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.report.title(title);
+        self
+    }
+
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.report.text(text);
+        self
+    }
+
+    pub fn file(&mut self, filename: &str, content: &str) -> &mut Self {
+        self.report.file(filename, content);
+        self
+    }
+
+    pub fn attachment(&mut self, filename: &str, data: &[u8]) -> &mut Self {
+        self.report.attachment(filename, data);
+        self
+    }
+
+    /// Link the issue to the Linear customer with this external ID.
+    pub fn customer_id(&mut self, external_id: &str) -> &mut Self {
+        self.report.customer_id(external_id);
+        self
+    }
+
+    /// Link the issue to the Linear customer whose domain matches this email.
+    pub fn customer_email(&mut self, email: &str) -> &mut Self {
+        self.report.customer_email(email);
+        self
+    }
+
+    /// See [`Report::label`].
+    pub fn label(&mut self, name: &str) -> &mut Self {
+        self.report.label(name);
+        self
+    }
+
+    /// See [`Report::severity`].
+    pub fn severity(&mut self, severity: Severity) -> &mut Self {
+        self.report.severity(severity);
+        self
+    }
+
+    /// See [`Report::feedback`].
+    pub fn feedback(&mut self, text: &str) -> &mut Self {
+        self.report.feedback(text);
+        self
+    }
+
+    /// See [`Report::reporter`].
+    pub fn reporter(&mut self, name: &str, email: &str) -> &mut Self {
+        self.report.reporter(name, email);
+        self
+    }
+
+    /// See [`Report::extra`].
+    pub fn extra(&mut self, key: &str, value: &impl serde::Serialize) -> &mut Self {
+        self.report.extra(key, value);
+        self
+    }
+
+    /// See [`Report::with_state`].
+    pub fn with_state(&mut self, state: &impl serde::Serialize) -> &mut Self {
+        self.report.with_state(state);
+        self
+    }
+
+    /// See [`Client::dry_run`].
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.client.dry_run(dry_run);
+        self
+    }
+
+    /// Create the issue. Returns the issue URL.
+    pub fn create(&self) -> Result<String, Error> {
+        self.client.report(&self.report)
+    }
 
-        let url = resp["url"]
-            .as_str()
-            .ok_or_else(|| Error::Parse("proxy response missing url".into()))?
-            .to_string();
+    /// Like [`create`](Self::create), but reports whether the proxy matched
+    /// an existing issue instead of creating a new one.
+    pub fn submit(&self) -> Result<Submission, Error> {
+        self.client.submit(&self.report)
+    }
 
-        Ok(url)
+    /// The report's support reference code. See [`Report::reference`].
+    pub fn reference(&self) -> &str {
+        &self.report.reference
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ci;
 
     #[test]
     fn test_create_issue() {
@@ -141,6 +800,51 @@ mod tests {
         mock.assert();
     }
 
+    #[test]
+    fn test_rate_limit() {
+        let mut server = mockito::Server::new();
+        let client = Client::new(&server.url());
+        assert_eq!(client.rate_limit(), None);
+
+        server
+            .mock("POST", "/linear")
+            .with_status(200)
+            .with_header("x-ratelimit-requests-limit", "1500")
+            .with_header("x-ratelimit-requests-remaining", "1499")
+            .with_header("x-ratelimit-requests-reset", "1767225600000")
+            .with_body(r#"{"url": "https://linear.app/test-org/issue/TEST-1"}"#)
+            .create();
+        client.report(Report::new().title("first")).unwrap();
+        let info = client.rate_limit().unwrap();
+        assert_eq!(info.requests_limit, Some(1500));
+        assert_eq!(info.requests_remaining, Some(1499));
+        assert_eq!(
+            info.requests_reset,
+            Some(std::time::UNIX_EPOCH + Duration::from_secs(1_767_225_600))
+        );
+        assert_eq!(info.complexity_remaining, None);
+
+        // Responses without the headers leave the last reading alone.
+        server
+            .mock("POST", "/linear/issues/TEST-1/comments")
+            .with_status(200)
+            .with_body(r#"{"url": "https://linear.app/test-org/issue/TEST-1#comment-1"}"#)
+            .create();
+        client
+            .comment("https://linear.app/test-org/issue/TEST-1", "again")
+            .unwrap();
+        assert_eq!(client.rate_limit(), Some(info));
+
+        server.reset();
+        server
+            .mock("POST", "/linear")
+            .with_status(429)
+            .with_header("x-ratelimit-requests-remaining", "0")
+            .create();
+        assert!(client.report(Report::new().title("second")).is_err());
+        assert_eq!(client.rate_limit().unwrap().requests_remaining, Some(0));
+    }
+
     #[test]
     fn test_with_token() {
         let mut server = mockito::Server::new();
@@ -231,6 +935,254 @@ mod tests {
         mock.assert();
     }
 
+    #[test]
+    fn test_severity() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/linear")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({ "severity": "crash" }).to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "url": "https://linear.app/test-org/issue/TEST-54"
+                })
+                .to_string(),
+            )
+            .create();
+
+        let url = Issue::new(&server.url())
+            .title("severity test")
+            .severity(Severity::Crash)
+            .create()
+            .unwrap();
+
+        assert_eq!(url, "https://linear.app/test-org/issue/TEST-54");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/linear")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({ "fingerprint": "3f2a9c" }).to_string(),
+            ))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "url": "https://linear.app/test-org/issue/TEST-7",
+                    "deduplicated": true,
+                })
+                .to_string(),
+            )
+            .create();
+
+        let submission = Client::new(&server.url())
+            .submit(Report::new().title("crash").fingerprint("3f2a9c"))
+            .unwrap();
+
+        assert_eq!(
+            submission,
+            Submission::Deduplicated("https://linear.app/test-org/issue/TEST-7".into())
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_default_labels_and_app_version() {
+        let mut client = Client::new("http://unused");
+        client.pipeline.ci_env = ci::Env::new(|_| None);
+        client
+            .dry_run(true)
+            .labels(&["sdk", "desktop"])
+            .app_version("1.4.2");
+        let mut report = Report::new();
+        report.text("desc").label("desktop").label("export");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&client.report(&report).unwrap()).unwrap();
+        assert_eq!(
+            json["labels"],
+            serde_json::json!(["sdk", "desktop", "export"])
+        );
+        assert!(json["description"].as_str().unwrap().ends_with(&format!(
+            "| Version | 1.4.2 |\n\nReference: `{}`",
+            report.reference
+        )));
+    }
+
+    #[test]
+    fn test_system_info() {
+        let mut client = Client::new("http://unused");
+        let mut build = crate::Build::new("2.0.1");
+        build.commit("abc123");
+        client
+            .dry_run(true)
+            .system_info(build)
+            .system_info([("Renderer", "wgpu")]);
+        let mut report = Report::new();
+        report.text("desc");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&client.report(&report).unwrap()).unwrap();
+        let description = json["description"].as_str().unwrap();
+        assert!(description.contains(
+            "## System Info\n\n| Field | Value |\n|-------|-------|\n| Version | 2.0.1 |\n| Commit | abc123 |"
+        ));
+        assert!(description.contains("| Renderer | wgpu |"));
+
+        client.with_privacy_level(PrivacyLevel::Minimal);
+        let json: serde_json::Value =
+            serde_json::from_str(&client.report(&report).unwrap()).unwrap();
+        assert!(
+            !json["description"]
+                .as_str()
+                .unwrap()
+                .contains("System Info")
+        );
+    }
+
+    #[test]
+    fn test_with_state() {
+        let mut client = Client::new("http://unused");
+        client.dry_run(true);
+        let mut report = Report::new();
+        report
+            .text("desc")
+            .with_state(&serde_json::json!({ "open_documents": 3 }));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&client.report(&report).unwrap()).unwrap();
+        assert!(
+            json["description"]
+                .as_str()
+                .unwrap()
+                .contains("+++ App state\n\n```json\n{\n  \"open_documents\": 3\n}\n```\n\n+++")
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let mut client = Client::new("http://unused");
+        client.dry_run(true);
+        let mut report = Report::new();
+        report
+            .link("CI run", "https://ci.example.com/run/1")
+            .link_with_subtitle("Replay", "12:04 UTC", "https://replay.example.com/s/9");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&client.report(&report).unwrap()).unwrap();
+        assert_eq!(
+            json["links"],
+            serde_json::json!([
+                { "url": "https://ci.example.com/run/1", "title": "CI run" },
+                { "url": "https://replay.example.com/s/9", "title": "Replay", "subtitle": "12:04 UTC" },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_links_folded_for_v2_proxy() {
+        let mut server = mockito::Server::new();
+        let probe = server
+            .mock("OPTIONS", "/linear")
+            .with_status(204)
+            .with_header("hotline-protocol", "2")
+            .create();
+        let mock = server
+            .mock("POST", "/linear")
+            .match_body(mockito::Matcher::Regex(
+                r"## Links\\n\\n- \[CI run\]\(https://ci.example.com/run/1\)".into(),
+            ))
+            .with_status(200)
+            .with_body(serde_json::json!({ "url": "https://linear.app/t/issue/T-1" }).to_string())
+            .create();
+
+        let mut report = Report::new();
+        report.link("CI run", "https://ci.example.com/run/1");
+        Client::new(&server.url()).report(&report).unwrap();
+
+        probe.assert();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_customer() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/linear")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({
+                    "customer": { "email": "jane@acme.com" }
+                })
+                .to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "url": "https://linear.app/test-org/issue/TEST-53"
+                })
+                .to_string(),
+            )
+            .create();
+
+        let url = Issue::new(&server.url())
+            .title("customer test")
+            .customer_email("jane@acme.com")
+            .create()
+            .unwrap();
+
+        assert_eq!(url, "https://linear.app/test-org/issue/TEST-53");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_extra_attached_as_json() {
+        let mut server = mockito::Server::new();
+        let mut issue = Issue::new(&server.url());
+        issue.client.pipeline.ci_env = ci::Env::new(|_| None);
+        issue
+            .title("extra test")
+            .text("details")
+            .extra("open_docs", &3);
+        let mock = server
+            .mock("POST", "/linear")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({
+                    "description": format!(
+                        "details\n\n## Extra\n\n| Key | Value |\n|-----|-------|\n| open_docs | 3 |\n\nReference: `{}`",
+                        issue.reference()
+                    ),
+                    "attachments": [{
+                        "filename": "extra.json",
+                        "contentType": "application/json",
+                        "data": "{\n  \"open_docs\": 3\n}",
+                        "encoding": "text",
+                    }]
+                })
+                .to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "url": "https://linear.app/test-org/issue/TEST-55"
+                })
+                .to_string(),
+            )
+            .create();
+
+        let url = issue.create().unwrap();
+
+        assert_eq!(url, "https://linear.app/test-org/issue/TEST-55");
+        mock.assert();
+    }
+
     #[test]
     fn test_binary_attachment_base64() {
         let mut server = mockito::Server::new();
@@ -266,4 +1218,325 @@ mod tests {
         assert_eq!(url, "https://linear.app/test-org/issue/TEST-52");
         mock.assert();
     }
+
+    #[test]
+    fn test_deadline() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/linear")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_secs(2));
+                w.write_all(b"{\"url\": \"https://linear.app/late\"}")
+            })
+            .create();
+
+        let started = std::time::Instant::now();
+        let result = Client::new(&server.url())
+            .with_deadline(Duration::from_millis(200))
+            .report(&Report::new());
+
+        assert!(matches!(result, Err(Error::Timeout(_))));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_spool_and_resend() {
+        let mut server = mockito::Server::new();
+        let down = server.mock("POST", "/linear").with_status(503).create();
+
+        let spool = Spool::new(crate::spool::temp_dir("linear-spool"));
+        let mut client = Client::new(&server.url());
+        client.spool(spool.clone());
+        let result = client.report(Report::new().title("offline"));
+        assert!(matches!(result, Err(Error::Proxy { status: 503, .. })));
+        assert_eq!(spool.len(), 1);
+
+        down.remove();
+        let up = server
+            .mock("POST", "/linear")
+            .match_body(mockito::Matcher::PartialJsonString(
+                serde_json::json!({ "title": "offline" }).to_string(),
+            ))
+            .with_status(200)
+            .with_body(serde_json::json!({ "url": "https://linear.app/x" }).to_string())
+            .create();
+        assert_eq!(client.send_spooled().unwrap(), 1);
+        assert!(spool.is_empty());
+        up.assert();
+        std::fs::remove_dir_all(spool.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit_reserve() {
+        let mut server = mockito::Server::new();
+        let reset = std::time::SystemTime::now() + Duration::from_secs(3600);
+        let reset = reset.duration_since(std::time::UNIX_EPOCH).unwrap();
+        let mock = server
+            .mock("POST", "/linear")
+            .with_status(200)
+            .with_header("x-ratelimit-requests-remaining", "5")
+            .with_header("x-ratelimit-requests-reset", &reset.as_millis().to_string())
+            .with_body(serde_json::json!({ "url": "https://linear.app/x" }).to_string())
+            .expect(2)
+            .create();
+
+        let spool = Spool::new(crate::spool::temp_dir("linear-reserve"));
+        let mut client = Client::new(&server.url());
+        client.spool(spool.clone()).rate_limit_reserve(10);
+        client.report(Report::new().title("first")).unwrap();
+
+        let mut bug = Report::new();
+        bug.title("minor").severity(Severity::Bug);
+        let submission = client.submit(&bug).unwrap();
+        assert_eq!(submission, Submission::Spooled(bug.reference.clone()));
+        assert_eq!(spool.len(), 1);
+
+        let mut crash = Report::new();
+        crash.title("crash").severity(Severity::Crash);
+        client.report(&crash).unwrap();
+        assert_eq!(client.send_spooled().unwrap(), 0);
+        assert_eq!(spool.len(), 1);
+        mock.assert();
+        std::fs::remove_dir_all(spool.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_report_or_log() {
+        let mut server = mockito::Server::new();
+        server.mock("POST", "/linear").with_status(503).create();
+        let mut client = Client::new(&server.url());
+        let report = Report::new();
+        assert_eq!(client.report_or_log(&report), Submission::Dropped);
+
+        let spool = Spool::new(crate::spool::temp_dir("linear-or-log"));
+        client.spool(spool.clone());
+        assert_eq!(
+            client.report_or_log(&report),
+            Submission::Spooled(report.reference.clone())
+        );
+        assert_eq!(spool.len(), 1);
+        std::fs::remove_dir_all(spool.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_retry_queue() {
+        let mut server = mockito::Server::new();
+        let down = server
+            .mock("POST", "/linear")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let up = server
+            .mock("POST", "/linear")
+            .with_status(200)
+            .with_body(serde_json::json!({ "url": "https://linear.app/x" }).to_string())
+            .create();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = std::sync::Mutex::new(tx);
+        let mut client = Client::new(&server.url());
+        client.retry_queue(4).on_result(move |reference, result| {
+            let _ = tx.lock().unwrap().send((reference.to_string(), result));
+        });
+        let report = Report::new();
+        let submission = client.submit(&report).unwrap();
+        assert_eq!(submission, Submission::Queued(report.reference.clone()));
+
+        let (reference, result) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(reference, report.reference);
+        assert_eq!(
+            result.unwrap(),
+            Submission::Created("https://linear.app/x".into())
+        );
+        down.assert();
+        up.assert();
+    }
+
+    #[test]
+    fn test_status() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/linear/issues/ENG-123")
+            .match_header("Authorization", "Bearer t")
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "identifier": "ENG-123",
+                    "title": "crash on start",
+                    "url": "https://linear.app/acme/issue/ENG-123",
+                    "state": "In Progress",
+                    "assignee": "Sam",
+                    "updatedAt": "2026-01-02T03:04:05Z",
+                })
+                .to_string(),
+            )
+            .create();
+
+        let status = Client::new(&server.url())
+            .with_token("t")
+            .status("https://linear.app/acme/issue/ENG-123/crash-on-start")
+            .unwrap();
+
+        assert_eq!(status.state, "In Progress");
+        assert_eq!(status.assignee.as_deref(), Some("Sam"));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_upload_file() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/linear/uploads")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "filename": "diag.bin",
+                "contentType": "application/octet-stream",
+                "data": "/wA=",
+                "encoding": "base64",
+            })))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({ "url": "https://uploads.linear.app/a/b/diag.bin" }).to_string(),
+            )
+            .create();
+
+        let url = Client::new(&server.url())
+            .upload_file("diag.bin", "application/octet-stream", &[0xff, 0x00])
+            .unwrap();
+
+        assert_eq!(url, "https://uploads.linear.app/a/b/diag.bin");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_embed_image() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/linear/uploads")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"filename":"shot.png","contentType":"image/png"}"#.into(),
+            ))
+            .with_status(200)
+            .with_body(r#"{"url":"https://uploads.linear.app/a/shot.png"}"#)
+            .create();
+        let client = Client::new(&server.url());
+
+        let snippet = client
+            .embed_image("shot.png", &[0x89, b'P', b'N', b'G'])
+            .unwrap();
+        assert_eq!(
+            snippet,
+            "![shot.png](https://uploads.linear.app/a/shot.png)"
+        );
+        assert!(matches!(
+            client.embed_image("notes.txt", b"hi"),
+            Err(Error::Config(_))
+        ));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_viewer() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/linear/viewer")
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "name": "Sam",
+                    "email": "sam@example.com",
+                    "organization": "Acme",
+                    "organizationKey": "acme",
+                    "teams": [
+                        { "key": "ENG", "name": "Engineering", "configured": true },
+                        { "key": "OPS", "name": "Operations", "configured": false },
+                    ],
+                })
+                .to_string(),
+            )
+            .create();
+
+        let viewer = Client::new(&server.url()).viewer().unwrap();
+
+        assert_eq!(viewer.organization_key, "acme");
+        assert_eq!(viewer.teams.len(), 2);
+        assert!(viewer.teams[0].configured);
+        mock.assert();
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/linear/viewer")
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "name": "Sam",
+                    "email": "sam@example.com",
+                    "organization": "Acme",
+                    "organizationKey": "acme",
+                    "teams": [{ "key": "ENG", "name": "Engineering", "configured": true }],
+                    "projects": [
+                        { "name": "Crashes", "feedback": false },
+                        { "name": null, "feedback": true },
+                    ],
+                })
+                .to_string(),
+            )
+            .create();
+        let validation = Client::new(&server.url()).validate();
+        assert_eq!(validation.team, Check::Passed("Engineering (ENG)".into()));
+        assert!(validation.project.is_failed());
+        assert!(!validation.is_ok());
+        assert!(validation.to_string().ends_with(
+            "Project: FAILED, LINEAR_FEEDBACK_PROJECT_ID doesn't match a project the key can see"
+        ));
+
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/linear/viewer")
+            .with_status(502)
+            .with_body("Linear API returned 401: bad key")
+            .create();
+        let validation = Client::new(&server.url()).validate();
+        assert_eq!(validation.proxy, Check::Passed("reachable".into()));
+        assert!(validation.api_key.is_failed());
+        assert!(matches!(validation.team, Check::Skipped(_)));
+    }
+
+    #[test]
+    fn test_search() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/linear/issues")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "seg fault".into()),
+                mockito::Matcher::UrlEncoded("state".into(), "open".into()),
+                mockito::Matcher::UrlEncoded("label".into(), "crash".into()),
+                mockito::Matcher::UrlEncoded("limit".into(), "20".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({ "issues": [{
+                    "identifier": "ENG-7",
+                    "title": "segfault on exit",
+                    "url": "https://linear.app/acme/issue/ENG-7",
+                    "state": "Todo",
+                    "assignee": null,
+                    "updatedAt": "2026-01-02T03:04:05Z",
+                }] })
+                .to_string(),
+            )
+            .create();
+
+        let mut query = IssueQuery::new("seg fault");
+        query.state("open").label("crash").limit(20);
+        let issues = Client::new(&server.url()).search(&query).unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].identifier, "ENG-7");
+        assert_eq!(issues[0].assignee, None);
+        mock.assert();
+    }
 }
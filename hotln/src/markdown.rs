@@ -0,0 +1,391 @@
+//! Composing report descriptions from sections, so callers don't assemble
+//! markdown by hand and user-supplied text can't break the layout.
+
+use std::fmt;
+
+/// Builds a report description from typed sections, rendered in the order
+/// they were added and separated by blank lines, like [`Report::text`]
+/// blocks.
+///
+/// Text passed to [`paragraph`](Self::paragraph), table cells, summaries,
+/// and checklist items is escaped, so it renders as written. Use
+/// [`markdown`](Self::markdown) for text that is already markdown.
+///
+/// ```
+/// let mut steps = hotln::DescriptionBuilder::new();
+/// steps.checklist(&[("Open a project", true), ("Export as PDF", false)]);
+///
+/// let mut description = hotln::DescriptionBuilder::new();
+/// description
+///     .heading("What happened")
+///     .paragraph("Export failed with *no* error.")
+///     .table(&["Format", "Pages"], &[&["PDF", "12"]])
+///     .code("toml", "[export]\nformat = \"pdf\"")
+///     .details("Steps", &steps);
+///
+/// let mut report = hotln::Report::new();
+/// report.title("export fails").text(&description.build());
+/// ```
+///
+/// [`Report::text`]: crate::Report::text
+#[derive(Debug, Clone, Default)]
+pub struct DescriptionBuilder {
+    blocks: Vec<String>,
+}
+
+impl DescriptionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A `##` heading.
+    pub fn heading(&mut self, text: &str) -> &mut Self {
+        self.push(format!("## {}", escape(&text.replace('\n', " "))))
+    }
+
+    /// Plain text, escaped so `*`, `_`, `#`, and the like show as written.
+    /// Line breaks are kept.
+    pub fn paragraph(&mut self, text: &str) -> &mut Self {
+        let lines: Vec<String> = text.lines().map(escape).collect();
+        self.push(lines.join("  \n"))
+    }
+
+    /// Markdown added as it is.
+    pub fn markdown(&mut self, markdown: &str) -> &mut Self {
+        self.push(markdown.to_string())
+    }
+
+    /// A table with a header row. Rows shorter than the header are padded
+    /// with empty cells; newlines in cells become spaces.
+    pub fn table(&mut self, header: &[&str], rows: &[&[&str]]) -> &mut Self {
+        let width = header.len().max(1);
+        let mut out = table_row(header.iter().copied(), width);
+        out.push('\n');
+        out.push_str(&table_row(std::iter::repeat_n("---", width), width));
+        for row in rows {
+            out.push('\n');
+            out.push_str(&table_row(row.iter().copied(), width));
+        }
+        self.push(out)
+    }
+
+    /// A fenced code block tagged with `lang`. The fence is made longer than
+    /// any run of backticks in `content`, so the block can't be closed
+    /// early, and content too long for one block is split across several.
+    pub fn code(&mut self, lang: &str, content: &str) -> &mut Self {
+        self.push(code_block(lang, content))
+    }
+
+    /// A file's contents as a code block under its name in bold, tagged with
+    /// its [language](code_language).
+    pub fn file(&mut self, filename: &str, content: &str) -> &mut Self {
+        self.push(format!(
+            "**{}**\n{}",
+            escape(filename),
+            code_block(code_language(filename), content)
+        ))
+    }
+
+    /// Captured log output as a code block tagged `log`.
+    pub fn log(&mut self, content: &str) -> &mut Self {
+        self.code("log", content)
+    }
+
+    /// A backtrace or stack trace as a `text` code block, so frames aren't
+    /// highlighted as source code.
+    pub fn backtrace(&mut self, content: &str) -> &mut Self {
+        self.code("text", content)
+    }
+
+    /// A collapsed `<details>` section with `body` inside.
+    pub fn details(&mut self, summary: &str, body: &DescriptionBuilder) -> &mut Self {
+        self.push(details(summary, &body.build()))
+    }
+
+    /// A task list of `(item, done)` pairs.
+    pub fn checklist(&mut self, items: &[(&str, bool)]) -> &mut Self {
+        let lines: Vec<String> = items
+            .iter()
+            .map(|(item, done)| {
+                let mark = if *done { 'x' } else { ' ' };
+                format!("- [{mark}] {}", escape(&item.replace('\n', " ")))
+            })
+            .collect();
+        self.push(lines.join("\n"))
+    }
+
+    /// Whether no sections have been added.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// The description, with sections separated by blank lines.
+    pub fn build(&self) -> String {
+        self.blocks.join("\n\n")
+    }
+
+    fn push(&mut self, block: String) -> &mut Self {
+        if !block.trim().is_empty() {
+            self.blocks.push(block);
+        }
+        self
+    }
+}
+
+impl fmt::Display for DescriptionBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.build())
+    }
+}
+
+/// The most bytes put in one code block. Linear's editor limits how large a
+/// single block can be, so longer content is split across several.
+pub(crate) const MAX_BLOCK_LEN: usize = 50_000;
+
+/// The language hint for a code block holding `filename`, such as `rust`
+/// for `main.rs` or `text` for `backtrace.txt`. Unknown extensions are used
+/// as they are; files without one get no hint.
+///
+/// ```
+/// assert_eq!(hotln::code_language("main.rs"), "rust");
+/// assert_eq!(hotln::code_language("app.log"), "log");
+/// assert_eq!(hotln::code_language("Dockerfile"), "dockerfile");
+/// ```
+pub fn code_language(filename: &str) -> &str {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let Some((_, ext)) = name.rsplit_once('.') else {
+        return match name {
+            "Makefile" | "GNUmakefile" => "makefile",
+            "Dockerfile" | "Containerfile" => "dockerfile",
+            _ => "",
+        };
+    };
+    match ext.to_ascii_lowercase().as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" => "typescript",
+        "json" | "ips" => "json",
+        "yml" | "yaml" => "yaml",
+        "xml" | "plist" => "xml",
+        "sh" | "bash" => "bash",
+        "ps1" => "powershell",
+        "h" | "c" => "c",
+        "cc" | "cpp" | "hpp" => "cpp",
+        "kt" => "kotlin",
+        "md" => "markdown",
+        "cfg" | "ini" => "ini",
+        "patch" | "diff" => "diff",
+        "txt" | "crash" => "text",
+        "log" | "out" | "err" => "log",
+        _ => ext,
+    }
+}
+
+/// `content` in fenced code blocks tagged with `lang`. Content longer than
+/// [`MAX_BLOCK_LEN`] is split at line breaks into several blocks, separated
+/// by blank lines.
+pub(crate) fn code_block(lang: &str, content: &str) -> String {
+    split(content, MAX_BLOCK_LEN)
+        .into_iter()
+        .map(|chunk| fence(lang, chunk))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// One fenced block, with a fence longer than any backtick run in `content`
+/// so it can't be closed early.
+fn fence(lang: &str, content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{lang}\n{content}\n{fence}")
+}
+
+/// Splits `content` into pieces of at most `max` bytes, at line breaks
+/// where possible and otherwise at a character boundary.
+fn split(content: &str, max: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = content;
+    while rest.len() > max {
+        let mut end = max;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, next) = match rest.as_bytes()[..=end].iter().rposition(|&b| b == b'\n') {
+            Some(newline) if newline > 0 => (&rest[..newline], &rest[newline + 1..]),
+            _ => rest.split_at(end),
+        };
+        chunks.push(chunk);
+        rest = next;
+    }
+    chunks.push(rest);
+    chunks
+}
+
+/// The number of lines above which [`Report::file`](crate::Report::file)
+/// collapses a file by default.
+pub(crate) const COLLAPSE_LINES: usize = 30;
+
+/// `body` in a collapsed `<details>` section. Linear, which has its own
+/// syntax for these, gets them [converted](linear_details).
+pub(crate) fn details(summary: &str, body: &str) -> String {
+    format!(
+        "<details>\n<summary>{}</summary>\n\n{body}\n\n</details>",
+        escape_html(summary)
+    )
+}
+
+/// Rewrites the `<details>` sections made by [`details`] as Linear's
+/// `+++ Summary` ... `+++`, leaving code blocks alone.
+pub(crate) fn linear_details(markdown: &str) -> String {
+    let mut out = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut lines = markdown.split('\n').peekable();
+    while let Some(line) = lines.next() {
+        let ticks = &line[..line.len() - line.trim_start_matches('`').len()];
+        match fence {
+            Some(open) if line.trim_end() == open => fence = None,
+            Some(_) => {}
+            None if ticks.len() >= 3 => fence = Some(ticks),
+            None if line == "<details>" => {
+                if let Some(summary) = lines
+                    .peek()
+                    .and_then(|next| next.strip_prefix("<summary>"))
+                    .and_then(|next| next.strip_suffix("</summary>"))
+                {
+                    out.push(format!("+++ {}", unescape_html(summary)));
+                    lines.next();
+                    continue;
+                }
+            }
+            None if line == "</details>" => {
+                out.push("+++".to_string());
+                continue;
+            }
+            None => {}
+        }
+        out.push(line.to_string());
+    }
+    out.join("\n")
+}
+
+/// A table cell: pipes escaped, newlines turned into spaces.
+pub(crate) fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn table_row<'a>(cells: impl Iterator<Item = &'a str>, width: usize) -> String {
+    let mut cells: Vec<String> = cells.take(width).map(escape_cell).collect();
+    cells.resize(width, String::new());
+    format!("| {} |", cells.join(" | "))
+}
+
+/// Escapes inline markdown, plus characters that would start a heading,
+/// list, or quote at the beginning of a line.
+fn escape(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    for c in line.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    let trimmed = out.trim_start();
+    let indent = out.len() - trimmed.len();
+    if trimmed.starts_with(['#', '-', '+', '=']) {
+        out.insert(indent, '\\');
+    } else if let Some(i) = trimmed.find(['.', ')'])
+        && i > 0
+        && trimmed[..i].bytes().all(|b| b.is_ascii_digit())
+    {
+        out.insert(indent + i, '\\');
+    }
+    out
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let mut inner = DescriptionBuilder::new();
+        inner.checklist(&[("Open *file*", true), ("Save", false)]);
+        let mut builder = DescriptionBuilder::new();
+        builder
+            .heading("Steps")
+            .paragraph("a_b <c>\n# not a heading\n1. not a list")
+            .table(&["Key", "Value"], &[&["a|b", "x\ny"], &["only"]])
+            .details("Log <tail>", &inner)
+            .paragraph("");
+        assert_eq!(
+            builder.build(),
+            "## Steps\n\n\
+             a\\_b \\<c\\>  \n\\# not a heading  \n1\\. not a list\n\n\
+             | Key | Value |\n| --- | --- |\n| a\\|b | x y |\n| only |  |\n\n\
+             <details>\n<summary>Log &lt;tail&gt;</summary>\n\n\
+             - [x] Open \\*file\\*\n- [ ] Save\n\n</details>"
+        );
+    }
+
+    #[test]
+    fn test_code_block() {
+        assert_eq!(
+            code_block("rust", "fn main() {}"),
+            "```rust\nfn main() {}\n```"
+        );
+        assert_eq!(
+            code_block("md", "```sh\nls\n```"),
+            "````md\n```sh\nls\n```\n````"
+        );
+
+        let log = "line\n".repeat(MAX_BLOCK_LEN / 5 + 1);
+        let blocks = code_block("log", &log);
+        assert_eq!(blocks.matches("```log\n").count(), 2);
+        assert_eq!(blocks.matches("line").count(), MAX_BLOCK_LEN / 5 + 1);
+    }
+
+    #[test]
+    fn test_linear_details() {
+        let log = "```\n</details>\n```";
+        let markdown = format!("intro\n\n{}", details("a <b>", log));
+        assert_eq!(
+            linear_details(&markdown),
+            "intro\n\n+++ a <b>\n\n```\n</details>\n```\n\n+++"
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("ab\ncd\nef", 5), ["ab\ncd", "ef"]);
+        assert_eq!(split("abcdefg", 3), ["abc", "def", "g"]);
+        // Never inside a character.
+        assert_eq!(split("ééé", 3), ["é", "é", "é"]);
+    }
+
+    #[test]
+    fn test_code_language() {
+        assert_eq!(code_language("src/main.rs"), "rust");
+        assert_eq!(code_language("backtrace.txt"), "text");
+        assert_eq!(code_language("Report.IPS"), "json");
+        assert_eq!(code_language("Makefile"), "makefile");
+        assert_eq!(code_language("config.custom"), "custom");
+        assert_eq!(code_language("LICENSE"), "");
+    }
+}
@@ -0,0 +1,548 @@
+//! Reading Windows minidumps, such as those Windows Error Reporting writes to
+//! `%LOCALAPPDATA%\CrashDumps` when `LocalDumps` is enabled, or an app's own
+//! crash handler writes next to it.
+//!
+//! Only the exception, thread, module list, and system info streams are read,
+//! which is enough to say what went wrong and in which DLL. Without symbols
+//! or unwind info the crashed thread's frames are guessed by scanning its
+//! stack for return addresses, as a debugger does as a last resort; the dump
+//! itself is attached to the issue for a proper look. Parsing works on any
+//! platform.
+//!
+//! ```no_run
+//! use hotln::{Minidump, Reporter};
+//!
+//! let data = std::fs::read(r"C:\Users\sam\AppData\Local\CrashDumps\myapp.exe.1234.dmp")?;
+//! if let Some(dump) = Minidump::parse(&data) {
+//!     hotln::LinearClient::new("https://worker.example.com").report(&dump.report())?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{Report, Severity, info_table};
+
+const SIGNATURE: &[u8; 4] = b"MDMP";
+const THREAD_LIST_STREAM: u32 = 3;
+const MODULE_LIST_STREAM: u32 = 4;
+const EXCEPTION_STREAM: u32 = 6;
+const SYSTEM_INFO_STREAM: u32 = 7;
+/// Size of a `MINIDUMP_MODULE`.
+const MODULE_SIZE: usize = 108;
+/// Size of a `MINIDUMP_THREAD`.
+const THREAD_SIZE: usize = 48;
+/// Where `MINIDUMP_EXCEPTION_STREAM` locates the thread's context.
+const EXCEPTION_CONTEXT: usize = 160;
+/// Frames listed in a summary.
+const MAX_FRAMES: usize = 10;
+/// Stack words scanned for return addresses.
+const MAX_SCAN: usize = 2048;
+/// `VS_FIXEDFILEINFO::dwSignature`.
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF_04BD;
+const EXCEPTION_ACCESS_VIOLATION: u32 = 0xC000_0005;
+const EXCEPTION_IN_PAGE_ERROR: u32 = 0xC000_0006;
+
+/// A module loaded in the crashed process.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Module {
+    /// The file name, such as `vcruntime140.dll`.
+    pub name: String,
+    /// The full path it was loaded from.
+    pub path: String,
+    pub base: u64,
+    pub size: u64,
+    /// The file version, such as `14.38.33135.0`.
+    pub version: Option<String>,
+}
+
+impl Module {
+    fn contains(&self, address: u64) -> bool {
+        address >= self.base && address - self.base < self.size
+    }
+}
+
+/// What a minidump says about a crash.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Minidump {
+    /// The executable's file name, such as `myapp.exe`.
+    pub process: Option<String>,
+    /// The exception code, such as `0xC0000005` for an access violation.
+    pub exception_code: Option<u32>,
+    pub exception_address: Option<u64>,
+    /// The exception's parameters; for access violations, whether it was a
+    /// read (0), write (1), or execute (8), and the address accessed.
+    pub exception_parameters: Vec<u64>,
+    pub thread_id: Option<u32>,
+    /// Loaded modules, the executable first.
+    pub modules: Vec<Module>,
+    /// Such as `Windows 10.0.22631`.
+    pub os_version: Option<String>,
+    /// Such as `x86_64`.
+    pub arch: Option<&'static str>,
+    /// The crashed thread's instruction pointer, then return addresses
+    /// found on its stack, innermost first. Some may be stale values left
+    /// by earlier calls.
+    pub frames: Vec<u64>,
+    /// The dump as it was read, attached to the issue.
+    pub data: Vec<u8>,
+}
+
+impl Minidump {
+    /// Parse a minidump, or `None` if `data` isn't one.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut dump = Self::read(data)?;
+        dump.data = data.to_vec();
+        Some(dump)
+    }
+
+    /// [`parse`](Self::parse) without keeping a copy of `data`.
+    pub(crate) fn read(data: &[u8]) -> Option<Self> {
+        if data.get(..4)? != SIGNATURE {
+            return None;
+        }
+        let streams = read_u32(data, 8)? as usize;
+        let directory = read_u32(data, 12)? as usize;
+        let mut dump = Minidump::default();
+        let mut threads = None;
+        let mut context = None;
+        for i in 0..streams {
+            let entry = directory.checked_add(i.checked_mul(12)?)?;
+            let kind = read_u32(data, entry)?;
+            let size = read_u32(data, entry + 4)? as usize;
+            let rva = read_u32(data, entry + 8)? as usize;
+            let Some(stream) = data.get(rva..rva.checked_add(size)?) else {
+                continue;
+            };
+            match kind {
+                EXCEPTION_STREAM => {
+                    dump.read_exception(stream);
+                    context = read_location(data, stream, EXCEPTION_CONTEXT);
+                }
+                THREAD_LIST_STREAM => threads = Some(stream),
+                MODULE_LIST_STREAM => {
+                    dump.read_modules(data, stream);
+                }
+                SYSTEM_INFO_STREAM => {
+                    dump.read_system_info(stream);
+                }
+                _ => {}
+            }
+        }
+        dump.process = dump.modules.first().map(|module| module.name.clone());
+        if let (Some(threads), Some(context)) = (threads, context) {
+            dump.frames = dump.scan_stack(data, threads, context).unwrap_or_default();
+        }
+        Some(dump)
+    }
+
+    /// The module the exception was raised in.
+    pub fn faulting_module(&self) -> Option<&Module> {
+        self.module_at(self.exception_address?)
+    }
+
+    /// The module loaded at `address`.
+    pub fn module_at(&self, address: u64) -> Option<&Module> {
+        self.modules.iter().find(|module| module.contains(address))
+    }
+
+    /// `address` as `module+0xoffset`, which stays the same across runs.
+    pub fn location(&self, address: u64) -> String {
+        match self.module_at(address) {
+            Some(module) => format!("{}+0x{:x}", module.name, address - module.base),
+            None => format!("0x{address:016x}"),
+        }
+    }
+
+    /// The exception code's name, such as `EXCEPTION_ACCESS_VIOLATION`, for
+    /// the common ones.
+    pub fn exception_name(&self) -> Option<&'static str> {
+        Some(match self.exception_code? {
+            EXCEPTION_ACCESS_VIOLATION => "EXCEPTION_ACCESS_VIOLATION",
+            EXCEPTION_IN_PAGE_ERROR => "EXCEPTION_IN_PAGE_ERROR",
+            0xC000_001D => "EXCEPTION_ILLEGAL_INSTRUCTION",
+            0xC000_008C => "EXCEPTION_ARRAY_BOUNDS_EXCEEDED",
+            0xC000_0094 => "EXCEPTION_INT_DIVIDE_BY_ZERO",
+            0xC000_0096 => "EXCEPTION_PRIV_INSTRUCTION",
+            0xC000_00FD => "EXCEPTION_STACK_OVERFLOW",
+            0xC000_0374 => "STATUS_HEAP_CORRUPTION",
+            0xC000_0409 => "STATUS_STACK_BUFFER_OVERRUN",
+            0xC000_0602 => "STATUS_FAIL_FAST_EXCEPTION",
+            0x8000_0003 => "EXCEPTION_BREAKPOINT",
+            0xE06D_7363 => "C++ exception",
+            _ => return None,
+        })
+    }
+
+    /// A crash report titled after the process, exception, and faulting
+    /// module, with the dump attached, which adds its
+    /// [summary](Self::summary). Dumps of the same exception at the same
+    /// place in the same module share a fingerprint.
+    pub fn report(&self) -> Report {
+        let process = self.process.as_deref().unwrap_or("Unknown process");
+        let module = self.faulting_module();
+        let mut title = format!("{process} crashed");
+        if let Some(name) = self
+            .exception_name()
+            .map(str::to_string)
+            .or_else(|| self.exception_code.map(|code| format!("0x{code:08X}")))
+        {
+            title.push_str(&format!(": {name}"));
+        }
+        if let Some(module) = module {
+            title.push_str(&format!(" in {}", module.name));
+        }
+
+        let mut report = Report::new();
+        report
+            .title(&title)
+            .severity(Severity::Crash)
+            .attachment(&format!("{process}.dmp"), &self.data);
+        if let Some(code) = self.exception_code {
+            // Module offsets are stable across runs; absolute addresses move
+            // with ASLR.
+            let place = match (module, self.exception_address) {
+                (Some(module), Some(address)) => format!(
+                    "{}+{:x}",
+                    module.name.to_ascii_lowercase(),
+                    address - module.base
+                ),
+                _ => process.to_ascii_lowercase(),
+            };
+            report.fingerprint = Some(crate::fingerprint::hash_hex(&format!(
+                "minidump:{code:08x}:{place}"
+            )));
+        }
+        report
+    }
+
+    /// The crash reason, faulting address and module, and the crashed
+    /// thread's top frames, as Markdown.
+    pub fn summary(&self) -> String {
+        let code = self.exception_code.map(|code| format!("0x{code:08X}"));
+        let exception = match (self.exception_name(), &code) {
+            (Some(name), Some(code)) => Some(format!("{name} ({code})")),
+            (None, code) => code.clone(),
+            (Some(name), None) => Some(name.to_string()),
+        };
+        let location = self.exception_address.map(|address| self.location(address));
+        let module_version = self
+            .faulting_module()
+            .and_then(|module| module.version.clone());
+        let detail = self.access_detail();
+        let thread = self.thread_id.map(|id| id.to_string());
+        let rows: Vec<(&str, &str)> = [
+            ("Process", self.process.as_deref()),
+            ("Exception", exception.as_deref()),
+            ("Details", detail.as_deref()),
+            ("Location", location.as_deref()),
+            ("Module Version", module_version.as_deref()),
+            ("Thread", thread.as_deref()),
+            ("OS", self.os_version.as_deref()),
+            ("Architecture", self.arch),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect();
+
+        let mut summary = info_table("Crash", &rows);
+        if !self.frames.is_empty() {
+            let frames: Vec<String> = self
+                .frames
+                .iter()
+                .take(MAX_FRAMES)
+                .enumerate()
+                .map(|(i, &address)| format!("{i:<3} {}", self.location(address)))
+                .collect();
+            summary.push_str(&format!(
+                "\n\n## Crashed thread\n\nFrames after the first are found by scanning the \
+                 stack, so some may be stale.\n\n```\n{}\n```",
+                frames.join("\n")
+            ));
+        }
+        summary
+    }
+
+    /// What an access violation tried to do, such as "reading address
+    /// 0x0000000000000000".
+    fn access_detail(&self) -> Option<String> {
+        if !matches!(
+            self.exception_code?,
+            EXCEPTION_ACCESS_VIOLATION | EXCEPTION_IN_PAGE_ERROR
+        ) {
+            return None;
+        }
+        let action = match self.exception_parameters.first()? {
+            0 => "reading",
+            1 => "writing",
+            8 => "executing",
+            _ => "accessing",
+        };
+        let address = self.exception_parameters.get(1)?;
+        Some(format!("{action} address 0x{address:016x}"))
+    }
+
+    /// `MINIDUMP_EXCEPTION_STREAM`: the thread, then `MINIDUMP_EXCEPTION`.
+    fn read_exception(&mut self, stream: &[u8]) -> Option<()> {
+        self.thread_id = Some(read_u32(stream, 0)?);
+        self.exception_code = Some(read_u32(stream, 8)?);
+        self.exception_address = Some(read_u64(stream, 24)?);
+        let parameters = read_u32(stream, 32)?.min(15) as usize;
+        self.exception_parameters = (0..parameters)
+            .map_while(|i| read_u64(stream, 40 + i * 8))
+            .collect();
+        Some(())
+    }
+
+    /// `MINIDUMP_MODULE_LIST`: a count, then packed `MINIDUMP_MODULE`s.
+    fn read_modules(&mut self, data: &[u8], stream: &[u8]) -> Option<()> {
+        let count = read_u32(stream, 0)? as usize;
+        for i in 0..count {
+            let module = stream.get(4 + i * MODULE_SIZE..4 + (i + 1) * MODULE_SIZE)?;
+            let path = read_string(data, read_u32(module, 20)? as usize).unwrap_or_default();
+            let version = (read_u32(module, 24)? == FIXED_FILE_INFO_SIGNATURE).then(|| {
+                let ms = read_u32(module, 32).unwrap_or_default();
+                let ls = read_u32(module, 36).unwrap_or_default();
+                format!("{}.{}.{}.{}", ms >> 16, ms & 0xFFFF, ls >> 16, ls & 0xFFFF)
+            });
+            self.modules.push(Module {
+                name: path.rsplit(['\\', '/']).next().unwrap_or(&path).to_string(),
+                base: read_u64(module, 0)?,
+                size: u64::from(read_u32(module, 8)?),
+                version,
+                path,
+            });
+        }
+        Some(())
+    }
+
+    /// The instruction pointer, then values on the crashed thread's stack
+    /// that point into a loaded module, as return addresses do.
+    fn scan_stack(&self, data: &[u8], threads: &[u8], context: &[u8]) -> Option<Vec<u64>> {
+        // Offsets of the instruction pointer, stack pointer, and (on Arm)
+        // link register in the Windows `CONTEXT` for each architecture.
+        let (width, ip, sp, lr) = match self.arch? {
+            "x86_64" => (8, 0xF8, 0x98, None),
+            "aarch64" => (8, 0x108, 0x100, Some(0xF8)),
+            "x86" => (4, 0xB8, 0xC4, None),
+            _ => return None,
+        };
+        let word = |bytes: &[u8], offset: usize| match width {
+            4 => read_u32(bytes, offset).map(u64::from),
+            _ => read_u64(bytes, offset),
+        };
+        let mut frames = vec![word(context, ip)?];
+        if let Some(lr) = lr.and_then(|lr| word(context, lr))
+            && self.module_at(lr).is_some()
+        {
+            frames.push(lr);
+        }
+
+        let sp = word(context, sp)?;
+        let count = read_u32(threads, 0)? as usize;
+        let thread = (0..count)
+            .filter_map(|i| threads.get(4 + i * THREAD_SIZE..4 + (i + 1) * THREAD_SIZE))
+            .find(|thread| read_u32(thread, 0) == self.thread_id)?;
+        let start = read_u64(thread, 24)?;
+        let stack = read_location(data, thread, 32)?;
+        let from = usize::try_from(sp.checked_sub(start)?).ok()?;
+        for chunk in stack.get(from..)?.chunks_exact(width).take(MAX_SCAN) {
+            if frames.len() >= MAX_FRAMES {
+                break;
+            }
+            let value = word(chunk, 0)?;
+            if self.module_at(value).is_some() && frames.last() != Some(&value) {
+                frames.push(value);
+            }
+        }
+        Some(frames)
+    }
+
+    /// `MINIDUMP_SYSTEM_INFO`.
+    fn read_system_info(&mut self, stream: &[u8]) -> Option<()> {
+        let arch = u16::from_le_bytes(stream.get(0..2)?.try_into().ok()?);
+        self.arch = match arch {
+            0 => Some("x86"),
+            5 => Some("arm"),
+            9 => Some("x86_64"),
+            12 => Some("aarch64"),
+            _ => None,
+        };
+        let major = read_u32(stream, 8)?;
+        let minor = read_u32(stream, 12)?;
+        let build = read_u32(stream, 16)?;
+        self.os_version = Some(format!("Windows {major}.{minor}.{build}"));
+        Some(())
+    }
+}
+
+/// The bytes a `MINIDUMP_LOCATION_DESCRIPTOR` at `offset` in `at` points to.
+fn read_location<'a>(data: &'a [u8], at: &[u8], offset: usize) -> Option<&'a [u8]> {
+    let size = read_u32(at, offset)? as usize;
+    let rva = read_u32(at, offset + 4)? as usize;
+    data.get(rva..rva.checked_add(size)?)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// A `MINIDUMP_STRING`: a byte length, then UTF-16.
+fn read_string(data: &[u8], rva: usize) -> Option<String> {
+    let len = read_u32(data, rva)? as usize;
+    let bytes = data.get(rva + 4..(rva + 4).checked_add(len)?)?;
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A dump with an access violation in the second of two modules.
+    fn sample() -> Vec<u8> {
+        let mut data = vec![0u8; 32];
+        data[..4].copy_from_slice(SIGNATURE);
+        data[8..12].copy_from_slice(&4u32.to_le_bytes());
+        data[12..16].copy_from_slice(&32u32.to_le_bytes());
+        data.resize(32 + 4 * 12, 0);
+
+        let mut names = Vec::new();
+        for path in [r"C:\app\myapp.exe", r"C:\Windows\System32\ntdll.dll"] {
+            names.push(data.len() as u32);
+            let utf16: Vec<u8> = path.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            data.extend((utf16.len() as u32).to_le_bytes());
+            data.extend(utf16);
+        }
+
+        // The thread's registers, and its stack from 0x1000_0000 with the
+        // stack pointer two words in.
+        let context = data.len() as u32;
+        let mut registers = vec![0u8; 0x100];
+        registers[0x98..0xA0].copy_from_slice(&0x1000_0010u64.to_le_bytes());
+        registers[0xF8..0x100].copy_from_slice(&0x7ffa_0000_1234u64.to_le_bytes());
+        data.extend(registers);
+        let stack = data.len() as u32;
+        for word in [
+            0x1_4000_9999u64,
+            0,
+            0x1_4000_2000,
+            0x55,
+            0x7ffa_0000_5678,
+            0x1_4000_3000,
+        ] {
+            data.extend(word.to_le_bytes());
+        }
+
+        let mut exception = vec![0u8; 168];
+        exception[0..4].copy_from_slice(&7u32.to_le_bytes());
+        exception[8..12].copy_from_slice(&EXCEPTION_ACCESS_VIOLATION.to_le_bytes());
+        exception[24..32].copy_from_slice(&0x7ffa_0000_1234u64.to_le_bytes());
+        exception[32..36].copy_from_slice(&2u32.to_le_bytes());
+        exception[40..48].copy_from_slice(&1u64.to_le_bytes());
+        exception[48..56].copy_from_slice(&0x10u64.to_le_bytes());
+        exception[160..164].copy_from_slice(&0x100u32.to_le_bytes());
+        exception[164..168].copy_from_slice(&context.to_le_bytes());
+
+        let mut threads = 1u32.to_le_bytes().to_vec();
+        let mut thread = vec![0u8; THREAD_SIZE];
+        thread[0..4].copy_from_slice(&7u32.to_le_bytes());
+        thread[24..32].copy_from_slice(&0x1000_0000u64.to_le_bytes());
+        thread[32..36].copy_from_slice(&48u32.to_le_bytes());
+        thread[36..40].copy_from_slice(&stack.to_le_bytes());
+        threads.extend(thread);
+
+        let mut modules = 2u32.to_le_bytes().to_vec();
+        for (base, name) in [(0x1_4000_0000u64, names[0]), (0x7ffa_0000_0000, names[1])] {
+            let mut module = vec![0u8; MODULE_SIZE];
+            module[0..8].copy_from_slice(&base.to_le_bytes());
+            module[8..12].copy_from_slice(&0x10_0000u32.to_le_bytes());
+            module[20..24].copy_from_slice(&name.to_le_bytes());
+            module[24..28].copy_from_slice(&FIXED_FILE_INFO_SIGNATURE.to_le_bytes());
+            module[32..36].copy_from_slice(&(10u32 << 16).to_le_bytes());
+            module[36..40].copy_from_slice(&((22621 << 16) | 2506u32).to_le_bytes());
+            modules.extend(module);
+        }
+
+        let mut system = vec![0u8; 56];
+        system[0..2].copy_from_slice(&9u16.to_le_bytes());
+        system[8..12].copy_from_slice(&10u32.to_le_bytes());
+        system[16..20].copy_from_slice(&22631u32.to_le_bytes());
+
+        for (i, (kind, stream)) in [
+            (EXCEPTION_STREAM, exception),
+            (THREAD_LIST_STREAM, threads),
+            (MODULE_LIST_STREAM, modules),
+            (SYSTEM_INFO_STREAM, system),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let entry = 32 + i * 12;
+            data[entry..entry + 4].copy_from_slice(&kind.to_le_bytes());
+            data[entry + 4..entry + 8].copy_from_slice(&(stream.len() as u32).to_le_bytes());
+            let rva = data.len() as u32;
+            data[entry + 8..entry + 12].copy_from_slice(&rva.to_le_bytes());
+            data.extend(stream);
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse() {
+        let dump = Minidump::parse(&sample()).unwrap();
+        assert_eq!(dump.process.as_deref(), Some("myapp.exe"));
+        assert_eq!(dump.thread_id, Some(7));
+        assert_eq!(dump.modules.len(), 2);
+        assert_eq!(dump.modules[1].path, r"C:\Windows\System32\ntdll.dll");
+        assert_eq!(dump.modules[1].version.as_deref(), Some("10.0.22621.2506"));
+        assert_eq!(dump.faulting_module().unwrap().name, "ntdll.dll");
+        assert_eq!(dump.os_version.as_deref(), Some("Windows 10.0.22631"));
+        assert_eq!(dump.arch, Some("x86_64"));
+        assert_eq!(
+            dump.frames,
+            [
+                0x7ffa_0000_1234,
+                0x1_4000_2000,
+                0x7ffa_0000_5678,
+                0x1_4000_3000
+            ]
+        );
+
+        let report = dump.report();
+        assert_eq!(
+            report.title,
+            "myapp.exe crashed: EXCEPTION_ACCESS_VIOLATION in ntdll.dll"
+        );
+        assert!(
+            report
+                .description
+                .contains("| Details | writing address 0x0000000000000010 |")
+        );
+        assert!(
+            report
+                .description
+                .contains("| Location | ntdll.dll+0x1234 |")
+        );
+        assert!(report.description.contains("1   myapp.exe+0x2000\n"));
+        assert_eq!(report.attachments[0].0, "myapp.exe.dmp");
+        assert!(report.fingerprint.is_some());
+    }
+
+    #[test]
+    fn test_not_a_minidump() {
+        assert!(Minidump::parse(b"PK\x03\x04").is_none());
+        // A truncated dump still parses, without the missing streams.
+        let dump = Minidump::parse(&sample()[..100]).unwrap();
+        assert!(dump.exception_code.is_none());
+    }
+}
@@ -0,0 +1,220 @@
+//! Filing reports from async code, on any executor.
+//!
+//! Clients send with blocking I/O, so an async submission runs the same
+//! client pipeline on a thread of its own and wakes the task when it is
+//! done. The future only uses [`Waker`], so it works the same under tokio,
+//! smol, async-std, or a plain `block_on`, and the blocking and async APIs
+//! can't drift apart. Dropping the future cancels the submission.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::cancel::Cancel;
+use crate::{Error, Report, Reporter, Submission};
+
+/// File `report` without blocking the async runtime. Resolves to what
+/// [`Reporter::submit`] returns.
+///
+/// Dropping the future before it finishes, or calling
+/// [`SubmitFuture::cancel`], abandons the report, such as on shutdown: no
+/// further requests are made for it, and it isn't queued or spooled. A
+/// request already being sent still completes.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), hotln::Error> {
+/// let client = std::sync::Arc::new(hotln::LinearClient::new("https://your-proxy.example.com"));
+/// let mut report = hotln::Report::new();
+/// report.title("sync failed");
+/// let url = hotln::submit_async(client, report).await?.into_url();
+/// # Ok(())
+/// # }
+/// ```
+pub fn submit_async<R>(reporter: Arc<R>, report: Report) -> SubmitFuture
+where
+    R: Reporter + Send + Sync + ?Sized + 'static,
+{
+    let shared = Arc::new(Mutex::new(Shared::default()));
+    let done = shared.clone();
+    let cancel = Cancel::default();
+    let token = cancel.clone();
+    let shared = std::thread::Builder::new()
+        .name("hotline-submit".into())
+        .spawn(move || {
+            let result = token
+                .check()
+                .and_then(|()| token.scope(|| reporter.submit(&report)));
+            let mut done = done.lock().unwrap_or_else(|e| e.into_inner());
+            done.result = Some(result);
+            if let Some(waker) = done.waker.take() {
+                waker.wake();
+            }
+        })
+        .map_or_else(
+            |e| {
+                Arc::new(Mutex::new(Shared {
+                    result: Some(Err(Error::Config(format!(
+                        "cannot start a thread to file the report: {e}"
+                    )))),
+                    waker: None,
+                }))
+            },
+            |_| shared,
+        );
+    SubmitFuture {
+        shared,
+        cancel,
+        finished: false,
+    }
+}
+
+/// A report being filed by [`submit_async`]. Dropping it before it finishes
+/// cancels the submission.
+#[must_use = "dropping the future cancels the submission"]
+pub struct SubmitFuture {
+    shared: Arc<Mutex<Shared>>,
+    cancel: Cancel,
+    finished: bool,
+}
+
+impl SubmitFuture {
+    /// Abandon the report: no further requests are made for it, including
+    /// retries by the client's retry queue, and the future resolves to
+    /// [`Error::Cancelled`] unless the report was already sent.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    result: Option<Result<Submission, Error>>,
+    waker: Option<Waker>,
+}
+
+impl Future for SubmitFuture {
+    type Output = Result<Submission, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut shared = this.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.result.take() {
+            Some(result) => {
+                this.finished = true;
+                Poll::Ready(result)
+            }
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for SubmitFuture {
+    fn drop(&mut self) {
+        // Once it has resolved, a queued report is left to the retry queue.
+        if !self.finished {
+            self.cancel.cancel();
+        }
+    }
+}
+
+impl std::fmt::Debug for SubmitFuture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubmitFuture").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_submit_async() {
+        let mut mock = MockReporter::new();
+        mock.with_url("https://linear.app/issue/ENG-1");
+        let mock = Arc::new(mock);
+        let mut report = Report::new();
+        report.title("sync failed");
+
+        let reporter: Arc<dyn Reporter + Send + Sync> = mock.clone();
+        let submission = futures_executor::block_on(submit_async(reporter, report)).unwrap();
+        assert_eq!(submission.url(), "https://linear.app/issue/ENG-1");
+        assert_eq!(mock.reports()[0].title, "sync failed");
+
+        let mut failing = MockReporter::new();
+        failing.with_error(500, "down");
+        let result = futures_executor::block_on(submit_async(Arc::new(failing), Report::new()));
+        assert!(matches!(result, Err(Error::Proxy { status: 500, .. })));
+    }
+
+    /// Holds each submission until the test lets it through, then reports
+    /// how it went.
+    struct Gated {
+        inner: crate::LinearClient,
+        entered: Mutex<mpsc::Sender<()>>,
+        gate: Mutex<mpsc::Receiver<()>>,
+        results: Mutex<mpsc::Sender<Result<Submission, Error>>>,
+    }
+
+    impl Reporter for Gated {
+        fn report(&self, report: &Report) -> Result<String, Error> {
+            self.submit(report).map(Submission::into_url)
+        }
+
+        fn submit(&self, report: &Report) -> Result<Submission, Error> {
+            self.entered.lock().unwrap().send(()).unwrap();
+            self.gate.lock().unwrap().recv().unwrap();
+            let result = self.inner.submit(report);
+            let echo = match &result {
+                Ok(submission) => Ok(submission.clone()),
+                Err(Error::Cancelled) => Err(Error::Cancelled),
+                Err(e) => Err(Error::Config(e.to_string())),
+            };
+            self.results.lock().unwrap().send(echo).unwrap();
+            result
+        }
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/linear").expect(0).create();
+        let (tx, entered) = mpsc::channel();
+        let (open, gate) = mpsc::channel();
+        let (results_tx, results) = mpsc::channel();
+        let reporter = Arc::new(Gated {
+            inner: crate::LinearClient::new(&server.url()),
+            entered: Mutex::new(tx),
+            gate: Mutex::new(gate),
+            results: Mutex::new(results_tx),
+        });
+
+        let timeout = Duration::from_secs(5);
+        let future = submit_async(reporter.clone(), Report::new());
+        entered.recv_timeout(timeout).unwrap();
+        future.cancel();
+        open.send(()).unwrap();
+        let result = futures_executor::block_on(future);
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(matches!(
+            results.recv_timeout(timeout).unwrap(),
+            Err(Error::Cancelled)
+        ));
+
+        // Dropping the future cancels it too.
+        let future = submit_async(reporter, Report::new());
+        entered.recv_timeout(timeout).unwrap();
+        drop(future);
+        open.send(()).unwrap();
+        let result = results.recv_timeout(timeout).unwrap();
+        assert!(matches!(result, Err(Error::Cancelled)));
+        mock.assert();
+    }
+}
@@ -0,0 +1,326 @@
+//! File a report whenever the process panics.
+//!
+//! ```no_run
+//! let mut client = hotln::LinearClient::new("https://worker.example.com");
+//! client.with_token("secret");
+//! hotln::PanicHook::new(client).install();
+//! ```
+
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::panic::PanicHookInfo;
+use std::time::Duration;
+
+use crate::capture::StderrCapture;
+use crate::{Dedup, Destination, Matcher, Report, Reporter, Router, Severity, info_table};
+
+/// A panic hook that files a [`Report`] for each panic.
+///
+/// The previously installed hook still runs first, so the usual message is
+/// printed to stderr before the report is sent. Filing blocks the panicking
+/// thread until the reporter returns.
+///
+/// Each distinct panic is filed once per process, or once per
+/// [`dedup_window`](Self::dedup_window), grouped by the
+/// [`fingerprint`](crate::fingerprint) of its backtrace, or by its
+/// [normalized](crate::normalize_message) message when the backtrace has no
+/// symbols.
+pub struct PanicHook {
+    reporter: Dedup<Router>,
+    stderr: Option<StderrCapture>,
+    collapse_lines: usize,
+    #[cfg(feature = "thread-dump")]
+    thread_dump: bool,
+    #[cfg(feature = "symbolicate")]
+    debug_info: Option<std::path::PathBuf>,
+}
+
+impl PanicHook {
+    pub fn new(reporter: impl Reporter + Send + Sync + 'static) -> Self {
+        Self {
+            reporter: Dedup::new(Router::new(reporter)),
+            stderr: None,
+            collapse_lines: crate::markdown::COLLAPSE_LINES,
+            #[cfg(feature = "thread-dump")]
+            thread_dump: false,
+            #[cfg(feature = "symbolicate")]
+            debug_info: None,
+        }
+    }
+
+    /// Resolve backtraces against this split debug file or `.dSYM` bundle,
+    /// for stripped release builds. Supported on Linux and macOS; elsewhere,
+    /// or if the file can't be read, the usual backtrace is sent.
+    #[cfg(feature = "symbolicate")]
+    pub fn debug_info(&mut self, path: impl Into<std::path::PathBuf>) -> &mut Self {
+        self.debug_info = Some(path.into());
+        self
+    }
+
+    /// Keep the last `max_bytes` written to stderr and attach them to panic
+    /// reports as `stderr.txt`, so the error output leading up to a panic
+    /// is kept even without a logging framework.
+    ///
+    /// Starts right away: stderr is redirected through a pipe and copied
+    /// back by a background thread, so it still reaches the terminal. Unix
+    /// only; elsewhere, or if the redirect fails, a warning is logged and
+    /// reports go without it.
+    pub fn capture_stderr(&mut self, max_bytes: usize) -> &mut Self {
+        match StderrCapture::start(max_bytes) {
+            Ok(capture) => self.stderr = Some(capture),
+            Err(e) => tracing::warn!("cannot capture stderr: {e}"),
+        }
+        self
+    }
+
+    /// Collapse the backtrace, message, and captured stderr of panic reports
+    /// when they are longer than `lines`. See [`Report::collapse_above`].
+    pub fn collapse_above(&mut self, lines: usize) -> &mut Self {
+        self.collapse_lines = lines;
+        self
+    }
+
+    /// Attach backtraces of the other threads to panic reports. See
+    /// [`Report::with_thread_dump`].
+    #[cfg(feature = "thread-dump")]
+    pub fn thread_dump(&mut self) -> &mut Self {
+        self.thread_dump = true;
+        self
+    }
+
+    /// File panics that match to another destination than the hook's
+    /// reporter, such as panics from `myapp::plugins` to the plugin team's
+    /// project. The first match wins. See [`Router`].
+    ///
+    /// ```no_run
+    /// use hotln::{LinearClient, Matcher, PanicHook};
+    ///
+    /// let mut hook = PanicHook::new(LinearClient::new("https://eng-proxy.example.com"));
+    /// hook.overrides(vec![(
+    ///     Matcher::Module("myapp::plugins".into()),
+    ///     Box::new(LinearClient::new("https://plugins-proxy.example.com")),
+    /// )]);
+    /// hook.install();
+    /// ```
+    pub fn overrides(&mut self, overrides: Vec<(Matcher, Destination)>) -> &mut Self {
+        self.reporter.inner.overrides(overrides);
+        self
+    }
+
+    /// File a panic again once `window` has passed since it was last filed,
+    /// instead of once per process. See [`Dedup::window`].
+    pub fn dedup_window(&mut self, window: Duration) -> &mut Self {
+        self.reporter.window(window);
+        self
+    }
+
+    /// Keep panic fingerprints and their issues in a file at `path`, so a
+    /// panic that repeats after a restart is matched to its issue. See
+    /// [`Dedup::registry`].
+    pub fn dedup_registry(&mut self, path: impl Into<std::path::PathBuf>) -> &mut Self {
+        self.reporter.registry(path);
+        self
+    }
+
+    /// Post one summary comment per issue at most once per `period` for
+    /// panics that repeat, instead of a comment for each. See
+    /// [`Dedup::digest`].
+    pub fn dedup_digest(&mut self, period: Duration) -> &mut Self {
+        self.reporter.digest(period);
+        self
+    }
+
+    /// Comment on the earlier issue each time a panic repeats within the
+    /// window. See [`Dedup::comment_occurrences`].
+    pub fn comment_occurrences(&mut self) -> &mut Self {
+        self.reporter.comment_occurrences();
+        self
+    }
+
+    /// Install the hook, chaining to the current one.
+    pub fn install(self) {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            self.handle(info);
+        }));
+    }
+
+    fn handle(&self, info: &PanicHookInfo<'_>) {
+        thread_local! {
+            static REPORTING: Cell<bool> = const { Cell::new(false) };
+        }
+        // A panic inside the reporter would otherwise recurse forever.
+        if REPORTING.with(|r| r.replace(true)) {
+            return;
+        }
+        let mut report = build_report(info, self.backtrace(), self.collapse_lines);
+        if let Some(stderr) = &self.stderr {
+            let tail = stderr.tail();
+            if !tail.is_empty() {
+                report.file("stderr.txt", &tail);
+            }
+        }
+        #[cfg(feature = "thread-dump")]
+        if self.thread_dump {
+            report.with_thread_dump();
+        }
+        self.reporter.report_or_log(&report);
+        REPORTING.with(|r| r.set(false));
+    }
+
+    fn backtrace(&self) -> String {
+        #[cfg(feature = "symbolicate")]
+        if let Some(backtrace) = self
+            .debug_info
+            .as_deref()
+            .and_then(crate::symbolicate::symbolicate)
+        {
+            return backtrace;
+        }
+        Backtrace::force_capture().to_string()
+    }
+}
+
+/// Build the report the panic hook would file for `info`.
+pub fn panic_report(info: &PanicHookInfo<'_>) -> Report {
+    build_report(
+        info,
+        Backtrace::force_capture().to_string(),
+        crate::markdown::COLLAPSE_LINES,
+    )
+}
+
+fn build_report(info: &PanicHookInfo<'_>, backtrace: String, collapse_lines: usize) -> Report {
+    let message = panic_message(info);
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let snippet = info
+        .location()
+        .filter(|_| cfg!(debug_assertions))
+        .and_then(|l| source_snippet(l.file(), l.line()));
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+
+    let fingerprint =
+        crate::fingerprint(&backtrace).unwrap_or_else(|| crate::message_fingerprint(&message));
+    let rows = [
+        ("Location", location.as_str()),
+        ("Thread", thread),
+        ("Fingerprint", fingerprint.as_str()),
+    ];
+
+    let mut report = Report::new();
+    report
+        .title(&format!("panic: {}", first_line(&message)))
+        .severity(Severity::Crash)
+        .collapse_above(collapse_lines)
+        .text(&info_table("Panic", &rows));
+    if let Some(snippet) = &snippet {
+        report.text(snippet);
+    }
+    report
+        .file("message.txt", &message)
+        .file("backtrace.txt", &backtrace);
+    report.fingerprint = Some(fingerprint);
+    report.module = crate::fingerprint::module(&backtrace);
+    report
+}
+
+pub(crate) fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+/// A few lines around `file:line`, if the source is where the panic
+/// location says. It only is when running from the project directory, as
+/// in development, and never for code from the standard library.
+fn source_snippet(file: &str, line: u32) -> Option<String> {
+    const CONTEXT: usize = 3;
+    let source = std::fs::read_to_string(file).ok()?;
+    let line = usize::try_from(line).ok()?.checked_sub(1)?;
+    let lines: Vec<&str> = source.lines().collect();
+    if line >= lines.len() {
+        return None;
+    }
+    let first = line.saturating_sub(CONTEXT);
+    let last = (line + CONTEXT).min(lines.len() - 1);
+    let width = (last + 1).to_string().len();
+    let mut out = format!("## Source\n\n**{file}**\n```rust");
+    for (i, text) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let marker = if i == line { '>' } else { ' ' };
+        out.push_str(&format!("\n{marker} {:>width$} | {text}", i + 1));
+    }
+    out.push_str("\n```");
+    Some(out)
+}
+
+fn first_line(message: &str) -> String {
+    const MAX_CHARS: usize = 80;
+    let line = message.lines().next().unwrap_or("");
+    if line.chars().count() <= MAX_CHARS {
+        line.to_string()
+    } else {
+        line.chars().take(MAX_CHARS - 3).collect::<String>() + "..."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_reports_panics() {
+        let mock = Arc::new(crate::testing::MockReporter::new());
+        PanicHook::new(mock.clone()).install();
+        let result = std::thread::Builder::new()
+            .name("worker".into())
+            .spawn(|| panic!("boom"))
+            .unwrap()
+            .join();
+        let _ = std::panic::take_hook();
+
+        assert!(result.is_err());
+        let reports = mock.reports();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].title, "panic: boom");
+        assert_eq!(reports[0].severity, Some(Severity::Crash));
+        assert!(reports[0].description.contains("| Thread | worker |"));
+        assert!(reports[0].fingerprint.is_some());
+    }
+
+    #[test]
+    fn test_source_snippet() {
+        let dir = crate::spool::temp_dir("snippet");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("main.rs");
+        let source: Vec<String> = (1..=20).map(|i| format!("line {i}")).collect();
+        std::fs::write(&path, source.join("\n")).unwrap();
+        let file = path.to_str().unwrap();
+
+        let snippet = source_snippet(file, 10).unwrap();
+        assert!(snippet.contains("```rust\n   7 | line 7\n"));
+        assert!(snippet.contains("\n> 10 | line 10\n"));
+        assert!(snippet.ends_with("  13 | line 13\n```"));
+        assert!(source_snippet(file, 1).unwrap().contains("> 1 | line 1"));
+        assert_eq!(source_snippet(file, 21), None);
+        assert_eq!(source_snippet("/rustc/library/core/src/option.rs", 1), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_first_line() {
+        assert_eq!(first_line("boom\nmore"), "boom");
+        assert_eq!(first_line(&"x".repeat(100)).chars().count(), 80);
+    }
+}
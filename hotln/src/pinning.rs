@@ -0,0 +1,189 @@
+//! Certificate pinning for proxy connections.
+//!
+//! A pinned client trusts the proxy only if its leaf certificate, or the
+//! certificate's public key, hashes to one of the configured pins. The usual
+//! CA chain check is replaced, so self-signed proxies work, but the handshake
+//! signature is still verified so the server must hold the private key.
+
+use std::sync::Arc;
+
+use base64::prelude::*;
+use ring::digest::{SHA256, digest};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Pin {
+    /// SHA-256 of the whole DER certificate.
+    Certificate([u8; 32]),
+    /// SHA-256 of the DER SubjectPublicKeyInfo, which survives renewals that
+    /// keep the same key.
+    Spki([u8; 32]),
+}
+
+/// Parse a pin, either `sha256/<base64>` for a public key (the format curl's
+/// `--pinnedpubkey` takes) or the certificate's SHA-256 fingerprint in hex,
+/// with or without colons.
+pub(crate) fn parse(pin: &str) -> Option<Pin> {
+    let pin = pin.trim();
+    if let Some(b64) = pin.strip_prefix("sha256/") {
+        let bytes = BASE64_STANDARD.decode(b64).ok()?;
+        return Some(Pin::Spki(bytes.try_into().ok()?));
+    }
+    let hex: String = pin.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(Pin::Certificate(bytes))
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    digest(&SHA256, data)
+        .as_ref()
+        .try_into()
+        .expect("SHA-256 is 32 bytes")
+}
+
+/// Split one DER TLV off the front of `der`, returning (whole TLV, contents, rest).
+fn der_next(der: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let [_tag, len_byte, after_len @ ..] = der else {
+        return None;
+    };
+    let (len, header) = if *len_byte < 0x80 {
+        (*len_byte as usize, 2)
+    } else {
+        let n = (len_byte & 0x7f) as usize;
+        if n == 0 || n > 4 || after_len.len() < n {
+            return None;
+        }
+        let len = after_len[..n]
+            .iter()
+            .fold(0usize, |acc, b| acc << 8 | *b as usize);
+        (len, 2 + n)
+    };
+    let end = header.checked_add(len)?;
+    if der.len() < end {
+        return None;
+    }
+    Some((&der[..end], &der[header..end], &der[end..]))
+}
+
+/// The DER SubjectPublicKeyInfo of an X.509 certificate.
+fn spki(cert: &[u8]) -> Option<&[u8]> {
+    let (_, certificate, _) = der_next(cert)?;
+    let (_, tbs, _) = der_next(certificate)?;
+    let mut rest = tbs;
+    // An explicit version is tagged [0]; skip it when present.
+    if rest.first() == Some(&0xa0) {
+        rest = der_next(rest)?.2;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        rest = der_next(rest)?.2;
+    }
+    Some(der_next(rest)?.0)
+}
+
+#[derive(Debug)]
+pub(crate) struct PinnedVerifier {
+    pub(crate) pins: Vec<Pin>,
+    pub(crate) provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let cert_hash = sha256(end_entity);
+        let spki_hash = spki(end_entity).map(sha256);
+        let matched = self.pins.iter().any(|pin| match pin {
+            Pin::Certificate(hash) => *hash == cert_hash,
+            Pin::Spki(hash) => Some(*hash) == spki_hash,
+        });
+        if matched {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "proxy certificate does not match any pin".to_string(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let hex = "AB:".repeat(31) + "AB";
+        assert_eq!(parse(&hex), Some(Pin::Certificate([0xab; 32])));
+        let b64 = format!("sha256/{}", BASE64_STANDARD.encode([7u8; 32]));
+        assert_eq!(parse(&b64), Some(Pin::Spki([7; 32])));
+        assert_eq!(parse("sha256/short"), None);
+        assert_eq!(parse("abcd"), None);
+    }
+
+    #[test]
+    fn test_spki() {
+        // SEQUENCE { SEQUENCE { [0] { INTEGER 2 }, INTEGER 1, SEQ, SEQ, SEQ, SEQ, SEQ { 0x55 } } }
+        let tbs: &[u8] = &[
+            0xa0, 0x03, 0x02, 0x01, 0x02, // version
+            0x02, 0x01, 0x01, // serial
+            0x30, 0x00, // signature
+            0x30, 0x00, // issuer
+            0x30, 0x00, // validity
+            0x30, 0x00, // subject
+            0x30, 0x01, 0x55, // subjectPublicKeyInfo
+        ];
+        let mut cert = vec![0x30, tbs.len() as u8 + 2, 0x30, tbs.len() as u8];
+        cert.extend_from_slice(tbs);
+        assert_eq!(spki(&cert), Some(&[0x30, 0x01, 0x55][..]));
+        assert_eq!(spki(&cert[..6]), None);
+    }
+}
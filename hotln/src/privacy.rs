@@ -0,0 +1,114 @@
+//! One switch for how much a report may say about the person who sent it.
+
+use crate::Report;
+
+/// How much context reports carry, from everything to nothing that could
+/// identify a user.
+///
+/// Set it on a client with `with_privacy_level`, or as `privacy_level` in a
+/// [`Config`](crate::Config). Collectors such as the Bevy and egui system
+/// info and the Tower request table check it through
+/// [`Reporter::privacy_level`](crate::Reporter::privacy_level), and the
+/// client strips anything the level doesn't allow before sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyLevel {
+    /// Everything the application and collectors add.
+    #[default]
+    Full,
+    /// What's needed to act on the bug: no system info collectors, request
+    /// headers, [`extra`](Report::extra), or [`state`](Report::state).
+    Minimal,
+    /// Minimal, and no reporter or customer, with email addresses in the
+    /// title, body, and text attachments replaced by `[email]`.
+    Anonymous,
+}
+
+impl PrivacyLevel {
+    /// Whether collectors of optional context, such as system info, run.
+    pub fn collects_context(self) -> bool {
+        self == PrivacyLevel::Full
+    }
+
+    pub(crate) fn apply(self, report: &mut Report) {
+        if self >= PrivacyLevel::Minimal {
+            report.extra.clear();
+            report.state = None;
+        }
+        if self >= PrivacyLevel::Anonymous {
+            report.reporter = None;
+            report.customer = None;
+            scrub_emails(&mut report.title);
+            scrub_emails(&mut report.description);
+            for (_, data) in &mut report.attachments {
+                if let Ok(text) = std::str::from_utf8(data) {
+                    let mut text = text.to_string();
+                    scrub_emails(&mut text);
+                    *data = text.into_bytes();
+                }
+            }
+        }
+    }
+}
+
+fn scrub_emails(text: &mut String) {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || ".-".contains(c);
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    let mut changed = false;
+    while let Some(at) = rest.find('@') {
+        let start = rest[..at].rfind(|c| !is_local(c)).map_or(0, |i| i + 1);
+        let end = rest[at + 1..]
+            .find(|c| !is_domain(c))
+            .map_or(rest.len(), |i| at + 1 + i);
+        let domain = rest[at + 1..end].trim_end_matches('.');
+        let end = at + 1 + domain.len();
+        if start < at && domain.contains('.') {
+            out.push_str(&rest[..start]);
+            out.push_str("[email]");
+            changed = true;
+        } else {
+            out.push_str(&rest[..end]);
+        }
+        rest = &rest[end..];
+    }
+    if changed {
+        out.push_str(rest);
+        *text = out;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut report = Report::new();
+        report
+            .title("sync fails for jane.doe@acme.com")
+            .text("contact sam@example.org. See @mentions and user@localhost")
+            .reporter("Jane", "jane.doe@acme.com")
+            .customer_email("jane.doe@acme.com")
+            .extra("open_docs", &3)
+            .with_state(&serde_json::json!({ "theme": "dark" }));
+
+        let mut full = report.clone();
+        PrivacyLevel::Full.apply(&mut full);
+        assert_eq!(full.extra.len(), 1);
+
+        let mut minimal = report.clone();
+        PrivacyLevel::Minimal.apply(&mut minimal);
+        assert!(minimal.extra.is_empty() && minimal.state.is_none());
+        assert!(minimal.reporter.is_some());
+
+        PrivacyLevel::Anonymous.apply(&mut report);
+        assert!(report.reporter.is_none() && report.customer.is_none());
+        assert_eq!(report.title, "sync fails for [email]");
+        assert_eq!(
+            report.description,
+            "contact [email]. See @mentions and user@localhost"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Proxy protocol versions.
+//!
+//! Clients send `Hotline-Protocol: 3` with each report, and current proxies
+//! answer every request with the version they speak. Version 1 workers
+//! predate structured fields (severity, labels, reporter, customer, feedback)
+//! and version 2 workers predate links, and both silently drop what they
+//! don't know. So before sending those to a proxy we probe it once and fold
+//! the fields into the title and description if it is too old.
+
+use serde_json::Value;
+
+use crate::{Link, append_block, info_table, links_block};
+
+pub(crate) const HEADER: &str = "Hotline-Protocol";
+pub(crate) const VERSION: u8 = 3;
+
+/// Ask the proxy which protocol it speaks.
+///
+/// Proxies answer the CORS preflight `OPTIONS` without auth. A successful
+/// answer without the header comes from a v1 worker; if the probe fails we
+/// can't tell, so assume the current version.
+pub(crate) fn probe(agent: &ureq::Agent, url: &str) -> u8 {
+    match agent.request("OPTIONS", url).call() {
+        Ok(resp) => {
+            let version = resp
+                .header(HEADER)
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(1);
+            crate::drain(resp);
+            version
+        }
+        Err(_) => VERSION,
+    }
+}
+
+/// The oldest protocol version that keeps everything in the payload.
+pub(crate) fn required(payload: &Value) -> u8 {
+    if payload.get("links").is_some() {
+        3
+    } else if ["severity", "labels", "reporter", "customer", "kind"]
+        .iter()
+        .any(|key| payload.get(key).is_some())
+    {
+        2
+    } else {
+        1
+    }
+}
+
+/// Rewrite a payload so a proxy speaking `version` keeps its information.
+pub(crate) fn downgrade(payload: &mut Value, version: u8) {
+    let Some(fields) = payload.as_object_mut() else {
+        return;
+    };
+    let mut description = fields
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    if let Some(Value::Array(links)) = fields.remove("links") {
+        let links: Vec<Link> = links
+            .iter()
+            .filter_map(|link| {
+                Some(Link {
+                    url: link["url"].as_str()?.to_string(),
+                    title: link["title"].as_str()?.to_string(),
+                    subtitle: link["subtitle"].as_str().map(str::to_string),
+                })
+            })
+            .collect();
+        append_block(&mut description, &links_block(&links));
+    }
+    if version >= 2 {
+        fields.insert("description".into(), description.into());
+        return;
+    }
+
+    if fields.remove("kind").is_some_and(|kind| kind == "feedback")
+        && let Some(Value::String(title)) = fields.get_mut("title")
+    {
+        title.insert_str(0, "Feedback: ");
+    }
+
+    let severity = fields.remove("severity");
+    let labels = fields.remove("labels");
+    let customer = fields.remove("customer");
+    let mut rows = Vec::new();
+    if let Some(severity) = severity.as_ref().and_then(Value::as_str) {
+        rows.push(("Severity", severity.to_string()));
+    }
+    if let Some(labels) = labels.as_ref().and_then(Value::as_array) {
+        let labels: Vec<&str> = labels.iter().filter_map(Value::as_str).collect();
+        rows.push(("Labels", labels.join(", ")));
+    }
+    if let Some(customer) = &customer {
+        let id = customer["externalId"]
+            .as_str()
+            .or(customer["email"].as_str());
+        rows.push(("Customer", id.unwrap_or_default().to_string()));
+    }
+    if !rows.is_empty() {
+        let rows: Vec<(&str, &str)> = rows.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        append_block(&mut description, &info_table("Triage", &rows));
+    }
+
+    if let Some(reporter) = fields.remove("reporter") {
+        let name = reporter["name"].as_str().unwrap_or_default();
+        let email = reporter["email"].as_str().unwrap_or_default();
+        let contact = match (name.is_empty(), email.is_empty()) {
+            (false, false) => format!("{name} <{email}>"),
+            (false, true) => name.to_string(),
+            (true, false) => email.to_string(),
+            (true, true) => String::new(),
+        };
+        if !contact.is_empty() {
+            append_block(&mut description, &format!("## Reported by\n\n{contact}"));
+        }
+    }
+
+    fields.insert("description".into(), description.into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downgrade() {
+        let mut payload = serde_json::json!({
+            "title": "love it",
+            "description": "details",
+            "severity": "feedback",
+            "labels": ["desktop", "ui"],
+            "kind": "feedback",
+            "reporter": { "name": "Jane", "email": "jane@example.com" },
+        });
+        assert_eq!(required(&payload), 2);
+        downgrade(&mut payload, 1);
+
+        assert_eq!(required(&payload), 1);
+        assert_eq!(payload["title"], "Feedback: love it");
+        assert_eq!(
+            payload["description"],
+            "details\n\n## Triage\n\n| Field | Value |\n|-------|-------|\n| Severity | feedback |\n| Labels | desktop, ui |\n\n## Reported by\n\nJane <jane@example.com>"
+        );
+    }
+
+    #[test]
+    fn test_downgrade_links() {
+        let mut payload = serde_json::json!({
+            "title": "crash",
+            "description": "details",
+            "severity": "crash",
+            "links": [
+                { "url": "https://ci.example.com/run/1", "title": "CI run" },
+                { "url": "https://grafana.example.com/d/x", "title": "Dashboard", "subtitle": "Last hour" },
+            ],
+        });
+        assert_eq!(required(&payload), 3);
+        downgrade(&mut payload, 2);
+
+        assert_eq!(required(&payload), 2);
+        assert_eq!(
+            payload["description"],
+            "details\n\n## Links\n\n- [CI run](https://ci.example.com/run/1)\n- [Dashboard](https://grafana.example.com/d/x): Last hour"
+        );
+    }
+}
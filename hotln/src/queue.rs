@@ -0,0 +1,214 @@
+//! In-memory retries for reports that hit a transient error.
+//!
+//! A bounded channel feeds one worker thread, which retries each report a few
+//! times with a short backoff. Only reports that still fail after that, or
+//! that arrive while the queue is full, reach the disk spool.
+
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::cancel::{self, Cancel};
+use crate::transport::Transport;
+use crate::{Error, Submission, telemetry};
+
+const ATTEMPTS: u32 = 3;
+const FIRST_BACKOFF: Duration = Duration::from_millis(250);
+
+#[derive(Debug)]
+pub(crate) struct RetryQueue {
+    jobs: SyncSender<Box<Job>>,
+}
+
+pub(crate) struct Job {
+    pub(crate) transport: Transport,
+    /// The submission's token, so a cancelled report isn't retried.
+    pub(crate) cancel: Option<Cancel>,
+    pub(crate) reference: String,
+    pub(crate) url: String,
+    pub(crate) token: Option<String>,
+    pub(crate) version: Arc<OnceLock<u8>>,
+    pub(crate) endpoint: &'static str,
+    pub(crate) payload: serde_json::Value,
+}
+
+impl RetryQueue {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let (jobs, rx) = mpsc::sync_channel::<Box<Job>>(capacity);
+        std::thread::Builder::new()
+            .name("hotline-retry".into())
+            .spawn(move || {
+                for job in rx {
+                    retry(*job);
+                }
+            })
+            .expect("failed to spawn hotline retry thread");
+        Self { jobs }
+    }
+
+    /// Queue a job, handing it back if the queue is full.
+    pub(crate) fn push(&self, job: Box<Job>) -> Result<(), Box<Job>> {
+        self.jobs.try_send(job).map_err(|e| match e {
+            TrySendError::Full(job) | TrySendError::Disconnected(job) => job,
+        })
+    }
+}
+
+fn retry(job: Job) {
+    let reference = job.reference.clone();
+    let on_result = job.transport.on_result.clone();
+    let result = deliver(job);
+    if let Some(OnResult(callback)) = on_result {
+        callback(&reference, result);
+    }
+}
+
+fn deliver(job: Job) -> Result<Submission, Error> {
+    let mut backoff = FIRST_BACKOFF;
+    let mut last = None;
+    for attempt in 1..=ATTEMPTS {
+        std::thread::sleep(backoff);
+        backoff *= 2;
+        telemetry::retry();
+        let result = cancel::scope(job.cancel.as_ref(), || {
+            job.transport.send(
+                job.url.clone(),
+                job.token.clone(),
+                job.version.clone(),
+                job.payload.clone(),
+            )
+        });
+        match result {
+            Ok(submission) => {
+                tracing::info!("report delivered on retry {attempt}: {}", submission.url());
+                return Ok(submission);
+            }
+            Err(e) if e.is_retryable() => {
+                tracing::debug!("retry {attempt} of report failed: {e}");
+                last = Some(e);
+            }
+            Err(e) => {
+                tracing::warn!("dropping queued report: {e}");
+                return Err(e);
+            }
+        }
+    }
+    let error = last.expect("at least one attempt");
+    match job.transport.spool_report(job.endpoint, job.payload) {
+        Ok(true) => Ok(Submission::Spooled(job.reference)),
+        Ok(false) => {
+            tracing::warn!("dropping report after {ATTEMPTS} retries");
+            Err(error)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Called with a queued report's reference and how it finally went.
+#[derive(Clone)]
+pub(crate) struct OnResult(pub(crate) Arc<ResultFn>);
+
+type ResultFn = dyn Fn(&str, Result<Submission, Error>) + Send + Sync;
+
+impl std::fmt::Debug for OnResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OnResult")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Spool;
+
+    fn job(server: &mockito::Server, transport: Transport) -> Job {
+        Job {
+            transport,
+            cancel: None,
+            reference: "R-TEST".into(),
+            url: format!("{}/linear", server.url()),
+            token: None,
+            version: Arc::new(OnceLock::from(crate::protocol::VERSION)),
+            endpoint: "linear",
+            payload: serde_json::json!({ "title": "crash" }),
+        }
+    }
+
+    #[test]
+    fn test_retry_then_success() {
+        let mut server = mockito::Server::new();
+        let down = server
+            .mock("POST", "/linear")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let up = server
+            .mock("POST", "/linear")
+            .with_body(serde_json::json!({ "url": "https://linear.app/x" }).to_string())
+            .create();
+
+        let result = deliver(job(&server, Transport::default()));
+
+        assert_eq!(
+            result.unwrap(),
+            Submission::Created("https://linear.app/x".into())
+        );
+        down.assert();
+        up.assert();
+    }
+
+    #[test]
+    fn test_retries_exhausted_into_spool() {
+        let mut server = mockito::Server::new();
+        let down = server
+            .mock("POST", "/linear")
+            .with_status(503)
+            .expect(ATTEMPTS as usize)
+            .create();
+        let spool = Spool::new(crate::spool::temp_dir("queue-spool"));
+        let mut transport = Transport::default();
+        transport.spool(spool.clone());
+
+        let result = deliver(job(&server, transport));
+
+        assert_eq!(result.unwrap(), Submission::Spooled("R-TEST".into()));
+        assert_eq!(spool.len(), 1);
+        down.assert();
+    }
+
+    #[test]
+    fn test_spool_write_failure() {
+        let mut server = mockito::Server::new();
+        server.mock("POST", "/linear").with_status(503).create();
+        // A spool directory under a regular file can't be created.
+        let dir = crate::spool::temp_dir("queue-spool-failure");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("file"), b"").unwrap();
+        let mut transport = Transport::default();
+        transport.spool(Spool::new(dir.join("file").join("spool")));
+
+        let result = deliver(job(&server, transport));
+
+        assert!(matches!(result, Err(Error::Spool(_))));
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/linear").expect(0).create();
+        let spool = Spool::new(crate::spool::temp_dir("queue-cancelled"));
+        let mut transport = Transport::default();
+        transport.spool(spool.clone());
+        let cancel = Cancel::default();
+        cancel.cancel();
+
+        let result = deliver(Job {
+            cancel: Some(cancel),
+            ..job(&server, transport)
+        });
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(spool.is_empty());
+        mock.assert();
+    }
+}
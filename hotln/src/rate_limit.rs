@@ -0,0 +1,74 @@
+//! Linear's rate-limit headers, which the proxy passes through.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Linear's rate limits as of the client's latest response, from
+/// [`LinearClient::rate_limit`](crate::LinearClient::rate_limit).
+///
+/// Linear limits both the number of requests and their total complexity per
+/// hour. Fields are `None` when the response didn't say.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RateLimitInfo {
+    pub requests_limit: Option<u64>,
+    pub requests_remaining: Option<u64>,
+    /// When the request allowance is next refilled.
+    pub requests_reset: Option<SystemTime>,
+    pub complexity_limit: Option<u64>,
+    pub complexity_remaining: Option<u64>,
+    pub complexity_reset: Option<SystemTime>,
+}
+
+impl RateLimitInfo {
+    /// Read the headers of a response, or `None` if it has none of them,
+    /// as with proxy routes that don't create issues.
+    pub(crate) fn from_response(resp: &ureq::Response) -> Option<Self> {
+        let number = |name| resp.header(name)?.trim().parse::<u64>().ok();
+        // Resets are milliseconds since the epoch.
+        let time = |name| Some(SystemTime::UNIX_EPOCH + Duration::from_millis(number(name)?));
+        let info = Self {
+            requests_limit: number("x-ratelimit-requests-limit"),
+            requests_remaining: number("x-ratelimit-requests-remaining"),
+            requests_reset: time("x-ratelimit-requests-reset"),
+            complexity_limit: number("x-ratelimit-complexity-limit"),
+            complexity_remaining: number("x-ratelimit-complexity-remaining"),
+            complexity_reset: time("x-ratelimit-complexity-reset"),
+        };
+        (info != Self::default()).then_some(info)
+    }
+}
+
+/// Agent middleware that keeps the latest [`RateLimitInfo`] from any
+/// response, errors included, so 429s are seen too.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tracker {
+    latest: Arc<Mutex<Option<RateLimitInfo>>>,
+}
+
+impl Tracker {
+    pub(crate) fn latest(&self) -> Option<RateLimitInfo> {
+        *self.latest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record(&self, resp: &ureq::Response) {
+        if let Some(info) = RateLimitInfo::from_response(resp) {
+            *self.latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(info);
+        }
+    }
+}
+
+impl ureq::Middleware for Tracker {
+    fn handle(
+        &self,
+        request: ureq::Request,
+        next: ureq::MiddlewareNext,
+    ) -> Result<ureq::Response, ureq::Error> {
+        let result = next.handle(request);
+        match &result {
+            Ok(resp) | Err(ureq::Error::Status(_, resp)) => self.record(resp),
+            Err(_) => {}
+        }
+        result
+    }
+}
@@ -0,0 +1,240 @@
+//! Per-key redaction of report contents before they leave the machine.
+//!
+//! Rules match keys in the report's tables (`| Key | Value |` rows, such as
+//! system info), in [`Report::extra`], and in [`Report::state`], at any
+//! depth. Keys match case-insensitively, and `*` sets the rule for keys that
+//! aren't listed, which turns the rules into an allowlist. The `hostname`
+//! rule also covers the machine's host name wherever it appears in the text.
+
+use std::collections::BTreeMap;
+
+use ring::digest::{SHA256, digest};
+
+use crate::{Report, scrub};
+
+/// What happens to a value whose key matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rule {
+    /// Send the value unchanged.
+    #[default]
+    Pass,
+    /// Leave the key and value out.
+    Drop,
+    /// Replace the value with a short SHA-256 hash, so reports from the same
+    /// machine or user can still be matched up.
+    Hash,
+}
+
+/// Redaction rules applied to every report a client sends.
+///
+/// ```
+/// use hotln::{RedactRule, Redaction};
+///
+/// let mut redaction = Redaction::new();
+/// redaction
+///     .rule("HOME", RedactRule::Drop)
+///     .rule("machine_name", RedactRule::Hash)
+///     .rule("GPU", RedactRule::Pass);
+/// ```
+///
+/// In a [`Config`](crate::Config) file:
+///
+/// ```toml
+/// [redact]
+/// HOME = "drop"
+/// machine_name = "hash"
+/// GPU = "pass"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Redaction {
+    rules: BTreeMap<String, Rule>,
+}
+
+impl Redaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `rule` to values under `key`, or to unlisted keys if `key` is `*`.
+    pub fn rule(&mut self, key: &str, rule: Rule) -> &mut Self {
+        self.rules.insert(key.to_string(), rule);
+        self
+    }
+
+    /// Drop or hash the machine's host name: in `hostname` keys, and
+    /// anywhere in the title, body, logs, and other text. Corporate host
+    /// names often name the employee or office.
+    ///
+    /// The same as `rule("hostname", rule)`.
+    pub fn hostname(&mut self, rule: Rule) -> &mut Self {
+        self.rule("hostname", rule)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn rule_for(&self, key: &str) -> Rule {
+        let key = key.trim();
+        self.rules
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .or_else(|| self.rules.get_key_value("*"))
+            .map_or(Rule::Pass, |(_, rule)| *rule)
+    }
+
+    /// A copy of `report` with the rules applied, and `host`, the machine's
+    /// host name, rewritten following the `hostname` rule.
+    pub(crate) fn apply(&self, report: &Report, host: Option<&str>) -> Report {
+        let mut report = report.clone();
+        if self.is_empty() {
+            return report;
+        }
+        report.description = self.redact_tables(&report.description);
+        let mut extra = serde_json::Value::Object(std::mem::take(&mut report.extra));
+        self.redact_json(&mut extra);
+        if let serde_json::Value::Object(extra) = extra {
+            report.extra = extra;
+        }
+        if let Some(state) = &mut report.state {
+            self.redact_json(state);
+        }
+        if let Some(host) = host {
+            self.redact_host(&mut report, host);
+        }
+        report
+    }
+
+    /// Rewrite `host` wherever it appears, following the `hostname` rule.
+    fn redact_host(&self, report: &mut Report, host: &str) {
+        let replacement = match self.rule_for("hostname") {
+            Rule::Pass => return,
+            Rule::Drop => "<host>".to_string(),
+            Rule::Hash => hash(host),
+        };
+        report.for_each_text(|text| scrub::host(text, host, &replacement));
+    }
+
+    fn redact_tables(&self, text: &str) -> String {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let mut out = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            // A table's header row is followed by its `|---|` separator.
+            let header = lines.get(i + 1).is_some_and(|next| next.starts_with("|-"));
+            let row = line
+                .strip_prefix("| ")
+                .and_then(|row| row.strip_suffix(" |"))
+                .and_then(|row| row.split_once(" | "))
+                .filter(|_| !header);
+            match row.map(|(key, value)| (key, value, self.rule_for(key))) {
+                Some((_, _, Rule::Drop)) => {}
+                Some((key, value, Rule::Hash)) => out.push(format!("| {key} | {} |", hash(value))),
+                _ => out.push(line.to_string()),
+            }
+        }
+        out.join("\n")
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                map.retain(|key, _| self.rule_for(key) != Rule::Drop);
+                for (key, value) in map.iter_mut() {
+                    if self.rule_for(key) == Rule::Hash {
+                        let text = match value.as_str() {
+                            Some(text) => text.to_string(),
+                            None => value.to_string(),
+                        };
+                        *value = hash(&text).into();
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.redact_json(v)),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn hash(value: &str) -> String {
+    let hash = digest(&SHA256, value.as_bytes());
+    let hex: String = hash.as_ref()[..6]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("sha256:{hex}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut redaction = Redaction::new();
+        redaction
+            .rule("HOME", Rule::Drop)
+            .rule("machine_name", Rule::Hash)
+            .rule("GPU", Rule::Pass);
+        let mut report = Report::new();
+        report
+            .text("## System\n\n| Field | Value |\n|-------|-------|\n| Home | /home/sam |\n| Machine_Name | sams-laptop |\n| GPU | RTX 4090 |")
+            .extra("home", &"/home/sam")
+            .with_state(&serde_json::json!({ "user": { "machine_name": "sams-laptop" } }));
+
+        let redacted = redaction.apply(&report, None);
+        let machine = hash("sams-laptop");
+        assert_eq!(
+            redacted.description,
+            format!(
+                "## System\n\n| Field | Value |\n|-------|-------|\n| Machine_Name | {machine} |\n| GPU | RTX 4090 |"
+            )
+        );
+        assert!(redacted.extra.is_empty());
+        assert_eq!(redacted.state.unwrap()["user"]["machine_name"], machine);
+
+        // With `*`, unlisted keys are dropped too.
+        report.text("| Locale | en-US |");
+        redaction.rule("*", Rule::Drop);
+        let redacted = redaction.apply(&report, None);
+        assert!(
+            redacted
+                .description
+                .trim_end()
+                .ends_with("| GPU | RTX 4090 |")
+        );
+        assert_eq!(redacted.state, Some(serde_json::json!({})));
+    }
+
+    #[test]
+    fn test_hostname() {
+        let mut report = Report::new();
+        report
+            .title("crash on build-07.corp.example.com")
+            .text("| Hostname | build-07.corp.example.com |\nssh sam@build-07: refused")
+            .extra("host", &"build-07");
+        let mut redaction = Redaction::new();
+
+        redaction.hostname(Rule::Hash);
+        let hashed = redaction.apply(&report, Some("build-07.corp.example.com"));
+        let host = hash("build-07.corp.example.com");
+        assert_eq!(hashed.title, format!("crash on {host}"));
+        assert!(
+            hashed
+                .description
+                .ends_with(&format!("ssh sam@{host}: refused"))
+        );
+        assert_eq!(hashed.extra["host"], host.as_str());
+
+        redaction.hostname(Rule::Drop);
+        let dropped = redaction.apply(&report, Some("build-07.corp.example.com"));
+        assert_eq!(dropped.title, "crash on <host>");
+        assert_eq!(dropped.description, "ssh sam@<host>: refused");
+
+        let passed = Redaction::new().apply(&report, Some("build-07.corp.example.com"));
+        assert_eq!(passed.title, report.title);
+    }
+}
@@ -0,0 +1,427 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::{Severity, SystemInfoSource, append_block, info_table, inline_file};
+
+/// The Linear customer an issue should be linked to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Customer {
+    /// Match the customer's external ID.
+    ExternalId(String),
+    /// Match the customer owning this email's domain.
+    Email(String),
+}
+
+/// An external page to attach to the issue, such as a CI run, a dashboard,
+/// or a session replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub url: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+}
+
+/// The contents of a bug report, independent of where it is filed.
+///
+/// Build one and hand it to any [`Reporter`](crate::Reporter). Backends
+/// ignore fields they can't represent (for example, GitHub has no customers).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Report {
+    pub title: String,
+    pub description: String,
+    pub attachments: Vec<(String, Vec<u8>)>,
+    /// Pages to attach to the issue. Linear shows them with the issue's
+    /// attachments; other backends list them in the body.
+    pub links: Vec<Link>,
+    pub severity: Option<Severity>,
+    pub labels: Vec<String>,
+    pub feedback: bool,
+    /// Name and email of the person who filed the report. Either may be empty.
+    pub reporter: Option<(String, String)>,
+    pub customer: Option<Customer>,
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// A snapshot of the application's state, shown as collapsible JSON.
+    pub state: Option<serde_json::Value>,
+    /// Backtraces of the process's other threads, shown collapsed. See
+    /// [`with_thread_dump`](Report::with_thread_dump).
+    pub threads: Option<String>,
+    /// A short code like `R-7F3K9`, generated locally and rendered into the
+    /// issue body. Show it to the user so support can find the issue even
+    /// if it was queued or spooled. Clear it to leave it out.
+    pub reference: String,
+    /// Groups reports of the same bug, such as a [`fingerprint`](crate::fingerprint)
+    /// of the backtrace. Reports without one are grouped by title.
+    pub fingerprint: Option<String>,
+    /// The module the report came from, such as `myapp::render`, for
+    /// [`Router::module`](crate::Router::module). The panic hook sets it from
+    /// the innermost application frame.
+    pub module: Option<String>,
+    /// Files added with [`file`](Report::file) that are longer than this
+    /// many lines are shown in a collapsed section. 30 by default.
+    pub collapse_lines: usize,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            title: "Untitled".to_string(),
+            description: String::new(),
+            attachments: Vec::new(),
+            links: Vec::new(),
+            severity: None,
+            labels: Vec::new(),
+            feedback: false,
+            reporter: None,
+            customer: None,
+            extra: serde_json::Map::new(),
+            state: None,
+            threads: None,
+            reference: reference_code(),
+            fingerprint: None,
+            module: None,
+            collapse_lines: crate::markdown::COLLAPSE_LINES,
+        }
+    }
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        append_block(&mut self.description, text);
+        self
+    }
+
+    /// Add a file's contents as a code block. Files longer than
+    /// [`collapse_above`](Report::collapse_above) lines, such as logs and
+    /// environment dumps, are collapsed under a summary with the file name
+    /// and line count, so the issue stays easy to scan.
+    pub fn file(&mut self, filename: &str, content: &str) -> &mut Self {
+        let block = inline_file(filename, content);
+        let lines = content.lines().count();
+        if lines > self.collapse_lines {
+            let summary = format!("{filename} ({lines} lines)");
+            append_block(
+                &mut self.description,
+                &crate::markdown::details(&summary, &block),
+            );
+        } else {
+            append_block(&mut self.description, &block);
+        }
+        self
+    }
+
+    /// Collapse files added after this that are longer than `lines`.
+    /// `usize::MAX` never collapses them.
+    pub fn collapse_above(&mut self, lines: usize) -> &mut Self {
+        self.collapse_lines = lines;
+        self
+    }
+
+    /// Attach a file to the issue. Minidumps are also
+    /// [summarized](crate::Minidump::summary) in the description, so the
+    /// crash can be triaged without opening the dump.
+    pub fn attachment(&mut self, filename: &str, data: &[u8]) -> &mut Self {
+        if let Some(dump) = crate::minidump::Minidump::read(data) {
+            append_block(&mut self.description, &dump.summary());
+        }
+        self.attachments.push((filename.to_string(), data.to_vec()));
+        self
+    }
+
+    /// Add a "System Info" table with the rows of `info`, leaving it out if
+    /// there are none.
+    pub fn system_info(&mut self, info: &(impl SystemInfoSource + ?Sized)) -> &mut Self {
+        let rows = info.collect();
+        if !rows.is_empty() {
+            let rows: Vec<(&str, &str)> = rows
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect();
+            append_block(&mut self.description, &info_table("System Info", &rows));
+        }
+        self
+    }
+
+    /// Attach the page at `url` to the issue, titled `title`.
+    pub fn link(&mut self, title: &str, url: &str) -> &mut Self {
+        self.links.push(Link {
+            url: url.to_string(),
+            title: title.to_string(),
+            subtitle: None,
+        });
+        self
+    }
+
+    /// Like [`link`](Self::link), with a subtitle Linear shows under the
+    /// title, such as when or where the page was captured.
+    pub fn link_with_subtitle(&mut self, title: &str, subtitle: &str, url: &str) -> &mut Self {
+        self.links.push(Link {
+            url: url.to_string(),
+            title: title.to_string(),
+            subtitle: Some(subtitle.to_string()),
+        });
+        self
+    }
+
+    /// Link the issue to the Linear customer with this external ID.
+    pub fn customer_id(&mut self, external_id: &str) -> &mut Self {
+        self.customer = Some(Customer::ExternalId(external_id.to_string()));
+        self
+    }
+
+    /// Link the issue to the Linear customer whose domain matches this email.
+    pub fn customer_email(&mut self, email: &str) -> &mut Self {
+        self.customer = Some(Customer::Email(email.to_string()));
+        self
+    }
+
+    /// Set the severity, which picks the issue's priority and label.
+    pub fn severity(&mut self, severity: Severity) -> &mut Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// Add a label by name. Linear skips unknown labels; GitHub creates them.
+    pub fn label(&mut self, name: &str) -> &mut Self {
+        self.labels.push(name.to_string());
+        self
+    }
+
+    /// File this as user feedback rather than a bug report.
+    ///
+    /// Appends `text` to the body and, unless a title was set, uses its first
+    /// line as the title. The proxy applies its feedback title prefix, labels,
+    /// and project instead of the bug report ones.
+    pub fn feedback(&mut self, text: &str) -> &mut Self {
+        self.feedback = true;
+        if self.title == "Untitled" {
+            self.title = feedback_title(text);
+        }
+        self.severity.get_or_insert(Severity::Feedback);
+        self.text(text)
+    }
+
+    /// Identify who filed the report so maintainers can follow up.
+    ///
+    /// Only set this after the user has explicitly agreed to be contacted.
+    /// Either value may be empty.
+    pub fn reporter(&mut self, name: &str, email: &str) -> &mut Self {
+        self.reporter = Some((name.to_string(), email.to_string()));
+        self
+    }
+
+    /// Attach a snapshot of application state, such as settings, feature
+    /// flags, or the number of open documents.
+    ///
+    /// The value is rendered as pretty-printed JSON in a collapsed "App
+    /// state" section at the end of the issue. Calling this again replaces
+    /// the snapshot.
+    pub fn with_state(&mut self, state: &impl serde::Serialize) -> &mut Self {
+        match serde_json::to_value(state) {
+            Ok(state) => self.state = Some(state),
+            Err(e) => tracing::warn!("skipping app state: {e}"),
+        }
+        self
+    }
+
+    /// Attach backtraces of every other thread, taken now, in a collapsed
+    /// "Threads" section. For hangs and deadlocks, where the stuck thread is
+    /// rarely the one reporting. Linux only; elsewhere nothing is attached.
+    #[cfg(feature = "thread-dump")]
+    pub fn with_thread_dump(&mut self) -> &mut Self {
+        self.threads = crate::threads::thread_dump();
+        self
+    }
+
+    /// Add a "Tokio Runtime" table with the current runtime's flavor, worker
+    /// count, alive tasks, and queue depths, plus the blocking pool when
+    /// built with `--cfg tokio_unstable`. Does nothing outside a runtime.
+    #[cfg(feature = "tokio")]
+    pub fn with_tokio_runtime(&mut self) -> &mut Self {
+        if let Some(rows) = crate::runtime::runtime_info() {
+            let rows: Vec<(&str, &str)> =
+                rows.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            self.text(&crate::info_table("Tokio Runtime", &rows));
+        }
+        self
+    }
+
+    /// Attach a trace of every task in the current runtime as
+    /// `tokio-tasks.txt`. Needs the `tokio-taskdump` feature and
+    /// `--cfg tokio_unstable`, on x86, x86-64, or AArch64 Linux.
+    #[cfg(all(
+        feature = "tokio-taskdump",
+        tokio_unstable,
+        target_os = "linux",
+        any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")
+    ))]
+    pub async fn with_tokio_task_dump(&mut self) -> &mut Self {
+        if let Some(dump) = crate::runtime::task_dump().await {
+            self.file("tokio-tasks.txt", &dump);
+        }
+        self
+    }
+
+    /// Say which module the report came from, such as `module_path!()`.
+    pub fn module(&mut self, path: &str) -> &mut Self {
+        self.module = Some(path.to_string());
+        self
+    }
+
+    /// Group this report with others that have the same `key`.
+    pub fn fingerprint(&mut self, key: &str) -> &mut Self {
+        self.fingerprint = Some(key.to_string());
+        self
+    }
+
+    /// Attach structured context under `key`, like Sentry's `extra`.
+    ///
+    /// A one-line preview of each value is rendered into an "Extra" table in
+    /// the description, and the full values are sent as `extra.json`.
+    pub fn extra(&mut self, key: &str, value: &impl serde::Serialize) -> &mut Self {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.extra.insert(key.to_string(), value);
+            }
+            Err(e) => tracing::warn!("skipping extra context {key}: {e}"),
+        }
+        self
+    }
+
+    /// Call `f` on each piece of text that ends up in the issue: the title,
+    /// body, thread dump, links, text attachments, and extra and state
+    /// strings.
+    pub(crate) fn for_each_text(&mut self, mut f: impl FnMut(&mut String)) {
+        f(&mut self.title);
+        f(&mut self.description);
+        if let Some(threads) = &mut self.threads {
+            f(threads);
+        }
+        for link in &mut self.links {
+            f(&mut link.url);
+            f(&mut link.title);
+            if let Some(subtitle) = &mut link.subtitle {
+                f(subtitle);
+            }
+        }
+        for (_, data) in &mut self.attachments {
+            if let Ok(text) = std::str::from_utf8(data) {
+                let mut text = text.to_string();
+                f(&mut text);
+                *data = text.into_bytes();
+            }
+        }
+        for value in self.extra.values_mut().chain(self.state.as_mut()) {
+            walk_strings(value, &mut f);
+        }
+    }
+}
+
+fn walk_strings(value: &mut serde_json::Value, f: &mut impl FnMut(&mut String)) {
+    match value {
+        serde_json::Value::String(s) => f(s),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|v| walk_strings(v, f)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|v| walk_strings(v, f)),
+        _ => {}
+    }
+}
+
+/// `R-` and five Crockford base32 digits, which skip I, L, O, and U so a code
+/// read out over the phone can't be misheard.
+fn reference_code() -> String {
+    const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+    let mut bytes = [0u8; 5];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (nanos >> (i * 5)) as u8;
+        }
+    }
+    let digits: String = bytes
+        .iter()
+        .map(|byte| ALPHABET[usize::from(byte % 32)] as char)
+        .collect();
+    format!("R-{digits}")
+}
+
+fn feedback_title(text: &str) -> String {
+    const MAX_CHARS: usize = 80;
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() <= MAX_CHARS {
+        return line.to_string();
+    }
+    let truncated: String = line.chars().take(MAX_CHARS - 3).collect();
+    format!("{}...", truncated.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_code() {
+        let code = reference_code();
+        assert_eq!(code.len(), 7);
+        assert!(code.starts_with("R-"));
+        assert!(!code.contains(['I', 'L', 'O', 'U']));
+        assert_ne!(Report::new().reference, Report::new().reference);
+    }
+
+    #[test]
+    fn test_feedback_title() {
+        assert_eq!(
+            feedback_title("love the new editor\nbut dark mode is off"),
+            "love the new editor"
+        );
+        let long = "a".repeat(100);
+        let title = feedback_title(&long);
+        assert_eq!(title.chars().count(), 80);
+        assert!(title.ends_with("..."));
+    }
+
+    #[test]
+    fn test_feedback_keeps_explicit_title_and_severity() {
+        let mut report = Report::new();
+        report
+            .title("Editor")
+            .severity(Severity::Bug)
+            .feedback("love it");
+        assert_eq!(report.title, "Editor");
+        assert_eq!(report.severity, Some(Severity::Bug));
+        assert!(report.feedback);
+        assert_eq!(report.description, "love it");
+    }
+
+    #[test]
+    fn test_file_collapses_long_content() {
+        let log = (1..=40).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let mut report = Report::new();
+        report
+            .file("short.txt", "one")
+            .file("app.log", &log.join("\n"))
+            .collapse_above(usize::MAX)
+            .file("all.log", &log.join("\n"));
+        assert!(
+            report
+                .description
+                .starts_with("**short.txt**\n```text\none\n```")
+        );
+        assert!(report.description.contains(
+            "<details>\n<summary>app.log (40 lines)</summary>\n\n**app.log**\n```log\nline 1\n"
+        ));
+        assert_eq!(report.description.matches("<details>").count(), 1);
+    }
+}
@@ -0,0 +1,256 @@
+//! Sending reports to different destinations from one reporter.
+//!
+//! ```
+//! use hotln::{LinearClient, Router, Severity};
+//!
+//! let engineering = LinearClient::new("https://eng-proxy.example.com");
+//! let product = LinearClient::new("https://product-proxy.example.com");
+//! let graphics = LinearClient::new("https://gfx-proxy.example.com");
+//!
+//! let mut router = Router::new(engineering);
+//! router
+//!     .severity(Severity::Feedback, product)
+//!     .module("myapp::render", graphics);
+//! hotln::PanicHook::new(router).install();
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{Error, PrivacyLevel, Report, Reporter, Severity, Submission};
+
+/// Where a [`Router`] sends matching reports: a client of its own.
+pub type Destination = Box<dyn Reporter + Send + Sync>;
+
+/// Which reports a [`Router`] rule or [`PanicHook`](crate::PanicHook)
+/// override applies to.
+pub enum Matcher {
+    /// Reports with this label.
+    Label(String),
+    /// Reports of this severity.
+    Severity(Severity),
+    /// Reports from this module or its submodules, such as `myapp::plugins`.
+    /// See [`Report::module`].
+    Module(String),
+    /// Reports whose title contains this text. Panic titles are
+    /// `panic: <message>`.
+    Title(String),
+    /// Reports the function returns true for.
+    Custom(Box<dyn Fn(&Report) -> bool + Send + Sync>),
+}
+
+impl Matcher {
+    pub fn matches(&self, report: &Report) -> bool {
+        match self {
+            Matcher::Label(label) => report.labels.contains(label),
+            Matcher::Severity(severity) => report.severity == Some(*severity),
+            Matcher::Module(prefix) => {
+                let prefix = prefix.trim_end_matches("::");
+                report.module.as_deref().is_some_and(|module| {
+                    module
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+                })
+            }
+            Matcher::Title(text) => report.title.contains(text.as_str()),
+            Matcher::Custom(rule) => rule(report),
+        }
+    }
+}
+
+/// A [`Reporter`] that hands each report to the first destination whose
+/// rule matches, or to the fallback when none does.
+///
+/// Each destination is a client of its own, usually a proxy configured for
+/// a different Linear team and project or GitHub repository. Rules are
+/// checked in the order they were added. Comments go to the destination
+/// that filed the issue.
+pub struct Router {
+    routes: Vec<(Matcher, Destination)>,
+    fallback: Destination,
+    /// Which route filed each issue or comment URL; `None` for the
+    /// fallback.
+    filed: Mutex<HashMap<String, Option<usize>>>,
+}
+
+impl Router {
+    /// Route reports no rule matches to `fallback`.
+    pub fn new(fallback: impl Reporter + Send + Sync + 'static) -> Self {
+        Self {
+            routes: Vec::new(),
+            fallback: Box::new(fallback),
+            filed: Mutex::default(),
+        }
+    }
+
+    /// Send reports `matcher` matches to `destination`.
+    pub fn rule(
+        &mut self,
+        matcher: Matcher,
+        destination: impl Reporter + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.routes.push((matcher, Box::new(destination)));
+        self
+    }
+
+    /// Add rules in bulk, checked after the ones already added.
+    pub fn overrides(&mut self, overrides: Vec<(Matcher, Destination)>) -> &mut Self {
+        self.routes.extend(overrides);
+        self
+    }
+
+    /// Send reports `rule` returns true for to `destination`.
+    pub fn route(
+        &mut self,
+        rule: impl Fn(&Report) -> bool + Send + Sync + 'static,
+        destination: impl Reporter + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.rule(Matcher::Custom(Box::new(rule)), destination)
+    }
+
+    /// Send reports with this label to `destination`.
+    pub fn label(
+        &mut self,
+        label: &str,
+        destination: impl Reporter + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.rule(Matcher::Label(label.to_string()), destination)
+    }
+
+    /// Send reports of this severity to `destination`.
+    pub fn severity(
+        &mut self,
+        severity: Severity,
+        destination: impl Reporter + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.rule(Matcher::Severity(severity), destination)
+    }
+
+    /// Send reports from `prefix` or its submodules, such as `myapp::render`,
+    /// to `destination`. See [`Report::module`].
+    pub fn module(
+        &mut self,
+        prefix: &str,
+        destination: impl Reporter + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.rule(Matcher::Module(prefix.to_string()), destination)
+    }
+
+    fn matching_route(&self, report: &Report) -> Option<usize> {
+        self.routes
+            .iter()
+            .position(|(matcher, _)| matcher.matches(report))
+    }
+
+    fn destination(&self, route: Option<usize>) -> &Destination {
+        route.map_or(&self.fallback, |i| &self.routes[i].1)
+    }
+}
+
+impl Reporter for Router {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        self.submit(report).map(Submission::into_url)
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        let route = self.matching_route(report);
+        let submission = self.destination(route).submit(report)?;
+        if !submission.url().is_empty()
+            && let Ok(mut filed) = self.filed.lock()
+        {
+            filed.insert(submission.url().to_string(), route);
+        }
+        Ok(submission)
+    }
+
+    /// Comment through the destination that filed `issue`, or the fallback
+    /// for issues this router didn't file. Comments are updated through the
+    /// same destination.
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        let route = self
+            .filed
+            .lock()
+            .ok()
+            .and_then(|filed| filed.get(issue).copied())
+            .flatten();
+        let comment = self.destination(route).comment(issue, body)?;
+        if let Ok(mut filed) = self.filed.lock() {
+            filed.insert(comment.clone(), route);
+        }
+        Ok(comment)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        let route = self
+            .filed
+            .lock()
+            .ok()
+            .and_then(|filed| filed.get(comment).copied())
+            .flatten();
+        self.destination(route).update_comment(comment, body)
+    }
+
+    fn spools(&self, report: &Report) -> bool {
+        self.destination(self.matching_route(report)).spools(report)
+    }
+
+    /// The strictest level of any destination, since collectors run before
+    /// the destination is known.
+    fn privacy_level(&self) -> PrivacyLevel {
+        self.routes
+            .iter()
+            .map(|(_, destination)| destination.privacy_level())
+            .fold(self.fallback.privacy_level(), PrivacyLevel::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_routes() {
+        let fallback = Arc::new(MockReporter::new());
+        let product = Arc::new(MockReporter::new());
+        let graphics = Arc::new(MockReporter::new());
+        let mut router = Router::new(fallback.clone());
+        router
+            .severity(Severity::Feedback, product.clone())
+            .label("ux", product.clone())
+            .module("myapp::render", graphics.clone())
+            .overrides(vec![(
+                Matcher::Title("plugin".into()),
+                Box::new(graphics.clone()),
+            )]);
+
+        router
+            .report(Report::new().title("idea").severity(Severity::Feedback))
+            .unwrap();
+        router
+            .report(Report::new().title("ugly").label("ux"))
+            .unwrap();
+        router
+            .report(
+                Report::new()
+                    .title("flicker")
+                    .module("myapp::render::shadows"),
+            )
+            .unwrap();
+        router
+            .report(Report::new().title("crash").module("myapp::renderer"))
+            .unwrap();
+        router
+            .report(Report::new().title("panic: plugin failed"))
+            .unwrap();
+
+        let titles = |mock: &MockReporter| -> Vec<String> {
+            mock.reports().into_iter().map(|r| r.title).collect()
+        };
+        assert_eq!(titles(&product), ["idea", "ugly"]);
+        assert_eq!(titles(&graphics), ["flicker", "panic: plugin failed"]);
+        assert_eq!(titles(&fallback), ["crash"]);
+    }
+}
@@ -0,0 +1,109 @@
+//! Scheduler context from the Tokio runtime a report is filed from.
+//!
+//! Reports about hangs in async apps rarely say whether the runtime was
+//! starved, so this records its size and queue depths. With
+//! `RUSTFLAGS="--cfg tokio_unstable"`, the blocking pool and per-worker
+//! queues are included, and with the `tokio-taskdump` feature as well, a
+//! trace of every task can be attached on Linux.
+
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Rows describing the current runtime, or `None` outside one.
+pub(crate) fn runtime_info() -> Option<Vec<(String, String)>> {
+    let handle = Handle::try_current().ok()?;
+    let metrics = handle.metrics();
+    let flavor = match handle.runtime_flavor() {
+        RuntimeFlavor::CurrentThread => "current thread",
+        RuntimeFlavor::MultiThread => "multi-thread",
+        _ => "other",
+    };
+    #[cfg_attr(not(tokio_unstable), allow(unused_mut))]
+    let mut rows = vec![
+        ("Flavor".to_string(), flavor.to_string()),
+        ("Workers".to_string(), metrics.num_workers().to_string()),
+        (
+            "Alive Tasks".to_string(),
+            metrics.num_alive_tasks().to_string(),
+        ),
+        (
+            "Global Queue".to_string(),
+            metrics.global_queue_depth().to_string(),
+        ),
+    ];
+    #[cfg(tokio_unstable)]
+    {
+        let local: Vec<String> = (0..metrics.num_workers())
+            .map(|worker| metrics.worker_local_queue_depth(worker).to_string())
+            .collect();
+        rows.push(("Local Queues".to_string(), local.join(", ")));
+        rows.push((
+            "Blocking Threads".to_string(),
+            format!(
+                "{} ({} idle)",
+                metrics.num_blocking_threads(),
+                metrics.num_idle_blocking_threads()
+            ),
+        ));
+        rows.push((
+            "Blocking Queue".to_string(),
+            metrics.blocking_queue_depth().to_string(),
+        ));
+    }
+    Some(rows)
+}
+
+/// A trace of every task in the current runtime, or `None` outside one.
+#[cfg(all(
+    feature = "tokio-taskdump",
+    tokio_unstable,
+    target_os = "linux",
+    any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")
+))]
+pub(crate) async fn task_dump() -> Option<String> {
+    use std::fmt::Write;
+
+    let handle = Handle::try_current().ok()?;
+    let dump = handle.dump().await;
+    let mut out = String::new();
+    for (i, task) in dump.tasks().iter().enumerate() {
+        let _ = writeln!(out, "Task {i}:\n{}\n", task.trace());
+    }
+    Some(out.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_info() {
+        assert_eq!(runtime_info(), None);
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let rows = runtime.block_on(async { runtime_info() }).unwrap();
+        assert_eq!(
+            rows[0],
+            ("Flavor".to_string(), "current thread".to_string())
+        );
+        assert_eq!(rows[1], ("Workers".to_string(), "1".to_string()));
+    }
+    #[cfg(all(
+        feature = "tokio-taskdump",
+        tokio_unstable,
+        target_os = "linux",
+        any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")
+    ))]
+    #[test]
+    fn test_task_dump() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let dump = runtime.block_on(async {
+            tokio::spawn(std::future::pending::<()>());
+            task_dump().await.unwrap()
+        });
+        assert!(dump.starts_with("Task 0:"), "{dump}");
+    }
+}
@@ -0,0 +1,198 @@
+//! Rewriting the user's home directory, login name, and host name out of
+//! reports.
+//!
+//! Paths in backtraces, logs, and error messages are the most common way a
+//! crash report names the person who hit it: `/home/sam/.config/app` becomes
+//! `~/.config/app`, and other mentions of `sam` become `<user>`. Host names
+//! are only rewritten when a [`Redaction`](crate::Redaction) asks for it.
+
+use crate::Report;
+
+/// Login names too generic to be personal, or common enough as words that
+/// rewriting them would mangle reports.
+const GENERIC_USERS: &[&str] = &["root", "admin", "administrator", "user", "guest"];
+
+/// The current user's home directory and login name.
+#[derive(Debug)]
+pub(crate) struct User {
+    home: Option<String>,
+    name: Option<String>,
+}
+
+impl User {
+    pub(crate) fn current() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    /// The user described by environment variables, read with `var`.
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| var(name).filter(|v| !v.is_empty());
+        Self::new(
+            var("HOME").or_else(|| var("USERPROFILE")),
+            var("USER")
+                .or_else(|| var("USERNAME"))
+                .or_else(|| var("LOGNAME")),
+        )
+    }
+
+    fn new(home: Option<String>, name: Option<String>) -> Self {
+        let home = home
+            .map(|home| home.trim_end_matches(['/', '\\']).to_string())
+            // `/` as a home directory would rewrite every absolute path.
+            .filter(|home| home.len() > 3);
+        let name = name.filter(|name| {
+            name.len() >= 3 && !GENERIC_USERS.contains(&name.to_ascii_lowercase().as_str())
+        });
+        Self { home, name }
+    }
+
+    /// Replace the home directory with `~` and the login name with `<user>`.
+    pub(crate) fn scrub(&self, text: &mut String) {
+        if let Some(home) = &self.home {
+            // Windows paths also show up with forward slashes, and with
+            // doubled backslashes in JSON and debug output.
+            let mut variants = vec![home.clone()];
+            if home.contains('\\') {
+                variants.push(home.replace('\\', "/"));
+                variants.push(home.replace('\\', "\\\\"));
+            }
+            for variant in &variants {
+                replace_word(text, variant, "~", |c| c == '-');
+            }
+        }
+        if let Some(name) = &self.name {
+            replace_word(text, name, "<user>", |_| false);
+        }
+    }
+}
+
+/// Scrub `report` for the current user.
+pub(crate) fn user_paths(report: &mut Report) {
+    let user = User::current();
+    if user.home.is_some() || user.name.is_some() {
+        report.for_each_text(|text| user.scrub(text));
+    }
+}
+
+/// The machine's host name, unless it is the uninformative `localhost`.
+#[cfg(unix)]
+pub(crate) fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    let host = String::from_utf8_lossy(&buf[..len]).into_owned();
+    (!host.is_empty() && !host.starts_with("localhost")).then_some(host)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME")
+        .ok()
+        .filter(|host| !host.is_empty())
+}
+
+/// Replace `host`, and its first label if it is qualified, with
+/// `replacement`.
+pub(crate) fn host(text: &mut String, host: &str, replacement: &str) {
+    replace_word(text, host, replacement, |c| c == '-');
+    if let Some((short, _)) = host.split_once('.')
+        && short.len() >= 3
+    {
+        replace_word(text, short, replacement, |c| c == '-');
+    }
+}
+
+/// Replace ASCII-case-insensitive occurrences of `needle` that aren't part
+/// of a longer name. Letters, digits, `_`, and anything `joins` accepts
+/// continue a name.
+fn replace_word(text: &mut String, needle: &str, replacement: &str, joins: fn(char) -> bool) {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || joins(c);
+    let lower = text.to_ascii_lowercase();
+    let needle_lower = needle.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, _) in lower.match_indices(&needle_lower) {
+        let end = start + needle.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        // A needle that starts or ends with a separator, like `C:\Users\sam`,
+        // brings its own boundary on that side.
+        let starts_name = needle.starts_with(is_name);
+        let ends_name = needle.ends_with(is_name);
+        if (starts_name && before.is_some_and(is_name)) || (ends_name && after.is_some_and(is_name))
+        {
+            continue;
+        }
+        out.push_str(&text[last..start]);
+        out.push_str(replacement);
+        last = end;
+    }
+    if last > 0 {
+        out.push_str(&text[last..]);
+        *text = out;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub() {
+        let user = User::new(Some("/home/sam/".into()), Some("sam".into()));
+        let mut text = "open /home/sam/.config/app.toml failed for sam@host; \
+                        /home/samuel and sample kept, /Users/Sam/x rewritten"
+            .to_string();
+        user.scrub(&mut text);
+        assert_eq!(
+            text,
+            "open ~/.config/app.toml failed for <user>@host; \
+             /home/samuel and sample kept, /Users/<user>/x rewritten"
+        );
+    }
+
+    #[test]
+    fn test_scrub_windows() {
+        let user = User::new(Some(r"C:\Users\Sam".into()), Some("Sam".into()));
+        let mut text =
+            r#"at C:\Users\Sam\app\main.rs, "C:\\Users\\Sam\\log", C:/Users/sam/x"#.to_string();
+        user.scrub(&mut text);
+        assert_eq!(text, r#"at ~\app\main.rs, "~\\log", ~/x"#);
+    }
+
+    #[test]
+    fn test_from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let user = User::from_env(env(&[("HOME", "/home/sam"), ("USER", "sam")]));
+        assert_eq!(user.home.as_deref(), Some("/home/sam"));
+        assert_eq!(user.name.as_deref(), Some("sam"));
+
+        // Windows names, and empty variables skipped.
+        let user = User::from_env(env(&[
+            ("HOME", ""),
+            ("USERPROFILE", r"C:\Users\Sam"),
+            ("USERNAME", "Sam"),
+        ]));
+        assert_eq!(user.home.as_deref(), Some(r"C:\Users\Sam"));
+        assert_eq!(user.name.as_deref(), Some("Sam"));
+
+        let user = User::from_env(env(&[]));
+        assert!(user.home.is_none() && user.name.is_none());
+    }
+
+    #[test]
+    fn test_generic_names_kept() {
+        let user = User::new(Some("/root".into()), Some("root".into()));
+        let mut text = "root cause in /root/app".to_string();
+        user.scrub(&mut text);
+        assert_eq!(text, "root cause in ~/app");
+    }
+}
@@ -0,0 +1,246 @@
+//! Posting a summary of each report to a Slack channel.
+
+use std::time::Duration;
+
+use crate::prepare::{Pipeline, prepare};
+use crate::router::Destination;
+use crate::transport::Transport;
+use crate::{
+    Error, PrivacyLevel, Redaction, Report, Reporter, Secrets, Severity, Submission, post_webhook,
+};
+
+/// Posts a one-line summary of each report to a Slack incoming webhook.
+///
+/// On its own, the sink only notifies the channel, and
+/// [`report`](Reporter::report) returns the report's
+/// [`reference`](Report::reference). Chained [`after`](Self::after) another
+/// reporter, it files the report there first and links the summary to the
+/// new issue:
+///
+/// ```no_run
+/// let linear = hotln::LinearClient::new("https://your-proxy.example.com");
+/// let mut slack = hotln::SlackSink::new("https://hooks.slack.com/services/T000/B000/XXXX");
+/// slack.after(linear);
+/// hotln::PanicHook::new(slack).install();
+/// ```
+pub struct SlackSink {
+    webhook: String,
+    inner: Option<Destination>,
+    pipeline: Pipeline,
+    transport: Transport,
+}
+
+impl SlackSink {
+    pub fn new(webhook_url: &str) -> Self {
+        Self {
+            webhook: webhook_url.to_string(),
+            inner: None,
+            pipeline: Pipeline::default(),
+            transport: Transport::default(),
+        }
+    }
+
+    /// File reports with `reporter` first, then post a summary linking to
+    /// the issue. Its result is returned even if posting to Slack fails, and
+    /// reports it deduplicates into an existing issue aren't posted again.
+    pub fn after(&mut self, reporter: impl Reporter + Send + Sync + 'static) -> &mut Self {
+        self.inner = Some(Box::new(reporter));
+        self
+    }
+
+    /// Give up on posting after `deadline`.
+    pub fn with_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.transport.deadline(deadline);
+        self
+    }
+
+    /// Route requests through a SOCKS proxy, such as `socks5://127.0.0.1:9050`.
+    pub fn socks_proxy(&mut self, url: &str) -> &mut Self {
+        self.transport.socks_proxy(url);
+        self
+    }
+
+    /// Apply these redaction rules to the summary before it is posted.
+    pub fn redact(&mut self, redaction: Redaction) -> &mut Self {
+        self.pipeline.redaction = redaction;
+        self
+    }
+
+    /// What to do with titles that seem to contain credentials. By default
+    /// they are replaced with `[REDACTED]`.
+    pub fn secrets(&mut self, policy: Secrets) -> &mut Self {
+        self.pipeline.secrets = policy;
+        self
+    }
+
+    /// Strip context from reports down to `level` before posting them.
+    pub fn with_privacy_level(&mut self, level: PrivacyLevel) -> &mut Self {
+        self.pipeline.privacy_level = level;
+        self
+    }
+
+    fn notify(&self, report: &Report, url: &str) -> Result<(), Error> {
+        let report = prepare(report, &self.pipeline)?;
+        post_webhook(
+            self.transport.agent()?,
+            &self.webhook,
+            &summary(&report, url),
+        )
+    }
+}
+
+impl Reporter for SlackSink {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        Ok(self.submit(report)?.into_url())
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        let Some(inner) = &self.inner else {
+            self.notify(report, "")?;
+            return Ok(Submission::Created(report.reference.clone()));
+        };
+        let submission = inner.submit(report)?;
+        if !matches!(submission, Submission::Deduplicated(_))
+            && let Err(e) = self.notify(report, submission.url())
+        {
+            tracing::warn!("Failed to post report to Slack: {e}");
+        }
+        Ok(submission)
+    }
+
+    /// Comment through the chained reporter.
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        match &self.inner {
+            Some(inner) => inner.comment(issue, body),
+            None => Err(Error::Config("no reporter to comment with".into())),
+        }
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        match &self.inner {
+            Some(inner) => inner.update_comment(comment, body),
+            None => Err(Error::Config("no reporter to comment with".into())),
+        }
+    }
+
+    /// The stricter of the sink's level and the chained reporter's.
+    fn privacy_level(&self) -> PrivacyLevel {
+        match &self.inner {
+            Some(inner) => inner.privacy_level().max(self.pipeline.privacy_level),
+            None => self.pipeline.privacy_level,
+        }
+    }
+}
+
+/// A Block Kit message: the title, linked to the issue when there is one,
+/// and a line with the severity, labels, and reference.
+fn summary(report: &Report, url: &str) -> serde_json::Value {
+    let emoji = match report.severity {
+        Some(Severity::Crash) => ":rotating_light:",
+        Some(Severity::Bug) => ":beetle:",
+        Some(Severity::Degraded) => ":warning:",
+        Some(Severity::Feedback) => ":speech_balloon:",
+        None => ":memo:",
+    };
+    let title = escape(if report.title.is_empty() {
+        "Untitled report"
+    } else {
+        &report.title
+    });
+    let headline = if url.is_empty() {
+        format!("{emoji} *{title}*")
+    } else {
+        format!("{emoji} *<{url}|{title}>*")
+    };
+
+    let mut details = Vec::new();
+    if let Some(severity) = report.severity {
+        details.push(format!("*Severity:* {}", severity.as_str()));
+    }
+    if !report.labels.is_empty() {
+        details.push(format!("*Labels:* {}", escape(&report.labels.join(", "))));
+    }
+    if !report.reference.is_empty() {
+        details.push(format!("*Ref:* `{}`", escape(&report.reference)));
+    }
+    let mut blocks = vec![serde_json::json!({
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": headline },
+    })];
+    if !details.is_empty() {
+        blocks.push(serde_json::json!({
+            "type": "context",
+            "elements": [{ "type": "mrkdwn", "text": details.join("  ·  ") }],
+        }));
+    }
+    serde_json::json!({
+        "text": format!("{emoji} {title}"),
+        "blocks": blocks,
+    })
+}
+
+/// Slack treats `<`, `>`, and `&` as markup even in plain text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_summary() {
+        let mut report = Report::new();
+        report
+            .title("crash in <Renderer>")
+            .severity(Severity::Crash)
+            .label("gpu");
+        report.reference = "HL-7K3Q".into();
+        let summary = summary(&report, "https://linear.app/acme/issue/ENG-1");
+
+        assert_eq!(
+            summary["blocks"][0]["text"]["text"],
+            ":rotating_light: *<https://linear.app/acme/issue/ENG-1|crash in &lt;Renderer&gt;>*"
+        );
+        assert_eq!(
+            summary["blocks"][1]["elements"][0]["text"],
+            "*Severity:* crash  ·  *Labels:* gpu  ·  *Ref:* `HL-7K3Q`"
+        );
+    }
+
+    #[test]
+    fn test_chained() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/services/T0/B0/x")
+            .match_body(mockito::Matcher::Regex(
+                "https://hotln.test/issue/1\\|sync fails".into(),
+            ))
+            .with_status(200)
+            .with_body("ok")
+            .create();
+
+        let mut slack = SlackSink::new(&format!("{}/services/T0/B0/x", server.url()));
+        slack.after(MockReporter::new());
+        let url = slack.report(Report::new().title("sync fails")).unwrap();
+
+        assert_eq!(url, "https://hotln.test/issue/1");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_chained_keeps_issue_when_slack_fails() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/hook").with_status(500).create();
+
+        let mut slack = SlackSink::new(&format!("{}/hook", server.url()));
+        slack.after(MockReporter::new());
+        let url = slack.report(Report::new().title("sync fails")).unwrap();
+
+        assert_eq!(url, "https://hotln.test/issue/1");
+        mock.assert();
+    }
+}
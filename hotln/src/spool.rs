@@ -0,0 +1,325 @@
+//! On-disk spool for reports that could not be delivered.
+//!
+//! Each report is one file holding the proxy payload and the endpoint it was
+//! meant for. File names start with a timestamp, so sorting them gives the
+//! order the reports were spooled in. With [`Spool::encrypted`] the files are
+//! sealed with ChaCha20-Poly1305; otherwise they are plain JSON.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::hkdf::{HKDF_SHA256, Salt};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::{Error, telemetry};
+
+const EXTENSION: &str = "report";
+/// Marks an encrypted spool file, and is authenticated along with it.
+const MAGIC: &[u8; 4] = b"HLS1";
+
+/// A directory where undeliverable reports wait to be resent.
+///
+/// ```no_run
+/// let mut spool = hotln::Spool::new("/var/lib/myapp/reports");
+/// spool.encrypted(b"secret from the OS keychain");
+///
+/// let mut client = hotln::LinearClient::new("https://worker.example.com");
+/// client.spool(spool);
+/// // Later, once the network is back:
+/// client.send_spooled()?;
+/// # Ok::<(), hotln::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Spool {
+    dir: PathBuf,
+    key: Option<Arc<LessSafeKey>>,
+    max_entries: Option<usize>,
+    max_bytes: Option<u64>,
+    on_evict: Option<Arc<EvictFn>>,
+}
+
+type EvictFn = dyn Fn(&serde_json::Value) + Send + Sync;
+
+impl std::fmt::Debug for Spool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Spool")
+            .field("dir", &self.dir)
+            .field("encrypted", &self.key.is_some())
+            .field("max_entries", &self.max_entries)
+            .field("max_bytes", &self.max_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct Entry {
+    pub(crate) endpoint: String,
+    pub(crate) payload: serde_json::Value,
+}
+
+impl Spool {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            key: None,
+            max_entries: None,
+            max_bytes: None,
+            on_evict: None,
+        }
+    }
+
+    /// Encrypt spooled reports with a key derived from `secret`.
+    ///
+    /// The key is derived with HKDF, not a password hash, so `secret` should
+    /// be random (for example, generated once and kept in the OS keychain)
+    /// rather than something a person chose. Reports spooled under another
+    /// secret, or unencrypted, are left in place and skipped.
+    pub fn encrypted(&mut self, secret: &[u8]) -> &mut Self {
+        let prk = Salt::new(HKDF_SHA256, b"hotline spool").extract(secret);
+        let okm = prk
+            .expand(&[b"chacha20-poly1305"], &CHACHA20_POLY1305)
+            .expect("HKDF output fits ChaCha20-Poly1305");
+        self.key = Some(Arc::new(LessSafeKey::new(UnboundKey::from(okm))));
+        self
+    }
+
+    /// Keep at most `max` reports, evicting the oldest beyond that.
+    pub fn max_entries(&mut self, max: usize) -> &mut Self {
+        self.max_entries = Some(max);
+        self
+    }
+
+    /// Keep the spool's files under `max` bytes in total, evicting the
+    /// oldest reports beyond that.
+    pub fn max_bytes(&mut self, max: u64) -> &mut Self {
+        self.max_bytes = Some(max);
+        self
+    }
+
+    /// Called with each evicted report's payload, or `null` if it couldn't
+    /// be read, before the report is deleted.
+    pub fn on_evict(
+        &mut self,
+        callback: impl Fn(&serde_json::Value) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_evict = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of reports waiting in the spool.
+    pub fn len(&self) -> usize {
+        self.files().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn push(&self, entry: &Entry) -> Result<PathBuf, Error> {
+        let mut data = serde_json::to_vec(entry).map_err(|e| Error::Spool(e.to_string()))?;
+        if let Some(key) = &self.key {
+            data = seal(key, data)?;
+        }
+        std::fs::create_dir_all(&self.dir).map_err(|e| self.io_error(e))?;
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let mut suffix = [0u8; 4];
+        SystemRandom::new()
+            .fill(&mut suffix)
+            .map_err(|_| Error::Spool("no randomness available".into()))?;
+        let name = format!("{nanos:024}-{:08x}", u32::from_be_bytes(suffix));
+        let path = self.dir.join(&name).with_extension(EXTENSION);
+        // Write under a name that files() ignores, then rename into place so a
+        // crash mid-write never leaves a truncated report behind.
+        let tmp = self.dir.join(&name).with_extension("tmp");
+        write_private(&tmp, &data).map_err(|e| self.io_error(e))?;
+        std::fs::rename(&tmp, &path).map_err(|e| self.io_error(e))?;
+        self.evict();
+        Ok(path)
+    }
+
+    /// Delete the oldest reports until the spool is within its limits.
+    fn evict(&self) {
+        if self.max_entries.is_none() && self.max_bytes.is_none() {
+            return;
+        }
+        let files: Vec<(PathBuf, u64)> = self
+            .files()
+            .into_iter()
+            .map(|path| {
+                let len = std::fs::metadata(&path).map_or(0, |m| m.len());
+                (path, len)
+            })
+            .collect();
+        let mut count = files.len();
+        let mut bytes: u64 = files.iter().map(|(_, len)| len).sum();
+        for (path, len) in files {
+            let over_count = self.max_entries.is_some_and(|max| count > max);
+            let over_bytes = self.max_bytes.is_some_and(|max| bytes > max);
+            if !over_count && !over_bytes {
+                break;
+            }
+            if let Some(callback) = &self.on_evict {
+                let payload = self.read(&path).map(|entry| entry.payload);
+                callback(&payload.unwrap_or_default());
+            }
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("failed to evict spooled report {}: {e}", path.display());
+            }
+            telemetry::spool_evicted();
+            count -= 1;
+            bytes -= len;
+        }
+    }
+
+    /// Spooled files, oldest first.
+    pub(crate) fn files(&self) -> Vec<PathBuf> {
+        let Ok(dir) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut files: Vec<PathBuf> = dir
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+            .collect();
+        files.sort();
+        files
+    }
+
+    pub(crate) fn read(&self, path: &Path) -> Result<Entry, Error> {
+        let mut data = std::fs::read(path).map_err(|e| self.io_error(e))?;
+        match (&self.key, data.starts_with(MAGIC)) {
+            (Some(key), true) => data = open(key, data)?,
+            (None, true) => return Err(Error::Spool("report is encrypted".into())),
+            (Some(_), false) => return Err(Error::Spool("report is not encrypted".into())),
+            (None, false) => {}
+        }
+        serde_json::from_slice(&data).map_err(|e| Error::Spool(e.to_string()))
+    }
+
+    fn io_error(&self, e: std::io::Error) -> Error {
+        Error::Spool(format!("{}: {e}", self.dir.display()))
+    }
+}
+
+fn seal(key: &LessSafeKey, mut data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| Error::Spool("no randomness available".into()))?;
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(MAGIC),
+        &mut data,
+    )
+    .map_err(|_| Error::Spool("encryption failed".into()))?;
+    let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + data.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&data);
+    Ok(sealed)
+}
+
+fn open(key: &LessSafeKey, sealed: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let undecryptable = || Error::Spool("report cannot be decrypted with this key".into());
+    let rest = &sealed[MAGIC.len()..];
+    if rest.len() < NONCE_LEN {
+        return Err(undecryptable());
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| undecryptable())?;
+    let mut data = ciphertext.to_vec();
+    let len = key
+        .open_in_place(nonce, Aad::from(MAGIC), &mut data)
+        .map_err(|_| undecryptable())?
+        .len();
+    data.truncate(len);
+    Ok(data)
+}
+
+// Spooled reports may hold logs and backtraces, so keep them owner-only.
+pub(crate) fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(data)
+}
+
+#[cfg(test)]
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hotline-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_roundtrip() {
+        let mut spool = Spool::new(temp_dir("spool-encrypted"));
+        spool.encrypted(b"secret");
+        let entry = Entry {
+            endpoint: "linear".into(),
+            payload: serde_json::json!({ "title": "backtrace here" }),
+        };
+        let path = spool.push(&entry).unwrap();
+
+        let raw = std::fs::read(&path).unwrap();
+        assert!(raw.starts_with(MAGIC));
+        assert!(!String::from_utf8_lossy(&raw).contains("backtrace"));
+        assert_eq!(spool.files(), std::slice::from_ref(&path));
+        assert_eq!(spool.read(&path).unwrap().payload, entry.payload);
+
+        let mut other = spool.clone();
+        other.encrypted(b"another secret");
+        assert!(matches!(other.read(&path), Err(Error::Spool(_))));
+        assert!(matches!(
+            Spool::new(spool.dir()).read(&path),
+            Err(Error::Spool(_))
+        ));
+        std::fs::remove_dir_all(spool.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_eviction() {
+        let evicted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut spool = Spool::new(temp_dir("spool-eviction"));
+        spool.max_entries(2).on_evict({
+            let evicted = evicted.clone();
+            move |payload| evicted.lock().unwrap().push(payload["title"].clone())
+        });
+        for title in ["one", "two", "three"] {
+            let payload = serde_json::json!({ "title": title });
+            spool
+                .push(&Entry {
+                    endpoint: "linear".into(),
+                    payload,
+                })
+                .unwrap();
+        }
+        assert_eq!(spool.len(), 2);
+        assert_eq!(*evicted.lock().unwrap(), ["one"]);
+
+        spool.max_bytes(1);
+        spool
+            .push(&Entry {
+                endpoint: "linear".into(),
+                payload: serde_json::json!({ "title": "four" }),
+            })
+            .unwrap();
+        assert!(spool.is_empty());
+        std::fs::remove_dir_all(spool.dir()).unwrap();
+    }
+}
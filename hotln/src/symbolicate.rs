@@ -0,0 +1,141 @@
+//! Resolving backtraces against separately shipped debug info.
+//!
+//! Release binaries are often stripped, with their DWARF kept in a split
+//! debug file (`objcopy --only-keep-debug`) or a `.dSYM` bundle. The standard
+//! backtrace then has no names, so instead the return addresses are captured
+//! directly and looked up in that file.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use addr2line::Loader;
+
+/// The current thread's backtrace, resolved against `debug_info`, in the
+/// same layout as [`std::backtrace::Backtrace`]. `None` if the file can't be
+/// loaded or this platform isn't supported.
+pub(crate) fn symbolicate(debug_info: &Path) -> Option<String> {
+    let bias = image_bias()?;
+    let loader = match Loader::new(dwarf_file(debug_info)) {
+        Ok(loader) => loader,
+        Err(e) => {
+            tracing::warn!("cannot load debug info {}: {e}", debug_info.display());
+            return None;
+        }
+    };
+
+    let mut addresses = Vec::new();
+    backtrace::trace(|frame| {
+        addresses.push(frame.ip() as u64);
+        true
+    });
+
+    let mut out = String::new();
+    let mut index = 0;
+    for address in addresses {
+        // Return addresses point just past the call; look up the call itself.
+        let probe = address.wrapping_sub(bias).saturating_sub(1);
+        let mut resolved = false;
+        if let Ok(mut frames) = loader.find_frames(probe) {
+            while let Ok(Some(frame)) = frames.next() {
+                let name = frame
+                    .function
+                    .as_ref()
+                    .and_then(|f| f.demangle().ok())
+                    .unwrap_or_else(|| "<unknown>".into());
+                let _ = writeln!(out, "{index:>4}: {name}");
+                if let Some(location) = frame.location
+                    && let (Some(file), Some(line)) = (location.file, location.line)
+                {
+                    let _ = write!(out, "             at {file}:{line}");
+                    if let Some(column) = location.column {
+                        let _ = write!(out, ":{column}");
+                    }
+                    out.push('\n');
+                }
+                index += 1;
+                resolved = true;
+            }
+        }
+        if !resolved {
+            let name = loader
+                .find_symbol(probe)
+                .map(|name| addr2line::demangle_auto(name.into(), None).into_owned())
+                .unwrap_or_else(|| format!("<unknown> ({address:#x})"));
+            let _ = writeln!(out, "{index:>4}: {name}");
+            index += 1;
+        }
+    }
+    Some(out)
+}
+
+/// A `.dSYM` bundle holds its DWARF in `Contents/Resources/DWARF/<name>`.
+fn dwarf_file(path: &Path) -> PathBuf {
+    if path.extension().is_some_and(|ext| ext == "dSYM") {
+        let dir = path.join("Contents/Resources/DWARF");
+        if let Some(file) = std::fs::read_dir(&dir)
+            .ok()
+            .and_then(|mut entries| entries.next()?.ok())
+        {
+            return file.path();
+        }
+    }
+    path.to_path_buf()
+}
+
+/// How far the executable was moved from the addresses in its debug info.
+#[cfg(target_os = "linux")]
+fn image_bias() -> Option<u64> {
+    use std::io::Read;
+
+    // Only position-independent executables (ELF type ET_DYN) are moved.
+    let mut header = [0u8; 18];
+    std::fs::File::open("/proc/self/exe")
+        .and_then(|mut exe| exe.read_exact(&mut header))
+        .ok()?;
+    if u16::from_ne_bytes([header[16], header[17]]) != 3 {
+        return Some(0);
+    }
+
+    let exe = std::fs::read_link("/proc/self/exe").ok()?;
+    let maps = std::fs::read_to_string("/proc/self/maps").ok()?;
+    maps.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let range = fields.next()?;
+        let offset = fields.nth(1)?;
+        let path = fields.nth(2)?;
+        if Path::new(path) != exe || u64::from_str_radix(offset, 16).ok()? != 0 {
+            return None;
+        }
+        u64::from_str_radix(range.split_once('-')?.0, 16).ok()
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn image_bias() -> Option<u64> {
+    unsafe extern "C" {
+        fn _dyld_get_image_vmaddr_slide(image_index: u32) -> isize;
+    }
+    // Image 0 is the main executable.
+    Some(unsafe { _dyld_get_image_vmaddr_slide(0) } as u64)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn image_bias() -> Option<u64> {
+    None
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbolicate() {
+        // The test binary carries its own debug info.
+        let exe = std::env::current_exe().unwrap();
+        let backtrace = symbolicate(&exe).unwrap();
+        assert!(backtrace.contains("test_symbolicate"), "{backtrace}");
+        assert!(crate::fingerprint(&backtrace).is_some());
+
+        assert_eq!(symbolicate(Path::new("/nonexistent.debug")), None);
+    }
+}
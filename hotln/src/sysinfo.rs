@@ -0,0 +1,798 @@
+//! The "System Info" rows shared by the CLI and the Bevy and egui
+//! integrations.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{PrivacyLevel, Report};
+
+/// Something that contributes rows to a report's "System Info" table.
+///
+/// The built-in sources are [`Os`], [`Hardware`], [`Build`], and [`Env`].
+/// Apps add their own context, such as the renderer or enabled plugins, by
+/// implementing it or, with the `macros` feature, with
+/// `#[derive(hotln::SystemInfo)]`. Sources combine as tuples, or by adding
+/// several to a client with `system_info`.
+pub trait SystemInfoSource {
+    fn collect(&self) -> Vec<(String, String)>;
+}
+
+impl SystemInfoSource for Vec<(String, String)> {
+    fn collect(&self) -> Vec<(String, String)> {
+        self.clone()
+    }
+}
+
+impl SystemInfoSource for [(&str, &str)] {
+    fn collect(&self) -> Vec<(String, String)> {
+        self.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+}
+
+impl<const N: usize> SystemInfoSource for [(&str, &str); N] {
+    fn collect(&self) -> Vec<(String, String)> {
+        self[..].collect()
+    }
+}
+
+impl<T: SystemInfoSource + ?Sized> SystemInfoSource for &T {
+    fn collect(&self) -> Vec<(String, String)> {
+        (**self).collect()
+    }
+}
+
+macro_rules! tuple_source {
+    ($($name:ident),+) => {
+        impl<$($name: SystemInfoSource),+> SystemInfoSource for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn collect(&self) -> Vec<(String, String)> {
+                let ($($name,)+) = self;
+                let mut rows = Vec::new();
+                $(rows.extend($name.collect());)+
+                rows
+            }
+        }
+    };
+}
+
+tuple_source!(A, B);
+tuple_source!(A, B, C);
+tuple_source!(A, B, C, D);
+tuple_source!(A, B, C, D, E);
+
+/// The sources a client adds to every report.
+#[derive(Clone, Default)]
+pub(crate) struct Sources(Vec<Arc<dyn SystemInfoSource + Send + Sync>>);
+
+impl std::fmt::Debug for Sources {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sources({})", self.0.len())
+    }
+}
+
+impl Sources {
+    pub(crate) fn push(&mut self, source: impl SystemInfoSource + Send + Sync + 'static) {
+        self.0.push(Arc::new(source));
+    }
+
+    /// `report` with a "System Info" table of the sources' rows, unless
+    /// `level` leaves out optional context.
+    pub(crate) fn apply<'a>(&self, report: &'a Report, level: PrivacyLevel) -> Cow<'a, Report> {
+        if self.0.is_empty() || !level.collects_context() {
+            return Cow::Borrowed(report);
+        }
+        let rows: Vec<(String, String)> =
+            self.0.iter().flat_map(|source| source.collect()).collect();
+        let mut report = report.clone();
+        report.system_info(&rows);
+        Cow::Owned(report)
+    }
+}
+
+/// The OS, architecture, container or VM environment, locale, preferred
+/// languages, and timezone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Os;
+
+impl SystemInfoSource for Os {
+    fn collect(&self) -> Vec<(String, String)> {
+        let mut rows = vec![
+            ("OS".to_string(), std::env::consts::OS.to_string()),
+            ("Arch".to_string(), std::env::consts::ARCH.to_string()),
+        ];
+        let environment = environment();
+        if !environment.is_empty() {
+            rows.push(("Environment".to_string(), environment.join(", ")));
+        }
+        if let Some(locale) = locale() {
+            rows.push(("Locale".to_string(), locale));
+        }
+        let languages = languages();
+        if !languages.is_empty() {
+            rows.push(("Languages".to_string(), languages.join(", ")));
+        }
+        rows.push(("Timezone".to_string(), timezone()));
+        rows
+    }
+}
+
+/// Available memory, the process's resident memory, and free space on the
+/// working directory's volume.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hardware;
+
+impl SystemInfoSource for Hardware {
+    fn collect(&self) -> Vec<(String, String)> {
+        let mut rows = Vec::new();
+        if let Some(memory) = memory() {
+            rows.push(("Memory".to_string(), memory));
+        }
+        if let Some(resident) = process_memory() {
+            rows.push(("Process Memory".to_string(), format_bytes(resident)));
+        }
+        if let Some(disk) = std::env::current_dir()
+            .ok()
+            .and_then(|dir| disk_space(&dir))
+        {
+            rows.push(("Disk Free".to_string(), disk));
+        }
+        rows
+    }
+}
+
+/// The app's version, the commit it was built from, and whether it is a
+/// debug or release build.
+///
+/// ```
+/// let mut build = hotln::Build::new(env!("CARGO_PKG_VERSION"));
+/// build.commit(option_env!("GIT_SHA").unwrap_or("unknown"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Build {
+    version: String,
+    commit: Option<String>,
+}
+
+impl Build {
+    pub fn new(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            commit: None,
+        }
+    }
+
+    pub fn commit(&mut self, commit: &str) -> &mut Self {
+        self.commit = Some(commit.to_string());
+        self
+    }
+}
+
+impl SystemInfoSource for Build {
+    fn collect(&self) -> Vec<(String, String)> {
+        let mut rows = vec![("Version".to_string(), self.version.clone())];
+        if let Some(commit) = &self.commit {
+            rows.push(("Commit".to_string(), commit.clone()));
+        }
+        let profile = if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        };
+        rows.push(("Profile".to_string(), profile.to_string()));
+        rows
+    }
+}
+
+/// The values of the named environment variables that are set, such as
+/// `RUST_LOG` or a renderer override. Only list variables that are safe to
+/// publish.
+#[derive(Debug, Clone, Default)]
+pub struct Env {
+    names: Vec<String>,
+}
+
+impl Env {
+    pub fn new(names: &[&str]) -> Self {
+        Self {
+            names: names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+impl SystemInfoSource for Env {
+    fn collect(&self) -> Vec<(String, String)> {
+        self.names
+            .iter()
+            .filter_map(|name| {
+                let value = std::env::var(name).ok().filter(|v| !v.is_empty())?;
+                Some((name.clone(), value))
+            })
+            .collect()
+    }
+}
+
+/// OS, architecture, container or VM environment, locale, preferred
+/// languages, timezone, memory, and free disk space, as rows for a report's
+/// system info table: the rows of [`Os`] and [`Hardware`].
+///
+/// Locale-dependent bugs (number and date formatting, sorting, text
+/// encoding), "disk full" or out-of-memory bugs, and bugs that only happen
+/// in a container or VM are hard to reproduce without these, and users
+/// rarely think to mention them. Disk space is for
+/// the working directory's volume; apps that keep data elsewhere can replace
+/// the "Disk Free" row with [`disk_space`] of their data directory. Rows that
+/// can't be determined are left out.
+pub fn system_info() -> Vec<(String, String)> {
+    (Os, Hardware).collect()
+}
+
+/// Containers, WSL, and hypervisors the process runs under, outermost last,
+/// e.g. `["Kubernetes", "Docker", "VM (QEMU)"]`. Empty on bare metal.
+#[cfg(target_os = "linux")]
+fn environment() -> Vec<String> {
+    let read = |path: &str| std::fs::read_to_string(path).unwrap_or_default();
+    let mut found = Vec::new();
+
+    if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        found.push("Kubernetes".to_string());
+    }
+    let cgroup = read("/proc/1/cgroup");
+    if Path::new("/.dockerenv").exists() || cgroup.contains("/docker") {
+        found.push("Docker".to_string());
+    } else if Path::new("/run/.containerenv").exists() {
+        found.push("Podman".to_string());
+    } else if let Ok(runtime) = std::env::var("container") {
+        // Set by systemd-nspawn, LXC, and others.
+        found.push(format!("Container ({runtime})"));
+    } else if cgroup.contains("/lxc") {
+        found.push("Container (lxc)".to_string());
+    }
+
+    let release = read("/proc/sys/kernel/osrelease").to_lowercase();
+    if release.contains("microsoft") {
+        let wsl = if release.contains("wsl2") || release.contains("microsoft-standard") {
+            "WSL2"
+        } else {
+            "WSL"
+        };
+        found.push(wsl.to_string());
+    } else if let Some(hypervisor) = hypervisor() {
+        found.push(hypervisor);
+    }
+    found
+}
+
+/// The hypervisor named by the DMI vendor strings, or `VM` when the CPU only
+/// says it's virtualized.
+#[cfg(target_os = "linux")]
+fn hypervisor() -> Option<String> {
+    const VENDORS: &[(&str, &str)] = &[
+        ("qemu", "QEMU"),
+        ("kvm", "KVM"),
+        ("vmware", "VMware"),
+        ("virtualbox", "VirtualBox"),
+        ("innotek", "VirtualBox"),
+        ("xen", "Xen"),
+        ("parallels", "Parallels"),
+        ("amazon ec2", "Amazon EC2"),
+        ("google compute engine", "Google Compute Engine"),
+        ("virtual machine", "Hyper-V"),
+    ];
+    let dmi: String = ["sys_vendor", "product_name", "bios_vendor"]
+        .iter()
+        .filter_map(|file| std::fs::read_to_string(format!("/sys/class/dmi/id/{file}")).ok())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if let Some((_, name)) = VENDORS.iter().find(|(needle, _)| dmi.contains(needle)) {
+        return Some(format!("VM ({name})"));
+    }
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    cpuinfo
+        .lines()
+        .any(|line| line.starts_with("flags") && line.split_whitespace().any(|f| f == "hypervisor"))
+        .then(|| "VM".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn environment() -> Vec<String> {
+    Vec::new()
+}
+
+/// Free space on the volume holding `path`, e.g. `48.2 GiB of 476.9 GiB`.
+pub fn disk_space(path: &Path) -> Option<String> {
+    let (free, total) = disk_free_total(path)?;
+    Some(format!("{} of {}", format_bytes(free), format_bytes(total)))
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(unix)]
+fn disk_free_total(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // Blocks available to unprivileged users, not the root reserve.
+    let block = stat.f_frsize as u64;
+    Some((stat.f_bavail as u64 * block, stat.f_blocks as u64 * block))
+}
+
+#[cfg(windows)]
+fn disk_free_total(path: &Path) -> Option<(u64, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let (mut free, mut total) = (0, 0);
+    let ok =
+        unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut free, &mut total, std::ptr::null_mut()) };
+    (ok != 0).then_some((free, total))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn disk_free_total(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Physical memory available to new allocations, out of the total.
+#[cfg(target_os = "linux")]
+fn memory() -> Option<String> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let total = proc_kib(&meminfo, "MemTotal:")?;
+    let available = proc_kib(&meminfo, "MemAvailable:")?;
+    Some(format!(
+        "{} available of {}",
+        format_bytes(available),
+        format_bytes(total)
+    ))
+}
+
+/// The resident set size.
+#[cfg(target_os = "linux")]
+fn process_memory() -> Option<u64> {
+    proc_kib(
+        &std::fs::read_to_string("/proc/self/status").ok()?,
+        "VmRSS:",
+    )
+}
+
+/// A `Name:   1234 kB` line from a `/proc` file, in bytes.
+#[cfg(target_os = "linux")]
+fn proc_kib(text: &str, name: &str) -> Option<u64> {
+    let line = text.lines().find(|line| line.starts_with(name))?;
+    let kib: u64 = line[name.len()..]
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// macOS doesn't report available memory simply, so only the total.
+#[cfg(target_os = "macos")]
+fn memory() -> Option<String> {
+    let mut total = 0u64;
+    let mut len = std::mem::size_of::<u64>();
+    let ok = unsafe {
+        libc::sysctlbyname(
+            c"hw.memsize".as_ptr(),
+            (&mut total as *mut u64).cast(),
+            &mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    (ok == 0).then(|| format!("{} total", format_bytes(total)))
+}
+
+/// The peak resident set size, which macOS reports in bytes.
+#[cfg(target_os = "macos")]
+fn process_memory() -> Option<u64> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    Some(usage.ru_maxrss as u64)
+}
+
+#[cfg(windows)]
+fn memory() -> Option<String> {
+    let mut status = MemoryStatusEx {
+        length: std::mem::size_of::<MemoryStatusEx>() as u32,
+        ..Default::default()
+    };
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return None;
+    }
+    Some(format!(
+        "{} available of {}",
+        format_bytes(status.avail_phys),
+        format_bytes(status.total_phys)
+    ))
+}
+
+/// The working set, Windows' resident set size.
+#[cfg(windows)]
+fn process_memory() -> Option<u64> {
+    unsafe extern "system" {
+        fn GetCurrentProcess() -> isize;
+    }
+    let cb = std::mem::size_of::<ProcessMemoryCounters>() as u32;
+    let mut counters = ProcessMemoryCounters {
+        cb,
+        ..Default::default()
+    };
+    let ok = unsafe { K32GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, cb) };
+    (ok != 0).then_some(counters.working_set_size as u64)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn memory() -> Option<String> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn process_memory() -> Option<u64> {
+    None
+}
+
+/// The GPU a graphical application would render on: its model, the
+/// graphics backend, device type, and driver version, in the same rows the
+/// Bevy plugin uses.
+///
+/// Asks wgpu for the high-performance adapter, which takes a moment while
+/// backends load, so call it once at startup and keep the rows. Empty if no
+/// adapter is found.
+#[cfg(feature = "gpu")]
+pub fn gpu_info() -> Vec<(String, String)> {
+    let instance =
+        wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+    let options = wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        ..Default::default()
+    };
+    let adapter = match futures_executor::block_on(instance.request_adapter(&options)) {
+        Ok(adapter) => adapter.get_info(),
+        Err(e) => {
+            tracing::debug!("no GPU adapter for system info: {e}");
+            return Vec::new();
+        }
+    };
+    let mut rows = vec![
+        ("GPU".to_string(), adapter.name),
+        ("Backend".to_string(), format!("{:?}", adapter.backend)),
+        (
+            "Device Type".to_string(),
+            format!("{:?}", adapter.device_type),
+        ),
+    ];
+    if !adapter.driver.is_empty() {
+        let driver = format!("{} {}", adapter.driver, adapter.driver_info);
+        rows.push(("Driver".to_string(), driver.trim().to_string()));
+    }
+    rows
+}
+
+/// For terminal applications: `$TERM`, color support, the terminal
+/// emulator, the shell, and whether stdin, stdout, and stderr are terminals.
+/// Rendering and encoding bugs depend on exactly these.
+pub fn terminal_info() -> Vec<(String, String)> {
+    use std::io::IsTerminal;
+
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let mut rows = Vec::new();
+    if let Some(term) = var("TERM") {
+        rows.push(("TERM".to_string(), term));
+    }
+    if let Some(colors) = var("COLORTERM") {
+        rows.push(("COLORTERM".to_string(), colors));
+    }
+    if let Some(emulator) = terminal_emulator(var) {
+        rows.push(("Terminal".to_string(), emulator));
+    }
+    // `SHELL` is the login shell; on Windows, `ComSpec` is cmd.exe.
+    if let Some(shell) = var("SHELL").or_else(|| var("ComSpec")) {
+        rows.push(("Shell".to_string(), shell));
+    }
+    let tty = |is_tty: bool| if is_tty { "yes" } else { "no" }.to_string();
+    rows.push(("Stdin TTY".to_string(), tty(std::io::stdin().is_terminal())));
+    rows.push((
+        "Stdout TTY".to_string(),
+        tty(std::io::stdout().is_terminal()),
+    ));
+    rows.push((
+        "Stderr TTY".to_string(),
+        tty(std::io::stderr().is_terminal()),
+    ));
+    rows
+}
+
+/// Terminals that don't set `TERM_PROGRAM` usually set a variable of their own.
+fn terminal_emulator(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    if let Some(program) = var("TERM_PROGRAM") {
+        return Some(match var("TERM_PROGRAM_VERSION") {
+            Some(version) => format!("{program} {version}"),
+            None => program,
+        });
+    }
+    const MARKERS: &[(&str, &str)] = &[
+        ("WT_SESSION", "Windows Terminal"),
+        ("KITTY_WINDOW_ID", "kitty"),
+        ("ALACRITTY_WINDOW_ID", "Alacritty"),
+        ("KONSOLE_VERSION", "Konsole"),
+        ("VTE_VERSION", "VTE-based (GNOME Terminal, Tilix, ...)"),
+        ("TMUX", "tmux"),
+        ("STY", "screen"),
+    ];
+    MARKERS
+        .iter()
+        .find(|(name, _)| var(name).is_some())
+        .map(|(_, emulator)| emulator.to_string())
+}
+
+/// The locale from the usual POSIX variables, in precedence order.
+#[cfg(not(windows))]
+fn locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// GNU `LANGUAGE`, a colon-separated list in order of preference.
+#[cfg(not(windows))]
+fn languages() -> Vec<String> {
+    std::env::var("LANGUAGE")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|lang| !lang.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetUserDefaultLocaleName(name: *mut u16, len: i32) -> i32;
+    fn GetUserPreferredUILanguages(
+        flags: u32,
+        count: *mut u32,
+        buffer: *mut u16,
+        len: *mut u32,
+    ) -> i32;
+    fn GetTimeZoneInformation(info: *mut TimeZoneInformation) -> u32;
+    fn GetDiskFreeSpaceExW(
+        path: *const u16,
+        free: *mut u64,
+        total: *mut u64,
+        all_free: *mut u64,
+    ) -> i32;
+    fn GlobalMemoryStatusEx(status: *mut MemoryStatusEx) -> i32;
+    fn K32GetProcessMemoryInfo(
+        process: isize,
+        counters: *mut ProcessMemoryCounters,
+        cb: u32,
+    ) -> i32;
+}
+
+#[cfg(windows)]
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)] // Laid out for Windows to fill in; only some fields are read.
+struct MemoryStatusEx {
+    length: u32,
+    memory_load: u32,
+    total_phys: u64,
+    avail_phys: u64,
+    total_page_file: u64,
+    avail_page_file: u64,
+    total_virtual: u64,
+    avail_virtual: u64,
+    avail_extended_virtual: u64,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)] // Laid out for Windows to fill in; only some fields are read.
+struct ProcessMemoryCounters {
+    cb: u32,
+    page_fault_count: u32,
+    peak_working_set_size: usize,
+    working_set_size: usize,
+    quota_peak_paged_pool_usage: usize,
+    quota_paged_pool_usage: usize,
+    quota_peak_non_paged_pool_usage: usize,
+    quota_non_paged_pool_usage: usize,
+    pagefile_usage: usize,
+    peak_pagefile_usage: usize,
+}
+
+#[cfg(windows)]
+fn locale() -> Option<String> {
+    let mut name = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    (len > 1).then(|| String::from_utf16_lossy(&name[..len as usize - 1]))
+}
+
+#[cfg(windows)]
+fn languages() -> Vec<String> {
+    const MUI_LANGUAGE_NAME: u32 = 0x8;
+    let (mut count, mut len) = (0, 0);
+    let sized = unsafe {
+        GetUserPreferredUILanguages(
+            MUI_LANGUAGE_NAME,
+            &mut count,
+            std::ptr::null_mut(),
+            &mut len,
+        )
+    };
+    if sized == 0 || len == 0 {
+        return Vec::new();
+    }
+    // A list of NUL-terminated names, ending with an empty one.
+    let mut buffer = vec![0u16; len as usize];
+    if unsafe {
+        GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, buffer.as_mut_ptr(), &mut len)
+    } == 0
+    {
+        return Vec::new();
+    }
+    buffer
+        .split(|&c| c == 0)
+        .filter(|name| !name.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect()
+}
+
+/// The zone name where known, and the current offset from UTC, e.g.
+/// `Europe/Berlin (UTC+02:00)`.
+fn timezone() -> String {
+    let minutes = utc_offset_minutes();
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let offset = format!(
+        "UTC{sign}{:02}:{:02}",
+        minutes.abs() / 60,
+        minutes.abs() % 60
+    );
+    match zone_name() {
+        Some(name) => format!("{name} ({offset})"),
+        None => offset,
+    }
+}
+
+/// `TZ` if set, otherwise the zone `/etc/localtime` links to.
+#[cfg(not(windows))]
+fn zone_name() -> Option<String> {
+    if let Ok(tz) = std::env::var("TZ")
+        && !tz.is_empty()
+    {
+        return Some(tz.trim_start_matches(':').to_string());
+    }
+    let target = std::fs::read_link("/etc/localtime").ok()?;
+    let target = target.to_str()?;
+    let (_, name) = target.split_once("zoneinfo/")?;
+    Some(name.to_string())
+}
+
+#[cfg(unix)]
+fn utc_offset_minutes() -> i32 {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return 0;
+    }
+    (tm.tm_gmtoff / 60) as i32
+}
+
+#[cfg(not(any(unix, windows)))]
+fn utc_offset_minutes() -> i32 {
+    0
+}
+
+#[cfg(windows)]
+#[repr(C)]
+#[allow(dead_code)] // Laid out for Windows to fill in; only some fields are read.
+struct TimeZoneInformation {
+    bias: i32,
+    standard_name: [u16; 32],
+    standard_date: [u16; 8],
+    standard_bias: i32,
+    daylight_name: [u16; 32],
+    daylight_date: [u16; 8],
+    daylight_bias: i32,
+}
+
+#[cfg(windows)]
+fn zone_name() -> Option<String> {
+    let mut info: TimeZoneInformation = unsafe { std::mem::zeroed() };
+    if unsafe { GetTimeZoneInformation(&mut info) } == u32::MAX {
+        return None;
+    }
+    let name = &info.standard_name;
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    (len > 0).then(|| String::from_utf16_lossy(&name[..len]))
+}
+
+#[cfg(windows)]
+fn utc_offset_minutes() -> i32 {
+    const TIME_ZONE_ID_STANDARD: u32 = 1;
+    const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+    let mut info: TimeZoneInformation = unsafe { std::mem::zeroed() };
+    // Bias is minutes to add to local time to get UTC.
+    let bias = match unsafe { GetTimeZoneInformation(&mut info) } {
+        TIME_ZONE_ID_STANDARD => info.bias + info.standard_bias,
+        TIME_ZONE_ID_DAYLIGHT => info.bias + info.daylight_bias,
+        u32::MAX => return 0,
+        _ => info.bias,
+    };
+    -bias
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_info() {
+        let rows = system_info();
+        let keys: Vec<&str> = rows.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys[..2], ["OS", "Arch"]);
+        let (_, timezone) = rows.iter().find(|(k, _)| k == "Timezone").unwrap();
+        assert!(
+            timezone.contains("UTC+") || timezone.contains("UTC-"),
+            "{timezone}"
+        );
+    }
+
+    #[test]
+    fn test_terminal_emulator() {
+        let vars = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            terminal_emulator(vars(&[
+                ("TERM_PROGRAM", "iTerm.app"),
+                ("TERM_PROGRAM_VERSION", "3.5.0")
+            ])),
+            Some("iTerm.app 3.5.0".to_string())
+        );
+        assert_eq!(
+            terminal_emulator(vars(&[("WT_SESSION", "f1e2")])),
+            Some("Windows Terminal".to_string())
+        );
+        assert_eq!(terminal_emulator(vars(&[])), None);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(476_938_752_000), "444.2 GiB");
+    }
+}
@@ -0,0 +1,119 @@
+//! Tauri plugin that reports frontend errors and Rust panics.
+//!
+//! ```ignore
+//! let mut client = hotln::LinearClient::new("https://worker.example.com");
+//! client.with_token("secret");
+//!
+//! tauri::Builder::default()
+//!     .plugin(hotln::tauri::init(client))
+//!     .run(tauri::generate_context!())
+//!     .expect("error while running tauri application");
+//! ```
+//!
+//! The plugin installs a [`PanicHook`] for the Rust side and injects a script
+//! that forwards uncaught webview errors and unhandled promise rejections to
+//! `report_panic`. The frontend can also file reports directly:
+//!
+//! ```js
+//! import { invoke } from "@tauri-apps/api/core";
+//!
+//! const url = await invoke("plugin:hotln|report_bug", {
+//!   title: "Export fails",
+//!   description: "Clicking export does nothing.",
+//! });
+//! ```
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{Manager, Runtime, State};
+
+use crate::{PanicHook, Report, Reporter, Severity, Submission};
+
+const INIT_SCRIPT: &str = r#"
+(function () {
+  function report(message, stack) {
+    window.__TAURI_INTERNALS__
+      .invoke("plugin:hotln|report_panic", { message: String(message), stack: stack || null })
+      .catch(function () {});
+  }
+  window.addEventListener("error", function (e) {
+    report(e.message, e.error && e.error.stack);
+  });
+  window.addEventListener("unhandledrejection", function (e) {
+    var reason = e.reason;
+    report(reason && reason.message ? reason.message : reason, reason && reason.stack);
+  });
+})();
+"#;
+
+struct PluginState {
+    reporter: Arc<dyn Reporter + Send + Sync>,
+    seen: Mutex<HashSet<String>>,
+}
+
+/// Build the plugin. `reporter` is shared by the panic hook and commands.
+pub fn init<R: Runtime>(reporter: impl Reporter + Send + Sync + 'static) -> TauriPlugin<R> {
+    let reporter: Arc<dyn Reporter + Send + Sync> = Arc::new(reporter);
+    PanicHook::new(reporter.clone()).install();
+
+    Builder::new("hotln")
+        .js_init_script(INIT_SCRIPT.to_string())
+        .invoke_handler(tauri::generate_handler![report_bug, report_panic])
+        .setup(move |app, _api| {
+            app.manage(PluginState {
+                reporter,
+                seen: Mutex::new(HashSet::new()),
+            });
+            Ok(())
+        })
+        .build()
+}
+
+/// File a bug report from the frontend. Returns the issue URL.
+#[tauri::command]
+async fn report_bug(
+    state: State<'_, PluginState>,
+    title: String,
+    description: String,
+) -> Result<String, String> {
+    let mut report = Report::new();
+    report
+        .title(&title)
+        .text(&description)
+        .severity(Severity::Bug);
+    file(state.reporter.clone(), report).await
+}
+
+/// Report an uncaught frontend error. Each distinct message is filed once.
+#[tauri::command]
+async fn report_panic(
+    state: State<'_, PluginState>,
+    message: String,
+    stack: Option<String>,
+) -> Result<Option<String>, String> {
+    if !state.seen.lock().unwrap().insert(message.clone()) {
+        return Ok(None);
+    }
+
+    let mut report = Report::new();
+    report
+        .title(&format!(
+            "webview error: {}",
+            message.lines().next().unwrap_or("")
+        ))
+        .severity(Severity::Crash)
+        .file("message.txt", &message);
+    if let Some(stack) = &stack {
+        report.file("stack.txt", stack);
+    }
+    file(state.reporter.clone(), report).await.map(Some)
+}
+
+async fn file(reporter: Arc<dyn Reporter + Send + Sync>, report: Report) -> Result<String, String> {
+    crate::submit_async(reporter, report)
+        .await
+        .map(Submission::into_url)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,58 @@
+//! Reporter health metrics, emitted through the `metrics` facade when the
+//! `metrics` feature is enabled and compiled out otherwise.
+
+use std::time::Duration;
+
+use crate::{Error, Spool, Submission};
+
+/// Record the outcome and latency of one submission to the proxy.
+#[cfg(feature = "metrics")]
+pub(crate) fn submission(result: &Result<Submission, Error>, elapsed: Duration) {
+    ::metrics::histogram!("hotline_submission_seconds").record(elapsed);
+    match result {
+        Ok(_) => ::metrics::counter!("hotline_reports_sent").increment(1),
+        Err(e) => {
+            let reason = match e {
+                Error::Http(_) => "http",
+                Error::Parse(_) => "parse",
+                Error::Proxy { .. } => "proxy",
+                Error::Config(_) => "config",
+                Error::Timeout(_) => "timeout",
+                Error::Spool(_) => "spool",
+                Error::Secret(_) => "secret",
+                Error::Smtp(_) => "smtp",
+                Error::Throttled(_) => "throttled",
+                Error::Cancelled => "cancelled",
+            };
+            ::metrics::counter!("hotline_reports_failed", "reason" => reason).increment(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn submission(_result: &Result<Submission, Error>, _elapsed: Duration) {}
+
+/// Record how many reports are waiting in the spool.
+#[cfg(feature = "metrics")]
+pub(crate) fn spool_depth(spool: &Spool) {
+    ::metrics::gauge!("hotline_spool_depth").set(spool.len() as f64);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn spool_depth(_spool: &Spool) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn spool_evicted() {
+    ::metrics::counter!("hotline_spool_evicted").increment(1);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn spool_evicted() {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn retry() {
+    ::metrics::counter!("hotline_retries").increment(1);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn retry() {}
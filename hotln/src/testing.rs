@@ -0,0 +1,125 @@
+//! Test doubles for code that files reports.
+//!
+//! ```
+//! use hotln::{Report, Reporter, testing::MockReporter};
+//!
+//! fn report_crash(reporter: &dyn Reporter) -> String {
+//!     reporter.report(Report::new().title("crash")).unwrap()
+//! }
+//!
+//! let mock = MockReporter::new();
+//! assert_eq!(report_crash(&mock), "https://hotln.test/issue/1");
+//! assert_eq!(mock.reports()[0].title, "crash");
+//! ```
+
+use std::sync::Mutex;
+
+use crate::{Error, Report, Reporter};
+
+/// A [`Reporter`] that keeps reports in memory instead of sending them.
+///
+/// Each report gets a canned URL: `https://hotln.test/issue/<n>` by default,
+/// or the URL set with [`with_url`](Self::with_url).
+#[derive(Debug, Default)]
+pub struct MockReporter {
+    url: Option<String>,
+    error: Option<(u16, String)>,
+    reports: Mutex<Vec<Report>>,
+    comments: Mutex<Vec<(String, String)>>,
+}
+
+impl MockReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `url` for every report.
+    pub fn with_url(&mut self, url: &str) -> &mut Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    /// Fail every report with [`Error::Proxy`] after recording it.
+    pub fn with_error(&mut self, status: u16, body: &str) -> &mut Self {
+        self.error = Some((status, body.to_string()));
+        self
+    }
+
+    /// Reports received so far, oldest first.
+    pub fn reports(&self) -> Vec<Report> {
+        self.reports.lock().unwrap().clone()
+    }
+
+    /// Comments received so far as `(issue, body)` pairs, oldest first, with
+    /// any updates applied.
+    pub fn comments(&self) -> Vec<(String, String)> {
+        self.comments.lock().unwrap().clone()
+    }
+}
+
+impl Reporter for MockReporter {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        let mut reports = self.reports.lock().unwrap();
+        reports.push(report.clone());
+        if let Some((status, body)) = &self.error {
+            return Err(Error::Proxy {
+                status: *status,
+                body: body.clone(),
+            });
+        }
+        Ok(match &self.url {
+            Some(url) => url.clone(),
+            None => format!("https://hotln.test/issue/{}", reports.len()),
+        })
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        let mut comments = self.comments.lock().unwrap();
+        comments.push((issue.to_string(), body.to_string()));
+        Ok(format!("{issue}#comment-{}", comments.len()))
+    }
+
+    /// Replace the body of a comment from [`comments`](Self::comments), as
+    /// numbered in its URL.
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        let mut comments = self.comments.lock().unwrap();
+        let entry = comment
+            .rsplit_once("#comment-")
+            .and_then(|(_, n)| n.parse::<usize>().ok())
+            .and_then(|n| comments.get_mut(n.checked_sub(1)?))
+            .ok_or_else(|| Error::Config(format!("no such comment: {comment}")))?;
+        entry.1 = body.to_string();
+        Ok(comment.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_reports() {
+        let mock = MockReporter::new();
+        mock.report(Report::new().title("first")).unwrap();
+        let url = mock.report(Report::new().title("second")).unwrap();
+
+        assert_eq!(url, "https://hotln.test/issue/2");
+        let titles: Vec<_> = mock.reports().into_iter().map(|r| r.title).collect();
+        assert_eq!(titles, ["first", "second"]);
+    }
+
+    #[test]
+    fn test_with_error() {
+        let mut mock = MockReporter::new();
+        mock.with_error(429, "rate limited");
+
+        match mock.report(&Report::new()).unwrap_err() {
+            Error::Proxy { status, body } => {
+                assert_eq!(status, 429);
+                assert_eq!(body, "rate limited");
+            }
+            other => panic!("expected Proxy error, got: {}", other),
+        }
+        assert_eq!(mock.reports().len(), 1);
+    }
+}
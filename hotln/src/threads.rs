@@ -0,0 +1,551 @@
+//! Backtraces of every thread in the process, for reports about hangs and
+//! deadlocks, where the interesting stack is rarely the reporting thread's.
+//!
+//! On Linux, each other thread is sent a signal whose handler saves its
+//! registers and waits while the sampling thread walks its stack with the
+//! `.eh_frame` unwind tables; frames are resolved to names once it has been
+//! let go. The handler does nothing that isn't safe in a signal handler, and
+//! stays installed after the first dump, so a thread that answers late runs
+//! a handler that finds nothing to do instead of the default action, which
+//! would kill the process. Other platforms, and other architectures, have
+//! no dump.
+
+/// The backtraces of all threads except the calling one, in the layout of
+/// [`std::backtrace::Backtrace`] under a `Thread <id> "<name>":` heading
+/// each. `None` where unsupported.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub(crate) fn thread_dump() -> Option<String> {
+    linux::dump()
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+pub(crate) fn thread_dump() -> Option<String> {
+    None
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod linux {
+    use std::ffi::{c_int, c_void};
+    use std::fmt::Write;
+    use std::ops::Range;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    use gimli::{
+        BaseAddresses, CfaRule, EhFrame, EhFrameHdr, EndianSlice, NativeEndian, ParsedEhFrameHdr,
+        Pointer, Register, RegisterRule, UnwindContext, UnwindSection,
+    };
+
+    /// Frames kept per thread.
+    const MAX_FRAMES: usize = 64;
+    /// How long to wait for a thread to run the handler.
+    const TIMEOUT: Duration = Duration::from_millis(100);
+    /// How many 50µs naps a thread spends in the handler at most before
+    /// carrying on, in case it is never let go.
+    const MAX_NAPS: usize = 20_000;
+
+    #[cfg(target_arch = "x86_64")]
+    const SP: Register = gimli::X86_64::RSP;
+    #[cfg(target_arch = "x86_64")]
+    const FP: Register = gimli::X86_64::RBP;
+    #[cfg(target_arch = "x86_64")]
+    const RA: Register = gimli::X86_64::RA;
+    #[cfg(target_arch = "aarch64")]
+    const SP: Register = gimli::AArch64::SP;
+    #[cfg(target_arch = "aarch64")]
+    const FP: Register = gimli::AArch64::X29;
+    #[cfg(target_arch = "aarch64")]
+    const RA: Register = gimli::AArch64::X30;
+
+    // A sample moves through these phases, packed with the thread ID into
+    // `STATE` so a handler can only claim the sample meant for its thread.
+    const WAITING: u64 = 1;
+    const CAPTURING: u64 = 2;
+    const CAPTURED: u64 = 3;
+    const IDLE: u64 = 0;
+
+    static DUMP: Mutex<()> = Mutex::new(());
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    static STATE: AtomicU64 = AtomicU64::new(IDLE);
+    /// The sampled thread's program counter, stack pointer, frame pointer
+    /// and link register.
+    static REGISTERS: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+
+    fn state(tid: libc::pid_t, phase: u64) -> u64 {
+        (tid as u64) << 2 | phase
+    }
+
+    extern "C" fn record(_signal: c_int, _info: *mut libc::siginfo_t, context: *mut c_void) {
+        let tid = unsafe { libc::gettid() };
+        // A thread that answers after its sample timed out, or that someone
+        // else signalled, finds no sample waiting for it.
+        if STATE
+            .compare_exchange(
+                state(tid, WAITING),
+                state(tid, CAPTURING),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return;
+        }
+        // SAFETY: the kernel passes the interrupted context to handlers
+        // installed with `SA_SIGINFO`.
+        let registers = registers(unsafe { &*(context as *const libc::ucontext_t) });
+        for (slot, value) in REGISTERS.iter().zip(registers) {
+            slot.store(value, Ordering::Relaxed);
+        }
+        STATE.store(state(tid, CAPTURED), Ordering::Release);
+        // Stay here so the stack doesn't change while it is walked.
+        let nap = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 50_000,
+        };
+        for _ in 0..MAX_NAPS {
+            if STATE.load(Ordering::Acquire) != state(tid, CAPTURED) {
+                break;
+            }
+            unsafe { libc::nanosleep(&nap, std::ptr::null_mut()) };
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn registers(context: &libc::ucontext_t) -> [u64; 4] {
+        let gregs = &context.uc_mcontext.gregs;
+        [
+            gregs[libc::REG_RIP as usize] as u64,
+            gregs[libc::REG_RSP as usize] as u64,
+            gregs[libc::REG_RBP as usize] as u64,
+            0,
+        ]
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn registers(context: &libc::ucontext_t) -> [u64; 4] {
+        let mcontext = &context.uc_mcontext;
+        [
+            mcontext.pc,
+            mcontext.sp,
+            mcontext.regs[29],
+            mcontext.regs[30],
+        ]
+    }
+
+    /// Install the handler for good, unless something else already handles
+    /// or ignores `signal`.
+    fn install(signal: c_int) -> bool {
+        let mut current: libc::sigaction = unsafe { std::mem::zeroed() };
+        if unsafe { libc::sigaction(signal, std::ptr::null(), &mut current) } != 0
+            || current.sa_sigaction != libc::SIG_DFL
+        {
+            return false;
+        }
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+        action.sa_sigaction =
+            record as extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void) as usize;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+        unsafe { libc::sigaction(signal, &action, std::ptr::null_mut()) == 0 }
+    }
+
+    pub(super) fn dump() -> Option<String> {
+        let _guard = DUMP.lock().ok()?;
+        // The second-highest realtime signal, which runtimes rarely claim.
+        let signal = libc::SIGRTMAX() - 1;
+        if !*INSTALLED.get_or_init(|| install(signal)) {
+            return None;
+        }
+
+        // Everything the walk needs is gathered up front: a sampled thread
+        // may hold the allocator's lock while it waits.
+        let mut unwinder = Unwinder {
+            modules: modules(),
+            stacks: readable_mappings(),
+            context: Box::new(UnwindContext::new()),
+        };
+        let mut frames = Vec::with_capacity(MAX_FRAMES);
+
+        let pid = unsafe { libc::getpid() };
+        let me = unsafe { libc::gettid() };
+        let mut out = String::new();
+        for tid in tasks().into_iter().filter(|&tid| tid != me) {
+            let name =
+                std::fs::read_to_string(format!("/proc/self/task/{tid}/comm")).unwrap_or_default();
+            let _ = writeln!(out, "Thread {tid} \"{}\":", name.trim());
+            if blocked(tid, signal) {
+                out.push_str("   <signal blocked>\n");
+            } else if sample(pid, tid, signal, &mut unwinder, &mut frames) {
+                out.push_str(&resolve(&frames));
+            } else {
+                out.push_str("   <no response>\n");
+            }
+            out.push('\n');
+        }
+        Some(out.trim_end().to_string())
+    }
+
+    /// Thread IDs in this process.
+    fn tasks() -> Vec<libc::pid_t> {
+        let Ok(entries) = std::fs::read_dir("/proc/self/task") else {
+            return Vec::new();
+        };
+        let mut tids: Vec<libc::pid_t> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .collect();
+        tids.sort_unstable();
+        tids
+    }
+
+    /// Whether thread `tid` has `signal` blocked, so it would stay pending
+    /// instead of being handled.
+    fn blocked(tid: libc::pid_t, signal: c_int) -> bool {
+        let Ok(status) = std::fs::read_to_string(format!("/proc/self/task/{tid}/status")) else {
+            return false;
+        };
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("SigBlk:"))
+            .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+            .is_some_and(|mask| mask & (1 << (signal - 1)) != 0)
+    }
+
+    /// Walk thread `tid`'s stack into `frames`, or return false if it
+    /// doesn't answer in time.
+    fn sample(
+        pid: libc::pid_t,
+        tid: libc::pid_t,
+        signal: c_int,
+        unwinder: &mut Unwinder,
+        frames: &mut Vec<usize>,
+    ) -> bool {
+        STATE.store(state(tid, WAITING), Ordering::Release);
+        if unsafe { libc::syscall(libc::SYS_tgkill, pid, tid, signal) } != 0 {
+            STATE.store(IDLE, Ordering::Release);
+            return false;
+        }
+        let deadline = Instant::now() + TIMEOUT;
+        while STATE.load(Ordering::Acquire) != state(tid, CAPTURED) {
+            // Once the handler has claimed the sample it is about to finish.
+            if Instant::now() > deadline
+                && STATE
+                    .compare_exchange(
+                        state(tid, WAITING),
+                        IDLE,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                    .is_ok()
+            {
+                return false;
+            }
+            std::thread::yield_now();
+        }
+        let [pc, sp, fp, lr] = REGISTERS
+            .each_ref()
+            .map(|value| value.load(Ordering::Relaxed));
+        unwinder.unwind(Registers { pc, sp, fp, lr }, frames);
+        STATE.store(IDLE, Ordering::Release);
+        true
+    }
+
+    #[derive(Clone, Copy)]
+    struct Registers {
+        pc: u64,
+        sp: u64,
+        fp: u64,
+        lr: u64,
+    }
+
+    impl Registers {
+        fn get(&self, register: Register) -> Option<u64> {
+            if register == SP {
+                Some(self.sp)
+            } else if register == FP {
+                Some(self.fp)
+            } else if cfg!(target_arch = "aarch64") && register == RA {
+                Some(self.lr)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// A loaded object with unwind tables.
+    struct Module {
+        segments: Vec<Range<u64>>,
+        bases: BaseAddresses,
+        header: ParsedEhFrameHdr<EndianSlice<'static, NativeEndian>>,
+        eh_frame: EhFrame<EndianSlice<'static, NativeEndian>>,
+    }
+
+    impl Module {
+        fn new(bias: u64, headers: &[libc::Elf64_Phdr]) -> Option<Self> {
+            let segments: Vec<Range<u64>> = headers
+                .iter()
+                .filter(|header| header.p_type == libc::PT_LOAD)
+                .map(|header| bias + header.p_vaddr..bias + header.p_vaddr + header.p_memsz)
+                .collect();
+            let header = headers
+                .iter()
+                .find(|header| header.p_type == libc::PT_GNU_EH_FRAME)?;
+            let address = bias + header.p_vaddr;
+            // SAFETY: loaded segments stay mapped while the object is loaded,
+            // which for anything on a live thread's stack is the whole dump.
+            let data = unsafe {
+                std::slice::from_raw_parts(address as *const u8, header.p_memsz as usize)
+            };
+            let bases = BaseAddresses::default().set_eh_frame_hdr(address);
+            let header = EhFrameHdr::new(data, NativeEndian)
+                .parse(&bases, std::mem::size_of::<usize>() as u8)
+                .ok()?;
+            header.table()?;
+            let Pointer::Direct(address) = header.eh_frame_ptr() else {
+                return None;
+            };
+            let end = segments.iter().find(|range| range.contains(&address))?.end;
+            // SAFETY: as above; `.eh_frame` runs to the end of its segment
+            // at most.
+            let data = unsafe {
+                std::slice::from_raw_parts(address as *const u8, (end - address) as usize)
+            };
+            Some(Self {
+                segments,
+                bases: bases.set_eh_frame(address),
+                header,
+                eh_frame: EhFrame::new(data, NativeEndian),
+            })
+        }
+
+        fn contains(&self, address: u64) -> bool {
+            self.segments.iter().any(|range| range.contains(&address))
+        }
+    }
+
+    /// Every loaded object with unwind tables, the vDSO included.
+    fn modules() -> Vec<Module> {
+        unsafe extern "C" fn add(
+            info: *mut libc::dl_phdr_info,
+            _size: usize,
+            modules: *mut c_void,
+        ) -> c_int {
+            // SAFETY: `dl_iterate_phdr` passes a valid entry, and `modules`
+            // is the vector below.
+            let (info, modules) = unsafe { (&*info, &mut *(modules as *mut Vec<Module>)) };
+            if !info.dlpi_phdr.is_null() {
+                let headers =
+                    unsafe { std::slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum.into()) };
+                modules.extend(Module::new(info.dlpi_addr, headers));
+            }
+            0
+        }
+
+        let mut modules = Vec::new();
+        unsafe {
+            libc::dl_iterate_phdr(Some(add), &mut modules as *mut Vec<Module> as *mut c_void)
+        };
+        modules
+    }
+
+    /// Readable mappings, which stacks are looked up in so walking one never
+    /// reads unmapped memory.
+    fn readable_mappings() -> Vec<Range<u64>> {
+        let maps = std::fs::read_to_string("/proc/self/maps").unwrap_or_default();
+        maps.lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let (start, end) = fields.next()?.split_once('-')?;
+                fields.next()?.starts_with('r').then_some(())?;
+                Some(u64::from_str_radix(start, 16).ok()?..u64::from_str_radix(end, 16).ok()?)
+            })
+            .collect()
+    }
+
+    struct Unwinder {
+        modules: Vec<Module>,
+        stacks: Vec<Range<u64>>,
+        context: Box<UnwindContext<usize>>,
+    }
+
+    impl Unwinder {
+        /// Walk the stack from `registers` into `frames` without allocating.
+        fn unwind(&mut self, mut registers: Registers, frames: &mut Vec<usize>) {
+            frames.clear();
+            let stack = self
+                .stacks
+                .iter()
+                .find(|range| range.contains(&registers.sp))
+                .cloned()
+                .unwrap_or(0..0);
+            while frames.len() < MAX_FRAMES && registers.pc != 0 {
+                frames.push(registers.pc as usize);
+                match self.step(registers, &stack, frames.len() == 1) {
+                    Some(caller) => registers = caller,
+                    None => break,
+                }
+            }
+        }
+
+        /// The caller's registers, or `None` at the outermost frame or where
+        /// the unwind tables don't say.
+        fn step(
+            &mut self,
+            registers: Registers,
+            stack: &Range<u64>,
+            first: bool,
+        ) -> Option<Registers> {
+            // Return addresses point past the call, which may be the next
+            // function already.
+            let pc = if first {
+                registers.pc
+            } else {
+                registers.pc.checked_sub(1)?
+            };
+            let module = self.modules.iter().find(|module| module.contains(pc))?;
+            let row = module
+                .header
+                .table()?
+                .unwind_info_for_address(
+                    &module.eh_frame,
+                    &module.bases,
+                    &mut self.context,
+                    pc,
+                    EhFrame::cie_from_offset,
+                )
+                .ok()?;
+            let cfa = match *row.cfa() {
+                CfaRule::RegisterAndOffset { register, offset } => {
+                    registers.get(register)?.checked_add_signed(offset)?
+                }
+                CfaRule::Expression(_) => return None,
+            };
+            if cfa < registers.sp {
+                return None;
+            }
+            let read = |address: u64| {
+                (address.is_multiple_of(8) && stack.start <= address && address + 8 <= stack.end)
+                    // SAFETY: inside a readable mapping, and the thread that
+                    // owns the stack is parked.
+                    .then(|| unsafe { (address as *const u64).read_volatile() })
+            };
+            let recover = |register: Register| match row.register(register) {
+                // Registers the tables don't mention keep their values.
+                RegisterRule::Undefined | RegisterRule::SameValue => registers.get(register),
+                RegisterRule::Offset(offset) => read(cfa.checked_add_signed(offset)?),
+                RegisterRule::ValOffset(offset) => cfa.checked_add_signed(offset),
+                RegisterRule::Register(other) => registers.get(other),
+                _ => None,
+            };
+            // An undefined return address marks the outermost frame, except
+            // in an aarch64 function that was interrupted before saving the
+            // link register.
+            if matches!(row.register(RA), RegisterRule::Undefined)
+                && !(cfg!(target_arch = "aarch64") && first)
+            {
+                return None;
+            }
+            let pc = recover(RA)?;
+            Some(Registers {
+                pc,
+                sp: cfa,
+                fp: recover(FP).unwrap_or(0),
+                lr: pc,
+            })
+        }
+    }
+
+    fn resolve(frames: &[usize]) -> String {
+        let mut out = String::new();
+        let mut index = 0;
+        for &ip in frames {
+            let mut resolved = false;
+            backtrace::resolve(ip as *mut c_void, |symbol| {
+                let name = symbol
+                    .name()
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("<unknown> ({ip:#x})"));
+                let _ = writeln!(out, "{index:>4}: {name}");
+                if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                    let _ = writeln!(out, "             at {}:{line}", file.display());
+                }
+                index += 1;
+                resolved = true;
+            });
+            if !resolved {
+                let _ = writeln!(out, "{index:>4}: <unknown> ({ip:#x})");
+                index += 1;
+            }
+        }
+        out
+    }
+}
+
+#[cfg(all(
+    test,
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_dump() {
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let parked = std::thread::Builder::new()
+            .name("parked-worker".into())
+            .spawn(move || rx.recv())
+            .unwrap();
+
+        let dump = thread_dump().unwrap();
+        drop(tx);
+        parked.join().unwrap().unwrap_err();
+
+        assert!(dump.contains("\"parked-worker\":"), "{dump}");
+        assert!(dump.contains("test_thread_dump"), "{dump}");
+    }
+
+    #[test]
+    fn test_blocked_thread_is_skipped() {
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel::<()>();
+        let masked = std::thread::Builder::new()
+            .name("masked-worker".into())
+            .spawn(move || {
+                unsafe {
+                    let mut set: libc::sigset_t = std::mem::zeroed();
+                    libc::sigemptyset(&mut set);
+                    libc::sigaddset(&mut set, libc::SIGRTMAX() - 1);
+                    libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+                }
+                ready_tx.send(()).unwrap();
+                let _ = rx.recv();
+                // Had the signal been sent, it would be delivered now.
+                unsafe {
+                    let mut set: libc::sigset_t = std::mem::zeroed();
+                    libc::sigemptyset(&mut set);
+                    libc::pthread_sigmask(libc::SIG_SETMASK, &set, std::ptr::null_mut());
+                }
+            })
+            .unwrap();
+        ready_rx.recv().unwrap();
+
+        let dump = thread_dump().unwrap();
+        drop(tx);
+        masked.join().unwrap();
+
+        let section = dump
+            .split("\n\n")
+            .find(|section| section.contains("\"masked-worker\":"))
+            .unwrap();
+        assert!(section.contains("<signal blocked>"), "{dump}");
+    }
+}
@@ -0,0 +1,179 @@
+//! Capping how many reports a client files per period.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::{Error, PrivacyLevel, Report, Reporter, Submission};
+
+/// A [`Reporter`] that files at most `max` reports per `period`, and refuses
+/// the rest with [`Error::Throttled`].
+///
+/// The limit is a sliding window over the reports filed so far, counted when
+/// they are handed to the inner reporter, whether or not filing succeeds.
+/// Counts live in memory unless given a [`state`](Self::state) file, which
+/// keeps them across restarts, so an app that crashes and restarts every few
+/// seconds can't file a report per run.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use hotln::{LinearClient, PanicHook, Throttle};
+///
+/// let mut reporter = Throttle::new(
+///     LinearClient::new("https://your-proxy.example.com"),
+///     10,
+///     Duration::from_secs(60 * 60),
+/// );
+/// reporter.state("/var/lib/myapp/hotline-throttle.json");
+/// PanicHook::new(reporter).install();
+/// ```
+pub struct Throttle<R = crate::Destination> {
+    inner: R,
+    max: usize,
+    period: Duration,
+    /// When each report in the window was filed, oldest first.
+    filed: Mutex<VecDeque<SystemTime>>,
+    state: Option<PathBuf>,
+}
+
+impl<R: Reporter> Throttle<R> {
+    pub fn new(reporter: R, max: usize, period: Duration) -> Self {
+        Self {
+            inner: reporter,
+            max,
+            period,
+            filed: Mutex::default(),
+            state: None,
+        }
+    }
+
+    /// Keep the times of recent reports in a JSON file at `path`, so the
+    /// limit holds across restarts. Times already in the file are loaded
+    /// now; a missing or unreadable file starts empty. The file is written
+    /// before each report is filed, so a crash while filing still counts.
+    pub fn state(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        let path = path.into();
+        match load(&path) {
+            Ok(filed) => *self.filed.get_mut().unwrap_or_else(|e| e.into_inner()) = filed,
+            Err(e) => tracing::warn!("cannot read throttle state {}: {e}", path.display()),
+        }
+        self.state = Some(path);
+        self
+    }
+
+    /// Count a report filed now, or say how long until the next one may be.
+    fn claim(&self) -> Result<(), Duration> {
+        let now = SystemTime::now();
+        let mut filed = self.filed.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some(oldest) = filed.front()
+            && now.duration_since(*oldest).unwrap_or_default() >= self.period
+        {
+            filed.pop_front();
+        }
+        if filed.len() >= self.max {
+            let age = filed
+                .front()
+                .map(|oldest| now.duration_since(*oldest).unwrap_or_default())
+                .unwrap_or_default();
+            return Err(self.period.saturating_sub(age));
+        }
+        filed.push_back(now);
+        if let Some(path) = &self.state
+            && let Err(e) = save(path, &filed)
+        {
+            tracing::warn!("cannot write throttle state {}: {e}", path.display());
+        }
+        Ok(())
+    }
+}
+
+impl<R: Reporter> Reporter for Throttle<R> {
+    fn report(&self, report: &Report) -> Result<String, Error> {
+        self.submit(report).map(Submission::into_url)
+    }
+
+    fn submit(&self, report: &Report) -> Result<Submission, Error> {
+        self.claim().map_err(Error::Throttled)?;
+        self.inner.submit(report)
+    }
+
+    fn comment(&self, issue: &str, body: &str) -> Result<String, Error> {
+        self.inner.comment(issue, body)
+    }
+
+    fn update_comment(&self, comment: &str, body: &str) -> Result<String, Error> {
+        self.inner.update_comment(comment, body)
+    }
+
+    fn privacy_level(&self) -> PrivacyLevel {
+        self.inner.privacy_level()
+    }
+
+    fn spools(&self, report: &Report) -> bool {
+        self.inner.spools(report)
+    }
+}
+
+fn load(path: &Path) -> Result<VecDeque<SystemTime>, String> {
+    match std::fs::read(path) {
+        Ok(data) => {
+            let mut filed: VecDeque<SystemTime> =
+                serde_json::from_slice(&data).map_err(|e| e.to_string())?;
+            filed.make_contiguous().sort();
+            Ok(filed)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(VecDeque::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn save(path: &Path, filed: &VecDeque<SystemTime>) -> Result<(), String> {
+    let data = serde_json::to_vec(filed).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    // Rename into place so a crash mid-write keeps the old counts.
+    let tmp = path.with_extension("tmp");
+    let _ = std::fs::remove_file(&tmp);
+    crate::spool::write_private(&tmp, &data).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::MockReporter;
+
+    #[test]
+    fn test_limit_survives_restart() {
+        let path = crate::spool::temp_dir("throttle").join("throttle.json");
+        let mock = Arc::new(MockReporter::new());
+        let hour = Duration::from_secs(60 * 60);
+
+        let mut first = Throttle::new(mock.clone(), 2, hour);
+        first.state(&path);
+        assert!(first.submit(&Report::new()).is_ok());
+        assert!(first.submit(&Report::new()).is_ok());
+        let result = first.submit(&Report::new());
+        assert!(matches!(result, Err(Error::Throttled(wait)) if wait <= hour));
+        drop(first);
+
+        // A restarted process picks up the counts from the file.
+        let mut second = Throttle::new(mock.clone(), 2, hour);
+        second.state(&path);
+        assert!(matches!(
+            second.submit(&Report::new()),
+            Err(Error::Throttled(_))
+        ));
+        assert_eq!(mock.reports().len(), 2);
+
+        // Counts outside the period no longer apply.
+        let mut later = Throttle::new(mock.clone(), 2, Duration::ZERO);
+        later.state(&path);
+        assert!(later.submit(&Report::new()).is_ok());
+        assert_eq!(mock.reports().len(), 3);
+    }
+}
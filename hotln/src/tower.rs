@@ -0,0 +1,273 @@
+//! Tower middleware that files issues when a service fails.
+//!
+//! ```no_run
+//! # fn wrap<S>(service: S) -> hotln::tower::ReportService<S, hotln::LinearClient> {
+//! use tower_layer::Layer;
+//!
+//! let mut client = hotln::LinearClient::new("https://worker.example.com");
+//! client.with_token("secret");
+//! hotln::tower::ReportLayer::new(client).layer(service)
+//! # }
+//! ```
+//!
+//! Works with any `tower::Service` over `http` requests, including axum
+//! routers and hyper services. A response with a 5xx status, or a panic while
+//! handling the request, files one report per distinct method, path, and
+//! failure. Panics are re-raised after reporting, so the service behaves the
+//! same as without the layer.
+
+use std::any::Any;
+use std::collections::HashSet;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures_util::FutureExt;
+
+use crate::{PrivacyLevel, Report, Reporter, Severity, info_table};
+
+/// Wraps services in a [`ReportService`].
+pub struct ReportLayer<R> {
+    reporter: Arc<R>,
+    seen: Arc<Mutex<HashSet<String>>>,
+}
+
+impl<R> ReportLayer<R> {
+    pub fn new(reporter: R) -> Self {
+        Self {
+            reporter: Arc::new(reporter),
+            seen: Arc::default(),
+        }
+    }
+}
+
+impl<R> Clone for ReportLayer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            reporter: self.reporter.clone(),
+            seen: self.seen.clone(),
+        }
+    }
+}
+
+impl<S, R> tower_layer::Layer<S> for ReportLayer<R> {
+    type Service = ReportService<S, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReportService {
+            inner,
+            reporter: self.reporter.clone(),
+            seen: self.seen.clone(),
+        }
+    }
+}
+
+/// A service that files a report when the inner service returns a 5xx or
+/// panics. Created by [`ReportLayer`].
+pub struct ReportService<S, R> {
+    inner: S,
+    reporter: Arc<R>,
+    seen: Arc<Mutex<HashSet<String>>>,
+}
+
+impl<S: Clone, R> Clone for ReportService<S, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            reporter: self.reporter.clone(),
+            seen: self.seen.clone(),
+        }
+    }
+}
+
+impl<S, R, ReqBody, ResBody> tower_service::Service<http::Request<ReqBody>> for ReportService<S, R>
+where
+    S: tower_service::Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    R: Reporter + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let context = RequestContext::new(&req, self.reporter.privacy_level());
+        let reporter = self.reporter.clone();
+        let seen = self.seen.clone();
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            match AssertUnwindSafe(future).catch_unwind().await {
+                Ok(Ok(response)) => {
+                    if response.status().is_server_error() {
+                        let status = response.status().to_string();
+                        file_once(reporter, &seen, context.report(&status, None));
+                    }
+                    Ok(response)
+                }
+                Ok(Err(e)) => Err(e),
+                Err(payload) => {
+                    let message = panic_message(payload.as_ref());
+                    file_once(reporter, &seen, context.report("panic", Some(&message)));
+                    std::panic::resume_unwind(payload)
+                }
+            }
+        })
+    }
+}
+
+struct RequestContext {
+    method: String,
+    path: String,
+    user_agent: Option<String>,
+}
+
+impl RequestContext {
+    fn new<B>(req: &http::Request<B>, privacy: PrivacyLevel) -> Self {
+        Self {
+            method: req.method().to_string(),
+            // The query string is left out: it often carries tokens or PII.
+            path: req.uri().path().to_string(),
+            user_agent: req
+                .headers()
+                .get(http::header::USER_AGENT)
+                .filter(|_| privacy.collects_context())
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        }
+    }
+
+    /// Returns the dedup key and the report for a failure.
+    fn report(&self, failure: &str, panic_message: Option<&str>) -> (String, Report) {
+        let key = format!(
+            "{} {} {} {}",
+            failure,
+            self.method,
+            self.path,
+            panic_message.unwrap_or("")
+        );
+
+        let mut rows = vec![
+            ("Method", self.method.as_str()),
+            ("Path", self.path.as_str()),
+        ];
+        if let Some(user_agent) = &self.user_agent {
+            rows.push(("User-Agent", user_agent));
+        }
+
+        let mut report = Report::new();
+        match panic_message {
+            Some(message) => {
+                report
+                    .title(&format!("panic in {} {}", self.method, self.path))
+                    .severity(Severity::Crash)
+                    .file("panic.txt", message);
+            }
+            None => {
+                report
+                    .title(&format!("{} from {} {}", failure, self.method, self.path))
+                    .severity(Severity::Bug);
+            }
+        }
+        report.text(&info_table("Request", &rows));
+        (key, report)
+    }
+}
+
+// Reporters block on network I/O, so the report is filed on its own thread
+// rather than on the async runtime.
+fn file_once<R>(reporter: Arc<R>, seen: &Mutex<HashSet<String>>, (key, report): (String, Report))
+where
+    R: Reporter + Send + Sync + 'static,
+{
+    if !seen.lock().unwrap().insert(key) {
+        return;
+    }
+    reporter.fire_and_forget(report);
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::time::{Duration, Instant};
+
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    use super::*;
+    use crate::testing::MockReporter;
+
+    struct Status(http::StatusCode);
+
+    impl Service<http::Request<()>> for Status {
+        type Response = http::Response<()>;
+        type Error = Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: http::Request<()>) -> Self::Future {
+            let mut resp = http::Response::new(());
+            *resp.status_mut() = self.0;
+            std::future::ready(Ok(resp))
+        }
+    }
+
+    fn wait_for_reports(mock: &MockReporter, n: usize) -> Vec<Report> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while mock.reports().len() < n && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        mock.reports()
+    }
+
+    #[test]
+    fn test_reports_server_errors_once() {
+        let mock = Arc::new(MockReporter::new());
+        let layer = ReportLayer::new(mock.clone());
+        let mut service = layer.layer(Status(http::StatusCode::INTERNAL_SERVER_ERROR));
+
+        for _ in 0..3 {
+            let req = http::Request::get("/users?token=secret").body(()).unwrap();
+            futures_executor::block_on(service.call(req)).unwrap();
+        }
+
+        let reports = wait_for_reports(&mock, 1);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            reports[0].title,
+            "500 Internal Server Error from GET /users"
+        );
+        assert!(!reports[0].description.contains("secret"));
+    }
+
+    #[test]
+    fn test_ignores_success() {
+        let mock = Arc::new(MockReporter::new());
+        let mut service = ReportLayer::new(mock.clone()).layer(Status(http::StatusCode::OK));
+
+        let req = http::Request::get("/").body(()).unwrap();
+        futures_executor::block_on(service.call(req)).unwrap();
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(mock.reports().is_empty());
+    }
+}
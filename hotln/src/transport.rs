@@ -0,0 +1,492 @@
+//! Connection settings shared by the proxy clients.
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+use crate::cancel;
+use crate::pinning::{self, Pin, PinnedVerifier};
+use crate::queue::{Job, OnResult, RetryQueue};
+use crate::rate_limit::{RateLimitInfo, Tracker};
+use crate::spool::{Entry, Spool};
+use crate::{Error, Submission, post_json, protocol, telemetry};
+
+/// How a client connects to its proxy, and the agent built from that.
+///
+/// The agent lives as long as the client and is shared by its clones and
+/// the retry queue, so requests to the same proxy reuse pooled connections
+/// rather than paying for a new TLS handshake each time.
+///
+/// Invalid settings are remembered rather than reported by the builder
+/// methods, and every request then fails with [`Error::Config`]: a typo in a
+/// pin or certificate must not silently fall back to a weaker connection.
+#[derive(Debug, Clone)]
+pub(crate) struct Transport {
+    pins: Vec<Pin>,
+    identity: Option<Arc<Identity>>,
+    proxy: Option<ureq::Proxy>,
+    deadline: Option<Duration>,
+    spool: Option<Spool>,
+    queue: Option<Arc<RetryQueue>>,
+    error: Option<String>,
+    rate_limit: Tracker,
+    reserve: Option<u64>,
+    pub(crate) on_result: Option<OnResult>,
+    agent: ureq::Agent,
+}
+
+#[derive(Debug)]
+struct Identity {
+    certs: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        let rate_limit = Tracker::default();
+        Self {
+            pins: Vec::new(),
+            identity: None,
+            proxy: None,
+            deadline: None,
+            spool: None,
+            queue: None,
+            error: None,
+            reserve: None,
+            on_result: None,
+            agent: ureq::AgentBuilder::new()
+                .middleware(rate_limit.clone())
+                .build(),
+            rate_limit,
+        }
+    }
+}
+
+impl Transport {
+    pub(crate) fn pin(&mut self, pin: &str) {
+        match pinning::parse(pin) {
+            Some(pin) => self.pins.push(pin),
+            None => self.fail(format!("invalid certificate pin {pin:?}")),
+        }
+        self.rebuild();
+    }
+
+    pub(crate) fn client_cert(&mut self, cert_pem: &[u8], key_pem: &[u8]) {
+        let certs: Result<Vec<_>, _> = CertificateDer::pem_slice_iter(cert_pem).collect();
+        match (certs, PrivateKeyDer::from_pem_slice(key_pem)) {
+            (Ok(certs), Ok(key)) if !certs.is_empty() => {
+                self.identity = Some(Arc::new(Identity { certs, key }));
+            }
+            (Ok(_), Ok(_)) => self.fail("no certificate in client certificate PEM".into()),
+            (Err(e), _) => self.fail(format!("invalid client certificate: {e}")),
+            (_, Err(e)) => self.fail(format!("invalid client key: {e}")),
+        }
+        self.rebuild();
+    }
+
+    pub(crate) fn socks_proxy(&mut self, url: &str) {
+        if !url.starts_with("socks") {
+            self.fail(format!("{url:?} is not a socks4:// or socks5:// URL"));
+        }
+        match ureq::Proxy::new(url) {
+            Ok(proxy) => self.proxy = Some(proxy),
+            Err(e) => self.fail(format!("invalid SOCKS proxy {url:?}: {e}")),
+        }
+        self.rebuild();
+    }
+
+    pub(crate) fn deadline(&mut self, deadline: Duration) {
+        self.deadline = Some(deadline);
+        self.rebuild();
+    }
+
+    pub(crate) fn spool(&mut self, spool: Spool) {
+        self.spool = Some(spool);
+    }
+
+    pub(crate) fn retry_queue(&mut self, capacity: usize) {
+        self.queue = Some(Arc::new(RetryQueue::new(capacity)));
+    }
+
+    pub(crate) fn on_result(
+        &mut self,
+        callback: impl Fn(&str, Result<Submission, Error>) + Send + Sync + 'static,
+    ) {
+        self.on_result = Some(OnResult(Arc::new(callback)));
+    }
+
+    pub(crate) fn reserve(&mut self, requests: u64) {
+        self.reserve = Some(requests);
+    }
+
+    /// Whether Linear's remaining request allowance is down to the reserve
+    /// and hasn't been refilled since.
+    fn over_budget(&self) -> bool {
+        let (Some(reserve), Some(info)) = (self.reserve, self.rate_limit.latest()) else {
+            return false;
+        };
+        let refilled = info
+            .requests_reset
+            .is_some_and(|reset| reset <= SystemTime::now());
+        !refilled && info.requests_remaining.is_some_and(|left| left <= reserve)
+    }
+
+    /// Post a report to `{base}/{endpoint}`. If the proxy can't be reached,
+    /// hand the report to the retry queue, or failing that, the spool. A
+    /// [cancelled](crate::cancel) report is neither sent nor kept.
+    pub(crate) fn submit(
+        &self,
+        base: &str,
+        endpoint: &'static str,
+        token: Option<String>,
+        version: Arc<OnceLock<u8>>,
+        payload: serde_json::Value,
+        reference: &str,
+    ) -> Result<Submission, Error> {
+        let cancel = cancel::current();
+        cancel::check(cancel.as_ref())?;
+        // If the spool can't take it, send it now rather than lose it.
+        if self.spool.is_some()
+            && !is_urgent(&payload)
+            && self.over_budget()
+            && self.spool_report(endpoint, payload.clone()).is_ok()
+        {
+            tracing::info!("deferred report {reference} to keep rate limit for crashes");
+            return Ok(Submission::Spooled(reference.to_string()));
+        }
+        let url = format!("{base}/{endpoint}");
+        // Keep a copy only when a failure has somewhere to go.
+        let backup = (self.queue.is_some() || self.spool.is_some()).then(|| payload.clone());
+        let result = self.send(url.clone(), token.clone(), version.clone(), payload);
+        let Some(mut payload) = backup else {
+            return result;
+        };
+        if !result.as_ref().is_err_and(Error::is_retryable) {
+            return result;
+        }
+        cancel::check(cancel.as_ref())?;
+        if let Some(queue) = &self.queue {
+            let job = Box::new(Job {
+                transport: self.clone(),
+                cancel,
+                reference: reference.to_string(),
+                url,
+                token,
+                version,
+                endpoint,
+                payload,
+            });
+            match queue.push(job) {
+                Ok(()) => return Ok(Submission::Queued(reference.to_string())),
+                Err(job) => payload = job.payload,
+            }
+        }
+        match (self.spool_report(endpoint, payload), result) {
+            (Err(Error::Spool(e)), Err(error)) => Err(Error::Spool(format!(
+                "cannot save report after failing to send it ({error}): {e}"
+            ))),
+            (_, result) => result,
+        }
+    }
+
+    pub(crate) fn has_spool(&self) -> bool {
+        self.spool.is_some()
+    }
+
+    /// Save a report to the spool. Returns false if there is none, and an
+    /// error if it couldn't be written.
+    pub(crate) fn spool_report(
+        &self,
+        endpoint: &str,
+        payload: serde_json::Value,
+    ) -> Result<bool, Error> {
+        let Some(spool) = &self.spool else {
+            return Ok(false);
+        };
+        let entry = Entry {
+            endpoint: endpoint.to_string(),
+            payload,
+        };
+        let result = spool.push(&entry);
+        telemetry::spool_depth(spool);
+        let path = result.inspect_err(|e| tracing::warn!("failed to spool report: {e}"))?;
+        tracing::info!("spooled report to {}", path.display());
+        Ok(true)
+    }
+
+    /// Resend spooled reports for `endpoint`, oldest first, and return how
+    /// many were delivered.
+    ///
+    /// Stops at the first report the proxy can't take right now, leaving it
+    /// and the rest for next time. Reports the proxy rejects outright are
+    /// dropped, since resending them would never succeed.
+    pub(crate) fn send_spooled(
+        &self,
+        base: &str,
+        endpoint: &'static str,
+        token: Option<&str>,
+        version: &Arc<OnceLock<u8>>,
+    ) -> Result<usize, Error> {
+        let Some(spool) = &self.spool else {
+            return Ok(0);
+        };
+        let mut sent = 0;
+        for path in spool.files() {
+            let entry = match spool.read(&path) {
+                // Deferred reports wait for the allowance to refill.
+                Ok(entry) if !is_urgent(&entry.payload) && self.over_budget() => continue,
+                Ok(entry) if entry.endpoint == endpoint => entry,
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::warn!("skipping spooled report {}: {e}", path.display());
+                    continue;
+                }
+            };
+            let url = format!("{base}/{endpoint}");
+            match self.send(
+                url,
+                token.map(str::to_string),
+                version.clone(),
+                entry.payload,
+            ) {
+                Ok(_) => sent += 1,
+                Err(e) if e.is_retryable() => return Err(e),
+                Err(e) => tracing::warn!("dropping spooled report {}: {e}", path.display()),
+            }
+            let _ = std::fs::remove_file(&path);
+            telemetry::spool_depth(spool);
+        }
+        Ok(sent)
+    }
+
+    /// Post a report, first downgrading it if the proxy only speaks v1.
+    pub(crate) fn send(
+        &self,
+        url: String,
+        token: Option<String>,
+        version: Arc<OnceLock<u8>>,
+        payload: serde_json::Value,
+    ) -> Result<Submission, Error> {
+        let started = Instant::now();
+        let result = self.submit_within_deadline(url, token, version, payload);
+        telemetry::submission(&result, started.elapsed());
+        result
+    }
+
+    /// With a deadline, the request runs on its own thread and is abandoned
+    /// once the deadline passes; ureq's matching timeout ends it soon after.
+    /// DNS lookups aren't covered by ureq's timeout, hence the thread.
+    fn submit_within_deadline(
+        &self,
+        url: String,
+        token: Option<String>,
+        version: Arc<OnceLock<u8>>,
+        payload: serde_json::Value,
+    ) -> Result<Submission, Error> {
+        let agent = self.agent()?.clone();
+        // Keep the caller's span as the parent when sending from a thread.
+        let parent = tracing::Span::current();
+        let cancel = cancel::current();
+        let send = move || {
+            let _entered = parent.enter();
+            let mut payload = payload;
+            let required = protocol::required(&payload);
+            if required > 1 {
+                cancel::check(cancel.as_ref())?;
+                let version = *version.get_or_init(|| protocol::probe(&agent, &url));
+                if version < required {
+                    protocol::downgrade(&mut payload, version);
+                }
+            }
+            cancel::check(cancel.as_ref())?;
+            post_json(&agent, &url, token.as_deref(), &payload)
+        };
+        let Some(deadline) = self.deadline else {
+            return send();
+        };
+        let (tx, rx) = mpsc::channel();
+        let handle = std::thread::Builder::new()
+            .name("hotline-submit".into())
+            .spawn(move || {
+                let _ = tx.send(send());
+            })
+            .map_err(|e| Error::Config(format!("spawning submit thread: {e}")))?;
+        match rx.recv_timeout(deadline) {
+            Ok(Err(Error::Http(e))) if is_timeout(&e) => Err(Error::Timeout(deadline)),
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(Error::Timeout(deadline)),
+            Err(RecvTimeoutError::Disconnected) => match handle.join() {
+                Err(panic) => std::panic::resume_unwind(panic),
+                Ok(()) => unreachable!("submit thread exited without sending"),
+            },
+        }
+    }
+
+    /// The agent to send with, or the first configuration error.
+    pub(crate) fn agent(&self) -> Result<&ureq::Agent, Error> {
+        match &self.error {
+            Some(e) => Err(Error::Config(e.clone())),
+            None => Ok(&self.agent),
+        }
+    }
+
+    /// Linear's rate limits from the latest response that had them.
+    pub(crate) fn rate_limit(&self) -> Option<RateLimitInfo> {
+        self.rate_limit.latest()
+    }
+
+    fn fail(&mut self, error: String) {
+        self.error.get_or_insert(error);
+    }
+
+    fn rebuild(&mut self) {
+        let mut builder = ureq::AgentBuilder::new().middleware(self.rate_limit.clone());
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        if let Some(deadline) = self.deadline {
+            builder = builder.timeout(deadline);
+        }
+        match self.tls_config() {
+            Ok(Some(config)) => builder = builder.tls_config(config),
+            Ok(None) => {}
+            Err(e) => self.fail(e),
+        }
+        self.agent = builder.build();
+    }
+
+    // None keeps ureq's default TLS setup.
+    fn tls_config(&self) -> Result<Option<Arc<rustls::ClientConfig>>, String> {
+        if self.pins.is_empty() && self.identity.is_none() {
+            return Ok(None);
+        }
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?;
+        let builder = if self.pins.is_empty() {
+            let roots = rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            builder.with_root_certificates(roots)
+        } else {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+                    pins: self.pins.clone(),
+                    provider,
+                }))
+        };
+        let config = match &self.identity {
+            Some(identity) => builder
+                .with_client_auth_cert(identity.certs.clone(), identity.key.clone_key())
+                .map_err(|e| format!("invalid client certificate: {e}"))?,
+            None => builder.with_no_client_auth(),
+        };
+        Ok(Some(Arc::new(config)))
+    }
+}
+
+/// Crash reports, which become Urgent issues, are sent even when the rate
+/// limit reserve is reached.
+fn is_urgent(payload: &serde_json::Value) -> bool {
+    payload["severity"] == "crash"
+}
+
+fn is_timeout(error: &ureq::Error) -> bool {
+    let ureq::Error::Transport(transport) = error else {
+        return false;
+    };
+    std::error::Error::source(transport)
+        .and_then(|e| e.downcast_ref::<std::io::Error>())
+        .is_some_and(|e| {
+            matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_settings_fail_closed() {
+        let mut transport = Transport::default();
+        assert!(transport.agent().is_ok());
+
+        transport.pin("not a pin");
+        assert!(matches!(transport.agent(), Err(Error::Config(_))));
+
+        let mut transport = Transport::default();
+        transport.client_cert(b"", b"");
+        assert!(matches!(transport.agent(), Err(Error::Config(_))));
+
+        let mut transport = Transport::default();
+        transport.socks_proxy("socks5://127.0.0.1:9050");
+        assert!(transport.agent().is_ok());
+        transport.socks_proxy("http://127.0.0.1:8080");
+        assert!(matches!(transport.agent(), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_connection_reuse() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.unwrap());
+                    loop {
+                        let mut length = 0;
+                        let mut line = String::new();
+                        while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                            if let Some(value) =
+                                line.to_ascii_lowercase().strip_prefix("content-length:")
+                            {
+                                length = value.trim().parse().unwrap();
+                            }
+                            line.clear();
+                        }
+                        if line.is_empty() {
+                            return;
+                        }
+                        reader.read_exact(&mut vec![0; length]).unwrap();
+                        // Chunked, so ureq only pools the connection once the
+                        // body has been read to the end.
+                        let body = r#"{"url":"https://linear.app/issue/ENG-1"}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                             {:x}\r\n{body}\r\n0\r\n\r\n",
+                            body.len()
+                        );
+                        reader.get_mut().write_all(response.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+
+        let transport = Transport::default();
+        let version = Arc::new(OnceLock::new());
+        for _ in 0..3 {
+            let payload = serde_json::json!({ "title": "t", "description": "d" });
+            let url = format!("{base}/issue");
+            transport.send(url, None, version.clone(), payload).unwrap();
+        }
+        let agent = transport.agent().unwrap();
+        crate::post_webhook(agent, &format!("{base}/hook"), &serde_json::json!({})).unwrap();
+        protocol::probe(agent, &format!("{base}/issue"));
+        crate::post_webhook(agent, &format!("{base}/hook"), &serde_json::json!({})).unwrap();
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+}